@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+// Substrate
+use parity_scale_codec::{Decode, Encode};
+use rocksdb::{Direction, IteratorMode};
+use sp_core::H256;
+use starknet_api::hash::StarkHash;
+
+use crate::{Column, DatabaseExt, DbError, DB};
+
+/// A `LogStateUpdate` event emitted by the Starknet core contract on L1
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct L1StateUpdateRecord {
+    /// Number of the L1 block containing the event
+    pub l1_block_number: u64,
+    /// Hash of the L1 transaction which emitted the event
+    pub l1_transaction_hash: H256,
+    /// Starknet block number verified by this update
+    pub block_number: u64,
+    /// Starknet global state root after this block
+    pub global_root: StarkHash,
+    /// Starknet block hash verified by this update
+    pub block_hash: StarkHash,
+}
+
+/// Allow interaction with the l1 db
+///
+/// The l1 db stores the history of the Starknet state updates verified on L1, indexed by Starknet
+/// block number. Keys are big endian so that iteration follows the chain order.
+pub struct L1Db {
+    db: Arc<DB>,
+}
+
+impl L1Db {
+    pub(crate) fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Store a state update verified on L1
+    pub fn write_state_update(&self, state_update: &L1StateUpdateRecord) -> Result<(), DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+
+        self.db.put_cf(&column, state_update.block_number.to_be_bytes(), state_update.encode())?;
+        Ok(())
+    }
+
+    /// Retrieve the state update verifying the given Starknet block, if any
+    pub fn state_update(&self, block_number: u64) -> Result<Option<L1StateUpdateRecord>, DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+
+        match self.db.get_cf(&column, block_number.to_be_bytes())? {
+            Some(raw) => Ok(Some(L1StateUpdateRecord::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the first stored state update verifying the given Starknet block or a later one, if
+    /// any
+    pub fn verifying_state_update(&self, block_number: u64) -> Result<Option<L1StateUpdateRecord>, DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+        let mode = IteratorMode::From(&block_number.to_be_bytes(), Direction::Forward);
+
+        match self.db.iterator_cf(&column, mode).next() {
+            Some(entry) => {
+                let (_, raw) = entry?;
+                Ok(Some(L1StateUpdateRecord::decode(&mut &raw[..])?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the state update verifying the given Starknet block, if it was emitted by the given
+    /// L1 transaction
+    pub fn remove_state_update(&self, block_number: u64, l1_transaction_hash: H256) -> Result<(), DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+
+        if self.state_update(block_number)?.is_some_and(|record| record.l1_transaction_hash == l1_transaction_hash) {
+            self.db.delete_cf(&column, block_number.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Retrieve the latest state update verified on L1, if any
+    pub fn last_state_update(&self) -> Result<Option<L1StateUpdateRecord>, DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+
+        match self.db.iterator_cf(&column, IteratorMode::End).next() {
+            Some(entry) => {
+                let (_, raw) = entry?;
+                Ok(Some(L1StateUpdateRecord::decode(&mut &raw[..])?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve every stored state update, ordered by Starknet block number
+    pub fn state_updates(&self) -> Result<Vec<L1StateUpdateRecord>, DbError> {
+        let column = self.db.get_column(Column::L1StateUpdates);
+
+        self.db
+            .iterator_cf(&column, IteratorMode::Start)
+            .map(|entry| {
+                let (_, raw) = entry?;
+                Ok(L1StateUpdateRecord::decode(&mut &raw[..])?)
+            })
+            .collect()
+    }
+}
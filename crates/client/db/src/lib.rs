@@ -15,16 +15,20 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock, RwLock};
 use std::{fmt, fs};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bonsai_db::{BonsaiDb, DatabaseKeyMapping};
 use bonsai_trie::id::BasicId;
 use bonsai_trie::{BonsaiStorage, BonsaiStorageConfig};
+use l1_db::L1Db;
 use mapping_db::MappingDb;
+use messaging_db::MessagingDb;
 use meta_db::MetaDb;
 use rocksdb::backup::{BackupEngine, BackupEngineOptions};
 
 mod error;
+mod l1_db;
 mod mapping_db;
+mod messaging_db;
 use rocksdb::{
     BoundColumnFamily, ColumnFamilyDescriptor, DBCompressionType, Env, MultiThreaded, OptimisticTransactionDB, Options,
     SliceTransform,
@@ -36,7 +40,11 @@ pub mod storage_handler;
 pub mod storage_updates;
 
 pub use error::{BonsaiDbError, DbError};
+pub use l1_db::L1StateUpdateRecord;
 pub use mapping_db::MappingCommitment;
+pub use messaging_db::{
+    l2_message_hash, L1MessageRecord, L2MessageFilter, L2MessageKey, L2MessageRecord, L2MessageStatus,
+};
 use storage_handler::bonsai_identifier;
 use tokio::sync::{mpsc, oneshot};
 
@@ -92,6 +100,28 @@ pub(crate) fn open_rocksdb(
     Ok(db)
 }
 
+/// Path of the rocksdb database in the node config directory
+fn rocksdb_path(db_config_dir: &Path) -> PathBuf {
+    db_config_dir.join("starknet/rockdb") //.deoxysdb/chains/starknet/starknet/rockdb
+}
+
+/// Subdirectory of a snapshot holding the copy of the Substrate database
+const SUBSTRATE_SNAPSHOT_DIR: &str = "substrate";
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &path)?;
+        } else {
+            fs::copy(entry.path(), path)?;
+        }
+    }
+    Ok(())
+}
+
 fn spawn_backup_db_task(
     backup_dir: &Path,
     restore_from_latest_backup: bool,
@@ -145,6 +175,8 @@ pub enum Column {
     StarknetBlockHashesMapping,
     /// Starknet block hash to block number
     StarknetBlockNumberMapping,
+    /// Transaction hash to the revert reason of a reverted transaction
+    RevertedTransactions,
 
     /// Contract class hash to class data
     ContractClassData,
@@ -165,6 +197,26 @@ pub enum Column {
     ContractStorage,
     /// Block number to state diff
     BlockStateDiff,
+    /// Starknet block number to the LogStateUpdate event verifying it on L1
+    L1StateUpdates,
+    /// L1 to L2 message nonce to the LogMessageToL2 event which sent it
+    L1Messages,
+    /// L1 transaction hash to the nonces of the messages it sent
+    L1TransactionMessages,
+    /// Hash of the content of a message to the nonce of the latest message with that content
+    L1MessageContents,
+    /// L1 to L2 message nonce to the hash of the L1 handler transaction which consumed it
+    L1HandlerTransactions,
+    /// (block number, transaction index, message index) to the L2 to L1 message sent there
+    L2Messages,
+    /// (L1 recipient, message key) index of the L2 to L1 messages
+    L2MessagesByRecipient,
+    /// (L2 sender, message key) index of the L2 to L1 messages
+    L2MessagesBySender,
+    /// L2 to L1 message hash to the number of messages sent with that hash
+    L2MessageHashCounts,
+    /// (message hash, L1 transaction hash, log index) of each consumption of an L2 to L1 message
+    L1ConsumedMessages,
 
     // Each bonsai storage has 3 columns
     BonsaiContractsTrie,
@@ -209,12 +261,23 @@ impl Column {
             StarknetTransactionHashesMapping,
             StarknetBlockHashesMapping,
             StarknetBlockNumberMapping,
+            RevertedTransactions,
             ContractClassData,
             ContractToClassHashes,
             ContractToNonces,
             ContractClassHashes,
             ContractStorage,
             BlockStateDiff,
+            L1StateUpdates,
+            L1Messages,
+            L1TransactionMessages,
+            L1MessageContents,
+            L1HandlerTransactions,
+            L2Messages,
+            L2MessagesByRecipient,
+            L2MessagesBySender,
+            L2MessageHashCounts,
+            L1ConsumedMessages,
             BonsaiContractsTrie,
             BonsaiContractsFlat,
             BonsaiContractsLog,
@@ -238,6 +301,7 @@ impl Column {
             StarknetTransactionHashesMapping => "starknet_transaction_hashes_mapping",
             StarknetBlockHashesMapping => "starnet_block_hashes_mapping",
             StarknetBlockNumberMapping => "starknet_block_number_mapping",
+            RevertedTransactions => "reverted_transactions",
             BonsaiContractsTrie => "bonsai_contracts_trie",
             BonsaiContractsFlat => "bonsai_contracts_flat",
             BonsaiContractsLog => "bonsai_contracts_log",
@@ -248,6 +312,16 @@ impl Column {
             BonsaiClassesFlat => "bonsai_classes_flat",
             BonsaiClassesLog => "bonsai_classes_log",
             BlockStateDiff => "block_state_diff",
+            L1StateUpdates => "l1_state_updates",
+            L1Messages => "l1_messages",
+            L1TransactionMessages => "l1_transaction_messages",
+            L1MessageContents => "l1_message_contents",
+            L1HandlerTransactions => "l1_handler_transactions",
+            L2Messages => "l2_messages",
+            L2MessagesByRecipient => "l2_messages_by_recipient",
+            L2MessagesBySender => "l2_messages_by_sender",
+            L2MessageHashCounts => "l2_message_hash_counts",
+            L1ConsumedMessages => "l1_consumed_messages",
             ContractClassData => "contract_class_data",
             ContractToClassHashes => "contract_to_class_hashes",
             ContractToNonces => "contract_to_nonces",
@@ -276,6 +350,21 @@ impl Column {
                     storage_handler::contract_data::CONTRACT_NONCES_PREFIX_EXTRACTOR,
                 ));
             }
+            Column::L2MessagesByRecipient => {
+                opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+                    messaging_db::L2_MESSAGES_BY_RECIPIENT_PREFIX_EXTRACTOR,
+                ));
+            }
+            Column::L2MessagesBySender => {
+                opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+                    messaging_db::L2_MESSAGES_BY_SENDER_PREFIX_EXTRACTOR,
+                ));
+            }
+            Column::L1ConsumedMessages => {
+                opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+                    messaging_db::L1_CONSUMED_MESSAGES_PREFIX_EXTRACTOR,
+                ));
+            }
             _ => {}
         }
         opts
@@ -304,6 +393,7 @@ impl DatabaseExt for DB {
 ///
 /// * `meta`: stores data aboud the current state of the chain.
 /// * `mapping`: maps Starknet blocks to Substrate blocks.
+/// * `l1`: stores the history of Starknet state updates verified on L1.
 /// * `da`: store Data Availability info that needs to be written to the Ethereum L1.
 /// * `messaging`: Stores Ethereum L1 messaging data.
 /// * `sierra_classes`: @antyro what is this for?
@@ -313,6 +403,8 @@ impl DatabaseExt for DB {
 pub struct DeoxysBackend {
     meta: Arc<MetaDb>,
     mapping: Arc<MappingDb>,
+    l1: Arc<L1Db>,
+    messaging: Arc<MessagingDb>,
     bonsai_contract: RwLock<BonsaiStorage<BasicId, BonsaiDb<'static>, Pedersen>>,
     bonsai_storage: RwLock<BonsaiStorage<BasicId, BonsaiDb<'static>, Pedersen>>,
     bonsai_class: RwLock<BonsaiStorage<BasicId, BonsaiDb<'static>, Poseidon>>,
@@ -349,7 +441,7 @@ impl DeoxysBackend {
         backup_dir: Option<PathBuf>,
         restore_from_latest_backup: bool,
     ) -> Result<&'static Arc<DeoxysBackend>> {
-        let db_path = db_config_dir.join("starknet/rockdb"); //.deoxysdb/chains/starknet/starknet/rockdb
+        let db_path = rocksdb_path(db_config_dir);
 
         let db =
             Arc::new(open_rocksdb(&db_path, true, backup_dir, restore_from_latest_backup).context("opening database")?);
@@ -407,6 +499,8 @@ impl DeoxysBackend {
         let backend = Arc::new(Self {
             mapping: Arc::new(MappingDb::new(Arc::clone(db))),
             meta: Arc::new(MetaDb::new(Arc::clone(db))),
+            l1: Arc::new(L1Db::new(Arc::clone(db))),
+            messaging: Arc::new(MessagingDb::new(Arc::clone(db))),
             bonsai_contract: RwLock::new(bonsai_contract),
             bonsai_storage: RwLock::new(bonsai_contract_storage),
             bonsai_class: RwLock::new(bonsai_classes),
@@ -417,6 +511,44 @@ impl DeoxysBackend {
         Ok(BACKEND_SINGLETON.get().unwrap())
     }
 
+    /// Restores the database from a snapshot, unless the database already exists. Returns whether
+    /// the snapshot was restored.
+    ///
+    /// A snapshot is the directory of a backup made with `backup_dir`, with a copy of the Substrate
+    /// database taken at the same block in its `substrate` subdirectory. Both are restored, as the
+    /// Starknet blocks are wrapped inside the Substrate ones.
+    ///
+    /// This must be called before [`DeoxysBackend::open`] and before the Substrate database is
+    /// opened.
+    pub fn restore_snapshot(db_config_dir: &Path, substrate_db_path: &Path, snapshot_dir: &Path) -> Result<bool> {
+        let db_path = rocksdb_path(db_config_dir);
+        match (db_path.exists(), substrate_db_path.exists()) {
+            (true, true) => return Ok(false),
+            (false, false) => {}
+            (true, false) | (false, true) => bail!(
+                "only one of {} and {} exists, remove it to restore the snapshot",
+                db_path.display(),
+                substrate_db_path.display()
+            ),
+        }
+
+        let substrate_snapshot = snapshot_dir.join(SUBSTRATE_SNAPSHOT_DIR);
+        if !substrate_snapshot.is_dir() {
+            bail!("snapshot {} has no {SUBSTRATE_SNAPSHOT_DIR} database", snapshot_dir.display());
+        }
+
+        log::info!("⏳ Restoring snapshot from {}...", snapshot_dir.display());
+        let backup_opts = BackupEngineOptions::new(snapshot_dir).context("creating backup options")?;
+        let mut engine = BackupEngine::open(&backup_opts, &Env::new().context("creating rocksdb env")?)
+            .context("opening snapshot")?;
+
+        fs::create_dir_all(&db_path).with_context(|| format!("creating directories {:?}", db_path))?;
+        let opts = rocksdb::backup::RestoreOptions::default();
+        engine.restore_from_latest_backup(&db_path, &db_path, &opts).context("restoring snapshot")?;
+        copy_dir(&substrate_snapshot, substrate_db_path).context("restoring the substrate database of the snapshot")?;
+        Ok(true)
+    }
+
     pub async fn backup() -> Result<()> {
         let chann = DB_BACKUP_SINGLETON.get().context("backups are not enabled")?;
         let (callback_sender, callback_recv) = oneshot::channel();
@@ -435,6 +567,16 @@ impl DeoxysBackend {
         BACKEND_SINGLETON.get().map(|backend| &backend.meta).expect("Backend not initialized")
     }
 
+    /// Return the l1 state update database manager
+    pub fn l1() -> &'static Arc<L1Db> {
+        BACKEND_SINGLETON.get().map(|backend| &backend.l1).expect("Backend not initialized")
+    }
+
+    /// Return the messaging database manager
+    pub fn messaging() -> &'static Arc<MessagingDb> {
+        BACKEND_SINGLETON.get().map(|backend| &backend.messaging).expect("Backend not initialized")
+    }
+
     pub(crate) fn bonsai_contract() -> &'static RwLock<BonsaiStorage<BasicId, BonsaiDb<'static>, Pedersen>> {
         BACKEND_SINGLETON.get().map(|backend| &backend.bonsai_contract).expect("Backend not initialized")
     }
@@ -454,4 +596,14 @@ impl DeoxysBackend {
     pub fn compact() {
         Self::expose_db().compact_range(None::<&[u8]>, None::<&[u8]>);
     }
+
+    /// Flushes the memtables of all the columns to disk, when the sync stops
+    pub fn flush() -> Result<()> {
+        let db = Self::expose_db();
+        for &column in Column::ALL {
+            db.flush_cf(&db.get_column(column))
+                .with_context(|| format!("flushing column {}", column.rocksdb_name()))?;
+        }
+        Ok(())
+    }
 }
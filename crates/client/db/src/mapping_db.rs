@@ -16,6 +16,8 @@ pub struct MappingCommitment<B: BlockT> {
     pub block_hash: B::Hash,
     pub starknet_block_hash: StarkHash,
     pub starknet_transaction_hashes: Vec<StarkHash>,
+    /// The reverted transactions of the block, with their revert reason
+    pub reverted_transactions: Vec<(StarkHash, String)>,
 }
 
 /// Allow interaction with the mapping db
@@ -68,6 +70,7 @@ impl MappingDb {
         let starknet_tx_hashes_col = self.db.get_column(Column::StarknetTransactionHashesMapping);
         let starknet_block_hashes_col = self.db.get_column(Column::StarknetBlockHashesMapping);
         let starknet_block_numbers_col = self.db.get_column(Column::StarknetBlockNumberMapping);
+        let reverted_transactions_col = self.db.get_column(Column::RevertedTransactions);
 
         let mut transaction: WriteBatchWithTransaction<true> = Default::default();
 
@@ -93,6 +96,10 @@ impl MappingDb {
             transaction.put_cf(&transaction_mapping_col, &transaction_hash.encode(), &commitment.block_hash.encode());
         }
 
+        for (transaction_hash, revert_reason) in commitment.reverted_transactions.iter() {
+            transaction.put_cf(&reverted_transactions_col, &transaction_hash.encode(), &revert_reason.encode());
+        }
+
         transaction.put_cf(
             &starknet_tx_hashes_col,
             &commitment.starknet_block_hash.encode(),
@@ -135,6 +142,20 @@ impl MappingDb {
         }
     }
 
+    /// Returns the revert reason of a transaction of a synced block, or `None` if it succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_hash` - the hash of a transaction of a synced block.
+    pub fn transaction_revert_reason(&self, transaction_hash: StarkHash) -> Result<Option<String>, DbError> {
+        let reverted_transactions_col = self.db.get_column(Column::RevertedTransactions);
+
+        match self.db.get_cf(&reverted_transactions_col, transaction_hash.encode())? {
+            Some(raw) => Ok(Some(String::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
     /// Returns the list of transaction hashes for the given block hash.
     ///
     /// # Arguments
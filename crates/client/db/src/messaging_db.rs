@@ -0,0 +1,505 @@
+use std::sync::Arc;
+
+// Substrate
+use parity_scale_codec::{Decode, Encode};
+use rocksdb::{Direction, IteratorMode, ReadOptions, WriteBatchWithTransaction};
+use sp_core::hashing::{blake2_256, keccak_256};
+use sp_core::{H160, H256};
+use starknet_api::hash::{StarkFelt, StarkHash};
+
+use crate::l1_db::L1Db;
+use crate::{Column, DatabaseExt, DbError, DB};
+
+/// A `LogMessageToL2` event emitted by the Starknet core contract on L1
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct L1MessageRecord {
+    /// Number of the L1 block containing the event
+    pub l1_block_number: u64,
+    /// Hash of the L1 transaction which sent the message
+    pub l1_transaction_hash: H256,
+    /// Nonce of the message, shared with the L1 handler transaction consuming it
+    pub nonce: StarkFelt,
+    /// L1 address of the sender
+    pub from_address: H160,
+    /// L2 address of the contract receiving the message
+    pub to_address: StarkFelt,
+    /// Selector of the L1 handler called on the receiving contract
+    pub selector: StarkFelt,
+    /// Message payload, passed as calldata to the L1 handler after the sender address
+    pub payload: Vec<StarkFelt>,
+    /// Fee paid on L1 for the message
+    pub fee: u128,
+}
+
+impl L1MessageRecord {
+    /// Key identifying a message by its content, regardless of its nonce
+    fn content_key(
+        from_address: &H160,
+        to_address: &StarkFelt,
+        selector: &StarkFelt,
+        payload: &[StarkFelt],
+    ) -> [u8; 32] {
+        blake2_256(&(from_address, to_address, selector, payload).encode())
+    }
+}
+
+pub(crate) const L2_MESSAGES_BY_RECIPIENT_PREFIX_EXTRACTOR: usize = 20;
+pub(crate) const L2_MESSAGES_BY_SENDER_PREFIX_EXTRACTOR: usize = 32;
+pub(crate) const L1_CONSUMED_MESSAGES_PREFIX_EXTRACTOR: usize = 32;
+
+/// Meta key of the last L1 block whose ConsumedMessageToL1 events were indexed
+const L1_CONSUMED_MESSAGES_CURSOR: &[u8] = b"L1_CONSUMED_MESSAGES_CURSOR";
+
+/// A message sent from L2 to L1, as found in a transaction receipt
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct L2MessageRecord {
+    pub block_number: u64,
+    /// Index of the sending transaction in its block
+    pub transaction_index: u32,
+    /// Index of the message in the receipt of the sending transaction
+    pub message_index: u32,
+    pub transaction_hash: StarkHash,
+    /// L2 address of the sending contract
+    pub from_address: StarkFelt,
+    /// L1 address of the recipient
+    pub to_address: H160,
+    pub payload: Vec<StarkFelt>,
+}
+
+impl L2MessageRecord {
+    /// Position of the message in the chain, used as its key
+    pub fn key(&self) -> L2MessageKey {
+        L2MessageKey {
+            block_number: self.block_number,
+            transaction_index: self.transaction_index,
+            message_index: self.message_index,
+        }
+    }
+
+    /// Hash of the message as computed by the Starknet core contract
+    pub fn message_hash(&self) -> H256 {
+        l2_message_hash(&self.from_address, &self.to_address, &self.payload)
+    }
+}
+
+/// Hash of a message sent from L2 to L1, as computed by the Starknet core contract:
+/// `keccak256(from_address, to_address, payload.len(), payload)` over 32 bytes words
+pub fn l2_message_hash(from_address: &StarkFelt, to_address: &H160, payload: &[StarkFelt]) -> H256 {
+    let mut data = Vec::with_capacity(32 * (3 + payload.len()));
+    data.extend_from_slice(&from_address.0);
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(to_address.as_bytes());
+    data.extend_from_slice(&[0u8; 24]);
+    data.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    for felt in payload {
+        data.extend_from_slice(&felt.0);
+    }
+    H256(keccak_256(&data))
+}
+
+/// Position of a message sent from L2 to L1 in the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct L2MessageKey {
+    pub block_number: u64,
+    pub transaction_index: u32,
+    pub message_index: u32,
+}
+
+impl L2MessageKey {
+    const LEN: usize = 16;
+
+    /// First possible message of a block
+    pub fn block_start(block_number: u64) -> Self {
+        Self { block_number, transaction_index: 0, message_index: 0 }
+    }
+
+    fn to_bytes(self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[..8].copy_from_slice(&self.block_number.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.transaction_index.to_be_bytes());
+        bytes[12..].copy_from_slice(&self.message_index.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DbError> {
+        let bytes: [u8; Self::LEN] =
+            bytes.try_into().map_err(|_| DbError::Format("L2 message key should be 16 bytes".into()))?;
+        Ok(Self {
+            block_number: u64::from_be_bytes(bytes[..8].try_into().unwrap()),
+            transaction_index: u32::from_be_bytes(bytes[8..12].try_into().unwrap()),
+            message_index: u32::from_be_bytes(bytes[12..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Filter on the messages sent from L2 to L1
+#[derive(Debug, Clone, Default)]
+pub struct L2MessageFilter {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Only keep messages sent by this contract
+    pub from_address: Option<StarkFelt>,
+    /// Only keep messages sent to this L1 address
+    pub to_address: Option<H160>,
+}
+
+/// A message sent from L2 to L1 along with its consumption status on L1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2MessageStatus {
+    pub message: L2MessageRecord,
+    /// Whether the message has been consumed on L1, `None` if the indexed L1 events do not go far
+    /// enough to tell
+    pub consumed_on_l1: Option<bool>,
+}
+
+/// Allow interaction with the messaging db
+///
+/// The messaging db indexes the messages sent from L1 to L2 and links each of them to the L1
+/// handler transaction which consumed it on L2. Messages are keyed by nonce, which is also the
+/// nonce of the matching L1 handler transaction.
+///
+/// It also indexes the messages sent from L2 to L1, by position in the chain, recipient and sender.
+/// Messages with the same content share the same hash: a message is considered consumed on L1 once
+/// the core contract consumed that hash more times than there were earlier messages with that hash.
+pub struct MessagingDb {
+    db: Arc<DB>,
+}
+
+impl MessagingDb {
+    pub(crate) fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Store a message sent from L1
+    pub fn write_l1_message(&self, message: &L1MessageRecord) -> Result<(), DbError> {
+        let messages_col = self.db.get_column(Column::L1Messages);
+        let transaction_messages_col = self.db.get_column(Column::L1TransactionMessages);
+        let message_contents_col = self.db.get_column(Column::L1MessageContents);
+
+        let mut nonces = self.l1_message_nonces(message.l1_transaction_hash)?;
+        if !nonces.contains(&message.nonce) {
+            nonces.push(message.nonce);
+        }
+        let content_key = L1MessageRecord::content_key(
+            &message.from_address,
+            &message.to_address,
+            &message.selector,
+            &message.payload,
+        );
+
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        transaction.put_cf(&messages_col, message.nonce.0, message.encode());
+        transaction.put_cf(&transaction_messages_col, message.l1_transaction_hash.encode(), nonces.encode());
+        transaction.put_cf(&message_contents_col, content_key, message.nonce.encode());
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    /// Remove the message with the given nonce, if it was sent by the given L1 transaction
+    pub fn remove_l1_message(&self, nonce: StarkFelt, l1_transaction_hash: H256) -> Result<(), DbError> {
+        let Some(message) = self.l1_message(nonce)? else {
+            return Ok(());
+        };
+        if message.l1_transaction_hash != l1_transaction_hash {
+            return Ok(());
+        }
+
+        let messages_col = self.db.get_column(Column::L1Messages);
+        let transaction_messages_col = self.db.get_column(Column::L1TransactionMessages);
+        let message_contents_col = self.db.get_column(Column::L1MessageContents);
+
+        let nonces: Vec<_> =
+            self.l1_message_nonces(l1_transaction_hash)?.into_iter().filter(|other| *other != nonce).collect();
+        let content_key = L1MessageRecord::content_key(
+            &message.from_address,
+            &message.to_address,
+            &message.selector,
+            &message.payload,
+        );
+
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        transaction.delete_cf(&messages_col, nonce.0);
+        if nonces.is_empty() {
+            transaction.delete_cf(&transaction_messages_col, l1_transaction_hash.encode());
+        } else {
+            transaction.put_cf(&transaction_messages_col, l1_transaction_hash.encode(), nonces.encode());
+        }
+        if self.db.get_cf(&message_contents_col, content_key)? == Some(nonce.encode()) {
+            transaction.delete_cf(&message_contents_col, content_key);
+        }
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    /// Retrieve the message with the given nonce, if any
+    pub fn l1_message(&self, nonce: StarkFelt) -> Result<Option<L1MessageRecord>, DbError> {
+        let messages_col = self.db.get_column(Column::L1Messages);
+
+        match self.db.get_cf(&messages_col, nonce.0)? {
+            Some(raw) => Ok(Some(L1MessageRecord::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the message with the highest nonce, if any
+    pub fn last_l1_message(&self) -> Result<Option<L1MessageRecord>, DbError> {
+        let messages_col = self.db.get_column(Column::L1Messages);
+
+        match self.db.iterator_cf(&messages_col, IteratorMode::End).next() {
+            Some(entry) => {
+                let (_, raw) = entry?;
+                Ok(Some(L1MessageRecord::decode(&mut &raw[..])?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the latest message sent with the given content, if any
+    pub fn l1_message_from_content(
+        &self,
+        from_address: &H160,
+        to_address: &StarkFelt,
+        selector: &StarkFelt,
+        payload: &[StarkFelt],
+    ) -> Result<Option<L1MessageRecord>, DbError> {
+        let message_contents_col = self.db.get_column(Column::L1MessageContents);
+        let content_key = L1MessageRecord::content_key(from_address, to_address, selector, payload);
+
+        match self.db.get_cf(&message_contents_col, content_key)? {
+            Some(raw) => self.l1_message(StarkFelt::decode(&mut &raw[..])?),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the messages sent by the given L1 transaction, in emission order
+    pub fn l1_messages_from_l1_transaction(&self, l1_transaction_hash: H256) -> Result<Vec<L1MessageRecord>, DbError> {
+        self.l1_message_nonces(l1_transaction_hash)?
+            .into_iter()
+            .filter_map(|nonce| self.l1_message(nonce).transpose())
+            .collect()
+    }
+
+    fn l1_message_nonces(&self, l1_transaction_hash: H256) -> Result<Vec<StarkFelt>, DbError> {
+        let transaction_messages_col = self.db.get_column(Column::L1TransactionMessages);
+
+        match self.db.get_cf(&transaction_messages_col, l1_transaction_hash.encode())? {
+            Some(raw) => Ok(Vec::<StarkFelt>::decode(&mut &raw[..])?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Link messages, by nonce, to the hash of the L1 handler transaction which consumed them
+    pub fn write_l1_handler_transactions(&self, l1_handlers: &[(StarkFelt, StarkHash)]) -> Result<(), DbError> {
+        let l1_handlers_col = self.db.get_column(Column::L1HandlerTransactions);
+
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        for (nonce, transaction_hash) in l1_handlers {
+            transaction.put_cf(&l1_handlers_col, nonce.0, transaction_hash.encode());
+        }
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    /// Retrieve the hash of the L1 handler transaction which consumed the message with the given
+    /// nonce, if it has been consumed
+    pub fn l1_handler_transaction(&self, nonce: StarkFelt) -> Result<Option<StarkHash>, DbError> {
+        let l1_handlers_col = self.db.get_column(Column::L1HandlerTransactions);
+
+        match self.db.get_cf(&l1_handlers_col, nonce.0)? {
+            Some(raw) => Ok(Some(StarkHash::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Index the messages sent from L2 to L1 by a block
+    pub fn write_l2_messages(&self, messages: &[L2MessageRecord]) -> Result<(), DbError> {
+        let messages_col = self.db.get_column(Column::L2Messages);
+        let by_recipient_col = self.db.get_column(Column::L2MessagesByRecipient);
+        let by_sender_col = self.db.get_column(Column::L2MessagesBySender);
+        let hash_counts_col = self.db.get_column(Column::L2MessageHashCounts);
+
+        let mut hash_counts = std::collections::HashMap::new();
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        for message in messages {
+            let key = message.key().to_bytes();
+            // re-importing a block must not count its messages twice
+            if self.db.get_cf(&messages_col, key)?.is_some() {
+                continue;
+            }
+
+            let message_hash = message.message_hash();
+            let ordinal = match hash_counts.get(&message_hash) {
+                Some(count) => *count,
+                None => self.l2_message_hash_count(&message_hash)?,
+            };
+            hash_counts.insert(message_hash, ordinal + 1);
+
+            transaction.put_cf(&messages_col, key, (message, ordinal).encode());
+            transaction.put_cf(&by_recipient_col, [message.to_address.as_bytes(), &key[..]].concat(), b"");
+            transaction.put_cf(&by_sender_col, [&message.from_address.0[..], &key[..]].concat(), b"");
+        }
+        for (message_hash, count) in hash_counts {
+            transaction.put_cf(&hash_counts_col, message_hash.as_bytes(), count.encode());
+        }
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    fn l2_message_hash_count(&self, message_hash: &H256) -> Result<u64, DbError> {
+        let hash_counts_col = self.db.get_column(Column::L2MessageHashCounts);
+
+        match self.db.get_cf(&hash_counts_col, message_hash.as_bytes())? {
+            Some(raw) => Ok(u64::decode(&mut &raw[..])?),
+            None => Ok(0),
+        }
+    }
+
+    /// Record that a message was consumed on L1, identified by the L1 log which consumed it, and
+    /// that the events were indexed up to the L1 block of that log
+    pub fn write_l1_consumed_message(
+        &self,
+        message_hash: H256,
+        l1_block_number: u64,
+        l1_transaction_hash: H256,
+        log_index: u64,
+    ) -> Result<(), DbError> {
+        let consumed_col = self.db.get_column(Column::L1ConsumedMessages);
+        let meta_col = self.db.get_column(Column::Meta);
+
+        let key = [message_hash.as_bytes(), l1_transaction_hash.as_bytes(), &log_index.to_be_bytes()[..]].concat();
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        transaction.put_cf(&consumed_col, key, b"");
+        transaction.put_cf(&meta_col, L1_CONSUMED_MESSAGES_CURSOR, l1_block_number.encode());
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    /// Remove a consumption of a message on L1 dropped by an L1 reorg, moving the indexed events
+    /// back before the L1 block of its log
+    pub fn remove_l1_consumed_message(
+        &self,
+        message_hash: H256,
+        l1_block_number: u64,
+        l1_transaction_hash: H256,
+        log_index: u64,
+    ) -> Result<(), DbError> {
+        let consumed_col = self.db.get_column(Column::L1ConsumedMessages);
+        let meta_col = self.db.get_column(Column::Meta);
+
+        let key = [message_hash.as_bytes(), l1_transaction_hash.as_bytes(), &log_index.to_be_bytes()[..]].concat();
+        let mut transaction: WriteBatchWithTransaction<true> = Default::default();
+        transaction.delete_cf(&consumed_col, key);
+        if self.l1_consumed_messages_cursor()?.is_some_and(|cursor| cursor >= l1_block_number) {
+            transaction.put_cf(&meta_col, L1_CONSUMED_MESSAGES_CURSOR, l1_block_number.saturating_sub(1).encode());
+        }
+        self.db.write(transaction)?;
+
+        Ok(())
+    }
+
+    /// Retrieve the last L1 block whose ConsumedMessageToL1 events were indexed, if any
+    pub fn l1_consumed_messages_cursor(&self) -> Result<Option<u64>, DbError> {
+        match self.db.get_cf(&self.db.get_column(Column::Meta), L1_CONSUMED_MESSAGES_CURSOR)? {
+            Some(raw) => Ok(Some(u64::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of times a message hash was consumed on L1
+    fn l1_consumed_count(&self, message_hash: &H256) -> Result<u64, DbError> {
+        let consumed_col = self.db.get_column(Column::L1ConsumedMessages);
+
+        let mut options = ReadOptions::default();
+        options.set_prefix_same_as_start(true);
+        let mode = IteratorMode::From(message_hash.as_bytes(), Direction::Forward);
+
+        let mut count = 0;
+        for entry in self.db.iterator_cf_opt(&consumed_col, options, mode) {
+            let (key, _) = entry?;
+            if !key.starts_with(message_hash.as_bytes()) {
+                break;
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Retrieve the messages sent from L2 to L1 matching the filter, in chain order
+    ///
+    /// At most `limit` messages are returned, starting at `start` if provided. The key of the next
+    /// matching message is returned along with them if there are more.
+    ///
+    /// A message is known not to be consumed on L1 once its block was verified on L1 and the
+    /// consumed messages were indexed past that L1 block: it cannot have been consumed before.
+    pub fn l2_messages(
+        &self,
+        filter: &L2MessageFilter,
+        start: Option<L2MessageKey>,
+        limit: usize,
+    ) -> Result<(Vec<L2MessageStatus>, Option<L2MessageKey>), DbError> {
+        let messages_col = self.db.get_column(Column::L2Messages);
+        let first_key = L2MessageKey::block_start(filter.from_block);
+        let start = start.map_or(first_key, |start| start.max(first_key));
+
+        // the most selective index available is used to walk over the message keys
+        let (column, prefix): (_, Vec<u8>) = match (&filter.to_address, &filter.from_address) {
+            (Some(to_address), _) => (Column::L2MessagesByRecipient, to_address.as_bytes().to_vec()),
+            (None, Some(from_address)) => (Column::L2MessagesBySender, from_address.0.to_vec()),
+            (None, None) => (Column::L2Messages, Vec::new()),
+        };
+        let index_col = self.db.get_column(column);
+
+        let mut options = ReadOptions::default();
+        options.set_prefix_same_as_start(!prefix.is_empty());
+        let seek = [&prefix[..], &start.to_bytes()[..]].concat();
+        let iter = self.db.iterator_cf_opt(&index_col, options, IteratorMode::From(&seek, Direction::Forward));
+
+        let l1 = L1Db::new(Arc::clone(&self.db));
+        let consumed_cursor = self.l1_consumed_messages_cursor()?;
+
+        let mut messages = Vec::new();
+        for entry in iter {
+            let (index_key, raw) = entry?;
+            if !index_key.starts_with(&prefix) {
+                break;
+            }
+            let key = L2MessageKey::from_bytes(&index_key[prefix.len()..])?;
+            if key.block_number > filter.to_block {
+                break;
+            }
+            if messages.len() == limit {
+                return Ok((messages, Some(key)));
+            }
+
+            let raw = match column {
+                Column::L2Messages => raw.to_vec(),
+                _ => self
+                    .db
+                    .get_cf(&messages_col, key.to_bytes())?
+                    .ok_or_else(|| DbError::ValueNotInitialized(Column::L2Messages, format!("{key:?}")))?,
+            };
+            let (message, ordinal) = <(L2MessageRecord, u64)>::decode(&mut &raw[..])?;
+            if filter.from_address.is_some_and(|from_address| from_address != message.from_address) {
+                continue;
+            }
+
+            let consumed_on_l1 = if self.l1_consumed_count(&message.message_hash())? > ordinal {
+                Some(true)
+            } else {
+                let verified_on_l1 = l1.verifying_state_update(message.block_number)?;
+                match (verified_on_l1, consumed_cursor) {
+                    (Some(state_update), Some(cursor)) if cursor >= state_update.l1_block_number => Some(false),
+                    _ => None,
+                }
+            };
+            messages.push(L2MessageStatus { message, consumed_on_l1 });
+        }
+
+        Ok((messages, None))
+    }
+}
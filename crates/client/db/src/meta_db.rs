@@ -18,6 +18,9 @@ pub struct MetaDb {
 const CURRENT_SYNCING_TIPS: &[u8] = b"CURRENT_SYNCING_TIPS";
 const CURRENT_SYNC_BLOCK: &[u8] = b"CURRENT_SYNC_BLOCK";
 const LATEST_BLOCK_HASH_AND_NUMBER: &[u8] = b"LATEST_BLOCK_HASH_AND_NUMBER";
+const LAST_DA_VERIFIED_BLOCK: &[u8] = b"LAST_DA_VERIFIED_BLOCK";
+const CHECKPOINT_BLOCK: &[u8] = b"CHECKPOINT_BLOCK";
+const LAST_ROOT_VERIFIED_BLOCK: &[u8] = b"LAST_ROOT_VERIFIED_BLOCK";
 
 impl MetaDb {
     pub(crate) fn new(db: Arc<DB>) -> Self {
@@ -80,4 +83,46 @@ impl MetaDb {
         )?;
         Ok(())
     }
+
+    /// Retrieve the last block whose state was checked against the data published on L1, if any
+    pub fn last_da_verified_block(&self) -> Result<Option<u64>, DbError> {
+        match self.db.get_cf(&self.db.get_column(Column::Meta), LAST_DA_VERIFIED_BLOCK)? {
+            Some(raw) => Ok(Some(u64::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the last block whose state was checked against the data published on L1
+    pub fn set_last_da_verified_block(&self, block_number: u64) -> Result<(), DbError> {
+        self.db.put_cf(&self.db.get_column(Column::Meta), LAST_DA_VERIFIED_BLOCK, block_number.encode())?;
+        Ok(())
+    }
+
+    /// Retrieve the block of the trusted checkpoint the imported state starts from, if any
+    pub fn checkpoint_block(&self) -> Result<Option<u64>, DbError> {
+        match self.db.get_cf(&self.db.get_column(Column::Meta), CHECKPOINT_BLOCK)? {
+            Some(raw) => Ok(Some(u64::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the block of the trusted checkpoint the imported state starts from
+    pub fn set_checkpoint_block(&self, block_number: u64) -> Result<(), DbError> {
+        self.db.put_cf(&self.db.get_column(Column::Meta), CHECKPOINT_BLOCK, block_number.encode())?;
+        Ok(())
+    }
+
+    /// Retrieve the last block whose state root was computed from the state tries and verified
+    pub fn last_root_verified_block(&self) -> Result<Option<u64>, DbError> {
+        match self.db.get_cf(&self.db.get_column(Column::Meta), LAST_ROOT_VERIFIED_BLOCK)? {
+            Some(raw) => Ok(Some(u64::decode(&mut &raw[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the last block whose state root was computed from the state tries and verified
+    pub fn set_last_root_verified_block(&self, block_number: u64) -> Result<(), DbError> {
+        self.db.put_cf(&self.db.get_column(Column::Meta), LAST_ROOT_VERIFIED_BLOCK, block_number.encode())?;
+        Ok(())
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Read, Write};
 use std::sync::Arc;
 
@@ -6,13 +6,20 @@ use anyhow::anyhow;
 use blockifier::execution::contract_class::{
     ContractClass as ContractClassBlockifier, ContractClassV0, ContractClassV0Inner, ContractClassV1, EntryPointV1,
 };
+use cairo_lang_starknet_classes::casm_contract_class::{
+    CasmContractClass, CasmContractEntryPoint, CasmContractEntryPoints,
+};
+use cairo_lang_starknet_classes::compiler_version::current_compiler_version_id;
+use cairo_lang_utils::bigint::BigUintAsHex;
+use cairo_vm::felt::Felt252;
 use cairo_vm::types::program::Program;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use indexmap::IndexMap;
 use mp_felt::Felt252Wrapper;
 use mp_transactions::from_broadcasted_transactions::flattened_sierra_to_casm_contract_class;
-use parity_scale_codec::{Decode, Encode};
+use num_bigint::BigUint;
+use parity_scale_codec::{Decode, Encode, Input};
 use starknet_api::core::{ClassHash, EntryPointSelector, Nonce};
 use starknet_api::deprecated_contract_class::{EntryPoint, EntryPointOffset, EntryPointType};
 use starknet_api::hash::StarkFelt;
@@ -22,13 +29,34 @@ use starknet_core::types::{
     LegacyEntryPointsByType, SierraEntryPoint,
 };
 
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug, Encode)]
 pub struct StorageContractClassData {
     pub contract_class: ContractClassBlockifier,
     pub abi: ContractAbi,
     pub sierra_program_length: u64,
     pub abi_length: u64,
     pub block_number: u64,
+    /// Version of the compiler the CASM of a Sierra class was compiled with, none for legacy
+    /// classes
+    pub compiler_version: Option<String>,
+}
+
+/// Records written before the compiler version was stored end right after the block number, so
+/// the compiler version is only decoded when the input has not been consumed yet.
+impl Decode for StorageContractClassData {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, parity_scale_codec::Error> {
+        let contract_class = ContractClassBlockifier::decode(input)?;
+        let abi = ContractAbi::decode(input)?;
+        let sierra_program_length = u64::decode(input)?;
+        let abi_length = u64::decode(input)?;
+        let block_number = u64::decode(input)?;
+        let compiler_version = match input.remaining_len()? {
+            Some(0) => None,
+            _ => Option::<String>::decode(input)?,
+        };
+
+        Ok(Self { contract_class, abi, sierra_program_length, abi_length, block_number, compiler_version })
+    }
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -52,6 +80,7 @@ pub struct ContractClassWrapper {
     pub abi: ContractAbi,
     pub sierra_program_length: u64,
     pub abi_length: u64,
+    pub compiler_version: Option<String>,
 }
 // TODO: move this somewhere more sensible? Would be a good idea to decouple
 // publicly available storage data from wrapper classes
@@ -191,6 +220,75 @@ pub fn from_contract_class_sierra(contract_class: FlattenedSierraClass) -> anyho
     anyhow::Ok(ContractClassBlockifier::V1(blockifier_contract))
 }
 
+/// Version of the compiler this node compiles Sierra classes with.
+///
+/// Classes stored before their compiler version was recorded were all compiled by the node
+/// itself, so this is the version their CASM is served with.
+pub fn local_compiler_version() -> String {
+    current_compiler_version_id().to_string()
+}
+
+/// Converts a [ContractClassV1] back to the [CasmContractClass] it was compiled from, with the
+/// version of the compiler stored alongside the class
+pub fn to_casm_contract_class(
+    contract_class: &ContractClassV1,
+    compiler_version: String,
+) -> anyhow::Result<CasmContractClass> {
+    let bytecode = contract_class
+        .program
+        .iter_data()
+        .map(|maybe_relocatable| {
+            maybe_relocatable
+                .get_int_ref()
+                .map(|felt| BigUintAsHex { value: felt.to_biguint() })
+                .ok_or(anyhow!("Relocatable value in compiled class bytecode"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // The blockifier keeps the hints of each pc under their json representation
+    let ordered_program = order_program(&contract_class.program);
+    let hints = BTreeMap::<usize, Vec<HintParamsWrapper>>::from(&ordered_program.shared_program_data.hints_collection)
+        .into_iter()
+        .map(|(pc, hint_params)| {
+            let hints = hint_params
+                .iter()
+                .map(|hint_param| {
+                    contract_class.hints.get(&hint_param.code).cloned().ok_or(anyhow!("Unknown hint at pc {pc}"))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            anyhow::Ok((pc, hints))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let to_casm_entry_points = |entry_point_type: EntryPointType| {
+        contract_class
+            .entry_points_by_type
+            .get(&entry_point_type)
+            .map(|entries| entries.iter().map(to_casm_entry_point).collect())
+            .unwrap_or_default()
+    };
+    let entry_points_by_type = CasmContractEntryPoints {
+        external: to_casm_entry_points(EntryPointType::External),
+        l1_handler: to_casm_entry_points(EntryPointType::L1Handler),
+        constructor: to_casm_entry_points(EntryPointType::Constructor),
+    };
+
+    // `NestedIntList` is not exported by cairo-lang, so the segment lengths go through their
+    // common json representation
+    let bytecode_segment_lengths =
+        serde_json::from_value(serde_json::to_value(contract_class.bytecode_segment_lengths())?)?;
+
+    Ok(CasmContractClass {
+        prime: Felt252::prime(),
+        compiler_version,
+        bytecode,
+        bytecode_segment_lengths,
+        hints,
+        pythonic_hints: None,
+        entry_points_by_type,
+    })
+}
+
 pub fn to_contract_class_cairo(
     contract_class: &ContractClassV0,
     abi: Option<Vec<LegacyContractAbiEntry>>,
@@ -320,6 +418,14 @@ fn to_entry_point(entry_point: EntryPointV1, index: u64) -> Result<SierraEntryPo
     Ok(SierraEntryPoint { selector, function_idx })
 }
 
+/// Returns a [CasmContractEntryPoint] (cairo-lang) from a [EntryPointV1]
+/// (blockifier)
+fn to_casm_entry_point(entry_point: &EntryPointV1) -> CasmContractEntryPoint {
+    let selector = BigUint::from_bytes_be(&entry_point.selector.0.0);
+    let offset = entry_point.offset.0;
+    CasmContractEntryPoint { selector, offset, builtins: entry_point.builtins.clone() }
+}
+
 /// Returns a [EntryPoint] (starknet-api) from a [LegacyContractEntryPoint]
 /// (starknet-rs)
 fn from_legacy_entry_point(entry_point: &LegacyContractEntryPoint) -> EntryPoint {
@@ -333,7 +439,7 @@ use starknet_core::types::{
     LegacyStructAbiEntry, LegacyStructAbiType, LegacyStructMember, LegacyTypedParameter,
 };
 
-use crate::storage_handler::primitives::program::order_program;
+use crate::storage_handler::primitives::program::{order_program, HintParamsWrapper};
 use crate::storage_handler::primitives::program_serializer::serialize_program;
 
 // Wrapper Class conversion
@@ -342,21 +448,36 @@ impl TryFrom<ContractClassCore> for ContractClassWrapper {
     type Error = anyhow::Error;
 
     fn try_from(contract_class: ContractClassCore) -> Result<Self, Self::Error> {
-        let contract = from_rpc_contract_class(contract_class.clone())?;
-        let abi = match &contract_class {
+        match contract_class {
+            ContractClassCore::Sierra(class_sierra) => {
+                let casm_contract_class = flattened_sierra_to_casm_contract_class(&Arc::new(class_sierra.clone()))?;
+                Self::from_sierra_and_casm(class_sierra, casm_contract_class)
+            }
             ContractClassCore::Legacy(class_cairo) => {
-                ContractAbi::Cairo(from_rpc_contract_abi(class_cairo.abi.clone()))
+                let abi = ContractAbi::Cairo(from_rpc_contract_abi(class_cairo.abi.clone()));
+                let abi_length = abi.length() as u64;
+                let contract = from_contract_class_cairo(class_cairo)?;
+
+                Ok(Self { contract, abi, sierra_program_length: 0, abi_length, compiler_version: None })
             }
-            ContractClassCore::Sierra(class_sierra) => ContractAbi::Sierra(class_sierra.abi.clone()),
-        };
+        }
+    }
+}
 
-        let sierra_program_length = match contract_class {
-            ContractClassCore::Sierra(class_sierra) => class_sierra.sierra_program.len(),
-            ContractClassCore::Legacy(_) => 0,
-        } as u64;
+impl ContractClassWrapper {
+    /// Builds the wrapper of a Sierra class which has already been compiled to CASM, avoiding a
+    /// second compilation.
+    pub fn from_sierra_and_casm(
+        class_sierra: FlattenedSierraClass,
+        casm_contract_class: CasmContractClass,
+    ) -> anyhow::Result<Self> {
+        let compiler_version = Some(casm_contract_class.compiler_version.clone());
+        let contract = ContractClassBlockifier::V1(ContractClassV1::try_from(casm_contract_class)?);
+        let sierra_program_length = class_sierra.sierra_program.len() as u64;
+        let abi = ContractAbi::Sierra(class_sierra.abi);
         let abi_length = abi.length() as u64;
 
-        Ok(Self { contract, abi, sierra_program_length, abi_length })
+        Ok(Self { contract, abi, sierra_program_length, abi_length, compiler_version })
     }
 }
 
@@ -586,3 +707,58 @@ impl From<AbiTypedParameterWrapper> for LegacyTypedParameter {
         LegacyTypedParameter { name: abi_typed_parameter.name, r#type: abi_typed_parameter.r#type }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mp_transactions::from_broadcasted_transactions::get_casm_contract_class_hash;
+
+    use super::*;
+
+    /// `hello_starknet` contract of the cairo-lang 2.6.0 test data, and the CASM its declaration
+    /// was compiled to
+    const SIERRA_CLASS: &str = include_str!("../../../resources/hello_starknet.contract_class.json");
+    const CASM_CLASS: &str = include_str!("../../../resources/hello_starknet.compiled_contract_class.json");
+
+    #[test]
+    fn test_casm_round_trip() {
+        let class_sierra: FlattenedSierraClass = serde_json::from_str(SIERRA_CLASS).unwrap();
+        let declared_casm: CasmContractClass = serde_json::from_str(CASM_CLASS).unwrap();
+        let declared_compiled_class_hash = get_casm_contract_class_hash(&declared_casm).unwrap();
+
+        let ContractClassWrapper { contract, compiler_version, .. } =
+            ContractClassWrapper::try_from(ContractClassCore::Sierra(class_sierra)).unwrap();
+        let ContractClassBlockifier::V1(contract) = contract else { panic!("Sierra class compiled to a legacy class") };
+        let casm = to_casm_contract_class(&contract, compiler_version.unwrap()).unwrap();
+
+        assert_eq!(casm.compiler_version, declared_casm.compiler_version);
+        assert_eq!(casm.bytecode_segment_lengths, declared_casm.bytecode_segment_lengths);
+        assert_eq!(get_casm_contract_class_hash(&casm).unwrap(), declared_compiled_class_hash);
+    }
+
+    #[test]
+    fn test_decode_record_without_compiler_version() {
+        let class_sierra: FlattenedSierraClass = serde_json::from_str(SIERRA_CLASS).unwrap();
+        let ContractClassWrapper { contract, abi, sierra_program_length, abi_length, compiler_version } =
+            ContractClassWrapper::try_from(ContractClassCore::Sierra(class_sierra)).unwrap();
+        let compiler_version = compiler_version.unwrap();
+
+        // Layout of the records written before the compiler version was stored
+        let record = (&contract, &abi, sierra_program_length, abi_length, 42u64).encode();
+        let decoded = StorageContractClassData::decode(&mut &record[..]).unwrap();
+        assert_eq!(decoded.block_number, 42);
+        assert_eq!(decoded.compiler_version, None);
+
+        let record = StorageContractClassData {
+            contract_class: contract,
+            abi,
+            sierra_program_length,
+            abi_length,
+            block_number: 42,
+            compiler_version: Some(compiler_version.clone()),
+        }
+        .encode();
+        let decoded = StorageContractClassData::decode(&mut &record[..]).unwrap();
+        assert_eq!(decoded.block_number, 42);
+        assert_eq!(decoded.compiler_version, Some(compiler_version));
+    }
+}
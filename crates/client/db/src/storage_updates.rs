@@ -16,7 +16,7 @@ use storage_handler::primitives::contract_class::{
 
 use crate::mapping_db::MappingCommitment;
 use crate::storage_handler::{self, DeoxysStorageError, StorageViewMut};
-use crate::{DbError, DeoxysBackend};
+use crate::{DbError, DeoxysBackend, L2MessageRecord};
 
 pub async fn store_state_update(block_number: u64, state_update: StateUpdate) -> Result<(), DeoxysStorageError> {
     let state_diff = state_update.state_diff.clone();
@@ -111,13 +111,25 @@ pub async fn store_class_update(block_number: u64, class_update: ClassUpdateWrap
 
     class_update.0.into_iter().for_each(
         |ContractClassData { hash: class_hash, contract_class: contract_class_wrapper }| {
-            let ContractClassWrapper { contract: contract_class, abi, sierra_program_length, abi_length } =
-                contract_class_wrapper;
+            let ContractClassWrapper {
+                contract: contract_class,
+                abi,
+                sierra_program_length,
+                abi_length,
+                compiler_version,
+            } = contract_class_wrapper;
 
             handler_contract_class_data_mut
                 .insert(
                     class_hash,
-                    StorageContractClassData { contract_class, abi, sierra_program_length, abi_length, block_number },
+                    StorageContractClassData {
+                        contract_class,
+                        abi,
+                        sierra_program_length,
+                        abi_length,
+                        block_number,
+                        compiler_version,
+                    },
                 )
                 .unwrap();
         },
@@ -151,13 +163,31 @@ pub async fn store_mapping(
     starknet_block_hash: StarkFelt,
     substrate_block_hash: H256,
     starknet_transaction_hashes: Vec<StarkFelt>,
+    reverted_transactions: Vec<(StarkFelt, String)>,
 ) -> Result<(), DbError> {
     let mapping_commitment = MappingCommitment {
         block_number,
         block_hash: substrate_block_hash,
         starknet_block_hash,
         starknet_transaction_hashes,
+        reverted_transactions,
     };
 
     DeoxysBackend::mapping().write_hashes(mapping_commitment)
 }
+
+pub async fn store_l1_handler_transactions(l1_handlers: Vec<(StarkFelt, StarkFelt)>) -> Result<(), DbError> {
+    if l1_handlers.is_empty() {
+        return Ok(());
+    }
+
+    DeoxysBackend::messaging().write_l1_handler_transactions(&l1_handlers)
+}
+
+pub async fn store_l2_to_l1_messages(messages: Vec<L2MessageRecord>) -> Result<(), DbError> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    DeoxysBackend::messaging().write_l2_messages(&messages)
+}
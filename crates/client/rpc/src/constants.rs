@@ -2,3 +2,13 @@
 pub const MAX_EVENTS_KEYS: usize = 100;
 /// Maximum number of events that can be fetched in a single chunk for the `get_events` RPC.
 pub const MAX_EVENTS_CHUNK_SIZE: usize = 1000;
+/// Maximum number of messages that can be fetched in a single chunk for the
+/// `deoxys_getMessagesToL1` RPC.
+pub const MAX_MESSAGES_TO_L1_CHUNK_SIZE: usize = 1000;
+/// Maximum number of calls that can be made at once with the `deoxys_multicall` RPC.
+pub const MAX_MULTICALL_CALLS: usize = 1000;
+/// Maximum number of blocks a subscription can go back to send the blocks already stored.
+pub const MAX_SUBSCRIPTION_BLOCKS_BACK: u64 = 1024;
+/// Maximum number of sender addresses that can be passed to the
+/// `starknet_subscribePendingTransactions` RPC.
+pub const MAX_SUBSCRIPTION_SENDER_ADDRESSES: usize = 1024;
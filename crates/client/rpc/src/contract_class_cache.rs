@@ -0,0 +1,270 @@
+//! Process-wide cache of the compiled classes used for execution.
+//!
+//! Decoding a compiled class from the database is expensive, and the same classes are executed
+//! over and over by calls, fee estimations, simulations and traces. The decoded classes are kept
+//! in a least recently used cache, bounded by a memory budget.
+
+use std::mem::{size_of, size_of_val};
+use std::sync::{Mutex, MutexGuard};
+
+use blockifier::execution::contract_class::{ContractClass, ContractClassV0Inner, ContractClassV1Inner};
+use blockifier::state::cached_state::GlobalContractCache;
+use cairo_vm::types::program::Program;
+use cairo_vm::types::relocatable::MaybeRelocatable;
+use lazy_static::lazy_static;
+use lru::LruCache;
+use mc_db::storage_handler::{self, DeoxysStorageError, StorageView};
+use prometheus_endpoint::prometheus::{IntCounter, IntGauge};
+use prometheus_endpoint::{register, PrometheusError, Registry};
+use starknet_api::core::ClassHash;
+
+/// Memory budget of the cache when none is configured, in bytes
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct ContractClassCacheMetrics {
+    pub hits: IntCounter,
+    pub misses: IntCounter,
+    pub size_bytes: IntGauge,
+    pub classes: IntGauge,
+}
+
+impl ContractClassCacheMetrics {
+    pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            hits: register(
+                IntCounter::new(
+                    "deoxys_contract_class_cache_hits",
+                    "Counter of the compiled classes found in the execution class cache",
+                )?,
+                registry,
+            )?,
+            misses: register(
+                IntCounter::new(
+                    "deoxys_contract_class_cache_misses",
+                    "Counter of the compiled classes read from the database by execution requests",
+                )?,
+                registry,
+            )?,
+            size_bytes: register(
+                IntGauge::new(
+                    "deoxys_contract_class_cache_size_bytes",
+                    "Gauge for the estimated memory used by the execution class cache",
+                )?,
+                registry,
+            )?,
+            classes: register(
+                IntGauge::new(
+                    "deoxys_contract_class_cache_classes",
+                    "Gauge for the number of cached compiled classes",
+                )?,
+                registry,
+            )?,
+        })
+    }
+}
+
+struct ContractClassCache {
+    /// Compiled classes along with their estimated size
+    classes: LruCache<ClassHash, (ContractClass, usize)>,
+    size: usize,
+    memory_budget: usize,
+    metrics: Option<ContractClassCacheMetrics>,
+}
+
+impl ContractClassCache {
+    fn get(&mut self, class_hash: &ClassHash) -> Option<ContractClass> {
+        let contract_class = self.classes.get(class_hash).map(|(contract_class, _)| contract_class.clone());
+        if let Some(metrics) = &self.metrics {
+            match &contract_class {
+                Some(_) => metrics.hits.inc(),
+                None => metrics.misses.inc(),
+            }
+        }
+        contract_class
+    }
+
+    fn insert(&mut self, class_hash: ClassHash, contract_class: ContractClass) {
+        let size = memory_size(&contract_class);
+        if size > self.memory_budget {
+            return;
+        }
+        if let Some((_, replaced_size)) = self.classes.put(class_hash, (contract_class, size)) {
+            self.size -= replaced_size;
+        }
+        self.size += size;
+        self.evict();
+    }
+
+    /// Evicts the least recently used classes until the cache fits in its memory budget
+    fn evict(&mut self) {
+        while self.size > self.memory_budget {
+            let Some((_, (_, size))) = self.classes.pop_lru() else {
+                break;
+            };
+            self.size -= size;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.size_bytes.set(self.size as i64);
+            metrics.classes.set(self.classes.len() as i64);
+        }
+    }
+}
+
+/// Memory used by a compiled class, in bytes
+///
+/// The size of a class is the sum of the sizes of its allocations: the words of its program,
+/// along with the limbs of each felt which are allocated on their own, its identifiers, hints and
+/// entry points. This is several times its encoded size.
+fn memory_size(contract_class: &ContractClass) -> usize {
+    match contract_class {
+        ContractClass::V0(contract_class) => {
+            size_of::<ContractClassV0Inner>()
+                + program_memory_size(&contract_class.program)
+                + contract_class
+                    .entry_points_by_type
+                    .values()
+                    .map(|entry_points| size_of_val(entry_points.as_slice()))
+                    .sum::<usize>()
+        }
+        ContractClass::V1(contract_class) => {
+            size_of::<ContractClassV1Inner>()
+                + program_memory_size(&contract_class.program)
+                + contract_class.hints.iter().map(|(code, hint)| code.len() + size_of_val(hint)).sum::<usize>()
+                + contract_class
+                    .entry_points_by_type
+                    .values()
+                    .flatten()
+                    .map(|entry_point| {
+                        size_of_val(entry_point) + entry_point.builtins.iter().map(String::len).sum::<usize>()
+                    })
+                    .sum::<usize>()
+        }
+    }
+}
+
+fn program_memory_size(program: &Program) -> usize {
+    let data = program
+        .iter_data()
+        .map(|word| match word {
+            MaybeRelocatable::Int(felt) => size_of_val(word) + felt.bits().div_ceil(64) as usize * size_of::<u64>(),
+            MaybeRelocatable::RelocatableValue(_) => size_of_val(word),
+        })
+        .sum::<usize>();
+    let identifiers =
+        program.iter_identifiers().map(|(name, identifier)| name.len() + size_of_val(identifier)).sum::<usize>();
+
+    data + identifiers
+}
+
+lazy_static! {
+    static ref CONTRACT_CLASS_CACHE: Mutex<ContractClassCache> = Mutex::new(ContractClassCache {
+        classes: LruCache::unbounded(),
+        size: 0,
+        memory_budget: DEFAULT_MEMORY_BUDGET,
+        metrics: None,
+    });
+
+    /// Class cache of the blockifier, shared by every execution request
+    ///
+    /// The blockifier only fills it with the classes of a block it has executed: it is left empty
+    /// by the rpc, since a class declared in a block is not declared at the previous ones.
+    static ref GLOBAL_CONTRACT_CACHE: GlobalContractCache = GlobalContractCache::new(16);
+}
+
+/// Returns the class cache of the blockifier shared by every execution request
+pub fn global_contract_cache() -> GlobalContractCache {
+    GLOBAL_CONTRACT_CACHE.clone()
+}
+
+fn contract_class_cache() -> MutexGuard<'static, ContractClassCache> {
+    CONTRACT_CLASS_CACHE.lock().expect("Failed to acquire lock on CONTRACT_CLASS_CACHE")
+}
+
+/// Sets the memory budget of the cache, in bytes, and the metrics it reports to
+pub fn configure(memory_budget: usize, metrics: Option<ContractClassCacheMetrics>) {
+    let mut cache = contract_class_cache();
+    cache.memory_budget = memory_budget;
+    cache.metrics = metrics;
+    cache.evict();
+}
+
+/// Returns the compiled class with the given hash, from the cache or else from the database
+pub fn get_contract_class(class_hash: &ClassHash) -> Result<Option<ContractClass>, DeoxysStorageError> {
+    if let Some(contract_class) = contract_class_cache().get(class_hash) {
+        return Ok(Some(contract_class));
+    }
+
+    // The class is decoded without holding the lock, so a class missing from the cache may be read
+    // from the database by concurrent requests
+    let Some(contract_class_data) = storage_handler::contract_class_data().get(class_hash)? else {
+        return Ok(None);
+    };
+    let contract_class = contract_class_data.contract_class;
+    contract_class_cache().insert(*class_hash, contract_class.clone());
+
+    Ok(Some(contract_class))
+}
+
+#[cfg(test)]
+mod tests {
+    use blockifier::execution::contract_class::ContractClassV0;
+    use starknet_api::hash::StarkFelt;
+
+    use super::*;
+
+    fn class_hash(n: u128) -> ClassHash {
+        ClassHash(StarkFelt::from(n))
+    }
+
+    fn cache(classes: usize) -> (ContractClassCache, usize) {
+        let class_size = memory_size(&ContractClass::V0(ContractClassV0::default()));
+        let cache = ContractClassCache {
+            classes: LruCache::unbounded(),
+            size: 0,
+            memory_budget: classes * class_size,
+            metrics: None,
+        };
+        (cache, class_size)
+    }
+
+    #[test]
+    fn test_eviction() {
+        let (mut cache, class_size) = cache(2);
+
+        cache.insert(class_hash(1), ContractClass::V0(ContractClassV0::default()));
+        cache.insert(class_hash(2), ContractClass::V0(ContractClassV0::default()));
+        // the class 1 becomes the most recently used
+        assert!(cache.get(&class_hash(1)).is_some());
+        cache.insert(class_hash(3), ContractClass::V0(ContractClassV0::default()));
+
+        assert!(cache.get(&class_hash(1)).is_some());
+        assert!(cache.get(&class_hash(2)).is_none());
+        assert!(cache.get(&class_hash(3)).is_some());
+        assert_eq!(cache.size, 2 * class_size);
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let (mut cache, class_size) = cache(2);
+
+        // replacing a class does not count it twice
+        cache.insert(class_hash(1), ContractClass::V0(ContractClassV0::default()));
+        cache.insert(class_hash(1), ContractClass::V0(ContractClassV0::default()));
+        assert_eq!(cache.size, class_size);
+
+        // lowering the budget evicts the classes which no longer fit
+        cache.insert(class_hash(2), ContractClass::V0(ContractClassV0::default()));
+        cache.memory_budget = class_size;
+        cache.evict();
+        assert_eq!(cache.classes.len(), 1);
+        assert!(cache.get(&class_hash(2)).is_some());
+
+        // a class larger than the budget is not cached
+        cache.memory_budget = class_size - 1;
+        cache.evict();
+        cache.insert(class_hash(3), ContractClass::V0(ContractClassV0::default()));
+        assert!(cache.get(&class_hash(3)).is_none());
+        assert_eq!(cache.size, 0);
+    }
+}
@@ -1,9 +1,11 @@
-use jsonrpsee::types::error::{CallError, ErrorObject};
+use jsonrpsee::types::error::{CallError, ErrorObject, ErrorObjectOwned};
 use mc_db::storage_handler::DeoxysStorageError;
 use mc_db::DbError;
 use pallet_starknet_runtime_api::StarknetTransactionExecutionError;
 use starknet_core::types::StarknetError;
 
+use crate::types::MulticallError;
+
 // Comes from the RPC Spec:
 // https://github.com/starkware-libs/starknet-specs/blob/0e859ff905795f789f1dfd6f7340cdaf5015acc8/api/starknet_write_api.json#L227
 #[derive(thiserror::Error, Debug)]
@@ -35,7 +37,7 @@ pub enum StarknetRpcApiError {
     #[error("Failed to fetch pending transactions")]
     FailedToFetchPendingTransactions,
     #[error("Contract error")]
-    ContractError,
+    ContractErrorData { revert_error: String },
     #[error("Transaction execution error")]
     TxnExecutionError,
     #[error("Invalid contract class")]
@@ -49,7 +51,7 @@ pub enum StarknetRpcApiError {
     #[error("Account balance is smaller than the transaction's max_fee")]
     InsufficientAccountBalance,
     #[error("Account validation failed")]
-    ValidationFailure,
+    ValidationFailure { data: String },
     #[error("Compilation failed")]
     CompilationFailed,
     #[error("Contract class size is too large")]
@@ -72,6 +74,12 @@ pub enum StarknetRpcApiError {
     UnimplementedMethod,
     #[error("Too many storage keys requested")]
     ProofLimitExceeded,
+    #[error("Too many addresses in filter sender_address filter")]
+    TooManyAddressesInFilter,
+    #[error("Cannot go back more than 1024 blocks")]
+    TooManyBlocksBack,
+    #[error("Failed to compile the contract")]
+    CompilationError { data: String },
 }
 
 impl From<&StarknetRpcApiError> for i32 {
@@ -90,14 +98,14 @@ impl From<&StarknetRpcApiError> for i32 {
             StarknetRpcApiError::InvalidContinuationToken => 33,
             StarknetRpcApiError::TooManyKeysInFilter => 34,
             StarknetRpcApiError::FailedToFetchPendingTransactions => 38,
-            StarknetRpcApiError::ContractError => 40,
+            StarknetRpcApiError::ContractErrorData { revert_error: _ } => 40,
             StarknetRpcApiError::TxnExecutionError => 41,
             StarknetRpcApiError::InvalidContractClass => 50,
             StarknetRpcApiError::ClassAlreadyDeclared => 51,
             StarknetRpcApiError::InvalidTxnNonce => 52,
             StarknetRpcApiError::InsufficientMaxFee => 53,
             StarknetRpcApiError::InsufficientAccountBalance => 54,
-            StarknetRpcApiError::ValidationFailure => 55,
+            StarknetRpcApiError::ValidationFailure { data: _ } => 55,
             StarknetRpcApiError::CompilationFailed => 56,
             StarknetRpcApiError::ContractClassSizeTooLarge => 57,
             StarknetRpcApiError::NonAccount => 58,
@@ -109,19 +117,33 @@ impl From<&StarknetRpcApiError> for i32 {
             StarknetRpcApiError::InternalServerError => 500,
             StarknetRpcApiError::UnimplementedMethod => 501,
             StarknetRpcApiError::ProofLimitExceeded => 10000,
+            StarknetRpcApiError::TooManyAddressesInFilter => 67,
+            StarknetRpcApiError::TooManyBlocksBack => 68,
+            StarknetRpcApiError::CompilationError { data: _ } => 100,
         }
     }
 }
 
 impl StarknetRpcApiError {
-    pub fn data(&self) -> Option<String> {
+    pub fn data(&self) -> Option<serde_json::Value> {
         match self {
-            StarknetRpcApiError::ErrUnexpectedError { data } => Some(data.clone()),
+            StarknetRpcApiError::ErrUnexpectedError { data }
+            | StarknetRpcApiError::CompilationError { data }
+            | StarknetRpcApiError::ValidationFailure { data } => Some(data.clone().into()),
+            StarknetRpcApiError::ContractErrorData { revert_error } => {
+                Some(serde_json::json!({ "revert_error": revert_error }))
+            }
             _ => None,
         }
     }
 }
 
+impl From<StarknetRpcApiError> for MulticallError {
+    fn from(err: StarknetRpcApiError) -> Self {
+        MulticallError { code: (&err).into(), message: err.to_string(), data: err.data() }
+    }
+}
+
 impl From<StarknetTransactionExecutionError> for StarknetRpcApiError {
     fn from(err: StarknetTransactionExecutionError) -> Self {
         match err {
@@ -129,14 +151,22 @@ impl From<StarknetTransactionExecutionError> for StarknetRpcApiError {
             StarknetTransactionExecutionError::ClassAlreadyDeclared => StarknetRpcApiError::ClassAlreadyDeclared,
             StarknetTransactionExecutionError::ClassHashNotFound => StarknetRpcApiError::ClassHashNotFound,
             StarknetTransactionExecutionError::InvalidContractClass => StarknetRpcApiError::InvalidContractClass,
-            StarknetTransactionExecutionError::ContractError => StarknetRpcApiError::ContractError,
+            StarknetTransactionExecutionError::ContractError(revert_error) => {
+                StarknetRpcApiError::ContractErrorData { revert_error }
+            }
         }
     }
 }
 
+impl From<StarknetRpcApiError> for ErrorObjectOwned {
+    fn from(err: StarknetRpcApiError) -> Self {
+        ErrorObject::owned((&err).into(), err.to_string(), err.data())
+    }
+}
+
 impl From<StarknetRpcApiError> for jsonrpsee::core::Error {
     fn from(err: StarknetRpcApiError) -> Self {
-        jsonrpsee::core::Error::Call(CallError::Custom(ErrorObject::owned((&err).into(), err.to_string(), err.data())))
+        jsonrpsee::core::Error::Call(CallError::Custom(err.into()))
     }
 }
 
@@ -153,12 +183,14 @@ impl From<StarknetError> for StarknetRpcApiError {
             StarknetError::NoBlocks => StarknetRpcApiError::NoBlocks,
             StarknetError::InvalidContinuationToken => StarknetRpcApiError::InvalidContinuationToken,
             StarknetError::TooManyKeysInFilter => StarknetRpcApiError::TooManyKeysInFilter,
-            StarknetError::ContractError(_) => StarknetRpcApiError::ContractError,
+            StarknetError::ContractError(data) => {
+                StarknetRpcApiError::ContractErrorData { revert_error: data.revert_error }
+            }
             StarknetError::ClassAlreadyDeclared => StarknetRpcApiError::ClassAlreadyDeclared,
             StarknetError::InvalidTransactionNonce => StarknetRpcApiError::InvalidTxnNonce,
             StarknetError::InsufficientMaxFee => StarknetRpcApiError::InsufficientMaxFee,
             StarknetError::InsufficientAccountBalance => StarknetRpcApiError::InsufficientAccountBalance,
-            StarknetError::ValidationFailure(_) => StarknetRpcApiError::ValidationFailure,
+            StarknetError::ValidationFailure(data) => StarknetRpcApiError::ValidationFailure { data },
             StarknetError::CompilationFailed => StarknetRpcApiError::CompilationFailed,
             StarknetError::ContractClassSizeIsTooLarge => StarknetRpcApiError::ContractClassSizeTooLarge,
             StarknetError::NonAccount => StarknetRpcApiError::NonAccount,
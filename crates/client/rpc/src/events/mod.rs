@@ -101,7 +101,7 @@ where
         Ok(tx_hashes)
     }
 
-    fn get_block_by_number(&self, block_number: u64) -> Result<DeoxysBlock, StarknetRpcApiError> {
+    pub(crate) fn get_block_by_number(&self, block_number: u64) -> Result<DeoxysBlock, StarknetRpcApiError> {
         let substrate_block_hash =
             self.substrate_block_hash_from_starknet_block(BlockId::Number(block_number)).map_err(|e| {
                 log::error!("'{e}'");
@@ -2,17 +2,23 @@
 //!
 //! It uses the deoxys client and backend in order to answer queries.
 
+#![feature(let_chains)]
+
 mod constants;
+pub mod contract_class_cache;
 pub mod deoxys_backend_client;
 mod errors;
 mod events;
 mod methods;
+pub mod submitted_transactions;
 mod types;
 pub mod utils;
+pub mod versions;
 
 use std::marker::PhantomData;
 use std::sync::Arc;
 
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use errors::StarknetRpcApiError;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
@@ -31,10 +37,15 @@ use starknet_core::serde::unsigned_field_element::UfeHex;
 use starknet_core::types::{
     BlockHashAndNumber, BlockId, BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
     BroadcastedInvokeTransaction, BroadcastedTransaction, ContractClass, DeclareTransactionResult,
-    DeployAccountTransactionResult, EventFilterWithPage, EventsPage, FeeEstimate, FieldElement, FunctionCall,
-    InvokeTransactionResult, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
-    MaybePendingStateUpdate, MsgFromL1, SimulatedTransaction, SimulationFlag, SimulationFlagForEstimateFee,
-    SyncStatusType, Transaction, TransactionReceiptWithBlockInfo, TransactionStatus, TransactionTraceWithHash,
+    DeployAccountTransactionResult, EmittedEvent, EventFilterWithPage, EventsPage, FeeEstimate, FieldElement,
+    FunctionCall, InvokeTransactionResult, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes,
+    MaybePendingBlockWithTxs, MaybePendingStateUpdate, MsgFromL1, SimulatedTransaction, SimulationFlag,
+    SimulationFlagForEstimateFee, SyncStatusType, Transaction, TransactionReceiptWithBlockInfo, TransactionStatus,
+    TransactionTraceWithHash,
+};
+use types::{
+    BlockHeader, MessageStatus, MessagesToL1Filter, MessagesToL1Page, MulticallResult, PendingTransaction,
+    StateOverrides, SubscriptionId, TransactionStatusUpdate,
 };
 use utils::helpers::block_n_from_id;
 
@@ -118,6 +129,10 @@ pub trait StarknetReadRpcApi {
     #[method(name = "estimateMessageFee")]
     async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate>;
 
+    /// Given an L1 transaction hash, returns the status of the messages it sent to L2
+    #[method(name = "getMessagesStatus")]
+    fn get_messages_status(&self, transaction_hash: H256) -> RpcResult<Vec<MessageStatus>>;
+
     /// Get block information with full transactions and receipts given the block id
     #[method(name = "getBlockWithReceipts")]
     async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithReceipts>;
@@ -143,6 +158,10 @@ pub trait StarknetReadRpcApi {
     #[method(name = "getClass")]
     fn get_class(&self, block_id: BlockId, class_hash: FieldElement) -> RpcResult<ContractClass>;
 
+    /// Get the CASM code resulting from compiling a given class
+    #[method(name = "getCompiledCasm")]
+    fn get_compiled_casm(&self, class_hash: FieldElement) -> RpcResult<CasmContractClass>;
+
     /// Returns all events matching the given filter
     #[method(name = "getEvents")]
     async fn get_events(&self, filter: EventFilterWithPage) -> RpcResult<EventsPage>;
@@ -203,6 +222,92 @@ pub trait StarknetTraceRpcApi {
     async fn trace_transaction(&self, transaction_hash: FieldElement) -> RpcResult<TransactionTraceWithHash>;
 }
 
+/// Deoxys specific rpc interface, for data and execution features the Starknet API does not expose.
+#[rpc(server, namespace = "deoxys")]
+pub trait DeoxysRpcApi {
+    /// Returns the messages sent from L2 to L1 matching the given filter
+    #[method(name = "getMessagesToL1")]
+    fn get_messages_to_l1(&self, filter: MessagesToL1Filter) -> RpcResult<MessagesToL1Page>;
+
+    /// Calls a function in a contract on top of the state of the given block, with the given
+    /// contracts state overridden
+    #[method(name = "call")]
+    fn call_with_state_overrides(
+        &self,
+        request: FunctionCall,
+        block_id: BlockId,
+        state_overrides: StateOverrides,
+    ) -> RpcResult<Vec<String>>;
+
+    /// Estimates the fee of transactions on top of the state of the given block, with the given
+    /// contracts state overridden
+    #[method(name = "estimateFee")]
+    async fn estimate_fee_with_state_overrides(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+        state_overrides: StateOverrides,
+    ) -> RpcResult<Vec<FeeEstimate>>;
+
+    /// Calls functions in contracts against the state of the given block, returning the result of
+    /// each call or the error it failed with
+    #[method(name = "multicall")]
+    fn multicall(&self, block_id: BlockId, calls: Vec<FunctionCall>) -> RpcResult<Vec<MulticallResult>>;
+}
+
+/// Starknet websocket rpc interface, the subscriptions of the Starknet API.
+///
+/// The subscriptions are driven by the sync events. Any of them is stopped by `starknet_unsubscribe`,
+/// the unsubscribe method of each subscription being kept for the clients which use it.
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetWsRpcApi {
+    /// Notifies the header of each new block, starting from the given stored block
+    #[subscription(
+        name = "subscribeNewHeads" => "subscriptionNewHeads",
+        unsubscribe = "unsubscribeNewHeads",
+        item = BlockHeader
+    )]
+    fn subscribe_new_heads(&self, block_id: Option<BlockId>);
+
+    /// Notifies the events matching the given filter, starting from the given stored block
+    #[subscription(
+        name = "subscribeEvents" => "subscriptionEvents",
+        unsubscribe = "unsubscribeEvents",
+        item = EmittedEvent
+    )]
+    fn subscribe_events(
+        &self,
+        from_address: Option<FieldElement>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        block_id: Option<BlockId>,
+    );
+
+    /// Notifies the changes of the finality status of a transaction
+    #[subscription(
+        name = "subscribeTransactionStatus" => "subscriptionTransactionStatus",
+        unsubscribe = "unsubscribeTransactionStatus",
+        item = TransactionStatusUpdate
+    )]
+    fn subscribe_transaction_status(&self, transaction_hash: FieldElement);
+
+    /// Notifies the transactions added to the pending block, optionally filtered by sender
+    #[subscription(
+        name = "subscribePendingTransactions" => "subscriptionPendingTransactions",
+        unsubscribe = "unsubscribePendingTransactions",
+        item = PendingTransaction
+    )]
+    fn subscribe_pending_transactions(
+        &self,
+        transaction_details: Option<bool>,
+        sender_address: Option<Vec<FieldElement>>,
+    );
+
+    /// Stops a subscription, returns false if there is no subscription with this id
+    #[method(name = "unsubscribe")]
+    fn unsubscribe(&self, subscription_id: SubscriptionId) -> RpcResult<bool>;
+}
+
 /// A Starknet RPC server for Deoxys
 pub struct Starknet<BE, C, H> {
     client: Arc<C>,
@@ -211,6 +316,19 @@ pub struct Starknet<BE, C, H> {
     _marker: PhantomData<(DBlockT, BE, H)>,
 }
 
+// Subscriptions hold a copy of the server, which derive would require `BE`, `C` and `H` to be
+// `Clone`
+impl<BE, C, H> Clone for Starknet<BE, C, H> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            sync_service: self.sync_service.clone(),
+            starting_block: self.starting_block,
+            _marker: PhantomData,
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 impl<BE, C, H> Starknet<BE, C, H> {
     pub fn new(
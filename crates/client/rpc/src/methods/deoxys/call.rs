@@ -0,0 +1,45 @@
+use jsonrpsee::core::RpcResult;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{BlockId, FunctionCall};
+
+use super::state_overrides::check_state_overrides;
+use crate::methods::read::call::call_with_state_overrides;
+use crate::types::StateOverrides;
+use crate::Starknet;
+
+/// Call a function in a contract on top of overridden state, without creating a transaction
+///
+/// ### Arguments
+///
+/// * `request` - The details of the function call to be made.
+/// * `block_id` - The identifier of the block whose state the call is made on, which can be any
+///   stored block.
+/// * `state_overrides` - The nonces, class hashes, fee token balances and storage slots overridden
+///   for each contract, on top of the state of the block.
+///
+/// ### Returns
+///
+/// * `result` - The function's return value, as defined in the Cairo output.
+///
+/// ### Errors
+///
+/// This method may return the errors of `starknet_call`, as well as `CLASS_HASH_NOT_FOUND` if a
+/// contract is overridden with a class which is not declared at the block.
+pub fn call<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    request: FunctionCall,
+    block_id: BlockId,
+    state_overrides: StateOverrides,
+) -> RpcResult<Vec<String>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    check_state_overrides(&state_overrides, block_id)?;
+    call_with_state_overrides(starknet, request, block_id, &state_overrides)
+}
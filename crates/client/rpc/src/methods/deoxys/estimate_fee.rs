@@ -0,0 +1,41 @@
+use jsonrpsee::core::RpcResult;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{BlockId, BroadcastedTransaction, FeeEstimate, SimulationFlagForEstimateFee};
+
+use super::state_overrides::check_state_overrides;
+use crate::methods::read::estimate_fee::estimate_fee_with_state_overrides;
+use crate::types::StateOverrides;
+use crate::Starknet;
+
+/// Estimate the fee associated with transactions on top of overridden state
+///
+/// # Arguments
+///
+/// * `request` - starknet transaction request
+/// * `simulation_flags` - flags of the estimation, as in `starknet_estimateFee`
+/// * `block_id` - hash of the requested block, number (height), or tag
+/// * `state_overrides` - the nonces, class hashes, fee token balances and storage slots overridden
+///   for each contract, on top of the state of the block
+///
+/// # Returns
+///
+/// * `fee_estimate` - fee estimate of each transaction
+pub async fn estimate_fee<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    request: Vec<BroadcastedTransaction>,
+    simulation_flags: Vec<SimulationFlagForEstimateFee>,
+    block_id: BlockId,
+    state_overrides: StateOverrides,
+) -> RpcResult<Vec<FeeEstimate>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    check_state_overrides(&state_overrides, block_id)?;
+    estimate_fee_with_state_overrides(starknet, request, simulation_flags, block_id, &state_overrides).await
+}
@@ -0,0 +1,141 @@
+use jsonrpsee::core::RpcResult;
+use mc_db::{DeoxysBackend, L2MessageFilter, L2MessageKey, L2MessageStatus};
+use mp_felt::Felt252Wrapper;
+use sp_core::H160;
+use starknet_api::hash::StarkFelt;
+use starknet_core::types::{BlockId, BlockTag, EthAddress, FieldElement};
+
+use crate::constants::MAX_MESSAGES_TO_L1_CHUNK_SIZE;
+use crate::errors::StarknetRpcApiError;
+use crate::types::{MessageToL1WithStatus, MessagesToL1Filter, MessagesToL1Page};
+use crate::utils::helpers::block_n_from_id;
+
+/// Returns the messages sent from L2 to L1 matching the given filter.
+///
+/// ### Arguments
+///
+/// * `filter` - The block range to search, defaulting to the whole chain, along with optional
+///   sender and recipient filters and the pagination parameters.
+///
+/// ### Returns
+///
+/// A page of messages in chain order, each flagged with whether it has been consumed on L1. The
+/// consumption status is `null` until the node has indexed the L1 events past the verification of
+/// the block of the message. Messages of the pending block are not indexed.
+///
+/// ### Errors
+///
+/// * `BLOCK_NOT_FOUND` - If one of the bounds of the block range does not exist.
+/// * `PAGE_SIZE_TOO_BIG` - If the chunk size is above the maximum.
+/// * `INVALID_CONTINUATION_TOKEN` - If the continuation token could not be parsed.
+pub fn get_messages_to_l1(filter: MessagesToL1Filter) -> RpcResult<MessagesToL1Page> {
+    if filter.chunk_size > MAX_MESSAGES_TO_L1_CHUNK_SIZE as u64 {
+        return Err(StarknetRpcApiError::PageSizeTooBig.into());
+    }
+
+    let from_block = match filter.from_block {
+        None => 0,
+        Some(BlockId::Tag(BlockTag::Pending)) => {
+            return Ok(MessagesToL1Page { messages: vec![], continuation_token: None });
+        }
+        Some(block_id) => block_n_from_id(block_id)?,
+    };
+    let to_block = match filter.to_block {
+        None | Some(BlockId::Tag(BlockTag::Pending)) => block_n_from_id(BlockId::Tag(BlockTag::Latest))?,
+        Some(block_id) => block_n_from_id(block_id)?,
+    };
+
+    let start = filter
+        .continuation_token
+        .map(|token| {
+            parse_continuation_token(&token).ok_or_else(|| {
+                log::error!("Failed to parse continuation token: {token}");
+                StarknetRpcApiError::InvalidContinuationToken
+            })
+        })
+        .transpose()?;
+
+    if from_block > to_block || filter.chunk_size == 0 {
+        return Ok(MessagesToL1Page { messages: vec![], continuation_token: None });
+    }
+
+    let db_filter = L2MessageFilter {
+        from_block,
+        to_block,
+        from_address: filter.from_address.map(|address| StarkFelt(address.to_bytes_be())),
+        to_address: filter.to_address.map(|address| H160(*address.as_bytes())),
+    };
+    let (messages, next) =
+        DeoxysBackend::messaging().l2_messages(&db_filter, start, filter.chunk_size as usize).map_err(|e| {
+            log::error!("Failed to retrieve messages sent to L1: {e}");
+            StarknetRpcApiError::InternalServerError
+        })?;
+
+    Ok(MessagesToL1Page {
+        messages: messages.into_iter().map(to_rpc_message).collect(),
+        continuation_token: next.map(continuation_token),
+    })
+}
+
+fn to_rpc_message(status: L2MessageStatus) -> MessageToL1WithStatus {
+    let L2MessageStatus { message, consumed_on_l1 } = status;
+
+    // safe unwraps because an H160 always fits in a felt and is a valid Ethereum address
+    let to_address =
+        EthAddress::from_felt(&FieldElement::from_byte_slice_be(message.to_address.as_bytes()).unwrap()).unwrap();
+
+    MessageToL1WithStatus {
+        block_number: message.block_number,
+        transaction_hash: Felt252Wrapper::from(message.transaction_hash).into(),
+        from_address: Felt252Wrapper::from(message.from_address).into(),
+        to_address,
+        payload: message.payload.into_iter().map(|felt| Felt252Wrapper::from(felt).into()).collect(),
+        consumed_on_l1,
+    }
+}
+
+/// Continuation tokens are the position of the next message to return, as
+/// `<block_number>-<transaction_index>-<message_index>`
+fn continuation_token(key: L2MessageKey) -> String {
+    format!("{}-{}-{}", key.block_number, key.transaction_index, key.message_index)
+}
+
+fn parse_continuation_token(token: &str) -> Option<L2MessageKey> {
+    let mut parts = token.split('-');
+    let key = L2MessageKey {
+        block_number: parts.next()?.parse().ok()?,
+        transaction_index: parts.next()?.parse().ok()?,
+        message_index: parts.next()?.parse().ok()?,
+    };
+    parts.next().is_none().then_some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, 0, 0)]
+    #[case(12, 3, 1)]
+    #[case(u64::MAX, u32::MAX, u32::MAX)]
+    fn continuation_token_roundtrip(
+        #[case] block_number: u64,
+        #[case] transaction_index: u32,
+        #[case] message_index: u32,
+    ) {
+        let key = L2MessageKey { block_number, transaction_index, message_index };
+        assert_eq!(Some(key), parse_continuation_token(&continuation_token(key)));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("1-2")]
+    #[case("1-2-3-4")]
+    #[case("1-a-3")]
+    #[case("1-4294967296-0")]
+    fn parse_continuation_token_fails(#[case] token: &str) {
+        assert_eq!(None, parse_continuation_token(token));
+    }
+}
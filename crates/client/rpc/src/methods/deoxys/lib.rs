@@ -0,0 +1,48 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{BlockId, BroadcastedTransaction, FeeEstimate, FunctionCall, SimulationFlagForEstimateFee};
+
+use super::call::call;
+use super::estimate_fee::estimate_fee;
+use super::get_messages_to_l1::get_messages_to_l1;
+use super::multicall::multicall;
+use crate::types::{MessagesToL1Filter, MessagesToL1Page, MulticallResult, StateOverrides};
+use crate::{DeoxysRpcApiServer, Starknet};
+
+#[async_trait]
+impl<BE, C, H> DeoxysRpcApiServer for Starknet<BE, C, H>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    fn get_messages_to_l1(&self, filter: MessagesToL1Filter) -> RpcResult<MessagesToL1Page> {
+        get_messages_to_l1(filter)
+    }
+
+    fn call_with_state_overrides(
+        &self,
+        request: FunctionCall,
+        block_id: BlockId,
+        state_overrides: StateOverrides,
+    ) -> RpcResult<Vec<String>> {
+        call(self, request, block_id, state_overrides)
+    }
+
+    async fn estimate_fee_with_state_overrides(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+        state_overrides: StateOverrides,
+    ) -> RpcResult<Vec<FeeEstimate>> {
+        estimate_fee(self, request, simulation_flags, block_id, state_overrides).await
+    }
+
+    fn multicall(&self, block_id: BlockId, calls: Vec<FunctionCall>) -> RpcResult<Vec<MulticallResult>> {
+        multicall(self, block_id, calls)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod call;
+pub mod estimate_fee;
+pub mod get_messages_to_l1;
+pub mod lib;
+pub mod multicall;
+pub mod state_overrides;
@@ -0,0 +1,73 @@
+use jsonrpsee::core::RpcResult;
+use mp_felt::Felt252Wrapper;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_api::transaction::Calldata;
+use starknet_core::types::{BlockId, FunctionCall};
+
+use crate::constants::MAX_MULTICALL_CALLS;
+use crate::errors::StarknetRpcApiError;
+use crate::types::MulticallResult;
+use crate::utils::execution::block_context;
+use crate::{utils, Arc, Starknet};
+
+/// Calls several functions in contracts without creating transactions
+///
+/// The calls share the block context and the state of the block, so what a call reads from the
+/// database is cached for the following ones, but the state changes of a call are not seen by the
+/// others.
+///
+/// ### Arguments
+///
+/// * `block_id` - The identifier of the block whose state the calls are made on.
+/// * `calls` - The contract address, entry point selector and calldata of each call.
+///
+/// ### Returns
+///
+/// * `results` - The return value of each call, or the error it failed with, in the order of the
+///   calls. A failing call does not fail the others.
+///
+/// ### Errors
+///
+/// This method may return the following errors:
+/// * `BLOCK_NOT_FOUND` - If the specified block does not exist in the blockchain.
+/// * `PAGE_SIZE_TOO_BIG` - If there are more than [`MAX_MULTICALL_CALLS`] calls.
+pub fn multicall<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    block_id: BlockId,
+    calls: Vec<FunctionCall>,
+) -> RpcResult<Vec<MulticallResult>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    if calls.len() > MAX_MULTICALL_CALLS {
+        return Err(StarknetRpcApiError::PageSizeTooBig.into());
+    }
+
+    let substrate_block_hash = starknet.substrate_block_hash_from_starknet_block(block_id)?;
+
+    let block_context = block_context(starknet.client.as_ref(), substrate_block_hash)?;
+
+    let calls = calls
+        .into_iter()
+        .map(|call| {
+            let calldata = Calldata(Arc::new(call.calldata.iter().map(|x| Felt252Wrapper::from(*x).into()).collect()));
+            (Felt252Wrapper(call.contract_address).into(), Felt252Wrapper(call.entry_point_selector).into(), calldata)
+        })
+        .collect();
+
+    let results = utils::execution::multicall(calls, &block_context)
+        .into_iter()
+        .map(|result| match result {
+            Ok(result) => MulticallResult::Result(result.into_iter().map(Into::into).collect()),
+            Err(e) => MulticallResult::Error(e.into()),
+        })
+        .collect();
+
+    Ok(results)
+}
@@ -0,0 +1,37 @@
+use mc_db::storage_handler::{self, StorageView};
+use mc_sync::l2::get_pending_class;
+use mp_felt::Felt252Wrapper;
+use starknet_core::types::{BlockId, BlockTag};
+
+use crate::errors::StarknetRpcApiError;
+use crate::types::StateOverrides;
+use crate::utils::helpers::block_n_from_id;
+
+/// Checks that the classes which contracts are overridden with are declared at the requested
+/// block, so that an unknown class is reported as such rather than as a failed execution
+///
+/// Classes declared in the pending block are only declared for the pending tag, as in
+/// `starknet_getClass`.
+pub(crate) fn check_state_overrides(
+    state_overrides: &StateOverrides,
+    block_id: BlockId,
+) -> Result<(), StarknetRpcApiError> {
+    let is_pending = matches!(block_id, BlockId::Tag(BlockTag::Pending));
+    let block_number = block_n_from_id(block_id)?;
+
+    for class_hash in state_overrides.0.values().filter_map(|contract_override| contract_override.class_hash) {
+        if is_pending && get_pending_class(&class_hash).is_some() {
+            continue;
+        }
+
+        match storage_handler::contract_class_data().get(&Felt252Wrapper(class_hash).into()) {
+            Ok(Some(class)) if class.block_number <= block_number => {}
+            Ok(_) => return Err(StarknetRpcApiError::ClassHashNotFound),
+            Err(e) => {
+                log::error!("Failed to retrieve contract class: {e}");
+                return Err(StarknetRpcApiError::InternalServerError);
+            }
+        }
+    }
+    Ok(())
+}
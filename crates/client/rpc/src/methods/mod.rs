@@ -1,4 +1,6 @@
+pub mod deoxys;
 pub mod get_block;
 pub mod read;
 pub mod trace;
 pub mod write;
+pub mod ws;
@@ -8,7 +8,7 @@ use sp_blockchain::HeaderBackend;
 use starknet_api::transaction::Calldata;
 use starknet_core::types::{BlockId, FunctionCall};
 
-use crate::errors::StarknetRpcApiError;
+use crate::types::StateOverrides;
 use crate::utils::execution::block_context;
 use crate::{utils, Arc, Starknet};
 
@@ -33,6 +33,21 @@ use crate::{utils, Arc, Starknet};
 /// * `CONTRACT_ERROR` - If there is an error with the contract or the function call.
 /// * `BLOCK_NOT_FOUND` - If the specified block does not exist in the blockchain.
 pub fn call<BE, C, H>(starknet: &Starknet<BE, C, H>, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<String>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    call_with_state_overrides(starknet, request, block_id, &StateOverrides::default())
+}
+
+/// [`call`], on top of the given state overrides
+pub(crate) fn call_with_state_overrides<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    request: FunctionCall,
+    block_id: BlockId,
+    state_overrides: &StateOverrides,
+) -> RpcResult<Vec<String>>
 where
     BE: Backend<DBlockT> + 'static,
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
@@ -49,11 +64,8 @@ where
         Felt252Wrapper(request.entry_point_selector).into(),
         calldata,
         &block_context,
-    )
-    .map_err(|_| {
-        log::error!("Request parameters error");
-        StarknetRpcApiError::InternalServerError
-    })?;
+        state_overrides,
+    )?;
 
     Ok(result.iter().map(|x| format!("{:#x}", x.0)).collect())
 }
@@ -1,5 +1,7 @@
 use blockifier::transaction::account_transaction::AccountTransaction;
 use jsonrpsee::core::RpcResult;
+use mc_sync::utility::chain_id;
+use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
 use mp_transactions::from_broadcasted_transactions::ToAccountTransaction;
 use mp_types::block::DBlockT;
@@ -9,6 +11,7 @@ use sp_blockchain::HeaderBackend;
 use starknet_core::types::{BlockId, BroadcastedTransaction, FeeEstimate, SimulationFlagForEstimateFee};
 
 use crate::errors::StarknetRpcApiError;
+use crate::types::StateOverrides;
 use crate::utils::execution::block_context;
 use crate::{utils, Starknet};
 
@@ -28,6 +31,22 @@ pub async fn estimate_fee<BE, C, H>(
     simulation_flags: Vec<SimulationFlagForEstimateFee>,
     block_id: BlockId,
 ) -> RpcResult<Vec<FeeEstimate>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    estimate_fee_with_state_overrides(starknet, request, simulation_flags, block_id, &StateOverrides::default()).await
+}
+
+/// [`estimate_fee`], on top of the given state overrides
+pub(crate) async fn estimate_fee_with_state_overrides<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    request: Vec<BroadcastedTransaction>,
+    simulation_flags: Vec<SimulationFlagForEstimateFee>,
+    block_id: BlockId,
+    state_overrides: &StateOverrides,
+) -> RpcResult<Vec<FeeEstimate>>
 where
     BE: Backend<DBlockT> + 'static,
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
@@ -37,9 +56,10 @@ where
 
     let block_context = block_context(starknet.client.as_ref(), substrate_block_hash)?;
 
+    let chain_id = Felt252Wrapper(chain_id());
     let transactions = request
         .into_iter()
-        .map(|tx| tx.to_account_transaction())
+        .map(|tx| tx.to_account_transaction(chain_id))
         .collect::<Result<Vec<AccountTransaction>, _>>()
         .map_err(|e| {
             log::error!("Failed to convert BroadcastedTransaction to AccountTransaction: {e}");
@@ -51,10 +71,10 @@ where
 
     let validate = !simulation_flags.contains(&SimulationFlagForEstimateFee::SkipValidate);
 
-    let fee_estimates =
-        utils::execution::estimate_fee(account_transactions, validate, &block_context).map_err(|e| {
+    let fee_estimates = utils::execution::estimate_fee(account_transactions, validate, &block_context, state_overrides)
+        .map_err(|e| {
             log::error!("Failed to call function: {:#?}", e);
-            StarknetRpcApiError::ContractError
+            StarknetRpcApiError::ContractErrorData { revert_error: e.to_string() }
         })?;
 
     Ok(fee_estimates)
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use blockifier::transaction::transactions::L1HandlerTransaction;
 use jsonrpsee::core::RpcResult;
-use log::error;
+use mc_db::DeoxysBackend;
 use mc_sync::utility::chain_id;
 use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
@@ -11,6 +11,7 @@ use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
+use sp_core::H160;
 use starknet_api::core::Nonce;
 use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{Calldata, Fee, TransactionVersion};
@@ -30,13 +31,13 @@ use crate::{utils, Starknet};
 ///
 /// # Returns
 ///
-/// * `FeeEstimate` - the fee estimation (gas consumed, gas price, overall fee, unit)
+/// * `FeeEstimate` - the fee estimation (gas and data gas consumed, at the gas and data gas prices
+///   of the block, overall fee, and unit, in wei as messages pay their fee in ETH)
 ///
 /// # Errors
 ///
 /// BlockNotFound : If the specified block does not exist.
-/// ContractNotFound : If the specified contract address does not exist.
-/// ContractError : If there is an error with the contract.
+/// ContractError : If the L1 handler fails or reverts, with the reason as `revert_error`.
 pub async fn estimate_message_fee<BE, C, H>(
     starknet: &Starknet<BE, C, H>,
     message: MsgFromL1,
@@ -52,20 +53,35 @@ where
     let block = get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash)?;
     let block_number = block.header().block_number;
 
-    let transaction = convert_message_into_tx::<H>(message, chain_id().into(), Some(block_number));
+    let paid_fee_on_l1 = paid_fee_on_l1(&message)?;
+    let transaction = convert_message_into_tx::<H>(message, chain_id().into(), Some(block_number), paid_fee_on_l1);
 
-    let message_fee = utils::execution::estimate_message_fee(transaction, &block_context).map_err(|e| {
-        error!("Function execution failed: {:#?}", e);
-        StarknetRpcApiError::ContractError
-    })?;
+    let message_fee = utils::execution::estimate_message_fee(transaction, &block_context)?;
 
     Ok(message_fee)
 }
 
+/// Fee paid on L1 for the given message
+///
+/// Messages which have already been sent on L1 use the fee indexed from their `LogMessageToL2`
+/// event. Otherwise no fee has been paid yet.
+fn paid_fee_on_l1(message: &MsgFromL1) -> Result<Fee, StarknetRpcApiError> {
+    let from_address = H160::from(*message.from_address.as_bytes());
+    let to_address: StarkFelt = Felt252Wrapper::from(message.to_address).into();
+    let selector: StarkFelt = Felt252Wrapper::from(message.entry_point_selector).into();
+    let payload: Vec<StarkFelt> = message.payload.iter().map(|felt| Felt252Wrapper::from(*felt).into()).collect();
+
+    let sent_message =
+        DeoxysBackend::messaging().l1_message_from_content(&from_address, &to_address, &selector, &payload)?;
+
+    Ok(sent_message.map_or(Fee(0), |sent_message| Fee(sent_message.fee)))
+}
+
 pub fn convert_message_into_tx<H: HasherT + Send + Sync + 'static>(
     message: MsgFromL1,
     chain_id: Felt252Wrapper,
     block_number: Option<u64>,
+    paid_fee_on_l1: Fee,
 ) -> L1HandlerTransaction {
     let calldata = std::iter::once(Felt252Wrapper::from(message.from_address).into())
         .chain(message.payload.into_iter().map(|felt| Felt252Wrapper::from(felt).into()))
@@ -79,5 +95,5 @@ pub fn convert_message_into_tx<H: HasherT + Send + Sync + 'static>(
     };
     let tx_hash = tx.compute_hash::<H>(chain_id, true, block_number);
 
-    L1HandlerTransaction { tx, tx_hash, paid_fee_on_l1: Fee(10) } //TODO: fix with real fee
+    L1HandlerTransaction { tx, tx_hash, paid_fee_on_l1 }
 }
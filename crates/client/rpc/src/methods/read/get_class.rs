@@ -1,8 +1,9 @@
 use jsonrpsee::core::RpcResult;
 use mc_db::storage_handler::primitives::contract_class::{ContractClassWrapper, StorageContractClassData};
 use mc_db::storage_handler::{self, StorageView};
+use mc_sync::l2::get_pending_class;
 use mp_felt::Felt252Wrapper;
-use starknet_core::types::{BlockId, ContractClass, FieldElement};
+use starknet_core::types::{BlockId, BlockTag, ContractClass, FieldElement};
 
 use crate::errors::StarknetRpcApiError;
 use crate::utils::helpers::block_n_from_id;
@@ -17,13 +18,20 @@ use crate::utils::helpers::block_n_from_id;
 ///
 /// ### Returns
 ///
-/// Returns the contract class definition if found. In case of an error, returns a
-/// `StarknetRpcApiError` indicating either `BlockNotFound` or `ClassHashNotFound`.
+/// Returns the contract class definition if found. Classes declared after the requested block are
+/// not found, and classes declared in the pending block are only found for the pending tag. In
+/// case of an error, returns a `StarknetRpcApiError` indicating either `BlockNotFound` or
+/// `ClassHashNotFound`.
 pub fn get_class(block_id: BlockId, class_hash: FieldElement) -> RpcResult<ContractClass> {
+    if matches!(block_id, BlockId::Tag(BlockTag::Pending))
+        && let Some(class) = get_pending_class(&class_hash)
+    {
+        return Ok(class);
+    }
+
+    let block_number = block_n_from_id(block_id)?;
     let class_hash = Felt252Wrapper(class_hash).into();
 
-    // TODO: get class for the given block when block_number will be stored in
-    // `StorageContractClassData`
     match storage_handler::contract_class_data().get(&class_hash) {
         Err(e) => {
             log::error!("Failed to retrieve contract class: {e}");
@@ -37,16 +45,23 @@ pub fn get_class(block_id: BlockId, class_hash: FieldElement) -> RpcResult<Contr
                 sierra_program_length,
                 abi_length,
                 block_number: declared_at_block,
+                compiler_version,
             } = class;
-            if declared_at_block >= block_n_from_id(block_id)? {
+            if declared_at_block > block_number {
                 return Err(StarknetRpcApiError::ClassHashNotFound.into());
             }
-            Ok(ContractClassWrapper { contract: contract_class, abi, sierra_program_length, abi_length }
-                .try_into()
-                .map_err(|e| {
-                    log::error!("Failed to convert contract class from hash '{class_hash}' to RPC contract class: {e}");
-                    StarknetRpcApiError::InternalServerError
-                })?)
+            Ok(ContractClassWrapper {
+                contract: contract_class,
+                abi,
+                sierra_program_length,
+                abi_length,
+                compiler_version,
+            }
+            .try_into()
+            .map_err(|e| {
+                log::error!("Failed to convert contract class from hash '{class_hash}' to RPC contract class: {e}");
+                StarknetRpcApiError::InternalServerError
+            })?)
         }
     }
 }
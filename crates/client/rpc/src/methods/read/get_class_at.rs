@@ -1,12 +1,9 @@
 use jsonrpsee::core::RpcResult;
-use mc_db::storage_handler::primitives::contract_class::{ContractClassWrapper, StorageContractClassData};
-use mc_db::storage_handler::{self, StorageView};
-use starknet_api::core::{ContractAddress, PatriciaKey};
-use starknet_api::hash::StarkFelt;
 use starknet_core::types::{BlockId, ContractClass, FieldElement};
 
+use super::get_class::get_class;
+use super::get_class_hash_at::class_hash_at;
 use crate::errors::StarknetRpcApiError;
-use crate::utils::helpers::block_n_from_id;
 
 /// Get the Contract Class Definition at a Given Address in a Specific Block
 ///
@@ -28,33 +25,11 @@ use crate::utils::helpers::block_n_from_id;
 /// * `BLOCK_NOT_FOUND` - If the specified block does not exist in the blockchain.
 /// * `CONTRACT_NOT_FOUND` - If the specified contract address does not exist.
 pub fn get_class_at(block_id: BlockId, contract_address: FieldElement) -> RpcResult<ContractClass> {
-    let block_number = block_n_from_id(block_id)?;
-    let key = ContractAddress(PatriciaKey(StarkFelt(contract_address.to_bytes_be())));
+    let class_hash = class_hash_at(block_id, contract_address)?;
 
-    let class_hash = match storage_handler::contract_class_hash().get_at(&key, block_number) {
-        Err(e) => {
-            log::error!("Failed to retrieve contract class: {e}");
-            return Err(StarknetRpcApiError::InternalServerError.into());
-        }
-        Ok(None) => {
-            return Err(StarknetRpcApiError::ContractNotFound.into());
-        }
-        Ok(Some(val)) => val,
-    };
-
-    // The class need to be stored
-    let Ok(Some(contract_class_data)) = storage_handler::contract_class_data().get(&class_hash) else {
-        log::error!("Failed to retrieve contract class from hash: '{}'", class_hash.0);
-        return Err(StarknetRpcApiError::InternalServerError.into());
-    };
-
-    // converting from stored Blockifier class to rpc class
-    let StorageContractClassData { contract_class, abi, sierra_program_length, abi_length, block_number: _ } =
-        contract_class_data;
-    Ok(ContractClassWrapper { contract: contract_class, abi, sierra_program_length, abi_length }.try_into().map_err(
-        |e| {
-            log::error!("Failed to convert contract class from hash '{class_hash}' to RPC contract class: {e}");
-            StarknetRpcApiError::InternalServerError
-        },
-    )?)
+    // The class of a deployed contract needs to be stored
+    get_class(block_id, class_hash).map_err(|e| {
+        log::error!("Failed to retrieve contract class from hash '{class_hash:#x}': {e}");
+        StarknetRpcApiError::InternalServerError.into()
+    })
 }
@@ -1,9 +1,10 @@
 use jsonrpsee::core::RpcResult;
 use mc_db::storage_handler;
+use mc_sync::l2::get_pending_state_update;
 use mp_felt::Felt252Wrapper;
 use starknet_api::core::{ContractAddress, PatriciaKey};
 use starknet_api::hash::StarkFelt;
-use starknet_core::types::{BlockId, FieldElement};
+use starknet_core::types::{BlockId, BlockTag, DeployedContractItem, FieldElement, ReplacedClassItem};
 
 use crate::errors::StarknetRpcApiError;
 use crate::utils::helpers::block_n_from_id;
@@ -22,6 +23,33 @@ use crate::Felt;
 ///
 /// * `class_hash` - The class hash of the given contract
 pub fn get_class_hash_at(block_id: BlockId, contract_address: FieldElement) -> RpcResult<Felt> {
+    Ok(Felt(class_hash_at(block_id, contract_address)?))
+}
+
+/// Resolves the class hash of a contract through its class hash history at the given block.
+///
+/// For the pending tag, the contracts deployed or replaced in the pending block take precedence
+/// over the latest stored state.
+pub(crate) fn class_hash_at(block_id: BlockId, contract_address: FieldElement) -> RpcResult<FieldElement> {
+    if matches!(block_id, BlockId::Tag(BlockTag::Pending))
+        && let Some(state_update) = get_pending_state_update()
+    {
+        let state_diff = state_update.state_diff;
+        let replaced = state_diff.replaced_classes.into_iter().find_map(
+            |ReplacedClassItem { contract_address: address, class_hash }| {
+                (address == contract_address).then_some(class_hash)
+            },
+        );
+        let deployed = || {
+            state_diff.deployed_contracts.into_iter().find_map(|DeployedContractItem { address, class_hash }| {
+                (address == contract_address).then_some(class_hash)
+            })
+        };
+        if let Some(class_hash) = replaced.or_else(deployed) {
+            return Ok(class_hash);
+        }
+    }
+
     let block_number = block_n_from_id(block_id)?;
     let key = ContractAddress(PatriciaKey(StarkFelt(contract_address.to_bytes_be())));
 
@@ -31,6 +59,6 @@ pub fn get_class_hash_at(block_id: BlockId, contract_address: FieldElement) -> R
             Err(StarknetRpcApiError::InternalServerError.into())
         }
         Ok(None) => Err(StarknetRpcApiError::ContractNotFound.into()),
-        Ok(Some(class_hash)) => Ok(Felt(Felt252Wrapper::from(class_hash).into())),
+        Ok(Some(class_hash)) => Ok(Felt252Wrapper::from(class_hash).into()),
     }
 }
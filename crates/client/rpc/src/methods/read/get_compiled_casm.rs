@@ -0,0 +1,54 @@
+use blockifier::execution::contract_class::ContractClass;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use jsonrpsee::core::RpcResult;
+use mc_db::storage_handler;
+use mc_db::storage_handler::primitives::contract_class::{
+    local_compiler_version, to_casm_contract_class, StorageContractClassData,
+};
+use mp_felt::Felt252Wrapper;
+use starknet_core::types::FieldElement;
+
+use crate::errors::StarknetRpcApiError;
+
+/// Get the CASM code resulting from compiling a given class.
+///
+/// ### Arguments
+///
+/// * `class_hash` - The hash of the Sierra class whose compiled CASM is requested.
+///
+/// ### Returns
+///
+/// Returns the compiled CASM class, with its bytecode, hints, entry points and bytecode segment
+/// lengths. Sierra programs are not stored by the node, so the CASM is converted back from the
+/// compiled class used for execution, with the version of the compiler it was compiled with.
+/// Classes stored without that version were compiled by the node itself, so they are served with
+/// the version of its compiler. In case of an error, returns a `StarknetRpcApiError` indicating
+/// either `ClassHashNotFound` or `CompilationError` for Cairo 0 classes.
+pub fn get_compiled_casm(class_hash: FieldElement) -> RpcResult<CasmContractClass> {
+    let class_hash = Felt252Wrapper(class_hash).into();
+
+    let (contract_class, compiler_version) = match storage_handler::contract_class_data().get(&class_hash) {
+        Err(e) => {
+            log::error!("Failed to retrieve contract class: {e}");
+            return Err(StarknetRpcApiError::InternalServerError.into());
+        }
+        Ok(None) => return Err(StarknetRpcApiError::ClassHashNotFound.into()),
+        Ok(Some(StorageContractClassData { contract_class, compiler_version, .. })) => {
+            (contract_class, compiler_version)
+        }
+    };
+
+    match (contract_class, compiler_version) {
+        (ContractClass::V0(_), _) => Err(StarknetRpcApiError::CompilationError {
+            data: format!("Class hash '{class_hash}' is not a Sierra class"),
+        }
+        .into()),
+        (ContractClass::V1(contract_class), compiler_version) => {
+            let compiler_version = compiler_version.unwrap_or_else(local_compiler_version);
+            Ok(to_casm_contract_class(&contract_class, compiler_version).map_err(|e| {
+                log::error!("Failed to convert contract class from hash '{class_hash}' to CASM: {e}");
+                StarknetRpcApiError::InternalServerError
+            })?)
+        }
+    }
+}
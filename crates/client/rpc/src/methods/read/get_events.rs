@@ -108,7 +108,11 @@ where
 }
 
 #[inline]
-fn event_match_filter(event: &EmittedEvent, address: Option<Felt252Wrapper>, keys: &[Vec<FieldElement>]) -> bool {
+pub(crate) fn event_match_filter(
+    event: &EmittedEvent,
+    address: Option<Felt252Wrapper>,
+    keys: &[Vec<FieldElement>],
+) -> bool {
     let match_from_address = address.map_or(true, |addr| addr.0 == event.from_address);
     let match_keys = keys
         .iter()
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::RpcResult;
+use mc_db::{DeoxysBackend, L1MessageRecord};
+use mc_sync::utility::chain_id;
+use mp_felt::Felt252Wrapper;
+use mp_hashers::HasherT;
+use mp_transactions::compute_hash::ComputeTransactionHash;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use starknet_api::core::{EntryPointSelector, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{Calldata, L1HandlerTransaction, TransactionVersion};
+use starknet_core::types::FieldElement;
+
+use crate::deoxys_backend_client::get_block_by_block_hash;
+use crate::errors::StarknetRpcApiError;
+use crate::types::{MessageFinalityStatus, MessageStatus};
+use crate::utils::helpers::is_accepted_on_l1;
+use crate::Starknet;
+
+/// Given an L1 transaction hash, returns the status of the messages it sent to L2
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the L1 transaction which sent the messages.
+///
+/// ### Returns
+///
+/// For each message sent by the transaction, in emission order, the hash of the L1 handler
+/// transaction consuming it along with its finality status. Messages which have not been consumed
+/// on L2 yet are reported as `RECEIVED`.
+///
+/// ### Errors
+///
+/// * `TXN_HASH_NOT_FOUND` - If no message sent by the given L1 transaction has been indexed.
+pub fn get_messages_status<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    transaction_hash: H256,
+) -> RpcResult<Vec<MessageStatus>>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    let messaging = DeoxysBackend::messaging();
+
+    let messages = messaging.l1_messages_from_l1_transaction(transaction_hash).map_err(|e| {
+        log::error!("Failed to retrieve messages sent by L1 transaction {transaction_hash:#x}: {e}");
+        StarknetRpcApiError::InternalServerError
+    })?;
+    if messages.is_empty() {
+        return Err(StarknetRpcApiError::TxnHashNotFound.into());
+    }
+
+    messages
+        .iter()
+        .map(|message| {
+            let l1_handler_hash = messaging.l1_handler_transaction(message.nonce).map_err(|e| {
+                log::error!("Failed to retrieve L1 handler transaction for message #{}: {e}", message.nonce);
+                StarknetRpcApiError::InternalServerError
+            })?;
+
+            let Some(l1_handler_hash) = l1_handler_hash else {
+                return Ok(MessageStatus {
+                    transaction_hash: l1_handler_transaction_hash::<H>(message),
+                    finality_status: MessageFinalityStatus::Received,
+                });
+            };
+
+            let substrate_block_hash = DeoxysBackend::mapping()
+                .substrate_block_hash_from_transaction_hash(l1_handler_hash)
+                .map_err(|e| {
+                    log::error!("Failed to get substrate block hash from transaction hash: {e}");
+                    StarknetRpcApiError::InternalServerError
+                })?
+                .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+            let block_number = get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash)
+                .map_err(|e| {
+                    log::error!("Failed to get block for block hash {substrate_block_hash}: '{e}'");
+                    StarknetRpcApiError::InternalServerError
+                })?
+                .header()
+                .block_number;
+
+            let finality_status = if is_accepted_on_l1(block_number) {
+                MessageFinalityStatus::AcceptedOnL1
+            } else {
+                MessageFinalityStatus::AcceptedOnL2
+            };
+
+            Ok(MessageStatus { transaction_hash: Felt252Wrapper::from(l1_handler_hash).into(), finality_status })
+        })
+        .collect()
+}
+
+/// Computes the hash of the L1 handler transaction which will consume a message
+fn l1_handler_transaction_hash<H: HasherT>(message: &L1MessageRecord) -> FieldElement {
+    let mut from_address = [0u8; 32];
+    from_address[12..].copy_from_slice(message.from_address.as_bytes());
+
+    let calldata =
+        std::iter::once(StarkFelt::new_unchecked(from_address)).chain(message.payload.iter().copied()).collect();
+    let tx = L1HandlerTransaction {
+        version: TransactionVersion::ZERO,
+        nonce: Nonce(message.nonce),
+        contract_address: Felt252Wrapper::from(message.to_address).into(),
+        entry_point_selector: EntryPointSelector(message.selector),
+        calldata: Calldata(Arc::new(calldata)),
+    };
+
+    Felt252Wrapper::from(tx.compute_hash::<H>(chain_id().into(), false, None)).into()
+}
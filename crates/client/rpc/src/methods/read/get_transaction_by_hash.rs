@@ -1,18 +1,21 @@
+use blockifier::transaction::account_transaction::AccountTransaction;
 use jsonrpsee::core::RpcResult;
 use mc_db::DeoxysBackend;
+use mc_sync::utility::chain_id;
 use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
+use mp_transactions::from_broadcasted_transactions::ToAccountTransaction;
 use mp_transactions::to_starknet_core_transaction::to_starknet_core_tx;
 use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use starknet_core::types::{FieldElement, Transaction};
+use starknet_core::types::{BroadcastedTransaction, FieldElement, Transaction};
 
 use crate::deoxys_backend_client::get_block_by_block_hash;
 use crate::errors::StarknetRpcApiError;
 use crate::utils::helpers::{block_hash_from_block_n, txs_hashes_from_block_hash};
-use crate::Starknet;
+use crate::{submitted_transactions, Starknet};
 
 /// Get the details and status of a submitted transaction.
 ///
@@ -30,8 +33,9 @@ use crate::Starknet;
 /// Returns information about the requested transaction, including its status, sender,
 /// recipient, and other transaction details. The information is encapsulated in a `Transaction`
 /// type, which is a combination of the `TXN` schema and additional properties, such as the
-/// `transaction_hash`. In case the specified transaction hash is not found, returns a
-/// `StarknetRpcApiError` with `TXN_HASH_NOT_FOUND`.
+/// `transaction_hash`. A transaction submitted through the node is returned as soon as it is
+/// received, before it is part of a block. In case the specified transaction hash is not found,
+/// returns a `StarknetRpcApiError` with `TXN_HASH_NOT_FOUND`.
 ///
 /// ### Errors
 ///
@@ -55,8 +59,13 @@ where
         .map_err(|e| {
             log::error!("Failed to get substrate block hash from transaction hash: {}", e);
             StarknetRpcApiError::InternalServerError
-        })?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+        })?;
+    let Some(substrate_block_hash) = substrate_block_hash else {
+        return match submitted_transactions::received_transaction(&transaction_hash) {
+            Some(transaction) => received_transaction(transaction, transaction_hash),
+            None => Err(StarknetRpcApiError::TxnHashNotFound.into()),
+        };
+    };
 
     let starknet_block = get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash)?;
     let block_number = starknet_block.header().block_number;
@@ -77,3 +86,18 @@ where
         None => Err(StarknetRpcApiError::InternalServerError.into()),
     }
 }
+
+/// Converts a transaction submitted through the node to the transaction the sequencer received
+fn received_transaction(transaction: BroadcastedTransaction, transaction_hash: FieldElement) -> RpcResult<Transaction> {
+    let transaction = transaction.to_account_transaction(Felt252Wrapper(chain_id())).map_err(|e| {
+        log::error!("Failed to convert submitted transaction '{transaction_hash:#x}': {e}");
+        StarknetRpcApiError::InternalServerError
+    })?;
+    let transaction = match transaction {
+        AccountTransaction::Declare(tx) => starknet_api::transaction::Transaction::Declare(tx.tx),
+        AccountTransaction::DeployAccount(tx) => starknet_api::transaction::Transaction::DeployAccount(tx.tx),
+        AccountTransaction::Invoke(tx) => starknet_api::transaction::Transaction::Invoke(tx.tx),
+    };
+
+    Ok(to_starknet_core_tx(transaction, transaction_hash))
+}
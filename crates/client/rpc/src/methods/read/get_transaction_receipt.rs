@@ -3,6 +3,8 @@ use blockifier::transaction::objects::TransactionExecutionInfo;
 use blockifier::transaction::transaction_execution as btx;
 use jsonrpsee::core::RpcResult;
 use mc_db::DeoxysBackend;
+use mc_sync::l2::get_pending_block;
+use mc_sync::utility::chain_id;
 use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
 use mp_types::block::{DBlockT, DHashT};
@@ -14,8 +16,7 @@ use starknet_api::transaction::Transaction;
 use starknet_core::types::{
     ComputationResources, DataAvailabilityResources, DataResources, DeclareTransactionReceipt,
     DeployAccountTransactionReceipt, ExecutionResources, ExecutionResult, FieldElement, Hash256,
-    InvokeTransactionReceipt, L1HandlerTransactionReceipt, TransactionFinalityStatus, TransactionReceipt,
-    TransactionReceiptWithBlockInfo,
+    InvokeTransactionReceipt, L1HandlerTransactionReceipt, TransactionReceipt, TransactionReceiptWithBlockInfo,
 };
 
 use crate::deoxys_backend_client::get_block_by_block_hash;
@@ -23,10 +24,12 @@ use crate::errors::StarknetRpcApiError;
 use crate::utils::call_info::{
     blockifier_call_info_to_starknet_resources, extract_events_from_call_info, extract_messages_from_call_info,
 };
-use crate::utils::execution::{block_context, re_execute_transactions};
-use crate::utils::helpers::{block_hash_from_block_n, tx_hash_retrieve, txs_hashes_from_block_hash};
+use crate::utils::execution::{block_context, header_block_context, re_execute_transactions};
+use crate::utils::helpers::{
+    block_hash_from_block_n, finality_status, tx_hash_compute, tx_hash_retrieve, txs_hashes_from_block_hash,
+};
 use crate::utils::transaction::blockifier_transactions;
-use crate::Starknet;
+use crate::{Felt, Starknet};
 
 /// Get the transaction receipt by the transaction hash.
 ///
@@ -50,6 +53,9 @@ use crate::Starknet;
 ///
 /// The function may return a `TXN_HASH_NOT_FOUND` error if the specified transaction hash is
 /// not found.
+///
+/// A transaction submitted through the node has no receipt until the pending block contains it,
+/// as it is not executed before.
 pub async fn get_transaction_receipt<BE, C, H>(
     starknet: &Starknet<BE, C, H>,
     transaction_hash: FieldElement,
@@ -65,10 +71,15 @@ where
         .map_err(|e| {
             log::error!("Failed to get substrate block hash from transaction hash: {}", e);
             StarknetRpcApiError::InternalServerError
-        })?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+        })?;
 
-    get_transaction_receipt_finalized(starknet, substrate_block_hash, transaction_hash)
+    match substrate_block_hash {
+        Some(substrate_block_hash) => {
+            get_transaction_receipt_finalized(starknet, substrate_block_hash, transaction_hash)
+        }
+        None => get_transaction_receipt_pending::<H>(transaction_hash)?
+            .ok_or_else(|| StarknetRpcApiError::TxnHashNotFound.into()),
+    }
 }
 
 pub fn get_transaction_receipt_finalized<BE, C, H>(
@@ -123,6 +134,44 @@ where
     Ok(TransactionReceiptWithBlockInfo { receipt, block: block_info })
 }
 
+/// Get the receipt of a transaction of the pending block, if it is part of it
+fn get_transaction_receipt_pending<H>(
+    transaction_hash: FieldElement,
+) -> RpcResult<Option<TransactionReceiptWithBlockInfo>>
+where
+    H: HasherT + Send + Sync + 'static,
+{
+    let Some(block) = get_pending_block() else {
+        return Ok(None);
+    };
+    let block_txs_hashes = tx_hash_compute::<H>(&block, Felt(chain_id()));
+
+    let Some(tx_index) = block_txs_hashes.iter().position(|hash| *hash == transaction_hash) else {
+        return Ok(None);
+    };
+    let transaction = &block.transactions()[tx_index];
+
+    // deploy transaction was not supported by blockifier
+    if let Transaction::Deploy(_) = transaction {
+        log::error!("re-executing a deploy transaction is not supported");
+        return Err(StarknetRpcApiError::UnimplementedMethod.into());
+    }
+
+    // the pending block is executed on top of the latest block, in its own context
+    let block_context = header_block_context(block.header());
+
+    let transaction_with_hash =
+        block.transactions().iter().cloned().zip(block_txs_hashes.iter().cloned()).take(tx_index + 1).collect();
+
+    let transactions_blockifier = blockifier_transactions(transaction_with_hash)?;
+
+    let execution_infos = execution_infos(transactions_blockifier, &block_context)?;
+
+    let receipt = receipt(transaction, &execution_infos, transaction_hash, block.header().block_number)?;
+
+    Ok(Some(TransactionReceiptWithBlockInfo { receipt, block: starknet_core::types::ReceiptBlock::Pending }))
+}
+
 pub(crate) fn execution_infos(
     transactions: Vec<btx::Transaction>,
     block_context: &BlockContext,
@@ -159,11 +208,7 @@ pub fn receipt(
         unit: starknet_core::types::PriceUnit::Wei,
     };
 
-    let finality_status = if block_number <= mc_sync::l1::ETHEREUM_STATE_UPDATE.read().unwrap().block_number {
-        TransactionFinalityStatus::AcceptedOnL1
-    } else {
-        TransactionFinalityStatus::AcceptedOnL2
-    };
+    let finality_status = finality_status(block_number);
 
     let execution_result = match execution_infos.revert_error.clone() {
         Some(err) => ExecutionResult::Reverted { reason: err },
@@ -1,18 +1,19 @@
 use jsonrpsee::core::RpcResult;
 use mc_db::DeoxysBackend;
+use mc_sync::l2::get_pending_execution_result;
 use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
-use mp_transactions::to_starknet_core_transaction::to_starknet_core_tx;
 use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use starknet_core::types::{FieldElement, TransactionStatus};
+use starknet_api::hash::StarkHash;
+use starknet_core::types::{FieldElement, TransactionExecutionStatus, TransactionFinalityStatus, TransactionStatus};
 
 use crate::deoxys_backend_client::get_block_by_block_hash;
 use crate::errors::StarknetRpcApiError;
-use crate::utils::helpers::{block_hash_from_block_n, txs_hashes_from_block_hash};
-use crate::Starknet;
+use crate::utils::helpers::finality_status;
+use crate::{submitted_transactions, Starknet};
 
 /// Gets the Transaction Status, Including Mempool Status and Execution Details
 ///
@@ -21,6 +22,11 @@ use crate::Starknet;
 /// mempool. The status includes both finality status and execution status of the
 /// transaction.
 ///
+/// The execution status is the one reported by the receipt of the transaction when its block was
+/// synced, so that the transaction is not executed again. A transaction submitted through the node
+/// is `RECEIVED` until the pending block or a synced block contains it, and `REJECTED` if none does
+/// before the submission timeout.
+///
 /// ### Arguments
 ///
 /// * `transaction_hash` - The hash of the transaction for which the status is requested.
@@ -41,46 +47,92 @@ where
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
     H: HasherT + Send + Sync + 'static,
 {
-    let substrate_block_hash = DeoxysBackend::mapping()
-        .substrate_block_hash_from_transaction_hash(Felt252Wrapper(transaction_hash).into())
-        .map_err(|e| {
+    let tx_hash: StarkHash = Felt252Wrapper(transaction_hash).into();
+    let substrate_block_hash =
+        DeoxysBackend::mapping().substrate_block_hash_from_transaction_hash(tx_hash).map_err(|e| {
             log::error!("Failed to get substrate block hash from transaction hash: {}", e);
             StarknetRpcApiError::InternalServerError
-        })?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+        })?;
+
+    let Some(substrate_block_hash) = substrate_block_hash else {
+        return unsynced_transaction_status(transaction_hash)
+            .ok_or_else(|| StarknetRpcApiError::TxnHashNotFound.into());
+    };
+
+    let block = get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash).map_err(|e| {
+        log::error!("Failed to retrieve block with hash {substrate_block_hash:?}: {e}");
+        StarknetRpcApiError::InternalServerError
+    })?;
+    let revert_reason = DeoxysBackend::mapping().transaction_revert_reason(tx_hash).map_err(|e| {
+        log::error!("Failed to get the revert reason of transaction {transaction_hash:#x}: {e}");
+        StarknetRpcApiError::InternalServerError
+    })?;
+
+    let execution_status = match revert_reason {
+        Some(_) => TransactionExecutionStatus::Reverted,
+        None => TransactionExecutionStatus::Succeeded,
+    };
+    Ok(match finality_status(block.header().block_number) {
+        TransactionFinalityStatus::AcceptedOnL2 => TransactionStatus::AcceptedOnL2(execution_status),
+        TransactionFinalityStatus::AcceptedOnL1 => TransactionStatus::AcceptedOnL1(execution_status),
+    })
+}
+
+/// Returns the status of a transaction which is not in a synced block, from the receipt of the
+/// pending block or from the transactions submitted through the node
+fn unsynced_transaction_status(transaction_hash: FieldElement) -> Option<TransactionStatus> {
+    match get_pending_execution_result(&Felt252Wrapper(transaction_hash).into()) {
+        Some(execution_result) => Some(TransactionStatus::AcceptedOnL2(execution_result.status())),
+        None => submitted_transactions::status(&transaction_hash),
+    }
+}
 
-    let starknet_block = get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash)?;
-    let block_number = starknet_block.header().block_number;
-    let starknet_block_hash = block_hash_from_block_n(block_number)?;
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-    let _starknet_tx = txs_hashes_from_block_hash(starknet_block_hash)?
-        .into_iter()
-        .zip(starknet_block.transactions())
-        .find(|(tx_hash, _)| *tx_hash == Felt252Wrapper(transaction_hash).into())
-        .map(|(_, tx)| to_starknet_core_tx(tx.clone(), transaction_hash));
+    use mc_sync::l2::update_pending;
+    use mp_block::DeoxysBlock;
+    use starknet_core::types::{ExecutionResult, PendingStateUpdate, StateDiff};
 
-    // TODO: Implement this method
-    Err(StarknetRpcApiError::UnimplementedMethod.into())
+    use super::*;
 
-    // let execution_status = {
-    //     let revert_error = starknet
-    //         .client
-    //         .runtime_api()
-    //         .get_tx_execution_outcome(substrate_block_hash,
-    // Felt252Wrapper(transaction_hash).into())         .map_err(|e| {
-    //             log::error!(
-    //                 "Failed to get transaction execution outcome. Substrate block hash:
-    // {substrate_block_hash}, \                  transaction hash: {transaction_hash}, error:
-    // {e}"             );
-    //             StarknetRpcApiError::InternalServerError
-    //         })?;
+    #[test]
+    fn test_pending_block_receipt() {
+        let succeeded = FieldElement::from(0x5c3_u64);
+        let reverted = FieldElement::from(0x2e7_u64);
+        let submitted = FieldElement::from(0x5b8_u64);
+        submitted_transactions::insert(submitted, submitted_transactions::tests::invoke_transaction(0));
 
-    //     if revert_error.is_none() {
-    //         TransactionExecutionStatus::Succeeded
-    //     } else {
-    //         TransactionExecutionStatus::Reverted
-    //     }
-    // };
+        let state_update = PendingStateUpdate {
+            old_root: FieldElement::ZERO,
+            state_diff: StateDiff {
+                storage_diffs: vec![],
+                deprecated_declared_classes: vec![],
+                declared_classes: vec![],
+                deployed_contracts: vec![],
+                replaced_classes: vec![],
+                nonces: vec![],
+            },
+        };
+        update_pending(
+            DeoxysBlock::default(),
+            vec![Felt252Wrapper(succeeded).into(), Felt252Wrapper(reverted).into()],
+            vec![ExecutionResult::Succeeded, ExecutionResult::Reverted { reason: "Insufficient balance".into() }],
+            state_update,
+            HashMap::new(),
+        );
 
-    // Ok(TransactionStatus::AcceptedOnL2(execution_status))
+        assert_eq!(
+            unsynced_transaction_status(succeeded),
+            Some(TransactionStatus::AcceptedOnL2(TransactionExecutionStatus::Succeeded))
+        );
+        assert_eq!(
+            unsynced_transaction_status(reverted),
+            Some(TransactionStatus::AcceptedOnL2(TransactionExecutionStatus::Reverted))
+        );
+        // not in the pending block yet
+        assert_eq!(unsynced_transaction_status(submitted), Some(TransactionStatus::Received));
+        assert_eq!(unsynced_transaction_status(FieldElement::ONE), None);
+    }
 }
@@ -1,9 +1,11 @@
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use jsonrpsee::core::{async_trait, RpcResult};
 use mp_hashers::HasherT;
 use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
+use sp_core::H256;
 use starknet_core::types::{
     BlockHashAndNumber, BlockId, BroadcastedTransaction, ContractClass, EventFilterWithPage, EventsPage, FeeEstimate,
     FieldElement, FunctionCall, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
@@ -22,7 +24,9 @@ use super::get_block_with_txs::*;
 use super::get_class::*;
 use super::get_class_at::*;
 use super::get_class_hash_at::*;
+use super::get_compiled_casm::*;
 use super::get_events::*;
+use super::get_messages_status::*;
 use super::get_nonce::*;
 use super::get_state_update::*;
 use super::get_storage_at::*;
@@ -31,6 +35,7 @@ use super::get_transaction_by_hash::*;
 use super::get_transaction_receipt::*;
 use super::get_transaction_status::*;
 use super::syncing::*;
+use crate::types::MessageStatus;
 use crate::{Felt, Starknet, StarknetReadRpcApiServer};
 
 #[async_trait]
@@ -77,6 +82,10 @@ where
         estimate_message_fee(self, message, block_id).await
     }
 
+    fn get_messages_status(&self, transaction_hash: H256) -> RpcResult<Vec<MessageStatus>> {
+        get_messages_status(self, transaction_hash)
+    }
+
     async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithReceipts> {
         get_block_with_receipts(self, block_id)
     }
@@ -101,6 +110,10 @@ where
         get_class(block_id, class_hash)
     }
 
+    fn get_compiled_casm(&self, class_hash: FieldElement) -> RpcResult<CasmContractClass> {
+        get_compiled_casm(class_hash)
+    }
+
     async fn get_events(&self, filter: EventFilterWithPage) -> RpcResult<EventsPage> {
         get_events(self, filter).await
     }
@@ -9,7 +9,9 @@ pub mod get_block_with_txs;
 pub mod get_class;
 pub mod get_class_at;
 pub mod get_class_hash_at;
+pub mod get_compiled_casm;
 pub mod get_events;
+pub mod get_messages_status;
 pub mod get_nonce;
 pub mod get_state_update;
 pub mod get_storage_at;
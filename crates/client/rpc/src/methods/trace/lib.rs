@@ -51,7 +51,9 @@ pub enum ConvertCallInfoToExecuteInvocationError {
 impl From<ConvertCallInfoToExecuteInvocationError> for StarknetRpcApiError {
     fn from(err: ConvertCallInfoToExecuteInvocationError) -> Self {
         match err {
-            ConvertCallInfoToExecuteInvocationError::TransactionExecutionFailed => StarknetRpcApiError::ContractError,
+            ConvertCallInfoToExecuteInvocationError::TransactionExecutionFailed => {
+                StarknetRpcApiError::ContractErrorData { revert_error: err.to_string() }
+            }
             ConvertCallInfoToExecuteInvocationError::GetFunctionInvocation(_) => {
                 StarknetRpcApiError::InternalServerError
             }
@@ -1,5 +1,7 @@
 use blockifier::transaction::objects::{FeeType, HasRelatedFeeType, TransactionExecutionInfo};
 use jsonrpsee::core::RpcResult;
+use mc_sync::utility::chain_id;
+use mp_felt::Felt252Wrapper;
 use mp_hashers::HasherT;
 use mp_simulations::SimulationFlags;
 use mp_transactions::from_broadcasted_transactions::ToAccountTransaction;
@@ -39,10 +41,13 @@ where
         charge_fee: !simulation_flags.contains(&SimulationFlag::SkipFeeCharge),
     };
 
+    let chain_id = Felt252Wrapper(chain_id());
     let tx_type_and_tx_iterator = transactions.into_iter().map(|tx| match tx {
-        BroadcastedTransaction::Invoke(_) => tx.to_account_transaction().map(|tx| (TxType::Invoke, tx)),
-        BroadcastedTransaction::Declare(_) => tx.to_account_transaction().map(|tx| (TxType::Declare, tx)),
-        BroadcastedTransaction::DeployAccount(_) => tx.to_account_transaction().map(|tx| (TxType::DeployAccount, tx)),
+        BroadcastedTransaction::Invoke(_) => tx.to_account_transaction(chain_id).map(|tx| (TxType::Invoke, tx)),
+        BroadcastedTransaction::Declare(_) => tx.to_account_transaction(chain_id).map(|tx| (TxType::Declare, tx)),
+        BroadcastedTransaction::DeployAccount(_) => {
+            tx.to_account_transaction(chain_id).map(|tx| (TxType::DeployAccount, tx))
+        }
     });
     let (tx_types, user_transactions) =
         itertools::process_results(tx_type_and_tx_iterator, |iter| iter.unzip::<_, _, Vec<_>, Vec<_>>()).map_err(
@@ -55,7 +60,7 @@ where
     let fee_types = user_transactions.iter().map(|tx| tx.fee_type()).collect::<Vec<_>>();
 
     let res = utils::execution::simulate_transactions(user_transactions, &simulation_flags, &block_context)
-        .map_err(|_| StarknetRpcApiError::ContractError)?;
+        .map_err(|e| StarknetRpcApiError::ContractErrorData { revert_error: e.to_string() })?;
 
     let simulated_transactions = tx_execution_infos_to_simulated_transactions(tx_types, res, block_number, fee_types)
         .map_err(StarknetRpcApiError::from)?;
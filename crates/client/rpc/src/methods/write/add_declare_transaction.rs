@@ -5,11 +5,12 @@ use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use starknet_core::types::{BroadcastedDeclareTransaction, DeclareTransactionResult};
+use starknet_core::types::{BroadcastedDeclareTransaction, BroadcastedTransaction, DeclareTransactionResult};
 use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 
+use super::validate_transaction::validate_transaction;
 use crate::errors::StarknetRpcApiError;
-use crate::Starknet;
+use crate::{submitted_transactions, Starknet};
 
 /// Submit a new declare transaction to be added to the chain
 ///
@@ -21,7 +22,7 @@ use crate::Starknet;
 ///
 /// * `declare_transaction_result` - the result of the declare transaction
 pub async fn add_declare_transaction<BE, C, H>(
-    _starknet: &Starknet<BE, C, H>,
+    starknet: &Starknet<BE, C, H>,
     declare_transaction: BroadcastedDeclareTransaction,
 ) -> RpcResult<DeclareTransactionResult>
 where
@@ -29,7 +30,10 @@ where
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
     H: HasherT + Send + Sync + 'static,
 {
-    let sequencer = SequencerGatewayProvider::new(feeder_gateway(), gateway(), chain_id());
+    let transaction = BroadcastedTransaction::Declare(declare_transaction.clone());
+    validate_transaction(starknet, &transaction).await?;
+
+    let sequencer = SequencerGatewayProvider::new(gateway(), feeder_gateway(), chain_id());
 
     let sequencer_response = match sequencer.add_declare_transaction(declare_transaction).await {
         Ok(response) => response,
@@ -42,5 +46,7 @@ where
         }
     };
 
+    submitted_transactions::insert(sequencer_response.transaction_hash, transaction);
+
     Ok(sequencer_response)
 }
@@ -5,11 +5,14 @@ use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use starknet_core::types::{BroadcastedDeployAccountTransaction, DeployAccountTransactionResult};
+use starknet_core::types::{
+    BroadcastedDeployAccountTransaction, BroadcastedTransaction, DeployAccountTransactionResult,
+};
 use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 
+use super::validate_transaction::validate_transaction;
 use crate::errors::StarknetRpcApiError;
-use crate::Starknet;
+use crate::{submitted_transactions, Starknet};
 
 /// Add an Deploy Account Transaction
 ///
@@ -22,7 +25,7 @@ use crate::Starknet;
 /// * `transaction_hash` - transaction hash corresponding to the invocation
 /// * `contract_address` - address of the deployed contract account
 pub async fn add_deploy_account_transaction<BE, C, H>(
-    _starknet: &Starknet<BE, C, H>,
+    starknet: &Starknet<BE, C, H>,
     deploy_account_transaction: BroadcastedDeployAccountTransaction,
 ) -> RpcResult<DeployAccountTransactionResult>
 where
@@ -30,7 +33,10 @@ where
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
     H: HasherT + Send + Sync + 'static,
 {
-    let sequencer = SequencerGatewayProvider::new(feeder_gateway(), gateway(), chain_id());
+    let transaction = BroadcastedTransaction::DeployAccount(deploy_account_transaction.clone());
+    validate_transaction(starknet, &transaction).await?;
+
+    let sequencer = SequencerGatewayProvider::new(gateway(), feeder_gateway(), chain_id());
 
     let sequencer_response = match sequencer.add_deploy_account_transaction(deploy_account_transaction).await {
         Ok(response) => response,
@@ -43,5 +49,7 @@ where
         }
     };
 
+    submitted_transactions::insert(sequencer_response.transaction_hash, transaction);
+
     Ok(sequencer_response)
 }
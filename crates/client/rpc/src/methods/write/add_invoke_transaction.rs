@@ -5,11 +5,12 @@ use mp_types::block::DBlockT;
 use sc_client_api::backend::{Backend, StorageProvider};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use starknet_core::types::{BroadcastedInvokeTransaction, InvokeTransactionResult};
+use starknet_core::types::{BroadcastedInvokeTransaction, BroadcastedTransaction, InvokeTransactionResult};
 use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 
+use super::validate_transaction::validate_transaction;
 use crate::errors::StarknetRpcApiError;
-use crate::Starknet;
+use crate::{submitted_transactions, Starknet};
 
 /// Add an Invoke Transaction to invoke a contract function
 ///
@@ -21,7 +22,7 @@ use crate::Starknet;
 ///
 /// * `transaction_hash` - transaction hash corresponding to the invocation
 pub async fn add_invoke_transaction<BE, C, H>(
-    _starknet: &Starknet<BE, C, H>,
+    starknet: &Starknet<BE, C, H>,
     invoke_transaction: BroadcastedInvokeTransaction,
 ) -> RpcResult<InvokeTransactionResult>
 where
@@ -29,7 +30,10 @@ where
     C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
     H: HasherT + Send + Sync + 'static,
 {
-    let sequencer = SequencerGatewayProvider::new(feeder_gateway(), gateway(), chain_id());
+    let transaction = BroadcastedTransaction::Invoke(invoke_transaction.clone());
+    validate_transaction(starknet, &transaction).await?;
+
+    let sequencer = SequencerGatewayProvider::new(gateway(), feeder_gateway(), chain_id());
 
     let sequencer_response = match sequencer.add_invoke_transaction(invoke_transaction).await {
         Ok(response) => response,
@@ -42,5 +46,7 @@ where
         }
     };
 
+    submitted_transactions::insert(sequencer_response.transaction_hash, transaction);
+
     Ok(sequencer_response)
 }
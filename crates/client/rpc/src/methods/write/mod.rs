@@ -2,3 +2,4 @@ pub mod add_declare_transaction;
 pub mod add_deploy_account_transaction;
 pub mod add_invoke_transaction;
 pub mod lib;
+mod validate_transaction;
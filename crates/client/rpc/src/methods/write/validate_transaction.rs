@@ -0,0 +1,92 @@
+use jsonrpsee::core::RpcResult;
+use mc_db::storage_handler::{self, StorageView};
+use mc_sync::utility::chain_id;
+use mp_felt::Felt252Wrapper;
+use mp_hashers::HasherT;
+use mp_transactions::from_broadcasted_transactions::{BroadcastedTransactionConversionError, ToAccountTransaction};
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_core::types::{
+    BlockId, BlockTag, BroadcastedDeclareTransaction, BroadcastedInvokeTransaction, BroadcastedTransaction,
+    FieldElement,
+};
+
+use crate::errors::StarknetRpcApiError;
+use crate::utils::execution::block_context;
+use crate::{utils, Starknet};
+
+/// Checks a transaction against the latest state before it is forwarded to the sequencer, so that
+/// a transaction which cannot be accepted is rejected right away with the spec error: invalid
+/// nonce, insufficient balance or max fee, failed `__validate__`...
+///
+/// A transaction whose nonce is ahead of the account nonce depends on transactions which are not
+/// part of the latest block yet, so it is left to the sequencer.
+pub(crate) async fn validate_transaction<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    transaction: &BroadcastedTransaction,
+) -> RpcResult<()>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    let block_number = starknet.current_block_number()?;
+
+    if let Some((sender_address, nonce)) = sender_address_and_nonce(transaction) {
+        let key = ContractAddress(PatriciaKey(StarkFelt(sender_address.to_bytes_be())));
+        match storage_handler::contract_nonces().get_at(&key, block_number) {
+            Ok(Some(account_nonce)) if nonce <= Felt252Wrapper::from(account_nonce).0 => {}
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                log::error!("Failed to get nonce: {e}");
+                return Err(StarknetRpcApiError::InternalServerError.into());
+            }
+        }
+    }
+
+    let substrate_block_hash = starknet.substrate_block_hash_from_starknet_block(BlockId::Tag(BlockTag::Latest))?;
+    let block_context = block_context(starknet.client.as_ref(), substrate_block_hash)?;
+
+    // Compiling and validating the transaction are blocking, so that they are kept off the async
+    // tasks serving the other requests
+    let transaction = transaction.clone();
+    tokio::task::spawn_blocking(move || {
+        let transaction = match transaction.to_account_transaction(Felt252Wrapper(chain_id())) {
+            Ok(transaction) => transaction,
+            Err(BroadcastedTransactionConversionError::UnsuportedTransactionVersion) => {
+                return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+            }
+            // The Sierra class is compiled locally with the compiler of this node, which may not be
+            // the one of the sequencer, so a mismatch of the compiled class hash or a failed
+            // compilation is left to the sequencer as well
+            Err(e) => {
+                log::debug!("Failed to convert BroadcastedTransaction to AccountTransaction, skipping validation: {e}");
+                return Ok(());
+            }
+        };
+
+        utils::execution::validate_transaction(transaction, &block_context)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Transaction validation failed: {e}");
+        Err(StarknetRpcApiError::InternalServerError)
+    })?;
+
+    Ok(())
+}
+
+fn sender_address_and_nonce(transaction: &BroadcastedTransaction) -> Option<(FieldElement, FieldElement)> {
+    match transaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(tx)) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(tx)) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V1(tx)) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedTransaction::DeployAccount(_) => None,
+    }
+}
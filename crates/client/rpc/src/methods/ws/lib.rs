@@ -0,0 +1,58 @@
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::SubscriptionSink;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{BlockId, FieldElement};
+
+use super::subscribe_events::subscribe_events;
+use super::subscribe_new_heads::subscribe_new_heads;
+use super::subscribe_pending_transactions::subscribe_pending_transactions;
+use super::subscribe_transaction_status::subscribe_transaction_status;
+use super::utils::unsubscribe;
+use crate::types::SubscriptionId;
+use crate::{Starknet, StarknetWsRpcApiServer};
+
+impl<BE, C, H> StarknetWsRpcApiServer for Starknet<BE, C, H>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    fn subscribe_new_heads(&self, sink: SubscriptionSink, block_id: Option<BlockId>) -> SubscriptionResult {
+        subscribe_new_heads(self, sink, block_id)
+    }
+
+    fn subscribe_events(
+        &self,
+        sink: SubscriptionSink,
+        from_address: Option<FieldElement>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        block_id: Option<BlockId>,
+    ) -> SubscriptionResult {
+        subscribe_events(self, sink, from_address, keys, block_id)
+    }
+
+    fn subscribe_transaction_status(
+        &self,
+        sink: SubscriptionSink,
+        transaction_hash: FieldElement,
+    ) -> SubscriptionResult {
+        subscribe_transaction_status(self, sink, transaction_hash)
+    }
+
+    fn subscribe_pending_transactions(
+        &self,
+        sink: SubscriptionSink,
+        transaction_details: Option<bool>,
+        sender_address: Option<Vec<FieldElement>>,
+    ) -> SubscriptionResult {
+        subscribe_pending_transactions::<H>(sink, transaction_details, sender_address)
+    }
+
+    fn unsubscribe(&self, subscription_id: SubscriptionId) -> RpcResult<bool> {
+        Ok(unsubscribe(&subscription_id))
+    }
+}
@@ -0,0 +1,74 @@
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::SubscriptionSink;
+use mc_sync::event_bus;
+use mp_felt::Felt252Wrapper;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{BlockId, FieldElement};
+
+use super::utils::{blocking, first_block, follow_blocks, is_stored, Subscription};
+use crate::constants::MAX_EVENTS_KEYS;
+use crate::errors::StarknetRpcApiError;
+use crate::methods::read::get_events::event_match_filter;
+use crate::Starknet;
+
+/// Subscribes to the events emitted in the new blocks
+///
+/// ### Arguments
+///
+/// * `from_address` - Only send the events emitted by this contract.
+/// * `keys` - Only send the events whose keys match these, with the semantics of
+///   `starknet_getEvents`.
+/// * `block_id` - The stored block to start from, the events of the blocks already stored from this
+///   one on are sent first. Defaults to the latest block.
+///
+/// ### Notifications
+///
+/// * Each matching event once its block is stored.
+pub fn subscribe_events<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    mut sink: SubscriptionSink,
+    from_address: Option<FieldElement>,
+    keys: Option<Vec<Vec<FieldElement>>>,
+    block_id: Option<BlockId>,
+) -> SubscriptionResult
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    let keys = keys.unwrap_or_default();
+    if keys.len() > MAX_EVENTS_KEYS {
+        sink.reject(StarknetRpcApiError::TooManyKeysInFilter)?;
+        return Ok(());
+    }
+
+    // subscribing first, so that no block is missed between the stored blocks and the new ones
+    let sync_events = event_bus::subscribe();
+    let starknet = starknet.clone();
+    let from_address = from_address.map(Felt252Wrapper);
+    tokio::spawn(async move {
+        let first_block = match blocking(move || first_block(block_id)).await {
+            Ok(first_block) => first_block,
+            Err(e) => {
+                let _ = sink.reject(e);
+                return;
+            }
+        };
+        let Some(subscription) = Subscription::accept(&mut sink) else { return };
+
+        subscription
+            .run(follow_blocks(sink, sync_events, first_block, move |block_number| {
+                if !is_stored(block_number)? {
+                    return Ok(None);
+                }
+                let events = starknet.get_block_events(BlockId::Number(block_number))?;
+                Ok(Some(events.into_iter().filter(|event| event_match_filter(event, from_address, &keys)).collect()))
+            }))
+            .await
+    });
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::SubscriptionSink;
+use mc_sync::event_bus;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::BlockId;
+
+use super::utils::{blocking, first_block, follow_blocks, is_stored, Subscription};
+use crate::errors::StarknetRpcApiError;
+use crate::types::BlockHeader;
+use crate::utils::block::{
+    l1_da_mode, l1_data_gas_price, l1_gas_price, new_root, parent_hash, sequencer_address, starknet_version, timestamp,
+};
+use crate::utils::helpers::block_hash_from_block_n;
+use crate::Starknet;
+
+/// Subscribes to the headers of the new blocks
+///
+/// ### Arguments
+///
+/// * `block_id` - The stored block to start from, the headers of the blocks already stored from
+///   this one on are sent first. Defaults to the latest block.
+///
+/// ### Notifications
+///
+/// * The header of each block once it is stored.
+pub fn subscribe_new_heads<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    mut sink: SubscriptionSink,
+    block_id: Option<BlockId>,
+) -> SubscriptionResult
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    // subscribing first, so that no block is missed between the stored blocks and the new ones
+    let sync_events = event_bus::subscribe();
+    let starknet = starknet.clone();
+    tokio::spawn(async move {
+        let first_block = match blocking(move || first_block(block_id)).await {
+            Ok(first_block) => first_block,
+            Err(e) => {
+                let _ = sink.reject(e);
+                return;
+            }
+        };
+        let Some(subscription) = Subscription::accept(&mut sink) else { return };
+
+        subscription
+            .run(follow_blocks(sink, sync_events, first_block, move |block_number| {
+                if !is_stored(block_number)? {
+                    return Ok(None);
+                }
+                Ok(Some(vec![block_header(&starknet, block_number)?]))
+            }))
+            .await
+    });
+    Ok(())
+}
+
+fn block_header<BE, C, H>(starknet: &Starknet<BE, C, H>, block_number: u64) -> Result<BlockHeader, StarknetRpcApiError>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    let block = starknet.get_block_by_number(block_number)?;
+
+    Ok(BlockHeader {
+        block_hash: block_hash_from_block_n(block_number)?,
+        parent_hash: parent_hash(&block),
+        block_number,
+        new_root: new_root(&block),
+        timestamp: timestamp(&block),
+        sequencer_address: sequencer_address(&block),
+        l1_gas_price: l1_gas_price(&block),
+        l1_data_gas_price: l1_data_gas_price(&block),
+        l1_da_mode: l1_da_mode(&block),
+        starknet_version: starknet_version(&block),
+    })
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::SubscriptionSink;
+use mc_sync::event_bus::{self, SyncEvent};
+use mc_sync::l2::get_pending_block;
+use mc_sync::utility::chain_id;
+use mp_hashers::HasherT;
+use starknet_core::types::{DeclareTransaction, FieldElement, InvokeTransaction, Transaction};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use super::utils::{blocking, send, Subscription};
+use crate::constants::MAX_SUBSCRIPTION_SENDER_ADDRESSES;
+use crate::errors::StarknetRpcApiError;
+use crate::types::PendingTransaction;
+use crate::utils::helpers::{tx_conv, tx_hash_compute};
+use crate::Felt;
+
+/// Subscribes to the transactions added to the pending block
+///
+/// ### Arguments
+///
+/// * `transaction_details` - Whether to send the whole transactions instead of their hashes.
+///   Defaults to false.
+/// * `sender_address` - Only send the transactions sent by these addresses. Deploy and deploy
+///   account transactions have no sender and are left out by this filter.
+///
+/// ### Notifications
+///
+/// * Each transaction of the pending block not sent yet, the ones already in the pending block when
+///   subscribing included.
+pub fn subscribe_pending_transactions<H>(
+    mut sink: SubscriptionSink,
+    transaction_details: Option<bool>,
+    sender_address: Option<Vec<FieldElement>>,
+) -> SubscriptionResult
+where
+    H: HasherT + Send + Sync + 'static,
+{
+    let sender_address = sender_address.map(HashSet::<FieldElement>::from_iter);
+    if sender_address.as_ref().is_some_and(|addresses| addresses.len() > MAX_SUBSCRIPTION_SENDER_ADDRESSES) {
+        sink.reject(StarknetRpcApiError::TooManyAddressesInFilter)?;
+        return Ok(());
+    }
+
+    let sync_events = event_bus::subscribe();
+    tokio::spawn(async move {
+        let Some(subscription) = Subscription::accept(&mut sink) else { return };
+        subscription
+            .run(follow_pending_transactions::<H>(
+                sink,
+                sync_events,
+                transaction_details.unwrap_or_default(),
+                sender_address,
+            ))
+            .await
+    });
+    Ok(())
+}
+
+async fn follow_pending_transactions<H>(
+    mut sink: SubscriptionSink,
+    mut sync_events: Receiver<SyncEvent>,
+    transaction_details: bool,
+    sender_address: Option<HashSet<FieldElement>>,
+) where
+    H: HasherT + Send + Sync + 'static,
+{
+    // transactions of the pending block already sent, the ones which left it are removed
+    let mut sent = HashSet::new();
+
+    loop {
+        // the transaction hashes are computed on the blocking threads
+        let pending_transactions = blocking(|| {
+            Ok(get_pending_block().map(|block| {
+                let tx_hashes = tx_hash_compute::<H>(&block, Felt(chain_id()));
+                tx_conv(block.transactions(), tx_hashes)
+            }))
+        })
+        .await;
+        let pending_transactions = match pending_transactions {
+            Ok(pending_transactions) => pending_transactions,
+            Err(e) => {
+                sink.close(e);
+                return;
+            }
+        };
+
+        if let Some(transactions) = pending_transactions {
+            sent.retain(|tx_hash| transactions.iter().any(|tx| tx.transaction_hash() == tx_hash));
+
+            for tx in transactions {
+                let tx_hash = *tx.transaction_hash();
+                if sent.contains(&tx_hash) {
+                    continue;
+                }
+                if let Some(sender_address) = &sender_address
+                    && !self::sender_address(&tx).is_some_and(|address| sender_address.contains(&address))
+                {
+                    continue;
+                }
+
+                let notification = if transaction_details {
+                    PendingTransaction::Transaction(tx)
+                } else {
+                    PendingTransaction::Hash(tx_hash)
+                };
+                if !send(&mut sink, &notification) {
+                    return;
+                }
+                sent.insert(tx_hash);
+            }
+        }
+
+        // waits for the pending block to be updated
+        loop {
+            match sync_events.recv().await {
+                Ok(SyncEvent::PendingBlockUpdated(_)) | Err(RecvError::Lagged(_)) => break,
+                Ok(_) => {}
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// The account sending the transaction, or the contract an L1 handler transaction is sent to
+fn sender_address(tx: &Transaction) -> Option<FieldElement> {
+    match tx {
+        Transaction::Invoke(InvokeTransaction::V0(tx)) => Some(tx.contract_address),
+        Transaction::Invoke(InvokeTransaction::V1(tx)) => Some(tx.sender_address),
+        Transaction::Invoke(InvokeTransaction::V3(tx)) => Some(tx.sender_address),
+        Transaction::Declare(DeclareTransaction::V0(tx)) => Some(tx.sender_address),
+        Transaction::Declare(DeclareTransaction::V1(tx)) => Some(tx.sender_address),
+        Transaction::Declare(DeclareTransaction::V2(tx)) => Some(tx.sender_address),
+        Transaction::Declare(DeclareTransaction::V3(tx)) => Some(tx.sender_address),
+        Transaction::L1Handler(tx) => Some(tx.contract_address),
+        Transaction::Deploy(_) | Transaction::DeployAccount(_) => None,
+    }
+}
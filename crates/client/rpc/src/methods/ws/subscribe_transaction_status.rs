@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::SubscriptionSink;
+use mc_db::DeoxysBackend;
+use mc_sync::event_bus::{self, SyncEvent};
+use mc_sync::l2::get_pending_block;
+use mc_sync::utility::chain_id;
+use mp_felt::Felt252Wrapper;
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_api::hash::StarkHash;
+use starknet_core::types::{FieldElement, TransactionFinalityStatus};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use super::utils::{blocking, send, Subscription};
+use crate::deoxys_backend_client::get_block_by_block_hash;
+use crate::errors::StarknetRpcApiError;
+use crate::types::{TransactionFinality, TransactionStatusUpdate};
+use crate::utils::helpers::{finality_status, tx_hash_compute};
+use crate::{Felt, Starknet};
+
+/// Subscribes to the finality status of a transaction
+///
+/// The transaction is `ACCEPTED_ON_L2` once it is in the pending block or a stored block, and
+/// `ACCEPTED_ON_L1` once its block is verified on L1. The execution status is not sent, as it
+/// requires executing the transaction.
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the transaction, which may not be known to the node yet.
+///
+/// ### Notifications
+///
+/// * The status of the transaction each time it changes. The subscription ends once the transaction
+///   is accepted on L1.
+pub fn subscribe_transaction_status<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    mut sink: SubscriptionSink,
+    transaction_hash: FieldElement,
+) -> SubscriptionResult
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    // subscribing first, so that the transaction cannot be stored unnoticed after it is looked up
+    let sync_events = event_bus::subscribe();
+    let starknet = starknet.clone();
+    tokio::spawn(async move {
+        let Some(subscription) = Subscription::accept(&mut sink) else { return };
+        subscription
+            .run(follow_transaction(sink, sync_events, transaction_hash, move || {
+                match transaction_block_number(&starknet, transaction_hash)? {
+                    Some(block_number) => Ok(Some(Inclusion::Block(block_number))),
+                    None if is_in_pending_block::<H>(transaction_hash) => Ok(Some(Inclusion::PendingBlock)),
+                    None => Ok(None),
+                }
+            }))
+            .await
+    });
+    Ok(())
+}
+
+/// The block a transaction is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inclusion {
+    Block(u64),
+    PendingBlock,
+}
+
+/// Sends the status of a transaction each time it changes
+///
+/// `lookup` finds the block the transaction is in. It reads the db, and runs on the blocking
+/// threads, when the subscription starts or lags behind the sync.
+async fn follow_transaction<F>(
+    mut sink: SubscriptionSink,
+    mut sync_events: Receiver<SyncEvent>,
+    transaction_hash: FieldElement,
+    lookup: F,
+) where
+    F: Fn() -> Result<Option<Inclusion>, StarknetRpcApiError> + Send + Sync + 'static,
+{
+    let tx_hash: StarkHash = Felt252Wrapper(transaction_hash).into();
+    let lookup = Arc::new(lookup);
+    let mut inclusion = None;
+    let mut needs_lookup = true;
+    let mut last_status = None;
+
+    loop {
+        if needs_lookup {
+            let lookup = Arc::clone(&lookup);
+            inclusion = match blocking(move || lookup()).await {
+                Ok(inclusion) => inclusion,
+                Err(e) => {
+                    sink.close(e);
+                    return;
+                }
+            };
+            needs_lookup = false;
+        }
+
+        let status = inclusion.map(|inclusion| match inclusion {
+            Inclusion::Block(block_number) => finality_status(block_number),
+            Inclusion::PendingBlock => TransactionFinalityStatus::AcceptedOnL2,
+        });
+        if let Some(finality_status) = status
+            && status != last_status
+        {
+            let update = TransactionStatusUpdate { transaction_hash, status: TransactionFinality { finality_status } };
+            if !send(&mut sink, &update) {
+                return;
+            }
+            if finality_status == TransactionFinalityStatus::AcceptedOnL1 {
+                return;
+            }
+            last_status = status;
+        }
+
+        match sync_events.recv().await {
+            Ok(SyncEvent::BlockImported(block)) => {
+                if block.tx_hashes.contains(&tx_hash) {
+                    inclusion = Some(Inclusion::Block(block.header.block_number));
+                }
+            }
+            Ok(SyncEvent::PendingBlockUpdated(block)) => {
+                if inclusion.is_none() && block.tx_hashes.contains(&tx_hash) {
+                    inclusion = Some(Inclusion::PendingBlock);
+                }
+            }
+            // the status is computed again against the last block verified on L1
+            Ok(SyncEvent::L1StateVerified(_)) => {}
+            // the sync keeps the stored blocks on a detected reorg
+            Ok(SyncEvent::ReorgDetected(_)) => {}
+            Err(RecvError::Lagged(_)) => needs_lookup = true,
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Returns the number of the stored block containing the transaction, if any
+fn transaction_block_number<BE, C, H>(
+    starknet: &Starknet<BE, C, H>,
+    transaction_hash: FieldElement,
+) -> Result<Option<u64>, StarknetRpcApiError>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    let substrate_block_hash = DeoxysBackend::mapping()
+        .substrate_block_hash_from_transaction_hash(Felt252Wrapper(transaction_hash).into())
+        .map_err(|e| {
+            log::error!("Failed to get substrate block hash from transaction hash: {}", e);
+            StarknetRpcApiError::InternalServerError
+        })?;
+
+    match substrate_block_hash {
+        Some(substrate_block_hash) => {
+            let starknet_block =
+                get_block_by_block_hash(starknet.client.as_ref(), substrate_block_hash).map_err(|e| {
+                    log::error!("'{e}'");
+                    StarknetRpcApiError::BlockNotFound
+                })?;
+            Ok(Some(starknet_block.header().block_number))
+        }
+        None => Ok(None),
+    }
+}
+
+fn is_in_pending_block<H>(transaction_hash: FieldElement) -> bool
+where
+    H: HasherT + Send + Sync + 'static,
+{
+    get_pending_block().is_some_and(|block| tx_hash_compute::<H>(&block, Felt(chain_id())).contains(&transaction_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpsee::RpcModule;
+    use mc_sync::event_bus::{ImportedBlock, PendingBlock, StateDiffSummary};
+    use mc_sync::l1::{L1StateUpdate, ETHEREUM_STATE_UPDATE};
+    use mp_block::Header;
+    use tokio::sync::broadcast;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_follow_transaction() {
+        let (sync_events, _) = broadcast::channel(16);
+        let transaction_hash = FieldElement::from(0x7a5_u64);
+        let tx_hash: StarkHash = Felt252Wrapper(transaction_hash).into();
+
+        let mut module = RpcModule::new(());
+        let events = sync_events.clone();
+        module
+            .register_subscription("subscribe", "notification", "unsubscribe", move |_, mut sink, _| {
+                let sync_events = events.subscribe();
+                tokio::spawn(async move {
+                    let Some(subscription) = Subscription::accept(&mut sink) else { return };
+                    subscription.run(follow_transaction(sink, sync_events, transaction_hash, || Ok(None))).await
+                });
+                Ok(())
+            })
+            .unwrap();
+        let mut subscription = module.subscribe("subscribe", Vec::<u8>::new()).await.unwrap();
+        let mut next = || async {
+            tokio::time::timeout(Duration::from_secs(5), subscription.next::<TransactionStatusUpdate>())
+                .await
+                .expect("timed out waiting for a notification")
+                .map(|notification| notification.unwrap().0.status.finality_status)
+        };
+
+        // the transaction is in the block after the last one verified on L1
+        let l1_block = |block_number| L1StateUpdate {
+            block_number,
+            global_root: StarkHash::default(),
+            block_hash: StarkHash::default(),
+        };
+        let tip = 1_000_000_000;
+        *ETHEREUM_STATE_UPDATE.write().unwrap() = l1_block(tip - 1);
+
+        let pending_block =
+            |tx_hashes| SyncEvent::PendingBlockUpdated(Arc::new(PendingBlock { header: Header::default(), tx_hashes }));
+        sync_events.send(pending_block(vec![])).unwrap();
+        sync_events.send(pending_block(vec![tx_hash])).unwrap();
+        assert_eq!(next().await, Some(TransactionFinalityStatus::AcceptedOnL2));
+
+        // the status does not change once the block is stored, until it is verified on L1
+        sync_events
+            .send(SyncEvent::BlockImported(Arc::new(ImportedBlock {
+                header: Header { block_number: tip, ..Default::default() },
+                block_hash: StarkHash::from(tip),
+                tx_hashes: vec![tx_hash],
+                state_diff: StateDiffSummary::default(),
+            })))
+            .unwrap();
+        *ETHEREUM_STATE_UPDATE.write().unwrap() = l1_block(tip);
+        sync_events.send(SyncEvent::L1StateVerified(l1_block(tip))).unwrap();
+        assert_eq!(next().await, Some(TransactionFinalityStatus::AcceptedOnL1));
+
+        // the subscription ends once the transaction is accepted on L1
+        assert_eq!(next().await, None);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee::SubscriptionSink;
+use lazy_static::lazy_static;
+use mc_sync::event_bus::SyncEvent;
+use serde::Serialize;
+use starknet_core::types::{BlockId, BlockTag};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::oneshot;
+
+use crate::constants::MAX_SUBSCRIPTION_BLOCKS_BACK;
+use crate::errors::StarknetRpcApiError;
+use crate::types::SubscriptionId;
+use crate::utils::helpers::block_n_from_id;
+
+lazy_static! {
+    /// The accepted subscriptions, which `starknet_unsubscribe` stops by their id
+    static ref SUBSCRIPTIONS: Mutex<HashMap<SubscriptionId, oneshot::Sender<()>>> = Mutex::new(HashMap::new());
+}
+
+/// An accepted subscription, registered for `starknet_unsubscribe` until it is dropped
+pub(crate) struct Subscription {
+    id: SubscriptionId,
+    unsubscribed: oneshot::Receiver<()>,
+}
+
+impl Subscription {
+    /// Accepts a subscription, returns `None` if the client is gone
+    pub(crate) fn accept(sink: &mut SubscriptionSink) -> Option<Self> {
+        sink.accept().ok()?;
+        let id = SubscriptionId::from(sink.subscription_id()?);
+        let (unsubscribe, unsubscribed) = oneshot::channel();
+        subscriptions().insert(id.clone(), unsubscribe);
+        Some(Self { id, unsubscribed })
+    }
+
+    /// Runs the subscription until it ends or `starknet_unsubscribe` is called with its id
+    pub(crate) async fn run(mut self, subscription: impl Future<Output = ()>) {
+        tokio::select! {
+            _ = &mut self.unsubscribed => {}
+            _ = subscription => {}
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        subscriptions().remove(&self.id);
+    }
+}
+
+fn subscriptions() -> std::sync::MutexGuard<'static, HashMap<SubscriptionId, oneshot::Sender<()>>> {
+    SUBSCRIPTIONS.lock().expect("Failed to acquire lock on SUBSCRIPTIONS")
+}
+
+/// Stops a subscription, returns false if there is no subscription with this id
+pub(crate) fn unsubscribe(id: &SubscriptionId) -> bool {
+    subscriptions().remove(id).is_some_and(|unsubscribe| unsubscribe.send(()).is_ok())
+}
+
+/// Runs a db read on the blocking threads, out of the tasks sending the notifications
+pub(crate) async fn blocking<T, F>(read: F) -> Result<T, StarknetRpcApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, StarknetRpcApiError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(read).await.unwrap_or_else(|e| {
+        log::error!("Subscription read failed: {e}");
+        Err(StarknetRpcApiError::InternalServerError)
+    })
+}
+
+/// Returns the first block a subscription sends, the latest block if none is given
+///
+/// A subscription cannot start more than [`MAX_SUBSCRIPTION_BLOCKS_BACK`] blocks before the
+/// latest block.
+pub(crate) fn first_block(block_id: Option<BlockId>) -> Result<u64, StarknetRpcApiError> {
+    let latest_block = block_n_from_id(BlockId::Tag(BlockTag::Latest))?;
+    let first_block = match block_id {
+        None | Some(BlockId::Tag(_)) => latest_block,
+        Some(block_id) => block_n_from_id(block_id)?,
+    };
+
+    if latest_block.saturating_sub(first_block) > MAX_SUBSCRIPTION_BLOCKS_BACK {
+        return Err(StarknetRpcApiError::TooManyBlocksBack);
+    }
+    Ok(first_block)
+}
+
+/// Whether a block is stored yet, which is the case of all the blocks up to the latest one
+pub(crate) fn is_stored(block_number: u64) -> Result<bool, StarknetRpcApiError> {
+    Ok(block_number <= block_n_from_id(BlockId::Tag(BlockTag::Latest))?)
+}
+
+/// Sends a notification, returns false once the subscription is closed
+pub(crate) fn send<T: Serialize>(sink: &mut SubscriptionSink, notification: &T) -> bool {
+    match sink.send(notification) {
+        Ok(sent) => sent,
+        Err(e) => {
+            log::error!("Failed to serialize subscription notification: {e}");
+            false
+        }
+    }
+}
+
+/// Sends the notifications of the stored blocks from `next_block`, then of the blocks as they are
+/// imported, until the subscription is closed
+///
+/// `read_block` returns the notifications of a block, or `None` if the block is not stored yet.
+/// It reads the db, and runs on the blocking threads. The blocks missed by a subscription lagging
+/// behind the sync are read from db as well.
+///
+/// Reorgs are not notified: the sync detects them but keeps the stored blocks, which the node
+/// keeps serving.
+pub(crate) async fn follow_blocks<T, F>(
+    mut sink: SubscriptionSink,
+    mut sync_events: Receiver<SyncEvent>,
+    mut next_block: u64,
+    read_block: F,
+) where
+    T: Serialize + Send + 'static,
+    F: Fn(u64) -> Result<Option<Vec<T>>, StarknetRpcApiError> + Send + Sync + 'static,
+{
+    let read_block = Arc::new(read_block);
+    loop {
+        loop {
+            let read_block = Arc::clone(&read_block);
+            match blocking(move || read_block(next_block)).await {
+                Ok(Some(notifications)) => {
+                    for notification in notifications {
+                        if !send(&mut sink, &notification) {
+                            return;
+                        }
+                    }
+                    next_block += 1;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("Failed to send block #{next_block} to subscription: {e}");
+                    sink.close(e);
+                    return;
+                }
+            }
+        }
+
+        // waits for a block which is not sent yet
+        loop {
+            match sync_events.recv().await {
+                Ok(SyncEvent::BlockImported(block)) if block.header.block_number >= next_block => break,
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpsee::RpcModule;
+    use mc_sync::event_bus::{ImportedBlock, StateDiffSummary};
+    use mp_block::Header;
+    use starknet_api::hash::StarkHash;
+    use tokio::sync::broadcast;
+
+    use super::*;
+
+    fn imported(block_number: u64) -> SyncEvent {
+        SyncEvent::BlockImported(Arc::new(ImportedBlock {
+            header: Header { block_number, ..Default::default() },
+            block_hash: StarkHash::from(block_number),
+            tx_hashes: vec![],
+            state_diff: StateDiffSummary::default(),
+        }))
+    }
+
+    /// A subscription following the blocks stored up to `stored`, each sending its number twice
+    fn follow_stored_blocks(
+        sync_events: &broadcast::Sender<SyncEvent>,
+        first_block: u64,
+        stored: Arc<Mutex<u64>>,
+    ) -> RpcModule<()> {
+        let sync_events = sync_events.clone();
+        let mut module = RpcModule::new(());
+        module
+            .register_subscription("subscribe", "notification", "unsubscribe", move |_, mut sink, _| {
+                let sync_events = sync_events.subscribe();
+                let stored = Arc::clone(&stored);
+                tokio::spawn(async move {
+                    let Some(subscription) = Subscription::accept(&mut sink) else { return };
+                    subscription
+                        .run(follow_blocks(sink, sync_events, first_block, move |block_number| {
+                            let stored = *stored.lock().unwrap();
+                            Ok((block_number <= stored).then(|| vec![block_number, block_number]))
+                        }))
+                        .await
+                });
+                Ok(())
+            })
+            .unwrap();
+        module
+    }
+
+    async fn next(subscription: &mut jsonrpsee::core::server::rpc_module::Subscription) -> Option<u64> {
+        tokio::time::timeout(Duration::from_secs(5), subscription.next::<u64>())
+            .await
+            .expect("timed out waiting for a notification")
+            .map(|notification| notification.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_follow_blocks() {
+        let (sync_events, _) = broadcast::channel(4);
+        let stored = Arc::new(Mutex::new(3));
+        let module = follow_stored_blocks(&sync_events, 2, Arc::clone(&stored));
+        let mut subscription = module.subscribe("subscribe", Vec::<u8>::new()).await.unwrap();
+
+        // the stored blocks are sent first
+        for block_number in [2, 2, 3, 3] {
+            assert_eq!(next(&mut subscription).await, Some(block_number));
+        }
+
+        // then the blocks as they are imported, an import already sent being ignored
+        *stored.lock().unwrap() = 4;
+        sync_events.send(imported(3)).unwrap();
+        sync_events.send(imported(4)).unwrap();
+        assert_eq!(next(&mut subscription).await, Some(4));
+        assert_eq!(next(&mut subscription).await, Some(4));
+
+        // a subscription lagging behind the sync reads the blocks it missed from db
+        *stored.lock().unwrap() = 10;
+        for block_number in 5..=10 {
+            sync_events.send(imported(block_number)).unwrap();
+        }
+        for block_number in 5..=10 {
+            assert_eq!(next(&mut subscription).await, Some(block_number));
+            assert_eq!(next(&mut subscription).await, Some(block_number));
+        }
+
+        // the subscription is stopped by its id
+        let id = SubscriptionId::from(subscription.subscription_id().clone());
+        assert!(unsubscribe(&id));
+        assert_eq!(next(&mut subscription).await, None);
+        assert!(!unsubscribe(&id));
+    }
+}
@@ -0,0 +1,218 @@
+//! Transactions submitted through this node, until a block contains them.
+//!
+//! The gateway only answers a submission with the hash of the transaction, and the node does not
+//! know about the transaction before it is part of the pending block or of a synced block. In the
+//! meantime the transaction is kept here, so that its status is reported as `RECEIVED` rather than
+//! not found, and the transaction itself can be served by its hash. A transaction which is still
+//! not in a block after the timeout is reported as `REJECTED`, and is dropped once the timeout has
+//! elapsed again.
+//!
+//! A transaction is forgotten as soon as the sync imports a pending or synced block containing it,
+//! its status is then read from the block.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use mc_sync::event_bus::SyncEvent;
+use mp_felt::Felt252Wrapper;
+use starknet_api::hash::StarkHash;
+use starknet_core::types::{BroadcastedTransaction, FieldElement, TransactionStatus};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+/// Timeout of the submitted transactions when none is configured
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Number of submitted transactions kept at most, the oldest ones are dropped first
+pub const MAX_SUBMITTED_TRANSACTIONS: usize = 10_000;
+
+struct SubmittedTransaction {
+    transaction: BroadcastedTransaction,
+    submitted_at: Instant,
+}
+
+struct SubmittedTransactions {
+    /// Submitted transactions by hash, oldest first
+    transactions: IndexMap<FieldElement, SubmittedTransaction>,
+    timeout: Duration,
+}
+
+impl SubmittedTransactions {
+    fn new(timeout: Duration) -> Self {
+        Self { transactions: IndexMap::new(), timeout }
+    }
+
+    /// Drops the transactions reported as rejected for a whole timeout, which are the oldest ones
+    fn evict(&mut self) {
+        while let Some((_, submitted)) = self.transactions.first()
+            && submitted.submitted_at.elapsed() >= self.timeout * 2
+        {
+            self.transactions.shift_remove_index(0);
+        }
+    }
+
+    fn insert(&mut self, transaction_hash: FieldElement, transaction: BroadcastedTransaction, submitted_at: Instant) {
+        self.evict();
+        // submitted again, the transaction moves to the end of the table
+        self.transactions.shift_remove(&transaction_hash);
+        if self.transactions.len() >= MAX_SUBMITTED_TRANSACTIONS {
+            self.transactions.shift_remove_index(0);
+        }
+        self.transactions.insert(transaction_hash, SubmittedTransaction { transaction, submitted_at });
+    }
+
+    /// Forgets the submitted transactions which are part of a block
+    fn remove_included(&mut self, tx_hashes: &[StarkHash]) {
+        for tx_hash in tx_hashes {
+            self.transactions.shift_remove(&Felt252Wrapper::from(*tx_hash).0);
+        }
+    }
+
+    fn status(&mut self, transaction_hash: &FieldElement) -> Option<TransactionStatus> {
+        self.evict();
+        self.transactions.get(transaction_hash).map(|submitted| {
+            if submitted.submitted_at.elapsed() < self.timeout {
+                TransactionStatus::Received
+            } else {
+                TransactionStatus::Rejected
+            }
+        })
+    }
+
+    fn received(&mut self, transaction_hash: &FieldElement) -> Option<BroadcastedTransaction> {
+        self.evict();
+        self.transactions
+            .get(transaction_hash)
+            .filter(|submitted| submitted.submitted_at.elapsed() < self.timeout)
+            .map(|submitted| submitted.transaction.clone())
+    }
+}
+
+lazy_static! {
+    static ref SUBMITTED_TRANSACTIONS: Mutex<SubmittedTransactions> =
+        Mutex::new(SubmittedTransactions::new(DEFAULT_TIMEOUT));
+}
+
+fn submitted_transactions() -> MutexGuard<'static, SubmittedTransactions> {
+    SUBMITTED_TRANSACTIONS.lock().expect("Failed to acquire lock on SUBMITTED_TRANSACTIONS")
+}
+
+/// Sets the delay after which a submitted transaction which is not in a block is rejected
+pub fn configure(timeout: Duration) {
+    let mut submitted = submitted_transactions();
+    submitted.timeout = timeout;
+    submitted.evict();
+}
+
+/// Forgets the submitted transactions once the sync imports a block containing them, until the
+/// event bus is closed
+pub async fn forget_included_transactions(mut sync_events: Receiver<SyncEvent>) {
+    loop {
+        match sync_events.recv().await {
+            Ok(SyncEvent::BlockImported(block)) => submitted_transactions().remove_included(&block.tx_hashes),
+            Ok(SyncEvent::PendingBlockUpdated(block)) => submitted_transactions().remove_included(&block.tx_hashes),
+            Ok(_) => {}
+            // the missed transactions are dropped with the timeout, their status is read from their block
+            // meanwhile
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+pub(crate) fn insert(transaction_hash: FieldElement, transaction: BroadcastedTransaction) {
+    submitted_transactions().insert(transaction_hash, transaction, Instant::now());
+}
+
+/// Returns the status of a transaction submitted through the node which is not in a block yet
+pub(crate) fn status(transaction_hash: &FieldElement) -> Option<TransactionStatus> {
+    submitted_transactions().status(transaction_hash)
+}
+
+/// Returns a transaction submitted through the node which is not in a block yet, as long as it is
+/// not rejected
+pub(crate) fn received_transaction(transaction_hash: &FieldElement) -> Option<BroadcastedTransaction> {
+    submitted_transactions().received(transaction_hash)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Arc;
+
+    use mc_sync::event_bus::{ImportedBlock, StateDiffSummary};
+    use mp_block::Header;
+    use starknet_core::types::{BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1};
+    use tokio::sync::broadcast;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub(crate) fn invoke_transaction(nonce: u64) -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(BroadcastedInvokeTransactionV1 {
+            sender_address: FieldElement::from(0x4d2_u64),
+            calldata: vec![],
+            max_fee: FieldElement::from(1_000_u64),
+            signature: vec![],
+            nonce: FieldElement::from(nonce),
+            is_query: false,
+        }))
+    }
+
+    fn submitted_before(submitted: &mut SubmittedTransactions, transaction_hash: u64, elapsed: Duration) {
+        let submitted_at = Instant::now().checked_sub(elapsed).expect("Failed to date the submission");
+        submitted.insert(FieldElement::from(transaction_hash), invoke_transaction(transaction_hash), submitted_at);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut submitted = SubmittedTransactions::new(TIMEOUT);
+        submitted_before(&mut submitted, 3, TIMEOUT * 2);
+        submitted_before(&mut submitted, 2, TIMEOUT);
+        submitted_before(&mut submitted, 1, Duration::ZERO);
+
+        assert_eq!(submitted.status(&FieldElement::from(1_u64)), Some(TransactionStatus::Received));
+        assert_eq!(submitted.status(&FieldElement::from(2_u64)), Some(TransactionStatus::Rejected));
+        assert_eq!(submitted.status(&FieldElement::from(3_u64)), None);
+        assert_eq!(submitted.transactions.len(), 2);
+
+        assert!(submitted.received(&FieldElement::from(1_u64)).is_some());
+        assert!(submitted.received(&FieldElement::from(2_u64)).is_none());
+    }
+
+    #[test]
+    fn test_max_submitted_transactions() {
+        let mut submitted = SubmittedTransactions::new(TIMEOUT);
+        for transaction_hash in 0..=MAX_SUBMITTED_TRANSACTIONS as u64 {
+            submitted_before(&mut submitted, transaction_hash, Duration::ZERO);
+        }
+
+        assert_eq!(submitted.transactions.len(), MAX_SUBMITTED_TRANSACTIONS);
+        assert_eq!(submitted.status(&FieldElement::ZERO), None);
+        assert_eq!(submitted.status(&FieldElement::ONE), Some(TransactionStatus::Received));
+    }
+
+    #[tokio::test]
+    async fn test_forget_included_transactions() {
+        let transaction_hash = FieldElement::from(0x5b7_u64);
+        insert(transaction_hash, invoke_transaction(0));
+        assert_eq!(status(&transaction_hash), Some(TransactionStatus::Received));
+
+        let (sync_events, _) = broadcast::channel(16);
+        let forget = tokio::spawn(forget_included_transactions(sync_events.subscribe()));
+        sync_events
+            .send(SyncEvent::BlockImported(Arc::new(ImportedBlock {
+                header: Header::default(),
+                block_hash: StarkHash::default(),
+                tx_hashes: vec![Felt252Wrapper(transaction_hash).into()],
+                state_diff: StateDiffSummary::default(),
+            })))
+            .unwrap();
+        drop(sync_events);
+        forget.await.expect("Failed to forget the included transactions");
+
+        assert_eq!(status(&transaction_hash), None);
+    }
+}
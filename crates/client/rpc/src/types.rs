@@ -1,6 +1,14 @@
+use std::collections::HashMap;
 use std::num::ParseIntError;
 use std::{fmt, u64};
 
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use starknet_core::serde::unsigned_field_element::UfeHex;
+use starknet_core::types::{
+    BlockId, EthAddress, FieldElement, L1DataAvailabilityMode, ResourcePrice, Transaction, TransactionFinalityStatus,
+};
+
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct ContinuationToken {
     pub block_n: u64,
@@ -32,6 +40,182 @@ impl ContinuationToken {
     }
 }
 
+/// Finality status of the L1 handler transaction consuming a message sent from L1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageFinalityStatus {
+    /// The message was sent on L1 but has not been consumed on L2 yet
+    #[serde(rename = "RECEIVED")]
+    Received,
+    #[serde(rename = "ACCEPTED_ON_L2")]
+    AcceptedOnL2,
+    #[serde(rename = "ACCEPTED_ON_L1")]
+    AcceptedOnL1,
+}
+
+/// Status of a message sent from L1, as returned by `starknet_getMessagesStatus`
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageStatus {
+    /// Hash of the L1 handler transaction consuming the message
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    pub finality_status: MessageFinalityStatus,
+}
+
+/// Filter for `deoxys_getMessagesToL1`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesToL1Filter {
+    pub from_block: Option<BlockId>,
+    pub to_block: Option<BlockId>,
+    /// Only return messages sent by this contract
+    #[serde_as(as = "Option<UfeHex>")]
+    #[serde(default)]
+    pub from_address: Option<FieldElement>,
+    /// Only return messages sent to this L1 address
+    #[serde(default)]
+    pub to_address: Option<EthAddress>,
+    #[serde(default)]
+    pub continuation_token: Option<String>,
+    pub chunk_size: u64,
+}
+
+/// A message sent from L2 to L1, as returned by `deoxys_getMessagesToL1`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageToL1WithStatus {
+    pub block_number: u64,
+    /// Hash of the transaction which sent the message
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub from_address: FieldElement,
+    pub to_address: EthAddress,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub payload: Vec<FieldElement>,
+    /// Whether the message has been consumed on L1, `null` if the L1 events indexed by the node do
+    /// not go far enough to tell
+    pub consumed_on_l1: Option<bool>,
+}
+
+/// A page of messages sent from L2 to L1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesToL1Page {
+    pub messages: Vec<MessageToL1WithStatus>,
+    /// Token to pass to the next call to fetch the following page, if there are more messages
+    pub continuation_token: Option<String>,
+}
+
+/// Header of a block, as sent by `starknet_subscribeNewHeads`
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    #[serde_as(as = "UfeHex")]
+    pub block_hash: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub parent_hash: FieldElement,
+    pub block_number: u64,
+    #[serde_as(as = "UfeHex")]
+    pub new_root: FieldElement,
+    pub timestamp: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+}
+
+/// Id of a subscription, as returned by the subscription methods and passed to
+/// `starknet_unsubscribe`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SubscriptionId {
+    Num(u64),
+    Str(String),
+}
+
+impl From<jsonrpsee::types::SubscriptionId<'_>> for SubscriptionId {
+    fn from(id: jsonrpsee::types::SubscriptionId<'_>) -> Self {
+        match id {
+            jsonrpsee::types::SubscriptionId::Num(id) => Self::Num(id),
+            jsonrpsee::types::SubscriptionId::Str(id) => Self::Str(id.into_owned()),
+        }
+    }
+}
+
+/// Status of a transaction, as sent by `starknet_subscribeTransactionStatus`
+///
+/// Only the finality status is known, the execution status requires executing the transaction.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionStatusUpdate {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    pub status: TransactionFinality,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionFinality {
+    pub finality_status: TransactionFinalityStatus,
+}
+
+/// A pending transaction, as sent by `starknet_subscribePendingTransactions`
+#[serde_as]
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum PendingTransaction {
+    Hash(#[serde_as(as = "UfeHex")] FieldElement),
+    Transaction(Transaction),
+}
+
+/// Result of a call made with `deoxys_multicall`, either the return value of the function or the
+/// error the call failed with
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MulticallResult {
+    Result(#[serde_as(as = "Vec<UfeHex>")] Vec<FieldElement>),
+    Error(MulticallError),
+}
+
+/// Error of a call made with `deoxys_multicall`, in the format of a json rpc error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MulticallError {
+    pub code: i32,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// State of a contract overridden by `deoxys_call` and `deoxys_estimateFee`, on top of the state
+/// of the requested block. Only the given fields are overridden.
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractStateOverride {
+    #[serde_as(as = "Option<UfeHex>")]
+    #[serde(default)]
+    pub nonce: Option<FieldElement>,
+    #[serde_as(as = "Option<UfeHex>")]
+    #[serde(default)]
+    pub class_hash: Option<FieldElement>,
+    /// Balance of the contract in both the ETH and STRK fee tokens
+    #[serde_as(as = "Option<UfeHex>")]
+    #[serde(default)]
+    pub balance: Option<FieldElement>,
+    /// Values of storage slots, the other slots keep their value at the requested block
+    #[serde_as(as = "HashMap<UfeHex, UfeHex>")]
+    #[serde(default)]
+    pub storage: HashMap<FieldElement, FieldElement>,
+}
+
+/// State overrides of `deoxys_call` and `deoxys_estimateFee`, by contract address
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StateOverrides(#[serde_as(as = "HashMap<UfeHex, _>")] pub HashMap<FieldElement, ContractStateOverride>);
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -74,4 +258,66 @@ mod tests {
         let result = ContinuationToken::parse(string_token);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn subscription_serialization() {
+        assert_eq!(
+            serde_json::to_value(TransactionStatusUpdate {
+                transaction_hash: FieldElement::THREE,
+                status: TransactionFinality { finality_status: TransactionFinalityStatus::AcceptedOnL2 },
+            })
+            .unwrap(),
+            serde_json::json!({ "transaction_hash": "0x3", "status": { "finality_status": "ACCEPTED_ON_L2" } })
+        );
+        assert_eq!(serde_json::from_value::<SubscriptionId>(serde_json::json!(7)).unwrap(), SubscriptionId::Num(7));
+        assert_eq!(
+            serde_json::from_value::<SubscriptionId>(serde_json::json!("abc")).unwrap(),
+            SubscriptionId::Str("abc".into())
+        );
+    }
+
+    #[test]
+    fn multicall_result_serialization() {
+        assert_eq!(
+            serde_json::to_value(MulticallResult::Result(vec![FieldElement::ONE, FieldElement::TWO])).unwrap(),
+            serde_json::json!({ "result": ["0x1", "0x2"] })
+        );
+        assert_eq!(
+            serde_json::to_value(MulticallResult::Error(MulticallError {
+                code: 20,
+                message: "Contract not found".to_string(),
+                data: None,
+            }))
+            .unwrap(),
+            serde_json::json!({ "error": { "code": 20, "message": "Contract not found" } })
+        );
+    }
+
+    #[test]
+    fn state_overrides_deserialization() {
+        let state_overrides: StateOverrides = serde_json::from_value(serde_json::json!({
+            "0x1": { "nonce": "0x2", "storage": { "0x3": "0x4" } },
+            "0x5": { "class_hash": "0x6", "balance": "0x7" },
+        }))
+        .unwrap();
+        assert_eq!(
+            state_overrides.0.get(&FieldElement::ONE),
+            Some(&ContractStateOverride {
+                nonce: Some(FieldElement::TWO),
+                storage: HashMap::from([(FieldElement::THREE, FieldElement::from(4u8))]),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            state_overrides.0.get(&FieldElement::from(5u8)),
+            Some(&ContractStateOverride {
+                class_hash: Some(FieldElement::from(6u8)),
+                balance: Some(FieldElement::from(7u8)),
+                ..Default::default()
+            })
+        );
+
+        let unknown_field = serde_json::json!({ "0x1": { "code": "0x2" } });
+        assert!(serde_json::from_value::<StateOverrides>(unknown_field).is_err());
+    }
 }
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use blockifier::abi::abi_utils::get_storage_var_address;
+use blockifier::context::FeeTokenAddresses;
 use blockifier::execution::contract_class::ContractClass;
 use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::state::errors::StateError;
@@ -7,10 +9,14 @@ use blockifier::state::state_api::{State, StateReader, StateResult};
 use indexmap::IndexMap;
 use mc_db::storage_handler::{self, StorageView};
 use mc_db::DeoxysBackend;
+use mp_felt::Felt252Wrapper;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
 
+use crate::contract_class_cache;
+use crate::types::{ContractStateOverride, StateOverrides};
+
 /// `BlockifierStateAdapter` is only use to re-executing or simulate transactions.
 /// None of the setters should therefore change the storage persistently,
 /// all changes are temporary stored in the struct and are discarded after the execution
@@ -36,6 +42,55 @@ impl BlockifierStateAdapter {
             visited_pcs: IndexMap::default(),
         }
     }
+
+    /// Returns an adapter on top of the state of the given block, with the given state overrides
+    /// layered over it as if they had been set by a previous execution
+    pub fn with_overrides(
+        block_number: u64,
+        fee_token_addresses: &FeeTokenAddresses,
+        state_overrides: &StateOverrides,
+    ) -> Self {
+        let mut adapter = Self::new(block_number);
+
+        for (address, contract_override) in state_overrides.0.iter() {
+            let contract_address: ContractAddress = Felt252Wrapper(*address).into();
+            let ContractStateOverride { nonce, class_hash, balance, storage } = contract_override;
+
+            if let Some(nonce) = nonce {
+                adapter.nonce_update.insert(contract_address, Felt252Wrapper(*nonce).into());
+            }
+            if let Some(class_hash) = class_hash {
+                adapter.class_hash_update.insert(contract_address, Felt252Wrapper(*class_hash).into());
+            }
+            if let Some(balance) = balance {
+                // Fee token balances are u256, stored as their low and high 128 bits in two
+                // consecutive storage slots
+                let balance = balance.to_bytes_be();
+                let (high, low) = balance.split_at(16);
+                let low = StarkFelt::from(u128::from_be_bytes(low.try_into().expect("16 bytes")));
+                let high = StarkFelt::from(u128::from_be_bytes(high.try_into().expect("16 bytes")));
+
+                let low_key = get_storage_var_address("ERC20_balances", &[*contract_address.0.key()]);
+                let high_key = low_key.next_storage_key().expect("Balance storage keys are below the storage bound");
+                for fee_token_address in
+                    [fee_token_addresses.eth_fee_token_address, fee_token_addresses.strk_fee_token_address]
+                {
+                    let fee_token_storage = adapter.storage_update.entry(fee_token_address).or_default();
+                    fee_token_storage.insert(low_key, low);
+                    fee_token_storage.insert(high_key, high);
+                }
+            }
+            for (key, value) in storage.iter() {
+                adapter
+                    .storage_update
+                    .entry(contract_address)
+                    .or_default()
+                    .insert(Felt252Wrapper(*key).into(), Felt252Wrapper(*value).into());
+            }
+        }
+
+        adapter
+    }
 }
 
 impl StateReader for BlockifierStateAdapter {
@@ -97,8 +152,8 @@ impl StateReader for BlockifierStateAdapter {
     fn get_compiled_contract_class(&mut self, class_hash: ClassHash) -> StateResult<ContractClass> {
         match self.contract_class_update.get(&class_hash) {
             Some(contract_class) => Ok(contract_class.clone()),
-            None => match storage_handler::contract_class_data().get(&class_hash) {
-                Ok(Some(contract_class_data)) => Ok(contract_class_data.contract_class),
+            None => match contract_class_cache::get_contract_class(&class_hash) {
+                Ok(Some(contract_class)) => Ok(contract_class),
                 _ => Err(StateError::UndeclaredClassHash(class_hash)),
             },
         }
@@ -4,31 +4,35 @@ use anyhow::Result;
 use blockifier::context::{BlockContext, FeeTokenAddresses, TransactionContext};
 use blockifier::execution::entry_point::{CallEntryPoint, CallType, EntryPointExecutionContext};
 use blockifier::fee::gas_usage::estimate_minimal_gas_vector;
-use blockifier::state::cached_state::{CachedState, GlobalContractCache};
+use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::{State, StateReader};
 use blockifier::transaction::account_transaction::AccountTransaction;
-use blockifier::transaction::errors::TransactionExecutionError;
+use blockifier::transaction::errors::{TransactionExecutionError, TransactionFeeError, TransactionPreValidationError};
 use blockifier::transaction::objects::{
     DeprecatedTransactionInfo, GasVector, HasRelatedFeeType, TransactionExecutionInfo, TransactionInfo,
 };
 use blockifier::transaction::transaction_execution::Transaction;
-use blockifier::transaction::transactions::{ExecutableTransaction, L1HandlerTransaction};
+use blockifier::transaction::transactions::{
+    Executable, ExecutableTransaction, L1HandlerTransaction, ValidatableTransaction,
+};
 use blockifier::versioned_constants::VersionedConstants;
-use mc_db::storage_handler::{self, StorageView};
+use mp_block::Header;
 use mp_felt::Felt252Wrapper;
 use mp_genesis_config::{ETH_TOKEN_ADDR, STRK_TOKEN_ADDR};
 use mp_simulations::SimulationFlags;
 use sp_blockchain::HeaderBackend;
 use sp_runtime::traits::Block as BlockT;
-use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkHash;
-use starknet_api::transaction::Calldata;
+use starknet_api::transaction::{Calldata, Fee};
 use starknet_core::types::{FeeEstimate, PriceUnit};
-use starknet_ff::FieldElement;
 
 use super::blockifier_state_adapter::BlockifierStateAdapter;
+use crate::contract_class_cache::global_contract_cache;
 use crate::errors::StarknetRpcApiError;
 use crate::get_block_by_block_hash;
+use crate::types::StateOverrides;
 
 pub fn block_context<B, C>(
     client: &C,
@@ -42,8 +46,12 @@ where
         log::error!("Failed to retrieve block by block hash: {e}");
         StarknetRpcApiError::BlockNotFound
     })?;
-    let block_header = block.header();
 
+    Ok(header_block_context(block.header()))
+}
+
+/// The context of a block which may not be stored, such as the pending block
+pub fn header_block_context(block_header: &Header) -> BlockContext {
     // safe unwrap because address is always valid and static
     let fee_token_address = FeeTokenAddresses {
         strk_fee_token_address: StarkHash::new_unchecked(STRK_TOKEN_ADDR.0.to_bytes_be()).try_into().unwrap(),
@@ -51,7 +59,7 @@ where
     };
     let chain_id = starknet_api::core::ChainId("SN_MAIN".to_string());
 
-    Ok(block_header.into_block_context(fee_token_address, chain_id))
+    block_header.into_block_context(fee_token_address, chain_id)
 }
 
 pub fn re_execute_transactions(
@@ -60,7 +68,7 @@ pub fn re_execute_transactions(
     block_context: &BlockContext,
 ) -> Result<Vec<TransactionExecutionInfo>, TransactionExecutionError> {
     let charge_fee = block_context.block_info().gas_prices.eth_l1_gas_price.get() != 1;
-    let mut cached_state = init_cached_state(block_context);
+    let mut cached_state = init_cached_state(block_context, &StateOverrides::default());
 
     transactions_before
         .into_iter()
@@ -80,7 +88,7 @@ pub fn simulate_transactions(
     simulation_flags: &SimulationFlags,
     block_context: &BlockContext,
 ) -> Result<Vec<TransactionExecutionInfo>, TransactionExecutionError> {
-    let mut cached_state = init_cached_state(block_context);
+    let mut cached_state = init_cached_state(block_context, &StateOverrides::default());
 
     let tx_execution_results = transactions
         .into_iter()
@@ -90,18 +98,61 @@ pub fn simulate_transactions(
     Ok(tx_execution_results)
 }
 
-/// Call a smart contract function.
+/// Call a smart contract function, on top of the given state overrides.
 pub fn call_contract(
     address: ContractAddress,
     function_selector: EntryPointSelector,
     calldata: Calldata,
     block_context: &BlockContext,
-) -> Result<Vec<Felt252Wrapper>, ()> {
+    state_overrides: &StateOverrides,
+) -> Result<Vec<Felt252Wrapper>, StarknetRpcApiError> {
+    let mut state = BlockifierStateAdapter::with_overrides(
+        block_context.block_info().block_number.0,
+        &block_context.chain_info().fee_token_addresses,
+        state_overrides,
+    );
+
+    execute_call(&mut state, address, function_selector, calldata, block_context)
+}
+
+/// Call several smart contract functions against a shared state, so that what a call reads is
+/// cached for the following ones. The state changes of each call are discarded after it.
+pub fn multicall(
+    calls: Vec<(ContractAddress, EntryPointSelector, Calldata)>,
+    block_context: &BlockContext,
+) -> Vec<Result<Vec<Felt252Wrapper>, StarknetRpcApiError>> {
+    let state_adapter = BlockifierStateAdapter::new(block_context.block_info().block_number.0);
+    let mut cached_state = CachedState::new(state_adapter, global_contract_cache());
+
+    calls
+        .into_iter()
+        .map(|(address, function_selector, calldata)| {
+            let mut transactional_state = CachedState::create_transactional(&mut cached_state);
+            let result = execute_call(&mut transactional_state, address, function_selector, calldata, block_context);
+            transactional_state.abort();
+            result
+        })
+        .collect()
+}
+
+fn execute_call(
+    state: &mut dyn State,
+    address: ContractAddress,
+    function_selector: EntryPointSelector,
+    calldata: Calldata,
+    block_context: &BlockContext,
+) -> Result<Vec<Felt252Wrapper>, StarknetRpcApiError> {
     // Get class hash
-    let class_hash = storage_handler::contract_class_hash().get(&address).map_err(|_| ())?;
+    let class_hash = state.get_class_hash_at(address).map_err(|e| {
+        log::error!("Failed to retrieve class hash of contract {}: {e}", address.0.0);
+        StarknetRpcApiError::InternalServerError
+    })?;
+    if class_hash == ClassHash::default() {
+        return Err(StarknetRpcApiError::ContractNotFound);
+    }
 
     let entrypoint = CallEntryPoint {
-        class_hash,
+        class_hash: Some(class_hash),
         code_address: None,
         entry_point_type: EntryPointType::External,
         entry_point_selector: function_selector,
@@ -120,13 +171,12 @@ pub fn call_contract(
         }),
         false,
     )
-    .map_err(|_| ())?;
+    .map_err(|e| {
+        log::error!("Failed to create the execution context of a call: {e}");
+        StarknetRpcApiError::InternalServerError
+    })?;
 
-    match entrypoint.execute(
-        &mut BlockifierStateAdapter::new(block_context.block_info().block_number.0),
-        &mut resources,
-        &mut entry_point_execution_context,
-    ) {
+    match entrypoint.execute(state, &mut resources, &mut entry_point_execution_context) {
         Ok(v) => {
             log::debug!("Successfully called a smart contract function: {:?}", v);
             let result = v.execution.retdata.0.iter().map(|x| (*x).into()).collect();
@@ -134,7 +184,7 @@ pub fn call_contract(
         }
         Err(e) => {
             log::error!("failed to call smart contract {:?}", e);
-            Err(())
+            Err(StarknetRpcApiError::ContractErrorData { revert_error: e.to_string() })
         }
     }
 }
@@ -143,49 +193,69 @@ pub fn estimate_fee(
     transactions: Vec<AccountTransaction>,
     validate: bool,
     block_context: &BlockContext,
+    state_overrides: &StateOverrides,
 ) -> Result<Vec<FeeEstimate>, TransactionExecutionError> {
     let fees = transactions
         .iter()
-        .map(|tx| execute_fee_transaction(tx.clone(), validate, block_context))
+        .map(|tx| execute_fee_transaction(tx.clone(), validate, block_context, state_overrides))
         .collect::<Result<Vec<_>, _>>()?;
     Ok(fees)
 }
 
+/// Estimates the fee of an L1 handler transaction, at the gas prices of the block
+///
+/// A message whose L1 handler fails or reverts results in a contract error carrying the reason.
+/// A message which has not been sent on L1 yet has paid no fee, which the blockifier rejects: its
+/// L1 handler is executed again as if it had paid the fee it requires.
 pub fn estimate_message_fee(
     message: L1HandlerTransaction,
     block_context: &BlockContext,
-) -> Result<FeeEstimate, TransactionExecutionError> {
-    let mut cached_state = init_cached_state(block_context);
+) -> Result<FeeEstimate, StarknetRpcApiError> {
+    let mut cached_state = init_cached_state(block_context, &StateOverrides::default());
 
-    let tx_execution_infos = message.clone().execute(&mut cached_state, block_context, true, true)?;
-
-    // TODO: implement this
-    // if !tx_execution_infos.is_reverted() {}
-
-    let unit = match message.fee_type() {
+    let fee_type = message.fee_type();
+    let gas_price = block_context.block_info().gas_prices.get_gas_price_by_fee_type(&fee_type).get();
+    let data_gas_price = block_context.block_info().gas_prices.get_data_gas_price_by_fee_type(&fee_type).get();
+    let unit = match fee_type {
         blockifier::transaction::objects::FeeType::Strk => PriceUnit::Fri,
         blockifier::transaction::objects::FeeType::Eth => PriceUnit::Wei,
     };
 
-    let fee = FeeEstimate {
-        gas_consumed: FieldElement::from(
-            tx_execution_infos.actual_resources.0.get("l1_gas_usage").cloned().unwrap_or_default(),
-        ),
-        gas_price: FieldElement::ZERO,
-        data_gas_consumed: tx_execution_infos.da_gas.l1_data_gas.into(),
-        data_gas_price: FieldElement::ZERO,
-        overall_fee: tx_execution_infos.actual_fee.0.into(),
-        unit,
-    };
-    Ok(fee)
+    let L1HandlerTransaction { tx, tx_hash, paid_fee_on_l1 } = message;
+    let result = L1HandlerTransaction { tx: tx.clone(), tx_hash, paid_fee_on_l1 }.execute(
+        &mut cached_state,
+        block_context,
+        true,
+        true,
+    );
+    let tx_info = match result {
+        Err(TransactionExecutionError::TransactionFeeError(TransactionFeeError::InsufficientL1Fee {
+            paid_fee: Fee(0),
+            actual_fee,
+        })) if actual_fee != Fee(0) => {
+            let message = L1HandlerTransaction { tx, tx_hash, paid_fee_on_l1: actual_fee };
+            message.execute(&mut cached_state, block_context, true, true)
+        }
+        result => result,
+    }
+    .map_err(|e| {
+        log::debug!("L1 handler execution failed: {e:#?}");
+        StarknetRpcApiError::ContractErrorData { revert_error: e.to_string() }
+    })?;
+    if let Some(revert_error) = &tx_info.revert_error {
+        return Err(StarknetRpcApiError::ContractErrorData { revert_error: revert_error.clone() });
+    }
+
+    Ok(from_tx_info_and_gas_price(&tx_info, gas_price, data_gas_price, unit, GasVector::default()))
 }
 
 fn execute_fee_transaction(
     transaction: AccountTransaction,
     validate: bool,
     block_context: &BlockContext,
+    state_overrides: &StateOverrides,
 ) -> Result<FeeEstimate, TransactionExecutionError> {
-    let mut cached_state = init_cached_state(block_context);
+    let mut cached_state = init_cached_state(block_context, state_overrides);
 
     let fee_type = transaction.fee_type();
 
@@ -221,6 +291,78 @@ fn execute_fee_transaction(
     }
 }
 
+/// Runs the checks the sequencer makes before accepting an account transaction, on top of the
+/// state at the end of the block: the nonce and fee pre-checks, then the validation entry point.
+/// The transaction itself is not executed, except for the deployment of an account which is only
+/// validated once deployed.
+///
+/// Returns the error the sequencer would reject the transaction with. Failures which may come from
+/// the local state lagging behind the sequencer are left to it.
+pub fn validate_transaction(
+    transaction: AccountTransaction,
+    block_context: &BlockContext,
+) -> Result<(), StarknetRpcApiError> {
+    let state_adapter = BlockifierStateAdapter::new(block_context.block_info().block_number.0);
+    let mut cached_state = CachedState::new(state_adapter, global_contract_cache());
+
+    match validate(&transaction, &mut cached_state, block_context) {
+        Ok(()) => Ok(()),
+        Err(e) => match rejection_error(&e) {
+            Some(error) => Err(error),
+            None => {
+                log::debug!("Leaving the failed local validation of a transaction to the sequencer: {e}");
+                Ok(())
+            }
+        },
+    }
+}
+
+fn validate<S: State + StateReader>(
+    transaction: &AccountTransaction,
+    state: &mut S,
+    block_context: &BlockContext,
+) -> Result<(), TransactionExecutionError> {
+    let tx_context = Arc::new(block_context.to_tx_context(transaction));
+    transaction.perform_pre_validation_stage(state, &tx_context, true, true)?;
+
+    let mut resources = cairo_vm::vm::runners::cairo_runner::ExecutionResources::default();
+    let mut remaining_gas = block_context.versioned_constants().tx_initial_gas();
+    if let AccountTransaction::DeployAccount(deploy_account) = transaction {
+        let mut context = EntryPointExecutionContext::new_validate(tx_context.clone(), true)?;
+        deploy_account.run_execute(state, &mut resources, &mut context, &mut remaining_gas)?;
+    }
+    transaction.validate_tx(state, &mut resources, tx_context, &mut remaining_gas, true)?;
+
+    Ok(())
+}
+
+fn rejection_error(error: &TransactionExecutionError) -> Option<StarknetRpcApiError> {
+    match error {
+        TransactionExecutionError::TransactionPreValidationError(e) => match e {
+            TransactionPreValidationError::InvalidNonce { .. } => Some(StarknetRpcApiError::InvalidTxnNonce),
+            TransactionPreValidationError::TransactionFeeError(e) => fee_rejection_error(e),
+            _ => None,
+        },
+        TransactionExecutionError::ValidateTransactionError { .. }
+        | TransactionExecutionError::InvalidValidateReturnData { .. } => {
+            Some(StarknetRpcApiError::ValidationFailure { data: error.to_string() })
+        }
+        _ => None,
+    }
+}
+
+fn fee_rejection_error(error: &TransactionFeeError) -> Option<StarknetRpcApiError> {
+    match error {
+        TransactionFeeError::MaxFeeExceedsBalance { .. } | TransactionFeeError::L1GasBoundsExceedBalance { .. } => {
+            Some(StarknetRpcApiError::InsufficientAccountBalance)
+        }
+        TransactionFeeError::MaxFeeTooLow { .. }
+        | TransactionFeeError::MaxL1GasPriceTooLow { .. }
+        | TransactionFeeError::MaxL1GasAmountTooLow { .. } => Some(StarknetRpcApiError::InsufficientMaxFee),
+        _ => None,
+    }
+}
+
 pub fn from_tx_info_and_gas_price(
     tx_info: &TransactionExecutionInfo,
     gas_price: u128,
@@ -250,7 +392,91 @@ pub fn from_tx_info_and_gas_price(
     }
 }
 
-fn init_cached_state(block_context: &BlockContext) -> CachedState<BlockifierStateAdapter> {
+fn init_cached_state(
+    block_context: &BlockContext,
+    state_overrides: &StateOverrides,
+) -> CachedState<BlockifierStateAdapter> {
     let block_number = block_context.block_info().block_number.0;
-    CachedState::new(BlockifierStateAdapter::new(block_number - 1), GlobalContractCache::new(16))
+    let state_adapter = BlockifierStateAdapter::with_overrides(
+        block_number - 1,
+        &block_context.chain_info().fee_token_addresses,
+        state_overrides,
+    );
+    CachedState::new(state_adapter, global_contract_cache())
+}
+
+#[cfg(test)]
+mod tests {
+    use blockifier::invoke_tx_args;
+    use blockifier::test_utils::contracts::FeatureContract;
+    use blockifier::test_utils::initial_test_state::test_state;
+    use blockifier::test_utils::{create_trivial_calldata, CairoVersion, NonceManager, BALANCE, MAX_FEE};
+    use blockifier::transaction::test_utils::{
+        account_invoke_tx, create_account_tx_for_validate_test, FaultyAccountTxCreatorArgs, INVALID, VALID,
+    };
+    use starknet_api::core::Nonce;
+    use starknet_api::transaction::TransactionVersion;
+
+    use super::*;
+
+    fn rejection(transaction: AccountTransaction, initial_balance: u128) -> Option<StarknetRpcApiError> {
+        let block_context = BlockContext::create_for_account_testing();
+        let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+        let faulty_account = FeatureContract::FaultyAccount(CairoVersion::Cairo0);
+        let mut state = test_state(block_context.chain_info(), initial_balance, &[(account, 1), (faulty_account, 1)]);
+
+        validate(&transaction, &mut state, &block_context).err().as_ref().and_then(rejection_error)
+    }
+
+    fn invoke(nonce: u64, max_fee: u128) -> AccountTransaction {
+        let account_address = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0).get_instance_address(0);
+        account_invoke_tx(invoke_tx_args! {
+            sender_address: account_address,
+            calldata: create_trivial_calldata(account_address),
+            version: TransactionVersion::ONE,
+            nonce: Nonce(StarkHash::from(nonce)),
+            max_fee: Fee(max_fee),
+        })
+    }
+
+    fn faulty_invoke(scenario: u64) -> AccountTransaction {
+        create_account_tx_for_validate_test(
+            &mut NonceManager::default(),
+            FaultyAccountTxCreatorArgs {
+                scenario,
+                sender_address: FeatureContract::FaultyAccount(CairoVersion::Cairo0).get_instance_address(0),
+                max_fee: Fee(MAX_FEE),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_valid_transaction() {
+        assert!(rejection(invoke(0, MAX_FEE), BALANCE).is_none());
+        assert!(rejection(faulty_invoke(VALID), BALANCE).is_none());
+    }
+
+    #[test]
+    fn test_invalid_nonce() {
+        assert!(matches!(rejection(invoke(1, MAX_FEE), BALANCE), Some(StarknetRpcApiError::InvalidTxnNonce)));
+    }
+
+    #[test]
+    fn test_insufficient_account_balance() {
+        assert!(matches!(rejection(invoke(0, MAX_FEE), 0), Some(StarknetRpcApiError::InsufficientAccountBalance)));
+    }
+
+    #[test]
+    fn test_insufficient_max_fee() {
+        assert!(matches!(rejection(invoke(0, 1), BALANCE), Some(StarknetRpcApiError::InsufficientMaxFee)));
+    }
+
+    #[test]
+    fn test_validation_failure() {
+        assert!(matches!(
+            rejection(faulty_invoke(INVALID), BALANCE),
+            Some(StarknetRpcApiError::ValidationFailure { .. })
+        ));
+    }
 }
@@ -6,7 +6,7 @@ use mp_hashers::HasherT;
 use mp_transactions::to_starknet_core_transaction::to_starknet_core_tx;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::transaction as stx;
-use starknet_core::types::{BlockId, BlockStatus, BlockTag, FieldElement};
+use starknet_core::types::{BlockId, BlockStatus, BlockTag, FieldElement, TransactionFinalityStatus};
 
 use crate::errors::StarknetRpcApiError;
 use crate::Felt;
@@ -34,11 +34,20 @@ pub(crate) fn tx_conv(
     txs.iter().zip(tx_hashes).map(|(tx, hash)| to_starknet_core_tx(tx.clone(), hash)).collect()
 }
 
+/// Whether the given block is at or below the last block verified on L1
+pub(crate) fn is_accepted_on_l1(block_number: u64) -> bool {
+    block_number <= ETHEREUM_STATE_UPDATE.read().unwrap().block_number
+}
+
 pub(crate) fn status(block_number: u64) -> BlockStatus {
-    if block_number <= ETHEREUM_STATE_UPDATE.read().unwrap().block_number {
-        BlockStatus::AcceptedOnL1
+    if is_accepted_on_l1(block_number) { BlockStatus::AcceptedOnL1 } else { BlockStatus::AcceptedOnL2 }
+}
+
+pub(crate) fn finality_status(block_number: u64) -> TransactionFinalityStatus {
+    if is_accepted_on_l1(block_number) {
+        TransactionFinalityStatus::AcceptedOnL1
     } else {
-        BlockStatus::AcceptedOnL2
+        TransactionFinalityStatus::AcceptedOnL2
     }
 }
 
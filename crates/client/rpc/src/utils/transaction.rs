@@ -1,15 +1,19 @@
 use blockifier::execution::contract_class::ClassInfo;
 use blockifier::transaction::transaction_execution as btx;
 use jsonrpsee::core::RpcResult;
-use mc_db::storage_handler;
 use mc_db::storage_handler::primitives::contract_class::StorageContractClassData;
 use mc_db::storage_handler::StorageView;
+use mc_db::{storage_handler, DeoxysBackend};
 use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{Transaction, TransactionHash};
 use starknet_ff::FieldElement;
 
 use crate::errors::StarknetRpcApiError;
 
+/// Fee assumed to be paid on L1 by the L1 handler transactions whose message is not indexed. The
+/// blockifier only rejects a zero paid fee.
+const DEFAULT_PAID_FEE_ON_L1: u128 = 1_000_000_000_000;
+
 pub(crate) fn blockifier_transactions(
     transaction_with_hash: Vec<(Transaction, FieldElement)>,
 ) -> RpcResult<Vec<btx::Transaction>> {
@@ -30,8 +34,22 @@ pub(crate) fn to_blockifier_transactions(
     transaction: &Transaction,
     tx_hash: &TransactionHash,
 ) -> RpcResult<btx::Transaction> {
+    // L1 handler transactions share their nonce with the message they consume, which holds the fee
+    // paid on L1
     let paid_fee_on_l1 = match transaction {
-        Transaction::L1Handler(_) => Some(starknet_api::transaction::Fee(1_000_000_000_000)),
+        Transaction::L1Handler(tx) => {
+            let fee = match DeoxysBackend::messaging().l1_message(tx.nonce.0).map_err(StarknetRpcApiError::from)? {
+                Some(message) => message.fee,
+                None => {
+                    log::warn!(
+                        "L1 message #{} is not indexed, re-executing its L1 handler with a default paid fee",
+                        tx.nonce.0
+                    );
+                    DEFAULT_PAID_FEE_ON_L1
+                }
+            };
+            Some(starknet_api::transaction::Fee(fee))
+        }
         _ => None,
     };
 
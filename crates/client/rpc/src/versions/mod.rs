@@ -0,0 +1,423 @@
+//! Versions of the Starknet RPC specification served alongside the current one
+//!
+//! The current version, v0.7, is implemented by the `Starknet*RpcApi` traits. The other versions
+//! only implement the methods whose results differ from v0.7, by converting the v0.7 results to
+//! the types of the version, so that all the versions share the same storage and execution. These
+//! methods are registered with the version in their name, e.g.
+//! `starknet_V0_6_getBlockWithTxHashes`, and the requests sent to the path of a version are
+//! redirected to them by [`RpcVersion::rewrite_request`].
+
+pub mod v0_6;
+pub mod v0_8;
+
+use serde::Serialize;
+use serde_json::Value;
+use serde_with::serde_as;
+use starknet_core::serde::unsigned_field_element::UfeHex;
+use starknet_core::types::{
+    Event, ExecutionResources, ExecutionResult, FeePayment, FieldElement, Hash256, MsgToL1, ReceiptBlock,
+    TransactionFinalityStatus,
+};
+
+/// A version of the Starknet RPC specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcVersion {
+    V0_6,
+    V0_7,
+    V0_8,
+}
+
+impl RpcVersion {
+    pub const ALL: [RpcVersion; 3] = [RpcVersion::V0_6, RpcVersion::V0_7, RpcVersion::V0_8];
+
+    /// The path the version is served at
+    pub fn path(self) -> &'static str {
+        match self {
+            RpcVersion::V0_6 => "/rpc/v0_6",
+            RpcVersion::V0_7 => "/rpc/v0_7",
+            RpcVersion::V0_8 => "/rpc/v0_8",
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.trim_end_matches('/');
+        Self::ALL.into_iter().find(|version| version.path() == path)
+    }
+
+    fn method_prefix(self) -> &'static str {
+        match self {
+            RpcVersion::V0_6 => "V0_6_",
+            RpcVersion::V0_7 => "V0_7_",
+            RpcVersion::V0_8 => "V0_8_",
+        }
+    }
+
+    /// Methods of the current version which are not part of this one
+    fn unsupported_methods(self) -> &'static [&'static str] {
+        match self {
+            RpcVersion::V0_6 => &["getBlockWithReceipts", "getMessagesStatus", "getCompiledCasm"],
+            RpcVersion::V0_7 => &["getMessagesStatus", "getCompiledCasm"],
+            RpcVersion::V0_8 => &[],
+        }
+    }
+
+    /// Returns the method serving `method` in this version, if it is not the one of the current
+    /// version
+    ///
+    /// The methods which are not part of this version are renamed to a method which is not
+    /// registered, so that they are not found.
+    pub fn rewrite_method(self, method: &str, is_registered: impl Fn(&str) -> bool) -> Option<String> {
+        let name = method.strip_prefix("starknet_")?;
+        let versioned_method = format!("starknet_{}{name}", self.method_prefix());
+        (is_registered(&versioned_method) || self.unsupported_methods().contains(&name)).then_some(versioned_method)
+    }
+
+    /// Rewrites the methods of a request or a batch of requests sent to the path of this version
+    ///
+    /// Returns `None` when the request is left unchanged, invalid requests included.
+    pub fn rewrite_request(self, request: &[u8], is_registered: impl Fn(&str) -> bool) -> Option<Vec<u8>> {
+        let mut request: Value = serde_json::from_slice(request).ok()?;
+
+        let mut rewritten = false;
+        let calls: Vec<&mut Value> = match &mut request {
+            Value::Array(calls) => calls.iter_mut().collect(),
+            call => vec![call],
+        };
+        for call in calls {
+            if let Some(Value::String(method)) = call.get_mut("method")
+                && let Some(versioned_method) = self.rewrite_method(method, &is_registered)
+            {
+                *method = versioned_method;
+                rewritten = true;
+            }
+        }
+
+        if rewritten { serde_json::to_vec(&request).ok() } else { None }
+    }
+}
+
+/// Type of a transaction, as tagged in its receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionType {
+    Invoke,
+    L1Handler,
+    Declare,
+    Deploy,
+    DeployAccount,
+}
+
+/// Receipt of a transaction in a version whose receipts only differ from the current version by
+/// their execution resources `R`
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionReceipt<R> {
+    pub r#type: TransactionType,
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    pub actual_fee: FeePayment,
+    pub finality_status: TransactionFinalityStatus,
+    pub messages_sent: Vec<MsgToL1>,
+    pub events: Vec<Event>,
+    pub execution_resources: R,
+    #[serde(flatten)]
+    pub execution_result: ExecutionResult,
+    /// Hash of the message sent from L1, for L1 handler transactions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<Hash256>,
+    /// Address of the deployed contract, for deploy and deploy account transactions
+    #[serde_as(as = "Option<UfeHex>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<FieldElement>,
+}
+
+/// Builds a receipt from the fields the receipts of all the transaction types have
+macro_rules! receipt {
+    ($receipt:ident, $type:ident, $message_hash:expr, $contract_address:expr) => {
+        TransactionReceipt {
+            r#type: TransactionType::$type,
+            transaction_hash: $receipt.transaction_hash,
+            actual_fee: $receipt.actual_fee,
+            finality_status: $receipt.finality_status,
+            messages_sent: $receipt.messages_sent,
+            events: $receipt.events,
+            execution_resources: $receipt.execution_resources.into(),
+            execution_result: $receipt.execution_result,
+            message_hash: $message_hash,
+            contract_address: $contract_address,
+        }
+    };
+}
+
+impl<R: From<ExecutionResources>> From<starknet_core::types::TransactionReceipt> for TransactionReceipt<R> {
+    fn from(receipt: starknet_core::types::TransactionReceipt) -> Self {
+        use starknet_core::types::TransactionReceipt as Receipt;
+
+        match receipt {
+            Receipt::Invoke(r) => receipt!(r, Invoke, None, None),
+            Receipt::L1Handler(r) => receipt!(r, L1Handler, Some(r.message_hash), None),
+            Receipt::Declare(r) => receipt!(r, Declare, None, None),
+            Receipt::Deploy(r) => receipt!(r, Deploy, None, Some(r.contract_address)),
+            Receipt::DeployAccount(r) => receipt!(r, DeployAccount, None, Some(r.contract_address)),
+        }
+    }
+}
+
+/// Receipt of a transaction with the block it is in, if it is not in the pending block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionReceiptWithBlockInfo<R> {
+    #[serde(flatten)]
+    pub receipt: TransactionReceipt<R>,
+    #[serde(flatten)]
+    pub block: ReceiptBlock,
+}
+
+impl<R: From<ExecutionResources>> From<starknet_core::types::TransactionReceiptWithBlockInfo>
+    for TransactionReceiptWithBlockInfo<R>
+{
+    fn from(receipt: starknet_core::types::TransactionReceiptWithBlockInfo) -> Self {
+        Self { receipt: receipt.receipt.into(), block: receipt.block }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use starknet_core::types::{
+        CallType, ComputationResources, DataAvailabilityMode, DataAvailabilityResources, DataResources, EntryPointType,
+        FeeEstimate, FunctionInvocation, InvokeTransaction, InvokeTransactionV3, L1DataAvailabilityMode,
+        L1HandlerTransactionReceipt, L1HandlerTransactionTrace, MaybePendingBlockWithTxHashes,
+        PendingBlockWithTxHashes, PriceUnit, ResourceBounds, ResourceBoundsMapping, ResourcePrice,
+        SimulatedTransaction, Transaction, TransactionReceipt, TransactionReceiptWithBlockInfo, TransactionTrace,
+    };
+
+    use super::*;
+
+    fn is_registered(method: &str) -> bool {
+        ["starknet_V0_6_specVersion", "starknet_V0_8_specVersion"].contains(&method)
+    }
+
+    #[test]
+    fn test_rpc_version_from_path() {
+        assert_eq!(RpcVersion::from_path("/rpc/v0_6"), Some(RpcVersion::V0_6));
+        assert_eq!(RpcVersion::from_path("/rpc/v0_8/"), Some(RpcVersion::V0_8));
+        assert_eq!(RpcVersion::from_path("/"), None);
+        assert_eq!(RpcVersion::from_path("/rpc/v0_5"), None);
+    }
+
+    #[test]
+    fn test_rewrite_method() {
+        let v0_6 = RpcVersion::V0_6;
+        assert_eq!(
+            v0_6.rewrite_method("starknet_specVersion", is_registered).as_deref(),
+            Some("starknet_V0_6_specVersion")
+        );
+        // shared with the current version
+        assert_eq!(v0_6.rewrite_method("starknet_blockNumber", is_registered), None);
+        assert_eq!(v0_6.rewrite_method("system_health", is_registered), None);
+        // not part of v0.6, renamed to a method which is not found
+        assert_eq!(
+            v0_6.rewrite_method("starknet_getBlockWithReceipts", is_registered).as_deref(),
+            Some("starknet_V0_6_getBlockWithReceipts")
+        );
+        assert_eq!(RpcVersion::V0_7.rewrite_method("starknet_specVersion", is_registered), None);
+    }
+
+    fn execution_resources() -> ExecutionResources {
+        ExecutionResources {
+            computation_resources: ComputationResources {
+                steps: 10,
+                memory_holes: None,
+                range_check_builtin_applications: None,
+                pedersen_builtin_applications: None,
+                poseidon_builtin_applications: None,
+                ec_op_builtin_applications: None,
+                ecdsa_builtin_applications: None,
+                bitwise_builtin_applications: None,
+                keccak_builtin_applications: None,
+                segment_arena_builtin: None,
+            },
+            data_resources: DataResources {
+                data_availability: DataAvailabilityResources { l1_gas: 0, l1_data_gas: 128 },
+            },
+        }
+    }
+
+    fn invocation() -> FunctionInvocation {
+        FunctionInvocation {
+            contract_address: FieldElement::ONE,
+            entry_point_selector: FieldElement::TWO,
+            calldata: vec![],
+            caller_address: FieldElement::ZERO,
+            class_hash: FieldElement::THREE,
+            entry_point_type: EntryPointType::L1Handler,
+            call_type: CallType::Call,
+            result: vec![],
+            calls: vec![],
+            events: vec![],
+            messages: vec![],
+            execution_resources: execution_resources().computation_resources,
+        }
+    }
+
+    fn fee_estimate() -> FeeEstimate {
+        FeeEstimate {
+            gas_consumed: FieldElement::from(1_u8),
+            gas_price: FieldElement::from(2_u8),
+            data_gas_consumed: FieldElement::from(3_u8),
+            data_gas_price: FieldElement::from(4_u8),
+            overall_fee: FieldElement::from(14_u8),
+            unit: PriceUnit::Wei,
+        }
+    }
+
+    fn to_json<T: Serialize>(value: T) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_block_conversions() {
+        let price = ResourcePrice { price_in_fri: FieldElement::ONE, price_in_wei: FieldElement::ONE };
+        let block = MaybePendingBlockWithTxHashes::PendingBlock(PendingBlockWithTxHashes {
+            transactions: vec![FieldElement::ONE],
+            parent_hash: FieldElement::TWO,
+            timestamp: 1,
+            sequencer_address: FieldElement::THREE,
+            l1_gas_price: price.clone(),
+            l1_data_gas_price: price,
+            l1_da_mode: L1DataAvailabilityMode::Blob,
+            starknet_version: "0.13.1".to_string(),
+        });
+
+        let v0_6_block = to_json(v0_6::types::MaybePendingBlock::from(block.clone()));
+        assert!(v0_6_block.get("l1_data_gas_price").is_none() && v0_6_block.get("l1_da_mode").is_none());
+        assert_eq!(v0_6_block["transactions"], json!(["0x1"]));
+
+        let v0_8_block = to_json(v0_8::types::MaybePendingBlock::from(block));
+        assert_eq!(v0_8_block["l2_gas_price"], json!({ "price_in_fri": "0x0", "price_in_wei": "0x0" }));
+        assert_eq!(v0_8_block["l1_da_mode"], json!("BLOB"));
+    }
+
+    #[test]
+    fn test_transaction_conversions() {
+        let bounds = ResourceBounds { max_amount: 1, max_price_per_unit: 2 };
+        let transaction = Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+            transaction_hash: FieldElement::ONE,
+            sender_address: FieldElement::TWO,
+            calldata: vec![],
+            signature: vec![],
+            nonce: FieldElement::ZERO,
+            resource_bounds: ResourceBoundsMapping { l1_gas: bounds.clone(), l2_gas: bounds },
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        }));
+
+        let v0_8_transaction = to_json(v0_8::types::Transaction::from(transaction.clone()));
+        let mut expected = to_json(&transaction);
+        expected["resource_bounds"]["l1_data_gas"] = json!({ "max_amount": "0x0", "max_price_per_unit": "0x0" });
+        assert_eq!(v0_8_transaction, expected);
+
+        let receipt = TransactionReceipt::L1Handler(L1HandlerTransactionReceipt {
+            message_hash: Hash256::from_felt(&FieldElement::ONE),
+            transaction_hash: FieldElement::TWO,
+            actual_fee: FeePayment { amount: FieldElement::THREE, unit: PriceUnit::Wei },
+            finality_status: TransactionFinalityStatus::AcceptedOnL2,
+            messages_sent: vec![],
+            events: vec![],
+            execution_resources: execution_resources(),
+            execution_result: ExecutionResult::Succeeded,
+        });
+        let receipt = TransactionReceiptWithBlockInfo {
+            receipt,
+            block: ReceiptBlock::Block { block_hash: FieldElement::ONE, block_number: 1 },
+        };
+
+        // the receipts only differ by their execution resources
+        let v0_6_receipt = to_json(v0_6::types::TransactionReceiptWithBlockInfo::from(receipt.clone()));
+        let mut expected = to_json(&receipt);
+        expected["execution_resources"] = json!({ "steps": 10 });
+        assert_eq!(v0_6_receipt, expected);
+
+        let v0_8_receipt = to_json(v0_8::types::TransactionReceiptWithBlockInfo::from(receipt.clone()));
+        expected["execution_resources"] = json!({ "l1_gas": 0, "l1_data_gas": 128, "l2_gas": 0 });
+        assert_eq!(v0_8_receipt, expected);
+    }
+
+    #[test]
+    fn test_fee_estimate_conversions() {
+        assert_eq!(
+            to_json(v0_6::types::FeeEstimate::from(fee_estimate())),
+            json!({ "gas_consumed": "0x1", "gas_price": "0x2", "overall_fee": "0xe", "unit": "WEI" })
+        );
+        assert_eq!(
+            to_json(v0_8::types::FeeEstimate::from(fee_estimate())),
+            json!({
+                "l1_gas_consumed": "0x1",
+                "l1_gas_price": "0x2",
+                "l1_data_gas_consumed": "0x3",
+                "l1_data_gas_price": "0x4",
+                "l2_gas_consumed": "0x0",
+                "l2_gas_price": "0x0",
+                "overall_fee": "0xe",
+                "unit": "WEI",
+            })
+        );
+    }
+
+    #[test]
+    fn test_trace_conversions() {
+        let simulated = SimulatedTransaction {
+            transaction_trace: TransactionTrace::L1Handler(L1HandlerTransactionTrace {
+                function_invocation: invocation(),
+                state_diff: None,
+                execution_resources: execution_resources(),
+            }),
+            fee_estimation: fee_estimate(),
+        };
+
+        let v0_6_simulated = to_json(v0_6::types::SimulatedTransaction::from(simulated.clone()));
+        let mut expected = to_json(&simulated);
+        expected["transaction_trace"].as_object_mut().unwrap().remove("execution_resources");
+        expected["fee_estimation"] = to_json(v0_6::types::FeeEstimate::from(fee_estimate()));
+        assert_eq!(v0_6_simulated, expected);
+
+        let v0_8_simulated = to_json(v0_8::types::SimulatedTransaction::from(simulated.clone()));
+        let mut expected = to_json(&simulated);
+        let trace = &mut expected["transaction_trace"];
+        trace["execution_resources"] = json!({ "l1_gas": 0, "l1_data_gas": 128, "l2_gas": 0 });
+        trace["function_invocation"]["execution_resources"] = json!({ "l1_gas": 0, "l2_gas": 0 });
+        trace["function_invocation"]["is_reverted"] = json!(false);
+        expected["fee_estimation"] = to_json(v0_8::types::FeeEstimate::from(fee_estimate()));
+        assert_eq!(v0_8_simulated, expected);
+    }
+
+    #[test]
+    fn test_rewrite_request() {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "starknet_specVersion" });
+        let rewritten = RpcVersion::V0_8.rewrite_request(request.to_string().as_bytes(), is_registered).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&rewritten).unwrap(),
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "starknet_V0_8_specVersion" })
+        );
+
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "starknet_specVersion" },
+            { "jsonrpc": "2.0", "id": 2, "method": "starknet_blockNumber" },
+        ]);
+        let rewritten = RpcVersion::V0_6.rewrite_request(batch.to_string().as_bytes(), is_registered).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&rewritten).unwrap(),
+            json!([
+                { "jsonrpc": "2.0", "id": 1, "method": "starknet_V0_6_specVersion" },
+                { "jsonrpc": "2.0", "id": 2, "method": "starknet_blockNumber" },
+            ])
+        );
+
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "starknet_blockNumber" });
+        assert_eq!(RpcVersion::V0_6.rewrite_request(request.to_string().as_bytes(), is_registered), None);
+        assert_eq!(RpcVersion::V0_6.rewrite_request(b"not json", is_registered), None);
+    }
+}
@@ -0,0 +1,83 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{
+    BlockId, BroadcastedTransaction, FieldElement, MsgFromL1, SimulationFlag, SimulationFlagForEstimateFee, Transaction,
+};
+
+use super::types::{
+    FeeEstimate, MaybePendingBlock, SimulatedTransaction, TransactionReceiptWithBlockInfo, TransactionTraceWithHash,
+};
+use super::StarknetV06RpcApiServer;
+use crate::methods::read::estimate_fee::estimate_fee;
+use crate::methods::read::estimate_message_fee::estimate_message_fee;
+use crate::methods::read::get_block_with_tx_hashes::get_block_with_tx_hashes;
+use crate::methods::read::get_block_with_txs::get_block_with_txs;
+use crate::methods::read::get_transaction_receipt::get_transaction_receipt;
+use crate::methods::trace::simulate_transactions::simulate_transactions;
+use crate::methods::trace::trace_block_transactions::trace_block_transactions;
+use crate::methods::trace::trace_transaction::trace_transaction;
+use crate::Starknet;
+
+#[async_trait]
+impl<BE, C, H> StarknetV06RpcApiServer for Starknet<BE, C, H>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    fn spec_version(&self) -> RpcResult<String> {
+        Ok("0.6.0".to_string())
+    }
+
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<FieldElement>> {
+        Ok(get_block_with_tx_hashes(self, block_id)?.into())
+    }
+
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Transaction>> {
+        Ok(get_block_with_txs(self, block_id)?.into())
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> RpcResult<TransactionReceiptWithBlockInfo> {
+        Ok(get_transaction_receipt(self, transaction_hash).await?.into())
+    }
+
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<FeeEstimate>> {
+        let fee_estimates = estimate_fee(self, request, simulation_flags, block_id).await?;
+        Ok(fee_estimates.into_iter().map(Into::into).collect())
+    }
+
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate> {
+        Ok(estimate_message_fee(self, message, block_id).await?.into())
+    }
+
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        let simulated = simulate_transactions(self, block_id, transactions, simulation_flags).await?;
+        Ok(simulated.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>> {
+        let traces = trace_block_transactions(self, block_id).await?;
+        Ok(traces.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_transaction(&self, transaction_hash: FieldElement) -> RpcResult<TransactionTraceWithHash> {
+        Ok(trace_transaction(self, transaction_hash).await?.into())
+    }
+}
@@ -0,0 +1,69 @@
+//! Starknet RPC v0.6
+//!
+//! v0.6 has no L1 data gas: it is left out of the block headers, the receipts, the fee estimates
+//! and the traces.
+
+pub mod lib;
+pub mod types;
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use starknet_core::types::{
+    BlockId, BroadcastedTransaction, FieldElement, MsgFromL1, SimulationFlag, SimulationFlagForEstimateFee, Transaction,
+};
+use types::{
+    FeeEstimate, MaybePendingBlock, SimulatedTransaction, TransactionReceiptWithBlockInfo, TransactionTraceWithHash,
+};
+
+/// The methods of Starknet RPC v0.6 whose results differ from the current version
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetV06RpcApi {
+    /// Get the Version of the StarkNet JSON-RPC Specification Being Used
+    #[method(name = "V0_6_specVersion")]
+    fn spec_version(&self) -> RpcResult<String>;
+
+    /// Get block information with transaction hashes given the block id
+    #[method(name = "V0_6_getBlockWithTxHashes")]
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<FieldElement>>;
+
+    /// Get block information with full transactions given the block id
+    #[method(name = "V0_6_getBlockWithTxs")]
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Transaction>>;
+
+    /// Returns the receipt of a transaction by transaction hash.
+    #[method(name = "V0_6_getTransactionReceipt")]
+    async fn get_transaction_receipt(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> RpcResult<TransactionReceiptWithBlockInfo>;
+
+    /// Estimate the fee associated with transaction
+    #[method(name = "V0_6_estimateFee")]
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<FeeEstimate>>;
+
+    /// Estimate the L2 fee of a message sent on L1
+    #[method(name = "V0_6_estimateMessageFee")]
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate>;
+
+    /// Returns the execution trace of a transaction by simulating it in the runtime.
+    #[method(name = "V0_6_simulateTransactions")]
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+
+    /// Returns the execution traces of all transactions included in the given block
+    #[method(name = "V0_6_traceBlockTransactions")]
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>>;
+
+    /// Returns the execution trace of a transaction
+    #[method(name = "V0_6_traceTransaction")]
+    async fn trace_transaction(&self, transaction_hash: FieldElement) -> RpcResult<TransactionTraceWithHash>;
+}
@@ -0,0 +1,274 @@
+//! The results of Starknet RPC v0.6 which differ from the current version
+
+use serde::Serialize;
+use serde_with::serde_as;
+use starknet_core::serde::unsigned_field_element::UfeHex;
+use starknet_core::types::{
+    BlockStatus, ComputationResources, ExecuteInvocation, FieldElement, FunctionInvocation, PriceUnit, ResourcePrice,
+    StateDiff, Transaction,
+};
+
+/// Execution resources of a transaction, without the data availability resources of v0.7
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ExecutionResources(pub ComputationResources);
+
+impl From<starknet_core::types::ExecutionResources> for ExecutionResources {
+    fn from(execution_resources: starknet_core::types::ExecutionResources) -> Self {
+        Self(execution_resources.computation_resources)
+    }
+}
+
+pub type TransactionReceiptWithBlockInfo = crate::versions::TransactionReceiptWithBlockInfo<ExecutionResources>;
+
+/// Header of a block, without the L1 data gas price and the data availability mode of v0.7
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockHeader {
+    pub status: BlockStatus,
+    #[serde_as(as = "UfeHex")]
+    pub block_hash: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub parent_hash: FieldElement,
+    pub block_number: u64,
+    #[serde_as(as = "UfeHex")]
+    pub new_root: FieldElement,
+    pub timestamp: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: ResourcePrice,
+    pub starknet_version: String,
+}
+
+/// Header of the pending block, without the L1 data gas price and the data availability mode of
+/// v0.7
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingBlockHeader {
+    #[serde_as(as = "UfeHex")]
+    pub parent_hash: FieldElement,
+    pub timestamp: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: ResourcePrice,
+    pub starknet_version: String,
+}
+
+/// A block with its transactions, either their hashes or the whole transactions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Block<T> {
+    #[serde(flatten)]
+    pub header: BlockHeader,
+    pub transactions: Vec<T>,
+}
+
+/// The pending block with its transactions, either their hashes or the whole transactions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingBlock<T> {
+    #[serde(flatten)]
+    pub header: PendingBlockHeader,
+    pub transactions: Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum MaybePendingBlock<T> {
+    Block(Block<T>),
+    PendingBlock(PendingBlock<T>),
+}
+
+/// Builds the header of a block from any of the block types of the current version
+macro_rules! block_header {
+    ($block:ident) => {
+        BlockHeader {
+            status: $block.status,
+            block_hash: $block.block_hash,
+            parent_hash: $block.parent_hash,
+            block_number: $block.block_number,
+            new_root: $block.new_root,
+            timestamp: $block.timestamp,
+            sequencer_address: $block.sequencer_address,
+            l1_gas_price: $block.l1_gas_price,
+            starknet_version: $block.starknet_version,
+        }
+    };
+}
+
+/// Builds the header of the pending block from any of the pending block types of the current
+/// version
+macro_rules! pending_block_header {
+    ($block:ident) => {
+        PendingBlockHeader {
+            parent_hash: $block.parent_hash,
+            timestamp: $block.timestamp,
+            sequencer_address: $block.sequencer_address,
+            l1_gas_price: $block.l1_gas_price,
+            starknet_version: $block.starknet_version,
+        }
+    };
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxHashes> for MaybePendingBlock<FieldElement> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxHashes) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxHashes as Current;
+
+        match block {
+            Current::Block(block) => {
+                Self::Block(Block { transactions: block.transactions, header: block_header!(block) })
+            }
+            Current::PendingBlock(block) => Self::PendingBlock(PendingBlock {
+                transactions: block.transactions,
+                header: pending_block_header!(block),
+            }),
+        }
+    }
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxs> for MaybePendingBlock<Transaction> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxs) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxs as Current;
+
+        match block {
+            Current::Block(block) => {
+                Self::Block(Block { transactions: block.transactions, header: block_header!(block) })
+            }
+            Current::PendingBlock(block) => Self::PendingBlock(PendingBlock {
+                transactions: block.transactions,
+                header: pending_block_header!(block),
+            }),
+        }
+    }
+}
+
+/// Fee estimate, without the L1 data gas of v0.7
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeeEstimate {
+    #[serde_as(as = "UfeHex")]
+    pub gas_consumed: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub gas_price: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub overall_fee: FieldElement,
+    pub unit: PriceUnit,
+}
+
+impl From<starknet_core::types::FeeEstimate> for FeeEstimate {
+    fn from(fee_estimate: starknet_core::types::FeeEstimate) -> Self {
+        Self {
+            gas_consumed: fee_estimate.gas_consumed,
+            gas_price: fee_estimate.gas_price,
+            overall_fee: fee_estimate.overall_fee,
+            unit: fee_estimate.unit,
+        }
+    }
+}
+
+/// Trace of a transaction, without the execution resources of v0.7
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum TransactionTrace {
+    #[serde(rename = "INVOKE")]
+    Invoke(InvokeTransactionTrace),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(DeployAccountTransactionTrace),
+    #[serde(rename = "L1_HANDLER")]
+    L1Handler(L1HandlerTransactionTrace),
+    #[serde(rename = "DECLARE")]
+    Declare(DeclareTransactionTrace),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvokeTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    pub execute_invocation: ExecuteInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeployAccountTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    pub constructor_invocation: FunctionInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct L1HandlerTransactionTrace {
+    pub function_invocation: FunctionInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeclareTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+}
+
+impl From<starknet_core::types::TransactionTrace> for TransactionTrace {
+    fn from(trace: starknet_core::types::TransactionTrace) -> Self {
+        use starknet_core::types::TransactionTrace as Current;
+
+        match trace {
+            Current::Invoke(trace) => Self::Invoke(InvokeTransactionTrace {
+                validate_invocation: trace.validate_invocation,
+                execute_invocation: trace.execute_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            }),
+            Current::DeployAccount(trace) => Self::DeployAccount(DeployAccountTransactionTrace {
+                validate_invocation: trace.validate_invocation,
+                constructor_invocation: trace.constructor_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            }),
+            Current::L1Handler(trace) => Self::L1Handler(L1HandlerTransactionTrace {
+                function_invocation: trace.function_invocation,
+                state_diff: trace.state_diff,
+            }),
+            Current::Declare(trace) => Self::Declare(DeclareTransactionTrace {
+                validate_invocation: trace.validate_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            }),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionTraceWithHash {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    pub trace_root: TransactionTrace,
+}
+
+impl From<starknet_core::types::TransactionTraceWithHash> for TransactionTraceWithHash {
+    fn from(trace: starknet_core::types::TransactionTraceWithHash) -> Self {
+        Self { transaction_hash: trace.transaction_hash, trace_root: trace.trace_root.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SimulatedTransaction {
+    pub transaction_trace: TransactionTrace,
+    pub fee_estimation: FeeEstimate,
+}
+
+impl From<starknet_core::types::SimulatedTransaction> for SimulatedTransaction {
+    fn from(simulated: starknet_core::types::SimulatedTransaction) -> Self {
+        Self { transaction_trace: simulated.transaction_trace.into(), fee_estimation: simulated.fee_estimation.into() }
+    }
+}
@@ -0,0 +1,99 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_hashers::HasherT;
+use mp_types::block::DBlockT;
+use sc_client_api::backend::{Backend, StorageProvider};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use starknet_core::types::{
+    BlockId, BroadcastedTransaction, FieldElement, MsgFromL1, SimulationFlag, SimulationFlagForEstimateFee,
+};
+
+use super::types::{
+    FeeEstimate, MaybePendingBlock, SimulatedTransaction, Transaction, TransactionReceiptWithBlockInfo,
+    TransactionTraceWithHash, TransactionWithReceipt,
+};
+use super::StarknetV08RpcApiServer;
+use crate::methods::read::estimate_fee::estimate_fee;
+use crate::methods::read::estimate_message_fee::estimate_message_fee;
+use crate::methods::read::get_block_with_receipts::get_block_with_receipts;
+use crate::methods::read::get_block_with_tx_hashes::get_block_with_tx_hashes;
+use crate::methods::read::get_block_with_txs::get_block_with_txs;
+use crate::methods::read::get_transaction_by_block_id_and_index::get_transaction_by_block_id_and_index;
+use crate::methods::read::get_transaction_by_hash::get_transaction_by_hash;
+use crate::methods::read::get_transaction_receipt::get_transaction_receipt;
+use crate::methods::trace::simulate_transactions::simulate_transactions;
+use crate::methods::trace::trace_block_transactions::trace_block_transactions;
+use crate::methods::trace::trace_transaction::trace_transaction;
+use crate::Starknet;
+
+#[async_trait]
+impl<BE, C, H> StarknetV08RpcApiServer for Starknet<BE, C, H>
+where
+    BE: Backend<DBlockT> + 'static,
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    H: HasherT + Send + Sync + 'static,
+{
+    fn spec_version(&self) -> RpcResult<String> {
+        Ok("0.8.0".to_string())
+    }
+
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<FieldElement>> {
+        Ok(get_block_with_tx_hashes(self, block_id)?.into())
+    }
+
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Transaction>> {
+        Ok(get_block_with_txs(self, block_id)?.into())
+    }
+
+    async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<TransactionWithReceipt>> {
+        Ok(get_block_with_receipts(self, block_id)?.into())
+    }
+
+    fn get_transaction_by_block_id_and_index(&self, block_id: BlockId, index: u64) -> RpcResult<Transaction> {
+        Ok(get_transaction_by_block_id_and_index(self, block_id, index)?.into())
+    }
+
+    fn get_transaction_by_hash(&self, transaction_hash: FieldElement) -> RpcResult<Transaction> {
+        Ok(get_transaction_by_hash(self, transaction_hash)?.into())
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> RpcResult<TransactionReceiptWithBlockInfo> {
+        Ok(get_transaction_receipt(self, transaction_hash).await?.into())
+    }
+
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<FeeEstimate>> {
+        let fee_estimates = estimate_fee(self, request, simulation_flags, block_id).await?;
+        Ok(fee_estimates.into_iter().map(Into::into).collect())
+    }
+
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate> {
+        Ok(estimate_message_fee(self, message, block_id).await?.into())
+    }
+
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        let simulated = simulate_transactions(self, block_id, transactions, simulation_flags).await?;
+        Ok(simulated.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>> {
+        let traces = trace_block_transactions(self, block_id).await?;
+        Ok(traces.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_transaction(&self, transaction_hash: FieldElement) -> RpcResult<TransactionTraceWithHash> {
+        Ok(trace_transaction(self, transaction_hash).await?.into())
+    }
+}
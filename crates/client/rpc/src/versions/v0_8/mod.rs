@@ -0,0 +1,85 @@
+//! Starknet RPC v0.8
+//!
+//! v0.8 adds the L2 gas to the block headers, the resource bounds and the fee estimates, and the
+//! L1 data gas to the resource bounds. The blocks synced do not price the L2 gas and the
+//! transactions do not bound the L1 data gas, these are reported as zero. The receipts and the
+//! traces report the gas consumed instead of the execution resources, only the gas of the data
+//! availability is known as the feeder gateway does not split the gas of the execution.
+
+pub mod lib;
+pub mod types;
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use starknet_core::types::{
+    BlockId, BroadcastedTransaction, FieldElement, MsgFromL1, SimulationFlag, SimulationFlagForEstimateFee,
+};
+use types::{
+    FeeEstimate, MaybePendingBlock, SimulatedTransaction, Transaction, TransactionReceiptWithBlockInfo,
+    TransactionTraceWithHash, TransactionWithReceipt,
+};
+
+/// The methods of Starknet RPC v0.8 whose results differ from the current version
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetV08RpcApi {
+    /// Get the Version of the StarkNet JSON-RPC Specification Being Used
+    #[method(name = "V0_8_specVersion")]
+    fn spec_version(&self) -> RpcResult<String>;
+
+    /// Get block information with transaction hashes given the block id
+    #[method(name = "V0_8_getBlockWithTxHashes")]
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<FieldElement>>;
+
+    /// Get block information with full transactions given the block id
+    #[method(name = "V0_8_getBlockWithTxs")]
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Transaction>>;
+
+    /// Get block information with full transactions and receipts given the block id
+    #[method(name = "V0_8_getBlockWithReceipts")]
+    async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<TransactionWithReceipt>>;
+
+    /// Get the details of a transaction by a given block id and index
+    #[method(name = "V0_8_getTransactionByBlockIdAndIndex")]
+    fn get_transaction_by_block_id_and_index(&self, block_id: BlockId, index: u64) -> RpcResult<Transaction>;
+
+    /// Returns the information about a transaction by transaction hash.
+    #[method(name = "V0_8_getTransactionByHash")]
+    fn get_transaction_by_hash(&self, transaction_hash: FieldElement) -> RpcResult<Transaction>;
+
+    /// Returns the receipt of a transaction by transaction hash.
+    #[method(name = "V0_8_getTransactionReceipt")]
+    async fn get_transaction_receipt(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> RpcResult<TransactionReceiptWithBlockInfo>;
+
+    /// Estimate the fee associated with transaction
+    #[method(name = "V0_8_estimateFee")]
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<FeeEstimate>>;
+
+    /// Estimate the L2 fee of a message sent on L1
+    #[method(name = "V0_8_estimateMessageFee")]
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate>;
+
+    /// Returns the execution trace of a transaction by simulating it in the runtime.
+    #[method(name = "V0_8_simulateTransactions")]
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+
+    /// Returns the execution traces of all transactions included in the given block
+    #[method(name = "V0_8_traceBlockTransactions")]
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>>;
+
+    /// Returns the execution trace of a transaction
+    #[method(name = "V0_8_traceTransaction")]
+    async fn trace_transaction(&self, transaction_hash: FieldElement) -> RpcResult<TransactionTraceWithHash>;
+}
@@ -0,0 +1,603 @@
+//! The results of Starknet RPC v0.8 which differ from the current version
+
+use serde::Serialize;
+use serde_with::serde_as;
+use starknet_core::serde::num_hex::NumAsHex;
+use starknet_core::serde::unsigned_field_element::UfeHex;
+use starknet_core::types::{
+    BlockStatus, CallType, DataAvailabilityMode, EntryPointType, FieldElement, L1DataAvailabilityMode, OrderedEvent,
+    OrderedMessage, PriceUnit, ResourceBounds, ResourcePrice, RevertedInvocation, StateDiff,
+};
+
+/// Gas consumed by a transaction, replacing the execution resources of v0.7
+///
+/// The feeder gateway does not split the gas of the execution: only the gas of the data
+/// availability is reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutionResources {
+    pub l1_gas: u64,
+    pub l1_data_gas: u64,
+    pub l2_gas: u64,
+}
+
+impl From<starknet_core::types::ExecutionResources> for ExecutionResources {
+    fn from(execution_resources: starknet_core::types::ExecutionResources) -> Self {
+        let data_availability = execution_resources.data_resources.data_availability;
+        Self { l1_gas: data_availability.l1_gas, l1_data_gas: data_availability.l1_data_gas, l2_gas: 0 }
+    }
+}
+
+pub type TransactionReceipt = crate::versions::TransactionReceipt<ExecutionResources>;
+pub type TransactionReceiptWithBlockInfo = crate::versions::TransactionReceiptWithBlockInfo<ExecutionResources>;
+
+/// Resource bounds of a v3 transaction, with the L1 data gas added in v0.8
+///
+/// The transactions of v0.7 do not bound the L1 data gas, it is reported as zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResourceBoundsMapping {
+    pub l1_gas: ResourceBounds,
+    pub l1_data_gas: ResourceBounds,
+    pub l2_gas: ResourceBounds,
+}
+
+impl From<starknet_core::types::ResourceBoundsMapping> for ResourceBoundsMapping {
+    fn from(resource_bounds: starknet_core::types::ResourceBoundsMapping) -> Self {
+        Self {
+            l1_gas: resource_bounds.l1_gas,
+            l1_data_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+            l2_gas: resource_bounds.l2_gas,
+        }
+    }
+}
+
+/// A transaction, the ones before v3 being the same as in the current version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Transaction {
+    V3(TransactionV3),
+    Unchanged(starknet_core::types::Transaction),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum TransactionV3 {
+    #[serde(rename = "INVOKE")]
+    Invoke(InvokeTransactionV3),
+    #[serde(rename = "DECLARE")]
+    Declare(DeclareTransactionV3),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(DeployAccountTransactionV3),
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvokeTransactionV3 {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    #[serde_as(as = "NumAsHex")]
+    pub version: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sender_address: FieldElement,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub calldata: Vec<FieldElement>,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub signature: Vec<FieldElement>,
+    #[serde_as(as = "UfeHex")]
+    pub nonce: FieldElement,
+    pub resource_bounds: ResourceBoundsMapping,
+    #[serde_as(as = "NumAsHex")]
+    pub tip: u64,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub paymaster_data: Vec<FieldElement>,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub account_deployment_data: Vec<FieldElement>,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeclareTransactionV3 {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    #[serde_as(as = "NumAsHex")]
+    pub version: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sender_address: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub compiled_class_hash: FieldElement,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub signature: Vec<FieldElement>,
+    #[serde_as(as = "UfeHex")]
+    pub nonce: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub class_hash: FieldElement,
+    pub resource_bounds: ResourceBoundsMapping,
+    #[serde_as(as = "NumAsHex")]
+    pub tip: u64,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub paymaster_data: Vec<FieldElement>,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub account_deployment_data: Vec<FieldElement>,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeployAccountTransactionV3 {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    #[serde_as(as = "NumAsHex")]
+    pub version: u64,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub signature: Vec<FieldElement>,
+    #[serde_as(as = "UfeHex")]
+    pub nonce: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub contract_address_salt: FieldElement,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub constructor_calldata: Vec<FieldElement>,
+    #[serde_as(as = "UfeHex")]
+    pub class_hash: FieldElement,
+    pub resource_bounds: ResourceBoundsMapping,
+    #[serde_as(as = "NumAsHex")]
+    pub tip: u64,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub paymaster_data: Vec<FieldElement>,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+}
+
+impl From<starknet_core::types::Transaction> for Transaction {
+    fn from(transaction: starknet_core::types::Transaction) -> Self {
+        use starknet_core::types::{DeclareTransaction, DeployAccountTransaction, InvokeTransaction};
+
+        match transaction {
+            starknet_core::types::Transaction::Invoke(InvokeTransaction::V3(tx)) => {
+                Self::V3(TransactionV3::Invoke(InvokeTransactionV3 {
+                    transaction_hash: tx.transaction_hash,
+                    version: 3,
+                    sender_address: tx.sender_address,
+                    calldata: tx.calldata,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    resource_bounds: tx.resource_bounds.into(),
+                    tip: tx.tip,
+                    paymaster_data: tx.paymaster_data,
+                    account_deployment_data: tx.account_deployment_data,
+                    nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                    fee_data_availability_mode: tx.fee_data_availability_mode,
+                }))
+            }
+            starknet_core::types::Transaction::Declare(DeclareTransaction::V3(tx)) => {
+                Self::V3(TransactionV3::Declare(DeclareTransactionV3 {
+                    transaction_hash: tx.transaction_hash,
+                    version: 3,
+                    sender_address: tx.sender_address,
+                    compiled_class_hash: tx.compiled_class_hash,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    class_hash: tx.class_hash,
+                    resource_bounds: tx.resource_bounds.into(),
+                    tip: tx.tip,
+                    paymaster_data: tx.paymaster_data,
+                    account_deployment_data: tx.account_deployment_data,
+                    nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                    fee_data_availability_mode: tx.fee_data_availability_mode,
+                }))
+            }
+            starknet_core::types::Transaction::DeployAccount(DeployAccountTransaction::V3(tx)) => {
+                Self::V3(TransactionV3::DeployAccount(DeployAccountTransactionV3 {
+                    transaction_hash: tx.transaction_hash,
+                    version: 3,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    contract_address_salt: tx.contract_address_salt,
+                    constructor_calldata: tx.constructor_calldata,
+                    class_hash: tx.class_hash,
+                    resource_bounds: tx.resource_bounds.into(),
+                    tip: tx.tip,
+                    paymaster_data: tx.paymaster_data,
+                    nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                    fee_data_availability_mode: tx.fee_data_availability_mode,
+                }))
+            }
+            transaction => Self::Unchanged(transaction),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionWithReceipt {
+    pub transaction: Transaction,
+    pub receipt: TransactionReceipt,
+}
+
+impl From<starknet_core::types::TransactionWithReceipt> for TransactionWithReceipt {
+    fn from(transaction: starknet_core::types::TransactionWithReceipt) -> Self {
+        Self { transaction: transaction.transaction.into(), receipt: transaction.receipt.into() }
+    }
+}
+
+/// Header of a block, with the L2 gas price added in v0.8
+///
+/// The blocks synced do not price the L2 gas, it is reported as zero.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockHeader {
+    pub status: BlockStatus,
+    #[serde_as(as = "UfeHex")]
+    pub block_hash: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub parent_hash: FieldElement,
+    pub block_number: u64,
+    #[serde_as(as = "UfeHex")]
+    pub new_root: FieldElement,
+    pub timestamp: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l2_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+}
+
+/// Header of the pending block, with the L2 gas price added in v0.8
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingBlockHeader {
+    #[serde_as(as = "UfeHex")]
+    pub parent_hash: FieldElement,
+    pub timestamp: u64,
+    #[serde_as(as = "UfeHex")]
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l2_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+}
+
+/// A block with its transactions, either their hashes, the whole transactions or the transactions
+/// with their receipts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Block<T> {
+    #[serde(flatten)]
+    pub header: BlockHeader,
+    pub transactions: Vec<T>,
+}
+
+/// The pending block with its transactions, either their hashes, the whole transactions or the
+/// transactions with their receipts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingBlock<T> {
+    #[serde(flatten)]
+    pub header: PendingBlockHeader,
+    pub transactions: Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum MaybePendingBlock<T> {
+    Block(Block<T>),
+    PendingBlock(PendingBlock<T>),
+}
+
+fn l2_gas_price() -> ResourcePrice {
+    ResourcePrice { price_in_fri: FieldElement::ZERO, price_in_wei: FieldElement::ZERO }
+}
+
+/// Builds the header of a block from any of the block types of the current version
+macro_rules! block_header {
+    ($block:ident) => {
+        BlockHeader {
+            status: $block.status,
+            block_hash: $block.block_hash,
+            parent_hash: $block.parent_hash,
+            block_number: $block.block_number,
+            new_root: $block.new_root,
+            timestamp: $block.timestamp,
+            sequencer_address: $block.sequencer_address,
+            l1_gas_price: $block.l1_gas_price,
+            l1_data_gas_price: $block.l1_data_gas_price,
+            l2_gas_price: l2_gas_price(),
+            l1_da_mode: $block.l1_da_mode,
+            starknet_version: $block.starknet_version,
+        }
+    };
+}
+
+/// Builds the header of the pending block from any of the pending block types of the current
+/// version
+macro_rules! pending_block_header {
+    ($block:ident) => {
+        PendingBlockHeader {
+            parent_hash: $block.parent_hash,
+            timestamp: $block.timestamp,
+            sequencer_address: $block.sequencer_address,
+            l1_gas_price: $block.l1_gas_price,
+            l1_data_gas_price: $block.l1_data_gas_price,
+            l2_gas_price: l2_gas_price(),
+            l1_da_mode: $block.l1_da_mode,
+            starknet_version: $block.starknet_version,
+        }
+    };
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxHashes> for MaybePendingBlock<FieldElement> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxHashes) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxHashes as Current;
+
+        match block {
+            Current::Block(block) => {
+                Self::Block(Block { transactions: block.transactions, header: block_header!(block) })
+            }
+            Current::PendingBlock(block) => Self::PendingBlock(PendingBlock {
+                transactions: block.transactions,
+                header: pending_block_header!(block),
+            }),
+        }
+    }
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxs> for MaybePendingBlock<Transaction> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxs) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxs as Current;
+
+        match block {
+            Current::Block(block) => Self::Block(Block {
+                transactions: block.transactions.into_iter().map(Into::into).collect(),
+                header: block_header!(block),
+            }),
+            Current::PendingBlock(block) => Self::PendingBlock(PendingBlock {
+                transactions: block.transactions.into_iter().map(Into::into).collect(),
+                header: pending_block_header!(block),
+            }),
+        }
+    }
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithReceipts> for MaybePendingBlock<TransactionWithReceipt> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithReceipts) -> Self {
+        use starknet_core::types::MaybePendingBlockWithReceipts as Current;
+
+        match block {
+            Current::Block(block) => Self::Block(Block {
+                transactions: block.transactions.into_iter().map(Into::into).collect(),
+                header: block_header!(block),
+            }),
+            Current::PendingBlock(block) => Self::PendingBlock(PendingBlock {
+                transactions: block.transactions.into_iter().map(Into::into).collect(),
+                header: pending_block_header!(block),
+            }),
+        }
+    }
+}
+
+/// Fee estimate, with the gas renamed to L1 gas and the L2 gas added in v0.8
+///
+/// The blocks synced do not price the L2 gas, it is reported as zero.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeeEstimate {
+    #[serde_as(as = "UfeHex")]
+    pub l1_gas_consumed: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub l1_gas_price: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub l1_data_gas_consumed: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub l1_data_gas_price: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub l2_gas_consumed: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub l2_gas_price: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub overall_fee: FieldElement,
+    pub unit: PriceUnit,
+}
+
+impl From<starknet_core::types::FeeEstimate> for FeeEstimate {
+    fn from(fee_estimate: starknet_core::types::FeeEstimate) -> Self {
+        Self {
+            l1_gas_consumed: fee_estimate.gas_consumed,
+            l1_gas_price: fee_estimate.gas_price,
+            l1_data_gas_consumed: fee_estimate.data_gas_consumed,
+            l1_data_gas_price: fee_estimate.data_gas_price,
+            l2_gas_consumed: FieldElement::ZERO,
+            l2_gas_price: FieldElement::ZERO,
+            overall_fee: fee_estimate.overall_fee,
+            unit: fee_estimate.unit,
+        }
+    }
+}
+
+/// Gas consumed by a call of a transaction, replacing the computation resources of v0.7
+///
+/// The gas of the calls is not known, it is reported as zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InnerCallExecutionResources {
+    pub l1_gas: u64,
+    pub l2_gas: u64,
+}
+
+/// Call made by a transaction, with the revert flag added in v0.8
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionInvocation {
+    #[serde_as(as = "UfeHex")]
+    pub contract_address: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub entry_point_selector: FieldElement,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub calldata: Vec<FieldElement>,
+    #[serde_as(as = "UfeHex")]
+    pub caller_address: FieldElement,
+    #[serde_as(as = "UfeHex")]
+    pub class_hash: FieldElement,
+    pub entry_point_type: EntryPointType,
+    pub call_type: CallType,
+    #[serde_as(as = "Vec<UfeHex>")]
+    pub result: Vec<FieldElement>,
+    pub calls: Vec<FunctionInvocation>,
+    pub events: Vec<OrderedEvent>,
+    pub messages: Vec<OrderedMessage>,
+    pub execution_resources: InnerCallExecutionResources,
+    pub is_reverted: bool,
+}
+
+impl From<starknet_core::types::FunctionInvocation> for FunctionInvocation {
+    fn from(invocation: starknet_core::types::FunctionInvocation) -> Self {
+        Self {
+            contract_address: invocation.contract_address,
+            entry_point_selector: invocation.entry_point_selector,
+            calldata: invocation.calldata,
+            caller_address: invocation.caller_address,
+            class_hash: invocation.class_hash,
+            entry_point_type: invocation.entry_point_type,
+            call_type: invocation.call_type,
+            result: invocation.result,
+            calls: invocation.calls.into_iter().map(Into::into).collect(),
+            events: invocation.events,
+            messages: invocation.messages,
+            execution_resources: InnerCallExecutionResources { l1_gas: 0, l2_gas: 0 },
+            // the calls of the traces of v0.7 are the ones which succeeded
+            is_reverted: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ExecuteInvocation {
+    Success(FunctionInvocation),
+    Reverted(RevertedInvocation),
+}
+
+impl From<starknet_core::types::ExecuteInvocation> for ExecuteInvocation {
+    fn from(invocation: starknet_core::types::ExecuteInvocation) -> Self {
+        match invocation {
+            starknet_core::types::ExecuteInvocation::Success(invocation) => Self::Success(invocation.into()),
+            starknet_core::types::ExecuteInvocation::Reverted(invocation) => Self::Reverted(invocation),
+        }
+    }
+}
+
+/// Trace of a transaction, with the gas consumed instead of the execution resources of v0.7
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum TransactionTrace {
+    #[serde(rename = "INVOKE")]
+    Invoke(InvokeTransactionTrace),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(DeployAccountTransactionTrace),
+    #[serde(rename = "L1_HANDLER")]
+    L1Handler(L1HandlerTransactionTrace),
+    #[serde(rename = "DECLARE")]
+    Declare(DeclareTransactionTrace),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvokeTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    pub execute_invocation: ExecuteInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+    pub execution_resources: ExecutionResources,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeployAccountTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    pub constructor_invocation: FunctionInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+    pub execution_resources: ExecutionResources,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct L1HandlerTransactionTrace {
+    pub function_invocation: FunctionInvocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+    pub execution_resources: ExecutionResources,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeclareTransactionTrace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+    pub execution_resources: ExecutionResources,
+}
+
+impl From<starknet_core::types::TransactionTrace> for TransactionTrace {
+    fn from(trace: starknet_core::types::TransactionTrace) -> Self {
+        use starknet_core::types::TransactionTrace as Current;
+
+        match trace {
+            Current::Invoke(trace) => Self::Invoke(InvokeTransactionTrace {
+                validate_invocation: trace.validate_invocation.map(Into::into),
+                execute_invocation: trace.execute_invocation.into(),
+                fee_transfer_invocation: trace.fee_transfer_invocation.map(Into::into),
+                state_diff: trace.state_diff,
+                execution_resources: trace.execution_resources.into(),
+            }),
+            Current::DeployAccount(trace) => Self::DeployAccount(DeployAccountTransactionTrace {
+                validate_invocation: trace.validate_invocation.map(Into::into),
+                constructor_invocation: trace.constructor_invocation.into(),
+                fee_transfer_invocation: trace.fee_transfer_invocation.map(Into::into),
+                state_diff: trace.state_diff,
+                execution_resources: trace.execution_resources.into(),
+            }),
+            Current::L1Handler(trace) => Self::L1Handler(L1HandlerTransactionTrace {
+                function_invocation: trace.function_invocation.into(),
+                state_diff: trace.state_diff,
+                execution_resources: trace.execution_resources.into(),
+            }),
+            Current::Declare(trace) => Self::Declare(DeclareTransactionTrace {
+                validate_invocation: trace.validate_invocation.map(Into::into),
+                fee_transfer_invocation: trace.fee_transfer_invocation.map(Into::into),
+                state_diff: trace.state_diff,
+                execution_resources: trace.execution_resources.into(),
+            }),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionTraceWithHash {
+    #[serde_as(as = "UfeHex")]
+    pub transaction_hash: FieldElement,
+    pub trace_root: TransactionTrace,
+}
+
+impl From<starknet_core::types::TransactionTraceWithHash> for TransactionTraceWithHash {
+    fn from(trace: starknet_core::types::TransactionTraceWithHash) -> Self {
+        Self { transaction_hash: trace.transaction_hash, trace_root: trace.trace_root.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SimulatedTransaction {
+    pub transaction_trace: TransactionTrace,
+    pub fee_estimation: FeeEstimate,
+}
+
+impl From<starknet_core::types::SimulatedTransaction> for SimulatedTransaction {
+    fn from(simulated: starknet_core::types::SimulatedTransaction) -> Self {
+        Self { transaction_trace: simulated.transaction_trace.into(), fee_estimation: simulated.fee_estimation.into() }
+    }
+}
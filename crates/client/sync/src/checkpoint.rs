@@ -0,0 +1,227 @@
+//! Trusted checkpoints, to start syncing from a known block instead of genesis
+//!
+//! A checkpoint names a block verified on L1 with its hash and global state root, and optionally
+//! the location of a snapshot of the db at that block. The node only syncs on top of a checkpoint
+//! once the imported state matches it, and it matches the `LogStateUpdate` event emitted for that
+//! block by the Starknet core contract.
+
+use std::path::{Path, PathBuf};
+
+use mc_db::storage_handler::{self, DeoxysStorageError};
+use mc_db::{DbError, DeoxysBackend, L1StateUpdateRecord};
+use mp_felt::Felt252Wrapper;
+use mp_hashers::poseidon::PoseidonHasher;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use starknet_api::hash::StarkHash;
+use starknet_ff::FieldElement;
+
+use crate::commitments::lib::calculate_state_root;
+use crate::l1::{EthereumClient, L1Error};
+
+/// A block the node can start syncing from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub block_number: u64,
+    pub block_hash: FieldElement,
+    pub global_root: FieldElement,
+    /// Directory of a snapshot of the db at this block, restored when the node starts without a db,
+    /// see [`DeoxysBackend::restore_snapshot`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<PathBuf>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CheckpointError {
+    #[error("failed to read checkpoint file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid checkpoint file: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("the imported state is at block #{imported}, not at checkpoint block #{block_number}")]
+    NotImported { block_number: u64, imported: u64 },
+    #[error("checkpoint block #{block_number} has {field} {expected:#x}, but the imported state has {actual:#x}")]
+    StateMismatch { block_number: u64, field: &'static str, expected: FieldElement, actual: FieldElement },
+    #[error("block #{0} was not verified on L1, only blocks verified by a LogStateUpdate event can be checkpoints")]
+    NotVerifiedOnL1(u64),
+    #[error("checkpoint block #{block_number} has {field} {expected:#x}, but L1 verified {actual:#x}")]
+    L1Mismatch { block_number: u64, field: &'static str, expected: FieldElement, actual: FieldElement },
+    #[error(transparent)]
+    L1(#[from] L1Error),
+    #[error("db error: {0}")]
+    Db(#[from] DbError),
+    #[error("storage error: {0}")]
+    Storage(#[from] DeoxysStorageError),
+}
+
+impl Checkpoint {
+    /// Read a checkpoint from a json file
+    pub fn from_file(path: &Path) -> Result<Self, CheckpointError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// Check the imported state and the L1 history against a checkpoint before syncing on top of it
+///
+/// The L1 endpoints are tried in order until one of them answers. Once accepted, the checkpoint is
+/// stored in db and only its block hash is checked on the next starts.
+pub async fn verify_checkpoint(checkpoint: &Checkpoint, l1_urls: &[Url]) -> Result<(), CheckpointError> {
+    let block_number = checkpoint.block_number;
+    let state_mismatch = |field, actual| CheckpointError::StateMismatch {
+        block_number,
+        field,
+        expected: if field == "block hash" { checkpoint.block_hash } else { checkpoint.global_root },
+        actual,
+    };
+
+    let block_hash = DeoxysBackend::mapping().starknet_block_hash_from_block_number(block_number)?;
+    let accepted = DeoxysBackend::meta().checkpoint_block()? == Some(block_number);
+    let imported = DeoxysBackend::meta().current_sync_block()?;
+    if block_hash.is_none() || (!accepted && imported != block_number) {
+        return Err(CheckpointError::NotImported { block_number, imported });
+    }
+
+    let block_hash = block_hash.map(to_felt).unwrap_or_default();
+    if block_hash != checkpoint.block_hash {
+        return Err(state_mismatch("block hash", block_hash));
+    }
+    if accepted {
+        return Ok(());
+    }
+
+    let contract_trie_root = storage_handler::contract_trie().root()?;
+    let class_trie_root = storage_handler::class_trie().root()?;
+    let global_root: FieldElement =
+        calculate_state_root::<PoseidonHasher>(contract_trie_root.into(), class_trie_root.into()).into();
+    if global_root != checkpoint.global_root {
+        return Err(state_mismatch("global root", global_root));
+    }
+
+    let (state_update, stored) = match DeoxysBackend::l1().state_update(block_number)? {
+        Some(state_update) => (state_update, true),
+        None => {
+            let state_update = find_state_update(block_number, l1_urls)
+                .await?
+                .ok_or(CheckpointError::NotVerifiedOnL1(block_number))?;
+            (state_update, false)
+        }
+    };
+    let l1_mismatch = |field, expected, actual| CheckpointError::L1Mismatch { block_number, field, expected, actual };
+    if to_felt(state_update.block_hash) != checkpoint.block_hash {
+        return Err(l1_mismatch("block hash", checkpoint.block_hash, to_felt(state_update.block_hash)));
+    }
+    if to_felt(state_update.global_root) != checkpoint.global_root {
+        return Err(l1_mismatch("global root", checkpoint.global_root, to_felt(state_update.global_root)));
+    }
+
+    if !stored {
+        DeoxysBackend::l1().write_state_update(&state_update)?;
+    }
+    DeoxysBackend::meta().set_checkpoint_block(block_number)?;
+    log::info!(
+        "✅ Starting from checkpoint #{block_number}, verified on L1 at block #{}",
+        state_update.l1_block_number
+    );
+    Ok(())
+}
+
+async fn find_state_update(block_number: u64, l1_urls: &[Url]) -> Result<Option<L1StateUpdateRecord>, L1Error> {
+    let mut last_error = L1Error::NoEndpoint;
+    for l1_url in l1_urls {
+        let state_update = match EthereumClient::new(l1_url.clone()).await {
+            Ok(client) => client.find_state_update(block_number).await,
+            Err(e) => Err(e),
+        };
+        match state_update {
+            Ok(state_update) => return Ok(state_update),
+            Err(e) if e.is_connection_error() => {
+                // only log the host, as the url may contain an api key
+                log::warn!("⚠️ Failed to search the L1 history on {}: {e}", l1_url.host_str().unwrap_or_default());
+                last_error = e;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error)
+}
+
+fn to_felt(hash: StarkHash) -> FieldElement {
+    Felt252Wrapper::from(hash).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::l2::l2_sync_tests::{synced_mock_chain, SyncedMockChain};
+    use crate::tests::mock_l1::{state_update_log, MockL1Node};
+
+    fn resource_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("resources").join(name)
+    }
+
+    #[test]
+    fn test_checkpoint_from_file() {
+        let checkpoint = Checkpoint::from_file(&resource_path("checkpoint.json")).unwrap();
+
+        assert_eq!(checkpoint.block_number, 3);
+        assert_eq!(checkpoint.block_hash, FieldElement::from_hex_be("0xb10c").unwrap());
+        assert_eq!(checkpoint.global_root, FieldElement::from_hex_be("0x5747e").unwrap());
+        assert_eq!(checkpoint.snapshot, Some(PathBuf::from("/var/lib/deoxys/snapshots/3")));
+    }
+
+    #[test]
+    fn test_checkpoint_from_invalid_file() {
+        let error = Checkpoint::from_file(&resource_path("mock_gateway/chain.json")).unwrap_err();
+        assert!(matches!(error, CheckpointError::Parse(_)), "unexpected error: {error}");
+
+        let error = Checkpoint::from_file(&resource_path("missing.json")).unwrap_err();
+        assert!(matches!(error, CheckpointError::Io(_)), "unexpected error: {error}");
+    }
+
+    /// Checks the synced tip of the mock chain as a checkpoint, as if it had been imported from a
+    /// snapshot
+    #[tokio::test]
+    async fn test_verify_mock_chain_checkpoint() {
+        let SyncedMockChain { gateway, core_address, tip, .. } = synced_mock_chain().await;
+        let checkpoint = Checkpoint {
+            block_number: tip,
+            block_hash: gateway.block_hash(tip),
+            global_root: gateway.state_root(tip),
+            snapshot: None,
+        };
+
+        let wrong_root = Checkpoint { global_root: FieldElement::ONE, ..checkpoint.clone() };
+        let error = verify_checkpoint(&wrong_root, &[]).await.unwrap_err();
+        assert!(
+            matches!(error, CheckpointError::StateMismatch { field: "global root", .. }),
+            "unexpected error: {error}"
+        );
+
+        // the L1 history goes from block #2 to #4, block #3 was never verified on its own
+        let mock = MockL1Node::start().await;
+        mock.push_log(state_update_log(core_address, 10, tip - 1, StarkHash::from(1_u64), StarkHash::from(1_u64)));
+        mock.push_log(state_update_log(core_address, 20, tip + 1, StarkHash::from(1_u64), StarkHash::from(1_u64)));
+        mock.set_block_number(30);
+        let error = verify_checkpoint(&checkpoint, &[mock.http_url()]).await.unwrap_err();
+        assert!(
+            matches!(error, CheckpointError::NotVerifiedOnL1(block_number) if block_number == tip),
+            "unexpected error: {error}"
+        );
+
+        let mock = MockL1Node::start().await;
+        let (global_root, block_hash): (StarkHash, StarkHash) =
+            (Felt252Wrapper::from(checkpoint.global_root).into(), Felt252Wrapper::from(checkpoint.block_hash).into());
+        mock.push_log(state_update_log(core_address, 10, tip, global_root, StarkHash::from(1_u64)));
+        let error = verify_checkpoint(&checkpoint, &[mock.http_url()]).await.unwrap_err();
+        assert!(matches!(error, CheckpointError::L1Mismatch { field: "block hash", .. }), "unexpected error: {error}");
+        assert!(DeoxysBackend::l1().state_update(tip).unwrap().is_none());
+
+        let mock = MockL1Node::start().await;
+        mock.push_log(state_update_log(core_address, 10, tip, global_root, block_hash));
+        verify_checkpoint(&checkpoint, &[mock.http_url()]).await.expect("Failed to verify the checkpoint");
+        assert_eq!(DeoxysBackend::meta().checkpoint_block().unwrap(), Some(tip));
+        assert_eq!(DeoxysBackend::l1().state_update(tip).unwrap().unwrap().l1_block_number, 10);
+
+        // once accepted, the checkpoint is verified without L1
+        verify_checkpoint(&checkpoint, &[]).await.expect("Failed to verify the accepted checkpoint");
+    }
+}
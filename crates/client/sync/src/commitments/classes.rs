@@ -10,18 +10,12 @@ use starknet_ff::FieldElement;
 const CONTRACT_CLASS_HASH_VERSION: FieldElement =
     FieldElement::from_mont([9331882290187415277, 12057587991035439952, 18444375821049509847, 115292049744600508]);
 
-/// Calculates the class trie root
+/// Applies the class updates of a state diff to the class trie, without committing them
 ///
 /// # Arguments
 ///
-/// * `csd`          - Commitment state diff for the current block.
-/// * `bonsai_class` - Bonsai db used to store class hashes.
-/// * `block_number` - The current block number.
-///
-/// # Returns
-///
-/// The class root.
-pub fn class_trie_root(csd: &CommitmentStateDiff, block_number: u64) -> Result<Felt252Wrapper, DeoxysStorageError> {
+/// * `csd` - Commitment state diff for the current block.
+pub fn update_class_trie(csd: &CommitmentStateDiff) -> Result<(), DeoxysStorageError> {
     let mut handler_class = storage_handler::class_trie_mut();
 
     let updates = csd
@@ -38,7 +32,21 @@ pub fn class_trie_root(csd: &CommitmentStateDiff, block_number: u64) -> Result<F
         .collect::<Vec<_>>();
 
     handler_class.init()?;
-    handler_class.update(updates)?;
+    handler_class.update(updates)
+}
+
+/// Commits the class trie and calculates its root
+///
+/// # Arguments
+///
+/// * `block_number` - The current block number.
+///
+/// # Returns
+///
+/// The class root.
+pub fn class_trie_root(block_number: u64) -> Result<Felt252Wrapper, DeoxysStorageError> {
+    let mut handler_class = storage_handler::class_trie_mut();
+
     handler_class.commit(block_number)?;
 
     Ok(handler_class.root()?.into())
@@ -10,11 +10,34 @@ use starknet_api::core::ContractAddress;
 use starknet_ff::FieldElement;
 use starknet_types_core::felt::Felt;
 
-/// Calculates the contract trie root
+/// Applies the storage updates of a state diff to the contract storage tries, without committing
+/// them
 ///
 /// # Arguments
 ///
-/// * `csd`             - Commitment state diff for the current block.
+/// * `csd` - Commitment state diff for the current block.
+pub fn update_storage_tries(csd: &CommitmentStateDiff) -> Result<(), DeoxysStorageError> {
+    let mut handler_storage_trie = storage_handler::contract_storage_trie_mut();
+
+    for (contract_address, updates) in csd.storage_updates.iter() {
+        handler_storage_trie.init(contract_address)?;
+
+        for (key, value) in updates {
+            handler_storage_trie.insert(*contract_address, *key, *value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Commits the contract storage tries and calculates the contract trie root
+///
+/// The storage updates must have been applied with [`update_storage_tries`]. The state diff may
+/// span several blocks, as long as they have all been applied since the last commit.
+///
+/// # Arguments
+///
+/// * `csd`             - Commitment state diff of the blocks applied since the last commit.
 /// * `block_number`    - The current block number.
 ///
 /// # Returns
@@ -26,16 +49,7 @@ pub fn contract_trie_root(csd: &CommitmentStateDiff, block_number: u64) -> Resul
     let mut handler_contract = storage_handler::contract_trie_mut();
     let mut handler_storage_trie = storage_handler::contract_storage_trie_mut();
 
-    // First we insert the contract storage changes
-    for (contract_address, updates) in csd.storage_updates.iter() {
-        handler_storage_trie.init(contract_address)?;
-
-        for (key, value) in updates {
-            handler_storage_trie.insert(*contract_address, *key, *value)?;
-        }
-    }
-
-    // Then we commit them
+    // First we commit the contract storage changes
     handler_storage_trie.commit(block_number)?;
 
     // We need to initialize the contract trie for each contract that has a class_hash or nonce update
@@ -80,7 +94,7 @@ pub fn contract_trie_root(csd: &CommitmentStateDiff, block_number: u64) -> Resul
 ///
 /// # Arguments
 ///
-/// * `csd`             - Commitment state diff for the current block.
+/// * `csd`             - Commitment state diff of the blocks applied since the last commit.
 /// * `contract_address` - The contract address.
 /// * `storage_root`     - The storage root of the contract.
 ///
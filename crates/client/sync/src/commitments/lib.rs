@@ -9,13 +9,13 @@ use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
 use starknet_api::transaction::{Event, Transaction};
 use starknet_core::types::{
-    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateUpdate,
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
     StorageEntry,
 };
 use starknet_ff::FieldElement;
 
-use super::classes::class_trie_root;
-use super::contracts::contract_trie_root;
+use super::classes::{class_trie_root, update_class_trie};
+use super::contracts::{contract_trie_root, update_storage_tries};
 use super::events::memory_event_commitment;
 use super::transactions::memory_transaction_commitment;
 
@@ -50,8 +50,8 @@ pub fn calculate_tx_and_event_commitments(
 /// Aggregates all the changes from last state update in a way that is easy to access
 /// when computing the state root
 ///
-/// * `state_update`: The last state update fetched from the sequencer
-pub fn build_commitment_state_diff(state_update: &StateUpdate) -> CommitmentStateDiff {
+/// * `state_diff`: The state diff of the last state update fetched from the sequencer
+pub fn build_commitment_state_diff(state_diff: &StateDiff) -> CommitmentStateDiff {
     let mut commitment_state_diff = CommitmentStateDiff {
         address_to_class_hash: IndexMap::new(),
         address_to_nonce: IndexMap::new(),
@@ -59,7 +59,7 @@ pub fn build_commitment_state_diff(state_update: &StateUpdate) -> CommitmentStat
         class_hash_to_compiled_class_hash: IndexMap::new(),
     };
 
-    for DeployedContractItem { address, class_hash } in state_update.state_diff.deployed_contracts.iter() {
+    for DeployedContractItem { address, class_hash } in state_diff.deployed_contracts.iter() {
         let address = ContractAddress::from_field_element(address);
         let class_hash = if address == ContractAddress::from_field_element(FieldElement::ZERO) {
             // System contracts doesnt have class hashes
@@ -70,25 +70,25 @@ pub fn build_commitment_state_diff(state_update: &StateUpdate) -> CommitmentStat
         commitment_state_diff.address_to_class_hash.insert(address, class_hash);
     }
 
-    for ReplacedClassItem { contract_address, class_hash } in state_update.state_diff.replaced_classes.iter() {
+    for ReplacedClassItem { contract_address, class_hash } in state_diff.replaced_classes.iter() {
         let address = ContractAddress::from_field_element(contract_address);
         let class_hash = ClassHash::from_field_element(class_hash);
         commitment_state_diff.address_to_class_hash.insert(address, class_hash);
     }
 
-    for DeclaredClassItem { class_hash, compiled_class_hash } in state_update.state_diff.declared_classes.iter() {
+    for DeclaredClassItem { class_hash, compiled_class_hash } in state_diff.declared_classes.iter() {
         let class_hash = ClassHash::from_field_element(class_hash);
         let compiled_class_hash = CompiledClassHash::from_field_element(compiled_class_hash);
         commitment_state_diff.class_hash_to_compiled_class_hash.insert(class_hash, compiled_class_hash);
     }
 
-    for NonceUpdate { contract_address, nonce } in state_update.state_diff.nonces.iter() {
+    for NonceUpdate { contract_address, nonce } in state_diff.nonces.iter() {
         let contract_address = ContractAddress::from_field_element(contract_address);
         let nonce_value = Nonce::from_field_element(nonce);
         commitment_state_diff.address_to_nonce.insert(contract_address, nonce_value);
     }
 
-    for ContractStorageDiffItem { address, storage_entries } in state_update.state_diff.storage_diffs.iter() {
+    for ContractStorageDiffItem { address, storage_entries } in state_diff.storage_diffs.iter() {
         let contract_address = ContractAddress::from_field_element(address);
         let mut storage_map = IndexMap::new();
         for StorageEntry { key, value } in storage_entries.iter() {
@@ -135,6 +135,48 @@ where
     }
 }
 
+/// Merge the state diff of a block into the state diff of the blocks preceding it
+///
+/// The result is the state diff of all the blocks, where the last update of each entry wins.
+pub fn squash_state_diff(squashed: &mut CommitmentStateDiff, csd: CommitmentStateDiff) {
+    squashed.address_to_class_hash.extend(csd.address_to_class_hash);
+    squashed.address_to_nonce.extend(csd.address_to_nonce);
+    squashed.class_hash_to_compiled_class_hash.extend(csd.class_hash_to_compiled_class_hash);
+    for (contract_address, storage_updates) in csd.storage_updates {
+        squashed.storage_updates.entry(contract_address).or_default().extend(storage_updates);
+    }
+}
+
+/// Apply a state diff to the state tries, without committing them nor computing their roots.
+///
+/// # Arguments
+///
+/// * `csd` - The commitment state diff of the block.
+pub fn update_state_tries(csd: &CommitmentStateDiff) {
+    rayon::join(
+        || update_storage_tries(csd).expect("Failed to update contract storage tries"),
+        || update_class_trie(csd).expect("Failed to update class trie"),
+    );
+}
+
+/// Commit the state tries and calculate the state commitment hash value.
+///
+/// # Arguments
+///
+/// * `csd` - The commitment state diff of the blocks applied to the tries since their last commit.
+/// * `block_number` - The current block number.
+///
+/// # Returns
+///
+/// The state root as a `Felt252Wrapper`.
+pub fn commit_state_root(csd: &CommitmentStateDiff, block_number: u64) -> Felt252Wrapper {
+    let (contract_trie_root, class_trie_root) = rayon::join(
+        || contract_trie_root(csd, block_number).expect("Failed to compute contract root"),
+        || class_trie_root(block_number).expect("Failed to compute class root"),
+    );
+    calculate_state_root::<PoseidonHasher>(contract_trie_root, class_trie_root)
+}
+
 /// Update the state commitment hash value.
 ///
 /// The state commitment is the digest that uniquely (up to hash collisions) encodes the state.
@@ -149,10 +191,6 @@ where
 ///
 /// The updated state root as a `Felt252Wrapper`.
 pub fn update_state_root(csd: CommitmentStateDiff, block_number: u64) -> Felt252Wrapper {
-    // Update contract and its storage tries
-    let (contract_trie_root, class_trie_root) = rayon::join(
-        || contract_trie_root(&csd, block_number).expect("Failed to compute contract root"),
-        || class_trie_root(&csd, block_number).expect("Failed to compute class root"),
-    );
-    calculate_state_root::<PoseidonHasher>(contract_trie_root, class_trie_root)
+    update_state_tries(&csd);
+    commit_state_root(&csd, block_number)
 }
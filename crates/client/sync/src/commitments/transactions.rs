@@ -25,12 +25,12 @@ use starknet_types_core::hash::Pedersen;
 ///
 /// # Returns
 ///
-/// The transaction hash with signature.
+/// The transaction hash and the transaction hash with signature.
 pub fn calculate_transaction_hash_with_signature<H: HasherT>(
     transaction: &Transaction,
     chain_id: Felt252Wrapper,
     block_number: u64,
-) -> FieldElement
+) -> (FieldElement, FieldElement)
 where
     H: HasherT,
 {
@@ -77,7 +77,7 @@ where
         || Felt252Wrapper::from(transaction.compute_hash::<H>(chain_id, false, Some(block_number)).0).into(),
     );
 
-    H::hash_elements(tx_hash, signature_hash)
+    (tx_hash, H::hash_elements(tx_hash, signature_hash))
 }
 
 /// Calculate the transaction commitment in memory using HashMapDb (which is more efficient for this
@@ -91,7 +91,7 @@ where
 ///
 /// # Returns
 ///
-/// The transaction commitment as `Felt252Wrapper`.
+/// The transaction commitment as `Felt252Wrapper` and the hashes of the transactions.
 pub fn memory_transaction_commitment(
     transactions: &[Transaction],
     chain_id: Felt252Wrapper,
@@ -111,9 +111,9 @@ pub fn memory_transaction_commitment(
         .collect::<Vec<_>>();
 
     // once transaction hashes have finished computing, they are inserted into the local Bonsai db
-    for (i, &tx_hash) in txs.iter().enumerate() {
+    for (i, &(_, tx_hash_with_signature)) in txs.iter().enumerate() {
         let key = BitVec::from_vec(i.to_be_bytes().to_vec());
-        let value = Felt::from(Felt252Wrapper::from(tx_hash));
+        let value = Felt::from(Felt252Wrapper::from(tx_hash_with_signature));
         bonsai_storage.insert(identifier, key.as_bitslice(), &value).expect("Failed to insert into bonsai storage");
     }
 
@@ -123,5 +123,5 @@ pub fn memory_transaction_commitment(
     bonsai_storage.commit(id).expect("Failed to commit to bonsai storage");
     let root_hash = bonsai_storage.root_hash(identifier).expect("Failed to get root hash");
 
-    Ok((Felt252Wrapper::from(root_hash), txs))
+    Ok((Felt252Wrapper::from(root_hash), txs.into_iter().map(|(tx_hash, _)| tx_hash).collect()))
 }
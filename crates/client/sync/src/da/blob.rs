@@ -0,0 +1,164 @@
+//! Decoding of the EIP-4844 blobs used for data availability since Starknet v0.13.1
+//!
+//! A blob holds 4096 elements of the BLS12-381 scalar field: the evaluations of a polynomial at the
+//! 4096th roots of unity, in bit-reversed order. The data published by Starknet is the list of
+//! coefficients of that polynomial, which is recovered with an inverse FFT.
+
+use primitive_types::{U256, U512};
+use sha2::{Digest, Sha256};
+use sp_core::H256;
+use starknet_ff::FieldElement;
+
+use super::DaError;
+
+/// Number of field elements in a blob
+pub const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+/// Size of a blob, in bytes
+pub const BYTES_PER_BLOB: usize = 32 * FIELD_ELEMENTS_PER_BLOB;
+
+/// Modulus of the BLS12-381 scalar field
+const BLS_MODULUS: U256 = U256([0xffffffff00000001, 0x53bda402fffe5bfe, 0x3339d80809a1d805, 0x73eda753299d7d48]);
+/// Generator of the multiplicative group of the BLS12-381 scalar field, from which the roots of
+/// unity are derived
+const PRIMITIVE_ROOT_OF_UNITY: u64 = 7;
+
+/// Recover the data encoded in a blob
+pub fn blob_to_felts(blob: &[u8]) -> Result<Vec<FieldElement>, DaError> {
+    if blob.len() != BYTES_PER_BLOB {
+        return Err(DaError::InvalidBlob(format!("expected {BYTES_PER_BLOB} bytes, got {}", blob.len())));
+    }
+
+    let mut values = blob
+        .chunks_exact(32)
+        .map(|chunk| {
+            let value = U256::from_big_endian(chunk);
+            if value >= BLS_MODULUS {
+                return Err(DaError::InvalidBlob(format!("{value:#x} is not a BLS12-381 scalar")));
+            }
+            Ok(value)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the evaluations are already in bit-reversed order, which is the input order of the FFT below
+    let n = U256::from(FIELD_ELEMENTS_PER_BLOB);
+    let root = pow_mod(U256::from(PRIMITIVE_ROOT_OF_UNITY), (BLS_MODULUS - 1) / n);
+    fft(&mut values, inverse(root));
+
+    let n_inverse = inverse(n);
+    values
+        .into_iter()
+        .map(|value| {
+            let mut bytes = [0u8; 32];
+            mul_mod(value, n_inverse).to_big_endian(&mut bytes);
+            FieldElement::from_bytes_be(&bytes)
+                .map_err(|_| DaError::InvalidBlob(format!("0x{} is not a Starknet felt", hex::encode(bytes))))
+        })
+        .collect()
+}
+
+/// The versioned hash of a blob, as referenced by the transactions carrying it
+pub fn kzg_to_versioned_hash(kzg_commitment: &[u8]) -> H256 {
+    const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+    let mut hash: [u8; 32] = Sha256::digest(kzg_commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    H256(hash)
+}
+
+/// In place iterative radix-2 FFT over the BLS12-381 scalar field, taking its input in
+/// bit-reversed order and producing its output in natural order
+fn fft(values: &mut [U256], root: U256) {
+    let n = values.len();
+    let mut len = 2;
+    while len <= n {
+        let step = pow_mod(root, U256::from(n / len));
+        for chunk in values.chunks_exact_mut(len) {
+            let (low, high) = chunk.split_at_mut(len / 2);
+            let mut twiddle = U256::one();
+            for (low, high) in low.iter_mut().zip(high.iter_mut()) {
+                let product = mul_mod(*high, twiddle);
+                *high = sub_mod(*low, product);
+                *low = add_mod(*low, product);
+                twiddle = mul_mod(twiddle, step);
+            }
+        }
+        len *= 2;
+    }
+}
+
+fn add_mod(a: U256, b: U256) -> U256 {
+    // the modulus is below 2^255, this does not overflow
+    let sum = a + b;
+    if sum >= BLS_MODULUS { sum - BLS_MODULUS } else { sum }
+}
+
+fn sub_mod(a: U256, b: U256) -> U256 {
+    if a >= b { a - b } else { a + BLS_MODULUS - b }
+}
+
+fn mul_mod(a: U256, b: U256) -> U256 {
+    let product = a.full_mul(b) % U512::from(BLS_MODULUS);
+    U256::try_from(product).expect("reduced modulo a 256 bits modulus")
+}
+
+fn pow_mod(mut base: U256, mut exponent: U256) -> U256 {
+    let mut result = U256::one();
+    while !exponent.is_zero() {
+        if exponent.bit(0) {
+            result = mul_mod(result, base);
+        }
+        base = mul_mod(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn inverse(value: U256) -> U256 {
+    pow_mod(value, BLS_MODULUS - 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::read_resource::read_resource_file;
+
+    fn felts(values: &serde_json::Value) -> Vec<FieldElement> {
+        values
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| FieldElement::from_hex_be(value.as_str().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_blob_to_felts() {
+        let state_diff: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let sidecars: serde_json::Value = serde_json::from_str(&read_resource_file("da/blob_sidecars.json")).unwrap();
+        let blob = hex::decode(sidecars["data"][0]["blob"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+
+        let data = blob_to_felts(&blob).unwrap();
+        let expected = felts(&state_diff["v0_11"]);
+
+        assert_eq!(data.len(), FIELD_ELEMENTS_PER_BLOB);
+        assert_eq!(data[..expected.len()], expected[..]);
+        assert!(data[expected.len()..].iter().all(|felt| *felt == FieldElement::ZERO));
+    }
+
+    #[test]
+    fn test_blob_to_felts_invalid_size() {
+        assert!(matches!(blob_to_felts(&[0; 32]), Err(DaError::InvalidBlob(_))));
+    }
+
+    #[test]
+    fn test_blob_to_felts_not_a_scalar() {
+        let blob = vec![0xff; BYTES_PER_BLOB];
+        assert!(matches!(blob_to_felts(&blob), Err(DaError::InvalidBlob(_))));
+    }
+
+    #[test]
+    fn test_zero_blob() {
+        let data = blob_to_felts(&vec![0; BYTES_PER_BLOB]).unwrap();
+        assert!(data.iter().all(|felt| *felt == FieldElement::ZERO));
+    }
+}
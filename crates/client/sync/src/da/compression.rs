@@ -0,0 +1,183 @@
+//! Stateless decompression of the data published on L1 since Starknet v0.13.3
+//!
+//! The compressed data starts with a header packed in a single felt, followed by the unique values
+//! of the data packed by size buckets, the pointers of the repeated values to the unique values,
+//! and the bucket of each element of the data. Several small elements are packed in each felt, as
+//! the digits of a number in base `bound` where `bound` is an upper bound on the elements.
+
+use primitive_types::U256;
+use starknet_ff::FieldElement;
+
+use super::DaError;
+
+const COMPRESSION_VERSION: usize = 0;
+/// Maximum number of bits packed in a felt
+const MAX_N_BITS: usize = 251;
+/// Number of bits of the elements of each unique value bucket
+const N_BITS_PER_BUCKET: [usize; 6] = [252, 125, 83, 62, 31, 15];
+/// The unique value buckets, followed by the repeated values bucket
+const TOTAL_N_BUCKETS: usize = N_BITS_PER_BUCKET.len() + 1;
+/// Number of bits of each element of the header
+const HEADER_ELM_N_BITS: usize = 20;
+/// Version, data length, unique value bucket lengths and number of repeated values
+const HEADER_LEN: usize = 1 + 1 + TOTAL_N_BUCKETS;
+
+/// Whether published data is compressed
+///
+/// The first felt of uncompressed data is a number of updated contracts, which is far below the
+/// smallest compressed header: the header of non empty data encodes its length above the first
+/// header element.
+pub fn is_compressed(data: &[FieldElement]) -> bool {
+    data.first().is_some_and(|first| to_u256(*first) >= U256::one() << HEADER_ELM_N_BITS)
+}
+
+/// Decompress data published on L1
+pub fn decompress(compressed: &[FieldElement]) -> Result<Vec<FieldElement>, DaError> {
+    let mut compressed = compressed.iter().copied();
+
+    let header = unpack_chunk(&mut compressed, HEADER_LEN, U256::one() << HEADER_ELM_N_BITS)?;
+    let header = header.iter().map(|elm| elm.as_usize()).collect::<Vec<_>>();
+    if header[0] != COMPRESSION_VERSION {
+        return Err(DaError::InvalidCompression(format!("unsupported compression version {}", header[0])));
+    }
+    let data_len = header[1];
+    let unique_value_bucket_lengths = &header[2..2 + N_BITS_PER_BUCKET.len()];
+    let n_repeating_values = header[2 + N_BITS_PER_BUCKET.len()];
+
+    let mut unique_values = vec![];
+    for (&bucket_len, &n_bits) in unique_value_bucket_lengths.iter().zip(&N_BITS_PER_BUCKET) {
+        unique_values.extend(unpack_chunk(&mut compressed, bucket_len, U256::one() << n_bits)?);
+    }
+
+    let repeating_value_pointers = unpack_chunk(&mut compressed, n_repeating_values, U256::from(unique_values.len()))?;
+    let repeating_values = repeating_value_pointers
+        .iter()
+        .map(|pointer| {
+            unique_values
+                .get(pointer.as_usize())
+                .copied()
+                .ok_or_else(|| DaError::InvalidCompression(format!("repeated value pointer {pointer} out of bounds")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let bucket_index_per_elm = unpack_chunk(&mut compressed, data_len, U256::from(TOTAL_N_BUCKETS))?;
+
+    // the values of each bucket are consumed in order
+    let all_bucket_lengths = unique_value_bucket_lengths.iter().copied().chain(std::iter::once(n_repeating_values));
+    let mut bucket_offsets = all_bucket_lengths
+        .scan(0, |offset, bucket_len| {
+            let bucket_offset = *offset;
+            *offset += bucket_len;
+            Some(bucket_offset)
+        })
+        .collect::<Vec<_>>();
+    let all_values = unique_values.into_iter().chain(repeating_values).collect::<Vec<_>>();
+
+    bucket_index_per_elm
+        .iter()
+        .map(|bucket_index| {
+            let offset = bucket_offsets
+                .get_mut(bucket_index.as_usize())
+                .ok_or_else(|| DaError::InvalidCompression(format!("invalid bucket index {bucket_index}")))?;
+            let value =
+                all_values.get(*offset).ok_or_else(|| DaError::InvalidCompression("bucket exhausted".to_string()))?;
+            *offset += 1;
+            FieldElement::from_bytes_be(&to_bytes(*value))
+                .map_err(|_| DaError::InvalidCompression(format!("{value:#x} is not a felt")))
+        })
+        .collect()
+}
+
+/// Number of elements bounded by `bound` packed in a single felt
+fn n_elms_per_felt(bound: U256) -> usize {
+    if bound <= U256::one() {
+        return MAX_N_BITS;
+    }
+    let n_bits = (bound - 1).bits();
+    if n_bits > MAX_N_BITS { 1 } else { MAX_N_BITS / n_bits }
+}
+
+/// Unpack `n_elms` elements bounded by `bound` from the next felts of `compressed`
+fn unpack_chunk(
+    compressed: &mut impl Iterator<Item = FieldElement>,
+    n_elms: usize,
+    bound: U256,
+) -> Result<Vec<U256>, DaError> {
+    let n_elms_per_felt = n_elms_per_felt(bound);
+    let n_felts = n_elms.div_ceil(n_elms_per_felt);
+
+    let mut elms = Vec::with_capacity(n_felts * n_elms_per_felt);
+    for _ in 0..n_felts {
+        let mut packed =
+            to_u256(compressed.next().ok_or_else(|| DaError::InvalidCompression("truncated data".into()))?);
+        if n_elms_per_felt == 1 {
+            elms.push(packed);
+            continue;
+        }
+        for _ in 0..n_elms_per_felt {
+            elms.push(packed % bound);
+            packed /= bound;
+        }
+        if !packed.is_zero() {
+            return Err(DaError::InvalidCompression("packed felt overflows its elements".into()));
+        }
+    }
+    elms.truncate(n_elms);
+    Ok(elms)
+}
+
+fn to_u256(felt: FieldElement) -> U256 {
+    U256::from_big_endian(&felt.to_bytes_be())
+}
+
+fn to_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::read_resource::read_resource_file;
+
+    fn felts(values: &serde_json::Value) -> Vec<FieldElement> {
+        values
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| FieldElement::from_hex_be(value.as_str().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decompress() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let compressed = felts(&fixture["v0_13_3_compressed"]);
+
+        assert!(is_compressed(&compressed));
+        assert_eq!(decompress(&compressed).unwrap(), felts(&fixture["v0_13_3"]));
+    }
+
+    #[test]
+    fn test_uncompressed_data() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        assert!(!is_compressed(&felts(&fixture["v0_11"])));
+        assert!(!is_compressed(&[]));
+    }
+
+    #[test]
+    fn test_decompress_truncated() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let compressed = felts(&fixture["v0_13_3_compressed"]);
+
+        let error = decompress(&compressed[..compressed.len() - 1]).unwrap_err();
+        assert!(matches!(error, DaError::InvalidCompression(_)), "unexpected error: {error}");
+    }
+
+    #[test]
+    fn test_decompress_unsupported_version() {
+        let error = decompress(&[FieldElement::ONE]).unwrap_err();
+        assert!(matches!(error, DaError::InvalidCompression(_)), "unexpected error: {error}");
+    }
+}
@@ -0,0 +1,293 @@
+//! Decoding of the state diffs published on L1
+//!
+//! The published data starts with the updated contracts, each as its address, a header word
+//! packing its new nonce, number of storage updates and whether its class changed, then its new
+//! class hash if it did, followed by its storage updates as (key, value) pairs. It ends with the
+//! declared classes, as (class hash, compiled class hash) pairs. Only the header word changed
+//! between the v0.11 and v0.13.3 encodings.
+//!
+//! Before Starknet v0.11, the published data started with the deployed contracts, as its number of
+//! words followed by (address, class hash) pairs. The updated contracts came next, each as its
+//! address, a header word packing its nonce and number of storage updates, and its storage updates.
+//! Declared classes were not published.
+
+use std::collections::BTreeMap;
+
+use primitive_types::U256;
+use starknet_ff::FieldElement;
+
+use super::DaError;
+
+/// Encoding of the published state diffs, which depends on the Starknet version of the batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaEncoding {
+    /// Before Starknet v0.11, for data published in calldata
+    PreV0_11,
+    /// Since Starknet v0.11, for data published in calldata or uncompressed blobs
+    V0_11,
+    /// Since Starknet v0.13.3, for compressed blobs
+    V0_13_3,
+}
+
+/// The state diff of a batch of blocks, as published on L1
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DaStateDiff {
+    pub contracts: BTreeMap<FieldElement, DaContractUpdate>,
+    pub declared_classes: BTreeMap<FieldElement, FieldElement>,
+}
+
+/// The state of a contract at the end of a batch, restricted to what changed during the batch
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DaContractUpdate {
+    /// The nonce is published whether it changed or not, except for the contracts only deployed
+    /// during a batch before Starknet v0.11
+    pub nonce: Option<FieldElement>,
+    /// Only published if the contract was deployed or its class was replaced
+    pub class_hash: Option<FieldElement>,
+    pub storage: BTreeMap<FieldElement, FieldElement>,
+}
+
+/// Decode a state diff published on L1
+///
+/// The data may be followed by zeros, as blobs are padded, but by nothing else.
+pub fn decode_state_diff(data: &[FieldElement], encoding: DaEncoding) -> Result<DaStateDiff, DaError> {
+    if encoding == DaEncoding::PreV0_11 {
+        return decode_pre_v0_11_state_diff(data);
+    }
+
+    let mut data = data.iter().copied();
+    let mut next = |what: &str| data.next().ok_or_else(|| DaError::InvalidEncoding(format!("missing {what}")));
+    let mut state_diff = DaStateDiff::default();
+
+    let n_contracts = to_usize(next("number of updated contracts")?, "number of updated contracts")?;
+    for _ in 0..n_contracts {
+        let address = next("contract address")?;
+        let header = ContractHeader::decode(next("contract header")?, encoding)?;
+        let class_hash = if header.class_updated { Some(next("class hash")?) } else { None };
+
+        let mut storage = BTreeMap::new();
+        for _ in 0..header.n_storage_updates {
+            let key = next("storage key")?;
+            let value = next("storage value")?;
+            if storage.insert(key, value).is_some() {
+                return Err(DaError::InvalidEncoding(format!(
+                    "duplicate storage key {key:#x} for contract {address:#x}"
+                )));
+            }
+        }
+
+        let update = DaContractUpdate { nonce: Some(header.nonce), class_hash, storage };
+        if state_diff.contracts.insert(address, update).is_some() {
+            return Err(DaError::InvalidEncoding(format!("duplicate contract {address:#x}")));
+        }
+    }
+
+    let n_declared_classes = to_usize(next("number of declared classes")?, "number of declared classes")?;
+    for _ in 0..n_declared_classes {
+        let class_hash = next("declared class hash")?;
+        let compiled_class_hash = next("compiled class hash")?;
+        state_diff.declared_classes.insert(class_hash, compiled_class_hash);
+    }
+
+    if data.any(|felt| felt != FieldElement::ZERO) {
+        return Err(DaError::InvalidEncoding("unexpected data after the state diff".to_string()));
+    }
+
+    Ok(state_diff)
+}
+
+/// Decode a state diff published on L1 before Starknet v0.11
+fn decode_pre_v0_11_state_diff(data: &[FieldElement]) -> Result<DaStateDiff, DaError> {
+    let mut data = data.iter().copied();
+    let mut next = |what: &str| data.next().ok_or_else(|| DaError::InvalidEncoding(format!("missing {what}")));
+    let mut state_diff = DaStateDiff::default();
+
+    let n_deployment_words = to_usize(next("deployed contracts length")?, "deployed contracts length")?;
+    if n_deployment_words % 2 != 0 {
+        return Err(DaError::InvalidEncoding(format!("invalid deployed contracts length {n_deployment_words}")));
+    }
+    for _ in 0..n_deployment_words / 2 {
+        let address = next("deployed contract address")?;
+        let class_hash = next("deployed class hash")?;
+        let update = DaContractUpdate { class_hash: Some(class_hash), ..Default::default() };
+        if state_diff.contracts.insert(address, update).is_some() {
+            return Err(DaError::InvalidEncoding(format!("duplicate deployed contract {address:#x}")));
+        }
+    }
+
+    let n_contracts = to_usize(next("number of updated contracts")?, "number of updated contracts")?;
+    for _ in 0..n_contracts {
+        let address = next("contract address")?;
+        let header = ContractHeader::decode(next("contract header")?, DaEncoding::PreV0_11)?;
+        let update = state_diff.contracts.entry(address).or_default();
+        if update.nonce.is_some() {
+            return Err(DaError::InvalidEncoding(format!("duplicate contract {address:#x}")));
+        }
+        update.nonce = Some(header.nonce);
+
+        for _ in 0..header.n_storage_updates {
+            let key = next("storage key")?;
+            let value = next("storage value")?;
+            if update.storage.insert(key, value).is_some() {
+                return Err(DaError::InvalidEncoding(format!(
+                    "duplicate storage key {key:#x} for contract {address:#x}"
+                )));
+            }
+        }
+    }
+
+    if data.any(|felt| felt != FieldElement::ZERO) {
+        return Err(DaError::InvalidEncoding("unexpected data after the state diff".to_string()));
+    }
+
+    Ok(state_diff)
+}
+
+struct ContractHeader {
+    class_updated: bool,
+    nonce: FieldElement,
+    n_storage_updates: usize,
+}
+
+impl ContractHeader {
+    fn decode(word: FieldElement, encoding: DaEncoding) -> Result<Self, DaError> {
+        let word = U256::from_big_endian(&word.to_bytes_be());
+        let low_64 = |word: U256| U256::from(word.low_u64());
+
+        // before v0.11: nonce * 2^64 + n_storage_updates
+        // v0.11: class_updated * 2^128 + nonce * 2^64 + n_storage_updates
+        // v0.13.3: ((class_updated * 2^64 + nonce) * 2^(8 or 64) + n_storage_updates) * 2 + is_small
+        let (n_storage_updates, rest) = match encoding {
+            DaEncoding::PreV0_11 | DaEncoding::V0_11 => (low_64(word), word >> 64),
+            DaEncoding::V0_13_3 => {
+                let n_bits = if word.bit(0) { 8 } else { 64 };
+                let word = word >> 1;
+                (word & ((U256::one() << n_bits) - 1), word >> n_bits)
+            }
+        };
+        let nonce = low_64(rest);
+        let class_updated = match rest >> 64 {
+            flag if flag.is_zero() => false,
+            flag if flag == U256::one() && encoding != DaEncoding::PreV0_11 => true,
+            flag => return Err(DaError::InvalidEncoding(format!("invalid class update flag {flag}"))),
+        };
+
+        Ok(Self {
+            class_updated,
+            nonce: FieldElement::from(nonce.low_u64()),
+            n_storage_updates: n_storage_updates.low_u64() as usize,
+        })
+    }
+}
+
+fn to_usize(felt: FieldElement, what: &str) -> Result<usize, DaError> {
+    u32::try_from(felt)
+        .map(|value| value as usize)
+        .map_err(|_| DaError::InvalidEncoding(format!("invalid {what} {felt:#x}")))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tests::utils::read_resource::read_resource_file;
+
+    fn felt(value: &serde_json::Value) -> FieldElement {
+        FieldElement::from_hex_be(value.as_str().unwrap()).unwrap()
+    }
+
+    fn felts(values: &serde_json::Value) -> Vec<FieldElement> {
+        values.as_array().unwrap().iter().map(felt).collect()
+    }
+
+    /// The state diff encoded in the `da/state_diff.json` fixture
+    pub(crate) fn expected_state_diff(fixture: &serde_json::Value) -> DaStateDiff {
+        let expected = &fixture["expected"];
+        let contracts = expected["contracts"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(address, update)| {
+                let update = DaContractUpdate {
+                    nonce: Some(felt(&update["nonce"])),
+                    class_hash: (!update["class_hash"].is_null()).then(|| felt(&update["class_hash"])),
+                    storage: update["storage"]
+                        .as_object()
+                        .unwrap()
+                        .iter()
+                        .map(|(key, value)| (FieldElement::from_hex_be(key).unwrap(), felt(value)))
+                        .collect(),
+                };
+                (FieldElement::from_hex_be(address).unwrap(), update)
+            })
+            .collect();
+        let declared_classes = expected["declared_classes"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                (FieldElement::from_hex_be(class_hash).unwrap(), felt(compiled_class_hash))
+            })
+            .collect();
+
+        DaStateDiff { contracts, declared_classes }
+    }
+
+    #[test]
+    fn test_decode_v0_11() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let state_diff = decode_state_diff(&felts(&fixture["v0_11"]), DaEncoding::V0_11).unwrap();
+        assert_eq!(state_diff, expected_state_diff(&fixture));
+    }
+
+    #[test]
+    fn test_decode_v0_13_3() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let state_diff = decode_state_diff(&felts(&fixture["v0_13_3"]), DaEncoding::V0_13_3).unwrap();
+        assert_eq!(state_diff, expected_state_diff(&fixture));
+    }
+
+    #[test]
+    fn test_decode_pre_v0_11() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let state_diff = decode_state_diff(&felts(&fixture["pre_v0_11"]), DaEncoding::PreV0_11).unwrap();
+
+        // no declared classes were published, and a contract deployed without storage updates has no
+        // published nonce
+        let mut expected = expected_state_diff(&fixture);
+        expected.declared_classes.clear();
+        expected.contracts.insert(
+            FieldElement::from_hex_be("0x789").unwrap(),
+            DaContractUpdate {
+                nonce: None,
+                class_hash: Some(FieldElement::from_hex_be("0xdead").unwrap()),
+                storage: BTreeMap::new(),
+            },
+        );
+        assert_eq!(state_diff, expected);
+
+        // the class update flag did not exist yet
+        let mut data = felts(&fixture["v0_11"]);
+        data.insert(0, FieldElement::ZERO);
+        let error = decode_state_diff(&data, DaEncoding::PreV0_11).unwrap_err();
+        assert!(matches!(error, DaError::InvalidEncoding(_)), "unexpected error: {error}");
+    }
+
+    #[test]
+    fn test_decode_padded() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let mut data = felts(&fixture["v0_11"]);
+        data.resize(data.len() + 16, FieldElement::ZERO);
+        assert_eq!(decode_state_diff(&data, DaEncoding::V0_11).unwrap(), expected_state_diff(&fixture));
+
+        data.push(FieldElement::ONE);
+        assert!(matches!(decode_state_diff(&data, DaEncoding::V0_11), Err(DaError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let fixture: serde_json::Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let data = felts(&fixture["v0_13_3"]);
+        let error = decode_state_diff(&data[..data.len() - 1], DaEncoding::V0_13_3).unwrap_err();
+        assert!(matches!(error, DaError::InvalidEncoding(_)), "unexpected error: {error}");
+    }
+}
@@ -0,0 +1,471 @@
+//! Retrieval of the data published on L1 by the transaction verifying a batch
+//!
+//! Before Starknet v0.13.1, the state diff was written to L1 in calldata, as continuous memory
+//! pages registered with the memory page fact registry before the state update. The transaction
+//! verifying the batch only commits to their hash and size. Since then, it is sent in EIP-4844
+//! blobs attached to the state update transaction, which are only served by the consensus layer.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use ethers::abi::{decode, ParamType, Token};
+use ethers::contract::EthEvent;
+use ethers::types::{BlockNumber, Filter, U256};
+use ethers::utils::{id, keccak256};
+use primitive_types::H256;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use starknet_ff::FieldElement;
+
+use super::blob::{blob_to_felts, kzg_to_versioned_hash};
+use super::DaError;
+use crate::l1::{decode_event, EthereumClient, L1Error};
+
+/// Number of L1 blocks searched for the memory pages of a state update, ~3h assuming a 12s block
+/// time
+const MEMORY_PAGES_LOOKBACK: u64 = 1000;
+/// Duration of a beacon chain slot, in seconds
+const SECONDS_PER_SLOT: u64 = 12;
+
+/// Emitted by the verifier with the hashes of the memory pages of a proven program output, the
+/// first one being the output itself
+#[derive(Clone, Debug, EthEvent)]
+pub struct LogMemoryPagesHashes {
+    pub program_output_fact: [u8; 32],
+    pub pages_hashes: Vec<[u8; 32]>,
+}
+
+/// Emitted by the memory page fact registry when a continuous memory page is registered
+#[derive(Clone, Debug, EthEvent)]
+pub struct LogMemoryPageFactContinuous {
+    pub fact_hash: [u8; 32],
+    pub memory_hash: U256,
+    pub prod: U256,
+}
+
+/// Client of the consensus layer API, serving the blobs
+pub struct BeaconClient {
+    client: reqwest::Client,
+    url: Url,
+    genesis_time: OnceLock<u64>,
+}
+
+#[derive(Deserialize)]
+struct BeaconResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+    genesis_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlobSidecar {
+    pub index: String,
+    pub blob: String,
+    pub kzg_commitment: String,
+}
+
+impl BeaconClient {
+    pub fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url, genesis_time: OnceLock::new() }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, DaError> {
+        let url = self.url.join(path).map_err(|e| DaError::InvalidBeaconResponse(e.to_string()))?;
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.json::<BeaconResponse<T>>().await?.data)
+    }
+
+    async fn genesis_time(&self) -> Result<u64, DaError> {
+        if let Some(genesis_time) = self.genesis_time.get() {
+            return Ok(*genesis_time);
+        }
+
+        let genesis: Genesis = self.get("eth/v1/beacon/genesis").await?;
+        let genesis_time = genesis
+            .genesis_time
+            .parse()
+            .map_err(|_| DaError::InvalidBeaconResponse(format!("invalid genesis time {}", genesis.genesis_time)))?;
+        Ok(*self.genesis_time.get_or_init(|| genesis_time))
+    }
+
+    /// The blob sidecars of the beacon block including the L1 block produced at `timestamp`
+    pub async fn blob_sidecars(&self, timestamp: u64) -> Result<Vec<BlobSidecar>, DaError> {
+        let slot = timestamp.saturating_sub(self.genesis_time().await?) / SECONDS_PER_SLOT;
+        self.get(&format!("eth/v1/beacon/blob_sidecars/{slot}")).await
+    }
+}
+
+/// Retrieve the data published on L1 by the transaction `tx_hash`
+pub async fn fetch_da(
+    client: &EthereumClient,
+    beacon: Option<&BeaconClient>,
+    tx_hash: H256,
+) -> Result<Vec<FieldElement>, DaError> {
+    let tx = get_transaction(client, tx_hash).await?;
+    let versioned_hashes = blob_versioned_hashes(&tx).map_err(|e| DaError::InvalidTransaction(tx_hash, e))?;
+
+    if versioned_hashes.is_empty() {
+        return fetch_calldata_da(client, tx_hash, &tx).await;
+    }
+
+    let beacon = beacon.ok_or(DaError::MissingBeaconEndpoint(tx_hash))?;
+    let block_number = tx.get("blockNumber").cloned().unwrap_or_default();
+    let block: Value = client.call_ethereum("eth_getBlockByNumber", vec![block_number, json!(false)]).await?;
+    let timestamp = hex_u64(&block["timestamp"])
+        .ok_or_else(|| L1Error::InvalidResponse("eth_getBlockByNumber", "no block timestamp".to_string()))?;
+
+    let sidecars = beacon.blob_sidecars(timestamp).await?;
+    let blobs = select_blobs(&sidecars, &versioned_hashes)?;
+    let mut data = Vec::with_capacity(blobs.len() * super::blob::FIELD_ELEMENTS_PER_BLOB);
+    for blob in blobs {
+        data.extend(blob_to_felts(&blob)?);
+    }
+    Ok(data)
+}
+
+async fn get_transaction(client: &EthereumClient, tx_hash: H256) -> Result<Value, DaError> {
+    let tx = client.call_ethereum("eth_getTransactionByHash", vec![json!(tx_hash)]).await?;
+    if tx.is_null() {
+        return Err(L1Error::InvalidResponse(
+            "eth_getTransactionByHash",
+            format!("transaction {tx_hash:#x} not found"),
+        )
+        .into());
+    }
+    Ok(tx)
+}
+
+/// The versioned hashes of the blobs attached to a transaction, in order
+pub fn blob_versioned_hashes(tx: &Value) -> Result<Vec<H256>, String> {
+    let Some(hashes) = tx.get("blobVersionedHashes").filter(|hashes| !hashes.is_null()) else {
+        return Ok(vec![]);
+    };
+    serde_json::from_value(hashes.clone()).map_err(|e| format!("invalid blob versioned hashes: {e}"))
+}
+
+/// The blobs matching `versioned_hashes`, in the same order
+pub fn select_blobs(sidecars: &[BlobSidecar], versioned_hashes: &[H256]) -> Result<Vec<Vec<u8>>, DaError> {
+    let decode_hex = |value: &str| {
+        hex::decode(value.trim_start_matches("0x")).map_err(|e| DaError::InvalidBeaconResponse(e.to_string()))
+    };
+
+    let mut blobs_by_hash = HashMap::new();
+    for sidecar in sidecars {
+        let versioned_hash = kzg_to_versioned_hash(&decode_hex(&sidecar.kzg_commitment)?);
+        blobs_by_hash.insert(versioned_hash, &sidecar.blob);
+    }
+
+    versioned_hashes
+        .iter()
+        .map(|versioned_hash| {
+            let blob = blobs_by_hash.get(versioned_hash).ok_or(DaError::MissingBlob(*versioned_hash))?;
+            decode_hex(blob)
+        })
+        .collect()
+}
+
+/// Retrieve the data published in calldata, from the memory pages committed to by the state update
+async fn fetch_calldata_da(client: &EthereumClient, tx_hash: H256, tx: &Value) -> Result<Vec<FieldElement>, DaError> {
+    let invalid_tx = |reason: String| DaError::InvalidTransaction(tx_hash, reason);
+
+    // updateState(uint256[] programOutput, uint256 onchainDataHash, uint256 onchainDataSize)
+    let input = hex_bytes(&tx["input"]).ok_or_else(|| invalid_tx("no input".to_string()))?;
+    let params = decode_call(
+        "updateState(uint256[],uint256,uint256)",
+        &[ParamType::Array(Box::new(ParamType::Uint(256))), ParamType::Uint(256), ParamType::Uint(256)],
+        &input,
+    )
+    .map_err(invalid_tx)?;
+    let (Some(data_hash), Some(data_size)) = (params[1].clone().into_uint(), params[2].clone().into_uint()) else {
+        return Err(invalid_tx("invalid updateState arguments".to_string()));
+    };
+    let block_number = hex_u64(&tx["blockNumber"]).ok_or_else(|| invalid_tx("not mined".to_string()))?;
+
+    let filter = |signature| {
+        Filter::new()
+            .from_block(block_number.saturating_sub(MEMORY_PAGES_LOOKBACK))
+            .to_block(BlockNumber::Number(block_number.into()))
+            .topic0(signature)
+    };
+    let pages_logs = client.get_logs(&filter(LogMemoryPagesHashes::signature())).await?;
+    let page_logs = client.get_logs(&filter(LogMemoryPageFactContinuous::signature())).await?;
+
+    // transactions registering each memory page, by page hash
+    let mut page_txs = HashMap::new();
+    for log in page_logs {
+        let (page, meta) = decode_event::<LogMemoryPageFactContinuous>(log)?;
+        page_txs.insert(H256(word_to_bytes(page.memory_hash)), meta.transaction_hash);
+    }
+
+    // the output of the program proving the batch is not known here, every candidate is checked
+    // against the commitment of the state update instead, starting with the most recent
+    for log in pages_logs.into_iter().rev() {
+        let (pages, _) = decode_event::<LogMemoryPagesHashes>(log)?;
+        let Some(da_pages_hashes) = pages.pages_hashes.get(1..) else {
+            continue;
+        };
+        let Some(data) = fetch_memory_pages(client, da_pages_hashes, &page_txs).await? else {
+            continue;
+        };
+
+        if U256::from(data.len()) == data_size && keccak256(encode_words(&data)) == word_to_bytes(data_hash) {
+            return data.iter().map(|word| to_felt(*word).map_err(&invalid_tx)).collect();
+        }
+    }
+
+    Err(DaError::PagesNotFound(tx_hash))
+}
+
+/// The concatenated values of the memory pages `pages_hashes`, or `None` if a page was not found
+async fn fetch_memory_pages(
+    client: &EthereumClient,
+    pages_hashes: &[[u8; 32]],
+    page_txs: &HashMap<H256, H256>,
+) -> Result<Option<Vec<U256>>, DaError> {
+    if pages_hashes.is_empty() {
+        return Ok(None);
+    }
+
+    let mut data = vec![];
+    for page_hash in pages_hashes {
+        let Some(&page_tx_hash) = page_txs.get(&H256(*page_hash)) else {
+            return Ok(None);
+        };
+        let invalid_tx = |reason: String| DaError::InvalidTransaction(page_tx_hash, reason);
+
+        // registerContinuousMemoryPage(uint256 startAddr, uint256[] values, uint256 z, uint256 alpha,
+        // uint256 prime)
+        let tx = get_transaction(client, page_tx_hash).await?;
+        let input = hex_bytes(&tx["input"]).ok_or_else(|| invalid_tx("no input".to_string()))?;
+        let params = decode_call(
+            "registerContinuousMemoryPage(uint256,uint256[],uint256,uint256,uint256)",
+            &[
+                ParamType::Uint(256),
+                ParamType::Array(Box::new(ParamType::Uint(256))),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+            ],
+            &input,
+        )
+        .map_err(invalid_tx)?;
+        let values = params[1]
+            .clone()
+            .into_array()
+            .and_then(|values| values.into_iter().map(Token::into_uint).collect::<Option<Vec<_>>>())
+            .ok_or_else(|| invalid_tx("invalid memory page values".to_string()))?;
+
+        if keccak256(encode_words(&values)) != *page_hash {
+            return Err(invalid_tx(format!("memory page does not match its hash 0x{}", hex::encode(page_hash))));
+        }
+        data.extend(values);
+    }
+    Ok(Some(data))
+}
+
+/// Decode the arguments of a call to `signature`
+fn decode_call(signature: &str, params: &[ParamType], input: &[u8]) -> Result<Vec<Token>, String> {
+    match input.strip_prefix(&id(signature)) {
+        Some(args) => decode(params, args).map_err(|e| format!("invalid {signature} call: {e}")),
+        None => Err(format!("not a {signature} call")),
+    }
+}
+
+/// Tightly packed words, as hashed by the memory page fact registry
+fn encode_words(words: &[U256]) -> Vec<u8> {
+    words.iter().flat_map(|word| word_to_bytes(*word)).collect()
+}
+
+fn word_to_bytes(word: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn to_felt(word: U256) -> Result<FieldElement, String> {
+    FieldElement::from_bytes_be(&word_to_bytes(word)).map_err(|_| format!("{word:#x} is not a felt"))
+}
+
+fn hex_bytes(value: &Value) -> Option<Vec<u8>> {
+    hex::decode(value.as_str()?.trim_start_matches("0x")).ok()
+}
+
+fn hex_u64(value: &Value) -> Option<u64> {
+    u64::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ethers::abi::encode;
+    use ethers::types::{Bytes, Log, U64};
+
+    use super::*;
+    use crate::da::encoding::tests::expected_state_diff;
+    use crate::da::{decode_published_data, DaConfig, DaError};
+    use crate::tests::mock_l1::MockL1Node;
+    use crate::tests::utils::read_resource::read_resource_file;
+
+    pub(crate) const UPDATE_STATE_BLOCK: u64 = 100;
+
+    fn sidecars() -> Vec<BlobSidecar> {
+        let sidecars = read_resource_file("da/blob_sidecars.json");
+        serde_json::from_str::<BeaconResponse<Vec<BlobSidecar>>>(&sidecars).unwrap().data
+    }
+
+    #[test]
+    fn test_blob_da() {
+        let tx: Value = serde_json::from_str(&read_resource_file("da/update_state_kzg_da_tx.json")).unwrap();
+        let fixture: Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+
+        let versioned_hashes = blob_versioned_hashes(&tx).unwrap();
+        assert_eq!(versioned_hashes.len(), 1);
+
+        let blobs = select_blobs(&sidecars(), &versioned_hashes).unwrap();
+        let data = blobs.iter().flat_map(|blob| blob_to_felts(blob).unwrap()).collect::<Vec<_>>();
+        assert_eq!(decode_published_data(&data, 0, &DaConfig::default()).unwrap(), expected_state_diff(&fixture));
+    }
+
+    #[test]
+    fn test_missing_blob() {
+        let missing = H256::repeat_byte(0x01);
+        let error = select_blobs(&sidecars(), &[missing]).unwrap_err();
+        assert!(matches!(error, DaError::MissingBlob(hash) if hash == missing), "unexpected error: {error}");
+    }
+
+    #[test]
+    fn test_no_blob() {
+        let tx = json!({ "hash": H256::zero(), "input": "0x" });
+        assert!(blob_versioned_hashes(&tx).unwrap().is_empty());
+    }
+
+    fn log(topic: H256, data: Vec<u8>, l1_block: u64, tx_hash: H256) -> Log {
+        Log {
+            topics: vec![topic],
+            data: Bytes::from(data),
+            block_hash: Some(H256::from_low_u64_be(l1_block)),
+            block_number: Some(U64::from(l1_block)),
+            transaction_hash: Some(tx_hash),
+            transaction_index: Some(U64::zero()),
+            log_index: Some(U256::zero()),
+            removed: Some(false),
+            ..Default::default()
+        }
+    }
+
+    fn transaction(tx_hash: H256, l1_block: u64, signature: &str, args: &[Token]) -> Value {
+        let input = [id(signature).as_slice(), &encode(args)].concat();
+        json!({ "hash": tx_hash, "blockNumber": U64::from(l1_block), "input": Bytes::from(input) })
+    }
+
+    fn words(values: &[U256]) -> Token {
+        Token::Array(values.iter().map(|value| Token::Uint(*value)).collect())
+    }
+
+    /// Publishes `data` in two memory pages, as the sequencer did before blobs
+    pub(crate) fn publish_calldata(node: &MockL1Node, data: &[U256]) -> H256 {
+        let (first_page, second_page) = data.split_at(data.len() / 2);
+        let mut pages_hashes = vec![Token::FixedBytes(vec![0x0f; 32])];
+
+        for (i, page) in (0..).zip([first_page, second_page]) {
+            let page_tx_hash = H256::from_low_u64_be(0x1000 + i);
+            let page_hash = keccak256(encode_words(page));
+            node.push_transaction(transaction(
+                page_tx_hash,
+                UPDATE_STATE_BLOCK - 10,
+                "registerContinuousMemoryPage(uint256,uint256[],uint256,uint256,uint256)",
+                &[
+                    Token::Uint(U256::zero()),
+                    words(page),
+                    Token::Uint(1.into()),
+                    Token::Uint(2.into()),
+                    Token::Uint(3.into()),
+                ],
+            ));
+            node.push_log(log(
+                LogMemoryPageFactContinuous::signature(),
+                encode(&[
+                    Token::FixedBytes(vec![0xfa; 32]),
+                    Token::Uint(U256::from_big_endian(&page_hash)),
+                    Token::Uint(U256::one()),
+                ]),
+                UPDATE_STATE_BLOCK - 10,
+                page_tx_hash,
+            ));
+            pages_hashes.push(Token::FixedBytes(page_hash.to_vec()));
+        }
+
+        node.push_log(log(
+            LogMemoryPagesHashes::signature(),
+            encode(&[Token::FixedBytes(vec![0xfa; 32]), Token::Array(pages_hashes)]),
+            UPDATE_STATE_BLOCK - 5,
+            H256::from_low_u64_be(0x2000),
+        ));
+        // a more recent proof, with pages which are not part of this state update
+        node.push_log(log(
+            LogMemoryPagesHashes::signature(),
+            encode(&[
+                Token::FixedBytes(vec![0xfb; 32]),
+                Token::Array(vec![Token::FixedBytes(vec![0x0f; 32]), Token::FixedBytes(vec![0x0e; 32])]),
+            ]),
+            UPDATE_STATE_BLOCK - 1,
+            H256::from_low_u64_be(0x2001),
+        ));
+
+        let tx_hash = H256::from_low_u64_be(0x3000);
+        node.push_transaction(transaction(
+            tx_hash,
+            UPDATE_STATE_BLOCK,
+            "updateState(uint256[],uint256,uint256)",
+            &[
+                words(&[]),
+                Token::Uint(U256::from_big_endian(&keccak256(encode_words(data)))),
+                Token::Uint(data.len().into()),
+            ],
+        ));
+        tx_hash
+    }
+
+    #[tokio::test]
+    async fn test_calldata_da() {
+        let fixture: Value = serde_json::from_str(&read_resource_file("da/state_diff.json")).unwrap();
+        let data = fixture["v0_11"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|word| U256::from_str_radix(word.as_str().unwrap().trim_start_matches("0x"), 16).unwrap())
+            .collect::<Vec<_>>();
+
+        let node = MockL1Node::start().await;
+        let tx_hash = publish_calldata(&node, &data);
+        let client = EthereumClient::new(node.http_url()).await.unwrap();
+
+        let published = fetch_da(&client, None, tx_hash).await.expect("Failed to fetch calldata");
+        assert_eq!(decode_published_data(&published, 0, &DaConfig::default()).unwrap(), expected_state_diff(&fixture));
+
+        // the same data is not valid in the encoding used before Starknet v0.11
+        let pre_v0_11 = DaConfig { v0_11_first_block: 1, ..Default::default() };
+        let error = decode_published_data(&published, 0, &pre_v0_11).unwrap_err();
+        assert!(matches!(error, DaError::InvalidEncoding(_)), "unexpected error: {error}");
+
+        // not a state update transaction
+        let error = fetch_da(&client, None, H256::from_low_u64_be(0x1000)).await.unwrap_err();
+        assert!(matches!(error, DaError::InvalidTransaction(..)), "unexpected error: {error}");
+    }
+
+    #[tokio::test]
+    async fn test_blob_da_without_beacon() {
+        let node = MockL1Node::start().await;
+        let tx: Value = serde_json::from_str(&read_resource_file("da/update_state_kzg_da_tx.json")).unwrap();
+        let tx_hash = serde_json::from_value(tx["hash"].clone()).unwrap();
+        node.push_transaction(tx);
+        let client = EthereumClient::new(node.http_url()).await.unwrap();
+
+        let error = fetch_da(&client, None, tx_hash).await.unwrap_err();
+        assert!(matches!(error, DaError::MissingBeaconEndpoint(hash) if hash == tx_hash), "unexpected error: {error}");
+    }
+}
@@ -0,0 +1,270 @@
+//! Verification of the imported state against the data availability published on L1
+//!
+//! Every state update verified on L1 comes with the state diff of its batch of blocks, so that the
+//! state can be rebuilt without trusting the feeder gateway. This decodes the state diffs published
+//! for the batches whose blocks have been synced and checks them against the imported state.
+
+pub mod blob;
+pub mod compression;
+pub mod encoding;
+pub mod fetch;
+pub mod verify;
+
+use std::time::Duration;
+
+use anyhow::Context;
+use mc_db::storage_handler::DeoxysStorageError;
+use mc_db::{DbError, DeoxysBackend};
+use primitive_types::H256;
+use reqwest::Url;
+use starknet_ff::FieldElement;
+
+use self::encoding::{decode_state_diff, DaEncoding, DaStateDiff};
+use self::fetch::{fetch_da, BeaconClient};
+use crate::l1::{EthereumClient, L1Error};
+
+/// Delay between two checks for newly synced batches
+const DA_POLLING_INTERVAL: Duration = Duration::from_secs(60);
+/// Number of L1 blocks after which blobs may be pruned, 4096 epochs of 32 slots
+const BLOB_RETENTION_L1_BLOCKS: u64 = 4096 * 32;
+
+/// Errors raised while verifying the data published on L1
+#[derive(thiserror::Error, Debug)]
+pub enum DaError {
+    #[error(transparent)]
+    L1(#[from] L1Error),
+    #[error("beacon API error: {0}")]
+    Beacon(#[from] reqwest::Error),
+    #[error("invalid beacon API response: {0}")]
+    InvalidBeaconResponse(String),
+    #[error("state update {0:#x} published its data in blobs, a beacon endpoint is needed to retrieve them")]
+    MissingBeaconEndpoint(H256),
+    #[error("blob {0:#x} is not served by the beacon endpoint")]
+    MissingBlob(H256),
+    #[error("invalid L1 transaction {0:#x}: {1}")]
+    InvalidTransaction(H256, String),
+    #[error("no memory pages matching state update {0:#x} found")]
+    PagesNotFound(H256),
+    #[error("invalid blob: {0}")]
+    InvalidBlob(String),
+    #[error("invalid compressed data: {0}")]
+    InvalidCompression(String),
+    #[error("invalid state diff encoding: {0}")]
+    InvalidEncoding(String),
+    #[error("db error: {0}")]
+    Db(#[from] DbError),
+    #[error("storage error: {0}")]
+    Storage(#[from] DeoxysStorageError),
+    #[error("state diff of blocks #{first_block} to #{last_block} published on L1 does not match: {reason}")]
+    Mismatch { first_block: u64, last_block: u64, reason: String },
+}
+
+impl DaError {
+    /// Whether the error cannot be solved by retrying, possibly with another endpoint
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Self::L1(e) => !e.is_connection_error(),
+            Self::Beacon(_) | Self::InvalidBeaconResponse(_) | Self::MissingBlob(_) | Self::PagesNotFound(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Configuration of the data availability verification
+#[derive(Debug, Clone, Default)]
+pub struct DaConfig {
+    /// Consensus layer endpoint serving the blobs, needed since Starknet v0.13.1
+    pub beacon_url: Option<Url>,
+    /// First block produced with Starknet v0.11, the batches ending before it were published in
+    /// the older encoding
+    pub v0_11_first_block: u64,
+}
+
+/// Decode the data published on L1 for a batch ending with `last_block`, compressed since Starknet
+/// v0.13.3
+pub fn decode_published_data(
+    data: &[FieldElement],
+    last_block: u64,
+    config: &DaConfig,
+) -> Result<DaStateDiff, DaError> {
+    if last_block < config.v0_11_first_block {
+        decode_state_diff(data, DaEncoding::PreV0_11)
+    } else if compression::is_compressed(data) {
+        decode_state_diff(&compression::decompress(data)?, DaEncoding::V0_13_3)
+    } else {
+        decode_state_diff(data, DaEncoding::V0_11)
+    }
+}
+
+/// Verify the state diffs published on L1 as blocks are synced
+///
+/// Like the L1 sync, this switches to the next endpoint on connection errors. It only returns on
+/// errors which are not related to the endpoints: the published data not matching the local state,
+/// or being invalid.
+pub async fn sync(l1_urls: Vec<Url>, config: DaConfig) -> anyhow::Result<()> {
+    if l1_urls.is_empty() {
+        return Err(L1Error::NoEndpoint.into());
+    }
+    let beacon = config.beacon_url.clone().map(BeaconClient::new);
+
+    let mut endpoint_index = 0;
+    let mut interval = tokio::time::interval(DA_POLLING_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let result = match EthereumClient::new(l1_urls[endpoint_index].clone()).await {
+            Ok(client) => verify_pending_batches(&client, beacon.as_ref(), &config).await,
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(()) => {}
+            Err(e) if e.is_fatal() => return Err(e).context("verifying the state diffs published on L1"),
+            Err(e) => {
+                endpoint_index = (endpoint_index + 1) % l1_urls.len();
+                log::warn!(
+                    "⚠️ Failed to retrieve the data published on L1: {e}, switching to endpoint #{endpoint_index}"
+                );
+            }
+        }
+    }
+}
+
+/// Verify the batches which have been synced since the last verified one
+pub(crate) async fn verify_pending_batches(
+    client: &EthereumClient,
+    beacon: Option<&BeaconClient>,
+    config: &DaConfig,
+) -> Result<(), DaError> {
+    let last_verified_block = DeoxysBackend::meta().last_da_verified_block()?;
+    let current_sync_block = DeoxysBackend::meta().current_sync_block()?;
+    let checkpoint_block = DeoxysBackend::meta().checkpoint_block()?;
+
+    let state_updates = DeoxysBackend::l1().state_updates()?;
+    for (index, state_update) in state_updates.iter().enumerate() {
+        let last_block = state_update.block_number;
+        if last_verified_block.is_some_and(|block_number| last_block <= block_number) {
+            continue;
+        }
+        if last_block > current_sync_block {
+            break;
+        }
+
+        // a batch starts after the block verified by the previous state update, which is looked up
+        // on L1 for the first stored one
+        let previous_block = match index.checked_sub(1) {
+            Some(previous) => Some(state_updates[previous].block_number),
+            None => client.find_previous_state_update(last_block).await?.map(|previous| previous.block_number),
+        };
+        let first_block = previous_block.map_or(0, |block_number| block_number + 1);
+
+        if checkpoint_block.is_some_and(|checkpoint_block| first_block <= checkpoint_block) {
+            log::debug!(
+                "Blocks #{first_block} to #{last_block} start before the checkpoint, skipping their verification"
+            );
+            DeoxysBackend::meta().set_last_da_verified_block(last_block)?;
+            continue;
+        }
+
+        let tx_hash = state_update.l1_transaction_hash;
+        let published = fetch_da(client, beacon, tx_hash).await;
+        match published.and_then(|data| decode_published_data(&data, last_block, config)) {
+            Ok(state_diff) => {
+                verify::verify_batch(first_block, last_block, &state_diff)?;
+                log::info!("✅ Verified state diff of blocks #{first_block} to #{last_block} against L1");
+            }
+            Err(DaError::MissingBlob(blob_hash)) => {
+                // blobs are only served by the consensus layer for a few weeks
+                let l1_head = client.get_latest_block_number().await?.as_u64();
+                if l1_head < state_update.l1_block_number + BLOB_RETENTION_L1_BLOCKS {
+                    return Err(DaError::MissingBlob(blob_hash));
+                }
+                log::warn!(
+                    "⚠️ Blob {blob_hash:#x} of blocks #{first_block} to #{last_block} is no longer served, skipping \
+                     their verification"
+                );
+            }
+            Err(e) => return Err(e),
+        }
+        DeoxysBackend::meta().set_last_da_verified_block(last_block)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+    use mc_db::L1StateUpdateRecord;
+    use starknet_api::hash::StarkHash;
+
+    use super::encoding::DaContractUpdate;
+    use super::fetch::tests::{publish_calldata, UPDATE_STATE_BLOCK};
+    use super::verify::verify_batch;
+    use super::*;
+    use crate::l2::l2_sync_tests::{synced_mock_chain, SyncedMockChain};
+    use crate::tests::mock_l1::{state_update_log, MockL1Node};
+
+    /// Checks the state diffs of the mock chain, as they would be published on L1, against the
+    /// synced state
+    #[tokio::test]
+    async fn test_verify_mock_chain_da() {
+        let SyncedMockChain { core_address, .. } = synced_mock_chain().await;
+        let felt = |value: u64| FieldElement::from(value);
+        let contract_update = |nonce: u64, storage: &[(u64, u64)]| DaContractUpdate {
+            nonce: Some(felt(nonce)),
+            class_hash: None,
+            storage: storage.iter().map(|&(key, value)| (felt(key), felt(value))).collect(),
+        };
+
+        // blocks 1 to 3 squashed: the first storage update of block 1 is reverted by block 3
+        let mut published = DaStateDiff::default();
+        published.contracts.insert(felt(0x1000), contract_update(0, &[(0x1, 0x0), (0x2, 0x1)]));
+        published.contracts.insert(felt(0x2000), contract_update(2, &[(0x1, 0x65)]));
+        verify_batch(1, 3, &published).expect("Failed to verify the published state diff");
+
+        let mut tampered = published.clone();
+        tampered.contracts.get_mut(&felt(0x2000)).unwrap().storage.insert(felt(0x1), felt(0x66));
+        let error = verify_batch(1, 3, &tampered).unwrap_err();
+        assert!(matches!(error, DaError::Mismatch { first_block: 1, last_block: 3, .. }), "unexpected error: {error}");
+
+        let mut incomplete = published.clone();
+        incomplete.contracts.get_mut(&felt(0x1000)).unwrap().storage.remove(&felt(0x1));
+        let error = verify_batch(1, 3, &incomplete).unwrap_err();
+        assert!(matches!(error, DaError::Mismatch { .. }), "unexpected error: {error}");
+
+        // block 2 changes nothing
+        verify_batch(2, 2, &DaStateDiff::default()).expect("Failed to verify an empty state diff");
+
+        // only the state update of block 3 is stored, its batch starts after the state update of block 0
+        // found on L1
+        let mock = MockL1Node::start().await;
+        let header = |nonce: u64, n_storage_updates: u64| (U256::from(nonce) << 64) | U256::from(n_storage_updates);
+        let words = [
+            [U256::from(2), U256::from(0x1000), header(0, 2)].as_slice(),
+            &[0x1, 0x0, 0x2, 0x1].map(U256::from),
+            &[U256::from(0x2000), header(2, 1), U256::from(0x1), U256::from(0x65)],
+            &[U256::zero()],
+        ]
+        .concat();
+        let tx_hash = publish_calldata(&mock, &words);
+        let one = StarkHash::from(1_u64);
+        mock.push_log(state_update_log(core_address, 50, 0, one, one));
+        mock.push_log(state_update_log(core_address, UPDATE_STATE_BLOCK, 3, one, one));
+        mock.set_block_number(UPDATE_STATE_BLOCK + 10);
+        let state_update = L1StateUpdateRecord {
+            l1_block_number: UPDATE_STATE_BLOCK,
+            l1_transaction_hash: tx_hash,
+            block_number: 3,
+            global_root: one,
+            block_hash: one,
+        };
+        DeoxysBackend::l1().write_state_update(&state_update).unwrap();
+
+        // once the checkpoint of the mock chain is accepted at block #3, the batch is skipped instead of
+        // fetched, with the same outcome
+        let client = EthereumClient::new(mock.http_url()).await.unwrap();
+        verify_pending_batches(&client, None, &DaConfig::default()).await.expect("Failed to verify the first batch");
+        assert_eq!(DeoxysBackend::meta().last_da_verified_block().unwrap(), Some(3));
+        DeoxysBackend::l1().remove_state_update(3, tx_hash).unwrap();
+    }
+}
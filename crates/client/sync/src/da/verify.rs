@@ -0,0 +1,161 @@
+//! Comparison of the state diffs published on L1 with the imported state updates
+//!
+//! A batch verified on L1 publishes the squashed state diff of its blocks: the final value of
+//! everything which changed during the batch. Every published value must match the local state at
+//! the last block of the batch, and everything the imported state updates changed but which was
+//! not published must be back to its value before the batch.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use mc_db::storage_handler;
+use mp_felt::Felt252Wrapper;
+use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_ff::FieldElement;
+
+use super::encoding::DaStateDiff;
+use super::DaError;
+
+/// Everything changed by the imported state updates of a batch
+#[derive(Default)]
+struct ImportedChanges {
+    storage: BTreeSet<(FieldElement, FieldElement)>,
+    nonces: BTreeSet<FieldElement>,
+    class_hashes: BTreeSet<FieldElement>,
+    declared_classes: BTreeMap<FieldElement, FieldElement>,
+}
+
+/// Check the state diff published on L1 for the blocks `first_block..=last_block` against the
+/// imported state
+pub fn verify_batch(first_block: u64, last_block: u64, published: &DaStateDiff) -> Result<(), DaError> {
+    let mismatch = |reason: String| DaError::Mismatch { first_block, last_block, reason };
+    let imported = imported_changes(first_block, last_block)?;
+
+    for (&address, update) in &published.contracts {
+        if let Some(published_nonce) = update.nonce {
+            let nonce = nonce_at(address, last_block)?;
+            if nonce != published_nonce {
+                return Err(mismatch(format!(
+                    "contract {address:#x} has nonce {nonce:#x}, but {published_nonce:#x} was published"
+                )));
+            }
+        }
+        if let Some(published_class_hash) = update.class_hash {
+            let class_hash = class_hash_at(address, last_block)?;
+            if class_hash != published_class_hash {
+                return Err(mismatch(format!(
+                    "contract {address:#x} has class hash {class_hash:#x}, but {published_class_hash:#x} was published"
+                )));
+            }
+        }
+        for (&key, &published_value) in &update.storage {
+            let value = storage_at(address, key, last_block)?;
+            if value != published_value {
+                return Err(mismatch(format!(
+                    "storage {key:#x} of contract {address:#x} is {value:#x}, but {published_value:#x} was published"
+                )));
+            }
+        }
+    }
+
+    // the values from before the batch, or the default values for the genesis batch
+    let before = first_block.checked_sub(1);
+    let published_contract = |address| published.contracts.get(&address);
+
+    for &(address, key) in &imported.storage {
+        if published_contract(address).is_some_and(|update| update.storage.contains_key(&key)) {
+            continue;
+        }
+        let value = storage_at(address, key, last_block)?;
+        let previous_value = before.map(|block| storage_at(address, key, block)).transpose()?.unwrap_or_default();
+        if value != previous_value {
+            return Err(mismatch(format!(
+                "storage {key:#x} of contract {address:#x} changed from {previous_value:#x} to {value:#x}, but was \
+                 not published"
+            )));
+        }
+    }
+    for &address in &imported.nonces {
+        if published_contract(address).is_some_and(|update| update.nonce.is_some()) {
+            continue;
+        }
+        let nonce = nonce_at(address, last_block)?;
+        let previous_nonce = before.map(|block| nonce_at(address, block)).transpose()?.unwrap_or_default();
+        if nonce != previous_nonce {
+            return Err(mismatch(format!(
+                "nonce of contract {address:#x} changed to {nonce:#x}, but was not published"
+            )));
+        }
+    }
+    for &address in &imported.class_hashes {
+        if published_contract(address).is_some_and(|update| update.class_hash.is_some()) {
+            continue;
+        }
+        let class_hash = class_hash_at(address, last_block)?;
+        let previous_class_hash = before.map(|block| class_hash_at(address, block)).transpose()?.unwrap_or_default();
+        if class_hash != previous_class_hash {
+            return Err(mismatch(format!(
+                "class hash of contract {address:#x} changed to {class_hash:#x}, but was not published"
+            )));
+        }
+    }
+
+    if imported.declared_classes != published.declared_classes {
+        return Err(mismatch(format!(
+            "{} classes were declared, but {} were published",
+            imported.declared_classes.len(),
+            published.declared_classes.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn imported_changes(first_block: u64, last_block: u64) -> Result<ImportedChanges, DaError> {
+    let mut changes = ImportedChanges::default();
+
+    for block_number in first_block..=last_block {
+        let state_diff = storage_handler::block_state_diff().get(block_number)?.ok_or_else(|| DaError::Mismatch {
+            first_block,
+            last_block,
+            reason: format!("no state diff stored for block {block_number}"),
+        })?;
+
+        for diff in state_diff.storage_diffs {
+            changes.storage.extend(diff.storage_entries.iter().map(|entry| (diff.address, entry.key)));
+        }
+        changes.nonces.extend(state_diff.nonces.iter().map(|nonce| nonce.contract_address));
+        changes.class_hashes.extend(state_diff.deployed_contracts.iter().map(|deployed| deployed.address));
+        changes.class_hashes.extend(state_diff.replaced_classes.iter().map(|replaced| replaced.contract_address));
+        changes.declared_classes.extend(
+            state_diff.declared_classes.iter().map(|declared| (declared.class_hash, declared.compiled_class_hash)),
+        );
+    }
+
+    Ok(changes)
+}
+
+fn contract_address(address: FieldElement) -> ContractAddress {
+    ContractAddress(PatriciaKey(Felt252Wrapper::from(address).into()))
+}
+
+fn to_felt(felt: StarkFelt) -> FieldElement {
+    Felt252Wrapper::from(felt).into()
+}
+
+fn storage_at(address: FieldElement, key: FieldElement, block_number: u64) -> Result<FieldElement, DaError> {
+    let key = StorageKey(PatriciaKey(Felt252Wrapper::from(key).into()));
+    let value = storage_handler::contract_storage().get_at(&(contract_address(address), key), block_number)?;
+    Ok(value.map(to_felt).unwrap_or_default())
+}
+
+fn nonce_at(address: FieldElement, block_number: u64) -> Result<FieldElement, DaError> {
+    let nonce = storage_handler::contract_nonces().get_at(&contract_address(address), block_number)?;
+    Ok(nonce.map(|nonce| to_felt(nonce.0)).unwrap_or_default())
+}
+
+fn class_hash_at(address: FieldElement, block_number: u64) -> Result<FieldElement, DaError> {
+    let class_hash = storage_handler::contract_class_hash().get_at(&contract_address(address), block_number)?;
+    Ok(class_hash.map(|ClassHash(class_hash)| to_felt(class_hash)).unwrap_or_default())
+}
@@ -0,0 +1,148 @@
+//! Broadcast of the sync events to in-process consumers
+//!
+//! The sync publishes an event whenever a block is imported, the pending block is updated, a state
+//! update is verified on L1 or a reorg is detected. Components such as the RPC websockets or export
+//! sinks subscribe to these events instead of polling the db.
+//!
+//! Publishing never blocks the sync: a subscriber lagging more than [`EVENT_BUS_CAPACITY`] events
+//! behind misses the oldest ones, and is told so by [`broadcast::error::RecvError::Lagged`].
+
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use mp_block::Header;
+use starknet_api::hash::StarkHash;
+use starknet_core::types::StateDiff;
+use tokio::sync::broadcast;
+
+use crate::l1::L1StateUpdate;
+
+/// Number of events kept for the subscribers which have not received them yet
+pub const EVENT_BUS_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref EVENT_BUS: broadcast::Sender<SyncEvent> = broadcast::channel(EVENT_BUS_CAPACITY).0;
+}
+
+/// An event published by the sync
+#[derive(Debug, Clone)]
+pub enum SyncEvent {
+    /// A block and its state update have been stored in db
+    BlockImported(Arc<ImportedBlock>),
+    /// The pending block has been replaced by a newer one
+    PendingBlockUpdated(Arc<PendingBlock>),
+    /// The Starknet state verified on L1 has been updated
+    L1StateVerified(L1StateUpdate),
+    /// A reorg has been detected, it is not handled yet
+    ReorgDetected(Reorg),
+}
+
+/// A block stored in db
+#[derive(Debug, Clone)]
+pub struct ImportedBlock {
+    pub header: Header,
+    pub block_hash: StarkHash,
+    pub tx_hashes: Vec<StarkHash>,
+    pub state_diff: StateDiffSummary,
+}
+
+/// The pending block, which has no hash yet
+#[derive(Debug, Clone)]
+pub struct PendingBlock {
+    pub header: Header,
+    pub tx_hashes: Vec<StarkHash>,
+}
+
+/// Number of updates of each kind in a state diff
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateDiffSummary {
+    /// Number of storage entries updated, over all contracts
+    pub storage_updates: usize,
+    pub deployed_contracts: usize,
+    pub replaced_classes: usize,
+    /// Number of classes declared, Cairo 0 classes included
+    pub declared_classes: usize,
+    pub nonce_updates: usize,
+}
+
+impl From<&StateDiff> for StateDiffSummary {
+    fn from(state_diff: &StateDiff) -> Self {
+        Self {
+            storage_updates: state_diff.storage_diffs.iter().map(|diff| diff.storage_entries.len()).sum(),
+            deployed_contracts: state_diff.deployed_contracts.len(),
+            replaced_classes: state_diff.replaced_classes.len(),
+            declared_classes: state_diff.declared_classes.len() + state_diff.deprecated_declared_classes.len(),
+            nonce_updates: state_diff.nonces.len(),
+        }
+    }
+}
+
+/// A reorg detected by the sync
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reorg {
+    /// An event of the Starknet core contract was removed by an L1 reorg
+    L1 { event: String, l1_block_number: Option<u64> },
+    /// A fetched block is not a child of the last imported block
+    L2 { block_number: u64, parent_block_hash: StarkHash, local_parent_block_hash: StarkHash },
+}
+
+/// Subscribe to the events published by the sync from now on
+pub fn subscribe() -> broadcast::Receiver<SyncEvent> {
+    EVENT_BUS.subscribe()
+}
+
+/// Publish an event to the current subscribers
+pub(crate) fn publish(event: SyncEvent) {
+    // sending only fails when there is no subscriber
+    let _ = EVENT_BUS.send(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_core::types::{ContractStorageDiffItem, NonceUpdate, StorageEntry};
+    use starknet_ff::FieldElement;
+
+    use super::*;
+
+    #[test]
+    fn test_state_diff_summary() {
+        let entry = StorageEntry { key: FieldElement::ONE, value: FieldElement::TWO };
+        let state_diff = StateDiff {
+            storage_diffs: vec![
+                ContractStorageDiffItem {
+                    address: FieldElement::ONE,
+                    storage_entries: vec![entry.clone(), entry.clone()],
+                },
+                ContractStorageDiffItem { address: FieldElement::TWO, storage_entries: vec![entry] },
+            ],
+            deprecated_declared_classes: vec![FieldElement::ONE],
+            declared_classes: vec![],
+            deployed_contracts: vec![],
+            replaced_classes: vec![],
+            nonces: vec![NonceUpdate { contract_address: FieldElement::ONE, nonce: FieldElement::ONE }],
+        };
+
+        assert_eq!(
+            StateDiffSummary::from(&state_diff),
+            StateDiffSummary { storage_updates: 3, declared_classes: 1, nonce_updates: 1, ..Default::default() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        // publishing does not depend on the subscribers
+        publish(SyncEvent::ReorgDetected(Reorg::L1 { event: "LogStateUpdate".into(), l1_block_number: None }));
+
+        let mut subscriber = subscribe();
+        let reorg = Reorg::L1 { event: "LogStateUpdate".into(), l1_block_number: Some(42) };
+        publish(SyncEvent::ReorgDetected(reorg.clone()));
+
+        // events are shared with the other tests of the binary
+        loop {
+            match subscriber.recv().await.expect("Failed to receive event") {
+                SyncEvent::ReorgDetected(received) if received == reorg => break,
+                _ => continue,
+            }
+        }
+    }
+}
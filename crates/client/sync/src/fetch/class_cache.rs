@@ -0,0 +1,186 @@
+//! De-duplication of the class definitions fetched from the feeder gateway
+//!
+//! Blocks are fetched in parallel and ahead of the blocks being stored, so a class declared in a
+//! block and deployed in the next ones is not in db yet when these are fetched. Each class is
+//! claimed by the earliest block needing it until that block is stored, the other blocks leave it
+//! out of their class update.
+//!
+//! Fetched definitions can also be kept in a directory shared between nodes, so that resyncs do not
+//! download them again. Definitions read from that directory are verified like fetched ones.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use starknet_core::types::ContractClass;
+use starknet_ff::FieldElement;
+
+lazy_static! {
+    /// Classes being fetched or waiting to be stored, with the block they are stored with
+    static ref CLASSES_IN_FLIGHT: Mutex<HashMap<FieldElement, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Claim a class missing from db for a block, returning whether the block has to fetch it
+///
+/// A class claimed by an earlier block is stored before this block is applied. A class claimed by
+/// a later block, fetched first, is claimed again as it belongs to the earliest block needing it.
+pub(crate) fn claim_class(class_hash: FieldElement, block_number: u64) -> bool {
+    let mut in_flight = CLASSES_IN_FLIGHT.lock().expect("Failed to acquire lock on CLASSES_IN_FLIGHT");
+    match in_flight.get(&class_hash) {
+        Some(&claimed_by) if claimed_by < block_number => false,
+        _ => {
+            in_flight.insert(class_hash, block_number);
+            true
+        }
+    }
+}
+
+/// Release the classes claimed by the blocks up to `block_number`, once they are stored
+pub(crate) fn release_classes(block_number: u64) {
+    CLASSES_IN_FLIGHT
+        .lock()
+        .expect("Failed to acquire lock on CLASSES_IN_FLIGHT")
+        .retain(|_, claimed_by| *claimed_by > block_number);
+}
+
+/// Release the classes claimed by a block which failed to fetch them
+pub(crate) fn release_block_classes(block_number: u64) {
+    CLASSES_IN_FLIGHT
+        .lock()
+        .expect("Failed to acquire lock on CLASSES_IN_FLIGHT")
+        .retain(|_, claimed_by| *claimed_by != block_number);
+}
+
+/// Release all the claimed classes, when the sync starts again
+pub(crate) fn clear_claims() {
+    CLASSES_IN_FLIGHT.lock().expect("Failed to acquire lock on CLASSES_IN_FLIGHT").clear();
+}
+
+/// A directory of class definitions as served by the feeder gateway, named after their class hash
+#[derive(Debug, Clone)]
+pub struct ClassCache {
+    dir: PathBuf,
+}
+
+impl ClassCache {
+    /// Open a class cache directory, creating it if needed
+    pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, class_hash: FieldElement) -> PathBuf {
+        self.dir.join(format!("{class_hash:#066x}.json"))
+    }
+
+    /// Read a class definition, unreadable entries are treated as missing
+    pub async fn get(&self, class_hash: FieldElement) -> Option<ContractClass> {
+        let path = self.path(class_hash);
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("⚠️ Failed to read cached class {}: {e}", path.display());
+                return None;
+            }
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(class) => Some(class),
+            Err(e) => {
+                log::warn!("⚠️ Invalid cached class {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Store a class definition
+    ///
+    /// It is written to a temporary file first, so that other nodes sharing the directory never
+    /// read a partial definition.
+    pub async fn insert(&self, class_hash: FieldElement, class: &ContractClass) -> std::io::Result<()> {
+        static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let bytes = serde_json::to_vec(class)?;
+        let tmp_path = self.dir.join(format!(
+            ".{class_hash:#066x}.{}.{}.tmp",
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp_path, bytes).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, self.path(class_hash)).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove a class definition which failed its verification
+    pub async fn remove(&self, class_hash: FieldElement) {
+        if let Err(e) = tokio::fs::remove_file(self.path(class_hash)).await
+            && e.kind() != ErrorKind::NotFound
+        {
+            log::warn!("⚠️ Failed to remove cached class {class_hash:#x}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::read_resource::read_resource_file;
+
+    #[test]
+    fn test_claim_class() {
+        // the classes are shared with the other tests of the binary, these are only used here
+        let class_hash = FieldElement::from_hex_be("0xc1a55c1a1").unwrap();
+        let other_class_hash = FieldElement::from_hex_be("0xc1a55c1a2").unwrap();
+
+        assert!(claim_class(class_hash, 12));
+        assert!(!claim_class(class_hash, 13));
+        assert!(!claim_class(class_hash, 12 + 5));
+        // fetched by a later block first, the class is stored with the earliest one
+        assert!(claim_class(class_hash, 11));
+        assert!(!claim_class(class_hash, 12));
+
+        assert!(claim_class(other_class_hash, 12));
+        release_block_classes(12);
+        assert!(claim_class(other_class_hash, 13));
+
+        release_classes(11);
+        assert!(claim_class(class_hash, 12));
+        release_classes(13);
+        assert!(claim_class(class_hash, 14));
+        assert!(claim_class(other_class_hash, 14));
+        release_classes(14);
+    }
+
+    #[tokio::test]
+    async fn test_class_cache() {
+        let dir = std::env::temp_dir().join(format!("deoxys-class-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let class_cache = ClassCache::open(&dir).unwrap();
+
+        let classes: serde_json::Value =
+            serde_json::from_str(&read_resource_file("mock_gateway/classes.json")).unwrap();
+        let class: ContractClass = serde_json::from_value(classes["0x4a7c0de"]["class"].clone()).unwrap();
+        let class_hash = FieldElement::from_hex_be("0x4a7c0de").unwrap();
+
+        assert!(class_cache.get(class_hash).await.is_none());
+        class_cache.insert(class_hash, &class).await.unwrap();
+        let cached = class_cache.get(class_hash).await.expect("class is cached");
+        assert_eq!(serde_json::to_value(cached).unwrap(), serde_json::to_value(&class).unwrap());
+        // only the class itself is left in the directory
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::write(class_cache.path(class_hash), "{}").unwrap();
+        assert!(class_cache.get(class_hash).await.is_none());
+        class_cache.remove(class_hash).await;
+        assert!(class_cache.get(class_hash).await.is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 //! Contains the code required to fetch data from the network efficiently.
 use core::time::Duration;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use itertools::Itertools;
@@ -7,11 +9,15 @@ use mc_db::storage_handler::primitives::contract_class::{ContractClassData, Cont
 use mc_db::storage_handler::{self, DeoxysStorageError, StorageView};
 use mp_block::DeoxysBlock;
 use mp_convert::state_update::ToStateUpdateCore;
+use mp_transactions::from_broadcasted_transactions::{
+    flattened_sierra_to_casm_contract_class, get_casm_contract_class_hash, get_legacy_contract_class_hash,
+};
 use sp_core::H160;
 use starknet_api::core::ClassHash;
 use starknet_api::hash::StarkFelt;
 use starknet_core::types::{
-    BlockId as BlockIdCore, DeclaredClassItem, DeployedContractItem, StarknetError, StateUpdate,
+    BlockId as BlockIdCore, ContractClass as ContractClassCore, DeclaredClassItem, DeployedContractItem, StarknetError,
+    StateUpdate,
 };
 use starknet_ff::FieldElement;
 use starknet_providers::sequencer::models::{self as p, BlockId};
@@ -19,7 +25,8 @@ use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 use tokio::task::JoinSet;
 use url::Url;
 
-use crate::l2::L2SyncError;
+use super::class_cache::{self, ClassCache};
+use crate::l2::{spawn_compute, L2SyncError, RootVerification};
 use crate::stopwatch_end;
 use crate::utils::PerfStopwatch;
 
@@ -39,14 +46,20 @@ pub struct FetchConfig {
     pub sound: bool,
     /// The L1 contract core address
     pub l1_core_address: H160,
-    /// Whether to check the root of the state update
-    pub verify: bool,
+    /// The L1 block the L1 contract core was deployed at, where the L1 event history starts
+    pub l1_core_deployment_block: u64,
+    /// When to check the root of the state update
+    pub root_verification: RootVerification,
     /// The optional API_KEY to avoid rate limiting from the sequencer gateway.
     pub api_key: Option<String>,
     /// Polling interval
     pub sync_polling_interval: Option<Duration>,
     /// Number of blocks to sync (for testing purposes)
     pub n_blocks_to_sync: Option<u64>,
+    /// Last block to sync, the node keeps running without syncing once it is stored
+    pub sync_until: Option<u64>,
+    /// Directory of class definitions, possibly shared between nodes, to avoid fetching them again
+    pub class_cache_dir: Option<PathBuf>,
 }
 
 pub struct L2BlockAndUpdates {
@@ -59,6 +72,7 @@ pub struct L2BlockAndUpdates {
 pub async fn fetch_block_and_updates(
     block_n: u64,
     provider: Arc<SequencerGatewayProvider>,
+    class_cache: Option<&ClassCache>,
 ) -> Result<L2BlockAndUpdates, L2SyncError> {
     const MAX_RETRY: u32 = 15;
     let base_delay = Duration::from_secs(1);
@@ -66,7 +80,7 @@ pub async fn fetch_block_and_updates(
     let sw = PerfStopwatch::new();
     let (state_update, block) =
         retry(|| fetch_state_update_with_block(&provider, block_n), MAX_RETRY, base_delay).await?;
-    let class_update = fetch_class_update(&provider, &state_update, block_n).await?;
+    let class_update = fetch_class_update(&provider, &state_update, block_n, class_cache).await?;
 
     stopwatch_end!(sw, "fetching {}: {:?}", block_n);
     Ok(L2BlockAndUpdates { block_n, block, state_update, class_update })
@@ -124,11 +138,23 @@ async fn fetch_state_update_with_block(
 }
 
 /// retrieves class updates from Starknet sequencer
+///
+/// Only the classes missing from db and not claimed by an earlier block being fetched are
+/// retrieved, see [`class_cache`].
 async fn fetch_class_update(
     provider: &SequencerGatewayProvider,
     state_update: &StateUpdate,
     block_number: u64,
+    class_cache: Option<&ClassCache>,
 ) -> Result<Vec<ContractClassData>, L2SyncError> {
+    // compiled class hashes of the Sierra classes declared in this block, used to verify their CASM
+    let compiled_class_hashes: HashMap<FieldElement, FieldElement> = state_update
+        .state_diff
+        .declared_classes
+        .iter()
+        .map(|DeclaredClassItem { class_hash, compiled_class_hash }| (*class_hash, *compiled_class_hash))
+        .collect();
+
     let missing_classes: Vec<&FieldElement> = std::iter::empty()
         .chain(
             state_update
@@ -151,20 +177,26 @@ async fn fetch_class_update(
             Ok(false) => None,
             Err(e) => Some(Err(e)),
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|class_hash| class_cache::claim_class(**class_hash, block_number))
+        .collect::<Vec<_>>();
 
     let arc_provider = Arc::new(provider.clone());
 
     let mut task_set = missing_classes.into_iter().fold(JoinSet::new(), |mut set, class_hash| {
         let provider = Arc::clone(&arc_provider);
+        let class_cache = class_cache.cloned();
         let class_hash = *class_hash;
+        let compiled_class_hash = compiled_class_hashes.get(&class_hash).copied();
         // Skip what appears to be a broken Sierra class definition (quick fix)
         if class_hash
             != FieldElement::from_hex_be("0x024f092a79bdff4efa1ec86e28fa7aa7d60c89b30924ec4dab21dbfd4db73698").unwrap()
         {
             // Fetch the class definition in parallel, retrying up to 15 times for each class
             set.spawn(async move {
-                retry(|| fetch_class(class_hash, block_number, &provider), 15, Duration::from_secs(1)).await
+                fetch_and_verify_class(class_hash, compiled_class_hash, block_number, &provider, class_cache.as_ref())
+                    .await
             });
         }
         set
@@ -173,24 +205,112 @@ async fn fetch_class_update(
     // WARNING: all class downloads will abort if even a single class fails to download.
     let mut classes = vec![];
     while let Some(res) = task_set.join_next().await {
-        classes.push(res.expect("Join error")?);
+        match res.expect("Join error") {
+            Ok(class) => classes.push(class),
+            Err(e) => {
+                class_cache::release_block_classes(block_number);
+                return Err(e);
+            }
+        }
     }
 
     Ok(classes)
 }
 
-/// Downloads a class definition from the Starknet sequencer. Note that because
-/// of the current type hell this needs to be converted into a blockifier equivalent
-async fn fetch_class(
+/// Reads a class definition from the class cache, or else downloads it, and verifies it.
+/// Downloaded definitions are added to the class cache once verified.
+async fn fetch_and_verify_class(
     class_hash: FieldElement,
+    compiled_class_hash: Option<FieldElement>,
     block_number: u64,
     provider: &SequencerGatewayProvider,
-) -> Result<ContractClassData, ProviderError> {
-    let core_class = provider.get_class(BlockIdCore::Number(block_number), class_hash).await?;
-    Ok(ContractClassData {
-        hash: ClassHash(StarkFelt(class_hash.to_bytes_be())),
-        contract_class: ContractClassWrapper::try_from(core_class).expect("converting contract class"),
+    class_cache: Option<&ClassCache>,
+) -> Result<ContractClassData, L2SyncError> {
+    if let Some(class_cache) = class_cache
+        && let Some(core_class) = class_cache.get(class_hash).await
+    {
+        match spawn_compute(move || verify_class(class_hash, compiled_class_hash, core_class)).await {
+            Ok(class) => return Ok(class),
+            Err(e) => {
+                log::warn!("⚠️ Ignoring cached class {class_hash:#x}: {e}");
+                class_cache.remove(class_hash).await;
+            }
+        }
+    }
+
+    let core_class = retry(|| fetch_class(class_hash, block_number, provider), 15, Duration::from_secs(1)).await?;
+    let Some(class_cache) = class_cache else {
+        return spawn_compute(move || verify_class(class_hash, compiled_class_hash, core_class)).await;
+    };
+
+    let (class, core_class) = spawn_compute(move || {
+        verify_class(class_hash, compiled_class_hash, core_class.clone()).map(|class| (class, core_class))
     })
+    .await?;
+    if let Err(e) = class_cache.insert(class_hash, &core_class).await {
+        log::warn!("⚠️ Failed to add class {class_hash:#x} to the class cache: {e}");
+    }
+    Ok(class)
+}
+
+/// Downloads a class definition from the Starknet sequencer.
+async fn fetch_class(
+    class_hash: FieldElement,
+    block_number: u64,
+    provider: &SequencerGatewayProvider,
+) -> Result<ContractClassCore, ProviderError> {
+    provider.get_class(BlockIdCore::Number(block_number), class_hash).await
+}
+
+/// Recomputes the hash of a downloaded class definition and checks it against the hash it was
+/// requested with. Note that because of the current type hell this needs to be converted into a
+/// blockifier equivalent.
+///
+/// The CASM of Sierra classes is compiled locally, and its hash must match the compiled class
+/// hash the class was declared with.
+///
+/// Compute heavy, this should only be called in a rayon ctx
+fn verify_class(
+    class_hash: FieldElement,
+    compiled_class_hash: Option<FieldElement>,
+    core_class: ContractClassCore,
+) -> Result<ContractClassData, L2SyncError> {
+    let contract_class = match core_class {
+        ContractClassCore::Sierra(class_sierra) => {
+            let computed_class_hash = class_sierra.class_hash();
+            if computed_class_hash != class_hash {
+                return Err(L2SyncError::MismatchedClassHash { expected: class_hash, computed: computed_class_hash });
+            }
+
+            let casm_contract_class = flattened_sierra_to_casm_contract_class(&Arc::new(class_sierra.clone()))
+                .map_err(|_| L2SyncError::CasmCompilation(class_hash))?;
+            if let Some(compiled_class_hash) = compiled_class_hash {
+                let computed_compiled_class_hash = get_casm_contract_class_hash(&casm_contract_class)
+                    .map_err(|_| L2SyncError::ClassHashComputation(class_hash))?;
+                if computed_compiled_class_hash != compiled_class_hash {
+                    return Err(L2SyncError::MismatchedCompiledClassHash {
+                        expected: compiled_class_hash,
+                        computed: computed_compiled_class_hash,
+                    });
+                }
+            }
+
+            ContractClassWrapper::from_sierra_and_casm(class_sierra, casm_contract_class)
+                .map_err(|_| L2SyncError::ClassConversion(class_hash))?
+        }
+        ContractClassCore::Legacy(class_cairo) => {
+            let computed_class_hash = get_legacy_contract_class_hash(&class_cairo)
+                .map_err(|_| L2SyncError::ClassHashComputation(class_hash))?;
+            if computed_class_hash != class_hash {
+                return Err(L2SyncError::MismatchedClassHash { expected: class_hash, computed: computed_class_hash });
+            }
+
+            ContractClassWrapper::try_from(ContractClassCore::Legacy(class_cairo))
+                .map_err(|_| L2SyncError::ClassConversion(class_hash))?
+        }
+    };
+
+    Ok(ContractClassData { hash: ClassHash(StarkFelt(class_hash.to_bytes_be())), contract_class })
 }
 
 /// Check if a class is stored in the local Substrate db.
@@ -201,3 +321,108 @@ fn is_missing_class(class_hash: &FieldElement) -> Result<bool, DeoxysStorageErro
     let class_hash = ClassHash(StarkFelt(class_hash.to_bytes_be()));
     storage_handler::contract_class_data().contains(&class_hash).map(|x| !x)
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+    use mc_db::storage_handler::primitives::contract_class::ClassUpdateWrapper;
+    use mc_db::storage_updates::store_class_update;
+    use starknet_core::types::{FlattenedSierraClass, StateDiff};
+
+    use super::*;
+    use crate::tests::mock_gateway::MockGateway;
+    use crate::tests::{init_test_config, open_test_backend};
+
+    /// OpenZeppelin 0.5.1 account, a legacy class served by the mock gateway
+    const LEGACY_CLASS_HASH: &str = "0x4d07e40e93398ed3c76981e72dd1fd22557a78ce36c0515f679e27f0bb5bc5f";
+
+    /// `hello_starknet` contract of the cairo-lang 2.6.0 test data
+    const SIERRA_CLASS: &str = include_str!("../../../db/resources/hello_starknet.contract_class.json");
+
+    fn deploying(class_hash: FieldElement, block_number: u64) -> StateUpdate {
+        StateUpdate {
+            block_hash: FieldElement::from(block_number),
+            old_root: FieldElement::ZERO,
+            new_root: FieldElement::ZERO,
+            state_diff: StateDiff {
+                storage_diffs: vec![],
+                deprecated_declared_classes: vec![],
+                declared_classes: vec![],
+                deployed_contracts: vec![DeployedContractItem {
+                    address: FieldElement::from(0xacc0_0000 + block_number),
+                    class_hash,
+                }],
+                replaced_classes: vec![],
+                nonces: vec![],
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_class_update_dedupe() {
+        init_test_config();
+        open_test_backend();
+        let gateway = MockGateway::start("chain.json").await;
+        let provider = gateway.provider();
+        let dir = std::env::temp_dir().join(format!("deoxys-class-dedupe-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let class_cache = ClassCache::open(&dir).unwrap();
+        let class_hash = FieldElement::from_hex_be(LEGACY_CLASS_HASH).unwrap();
+
+        // the blocks are above the ones claimed by the other tests of the binary, which release
+        // every claim up to their own blocks
+        let state_updates = (100..=103).map(|block_number| deploying(class_hash, block_number)).collect::<Vec<_>>();
+        let class_updates = join_all(state_updates.iter().zip(100..).map(|(state_update, block_number)| {
+            fetch_class_update(&provider, state_update, block_number, Some(&class_cache))
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+        // the class is fetched once, by the earliest block deploying it
+        let fetched = class_updates.iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(fetched, [1, 0, 0, 0]);
+        assert_eq!(class_updates[0][0].hash, ClassHash(StarkFelt(class_hash.to_bytes_be())));
+        assert_eq!(gateway.class_requests(class_hash), 1);
+
+        // block 100 failed to store it: the next block reads it from the class cache
+        class_cache::release_block_classes(100);
+        let class_update = fetch_class_update(&provider, &state_updates[1], 101, Some(&class_cache)).await.unwrap();
+        assert_eq!(class_update.len(), 1);
+        assert_eq!(gateway.class_requests(class_hash), 1);
+
+        // once stored, the class is not fetched again
+        store_class_update(101, ClassUpdateWrapper(class_update)).await.unwrap();
+        class_cache::release_classes(101);
+        let class_update = fetch_class_update(&provider, &deploying(class_hash, 104), 104, None).await.unwrap();
+        assert!(class_update.is_empty());
+        assert_eq!(gateway.class_requests(class_hash), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_class_compiled_class_hash() {
+        let class_sierra: FlattenedSierraClass = serde_json::from_str(SIERRA_CLASS).unwrap();
+        let class_hash = class_sierra.class_hash();
+        let casm_contract_class = flattened_sierra_to_casm_contract_class(&Arc::new(class_sierra.clone())).unwrap();
+        let compiled_class_hash = get_casm_contract_class_hash(&casm_contract_class).unwrap();
+
+        let class =
+            verify_class(class_hash, Some(compiled_class_hash), ContractClassCore::Sierra(class_sierra.clone()))
+                .expect("Failed to verify the class");
+        assert_eq!(class.hash, ClassHash(StarkFelt(class_hash.to_bytes_be())));
+
+        // a tampered compiled class hash fails the verification of the class
+        let tampered = compiled_class_hash + FieldElement::ONE;
+        match verify_class(class_hash, Some(tampered), ContractClassCore::Sierra(class_sierra)) {
+            Err(L2SyncError::MismatchedCompiledClassHash { expected, computed }) => {
+                assert_eq!(expected, tampered);
+                assert_eq!(computed, compiled_class_hash);
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("tampered compiled class hash was accepted"),
+        }
+    }
+}
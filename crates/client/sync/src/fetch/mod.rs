@@ -7,28 +7,39 @@ use starknet_core::types::StarknetError;
 use starknet_providers::{ProviderError, SequencerGatewayProvider};
 use tokio::sync::mpsc;
 
+use self::class_cache::ClassCache;
 use self::fetchers::L2BlockAndUpdates;
 use crate::fetch::fetchers::fetch_block_and_updates;
 use crate::l2::L2SyncError;
+use crate::metrics::block_metrics::{observe_stage, BlockMetrics, SyncStage};
+use crate::stopwatch_end;
+use crate::utils::PerfStopwatch;
 
+pub mod class_cache;
 pub mod fetchers;
 
 pub async fn l2_fetch_task(
     first_block: u64,
     n_blocks_to_sync: Option<u64>,
+    sync_until: Option<u64>,
     fetch_stream_sender: mpsc::Sender<L2BlockAndUpdates>,
     provider: Arc<SequencerGatewayProvider>,
     sync_polling_interval: Option<Duration>,
+    class_cache: Option<ClassCache>,
+    block_metrics: Option<BlockMetrics>,
 ) -> anyhow::Result<()> {
     // First, catch up with the chain
 
     let mut next_block = first_block;
+    let last_block = sync_until.unwrap_or(u64::MAX);
 
     {
         // Fetch blocks and updates in parallel one time before looping
-        let fetch_stream = (first_block..).take(n_blocks_to_sync.unwrap_or(u64::MAX) as _).map(|block_n| {
+        let fetch_stream = (first_block..=last_block).take(n_blocks_to_sync.unwrap_or(u64::MAX) as _).map(|block_n| {
             let provider = Arc::clone(&provider);
-            async move { (block_n, fetch_block_and_updates(block_n, provider).await) }
+            let class_cache = class_cache.clone();
+            let block_metrics = block_metrics.clone();
+            async move { (block_n, timed_fetch(block_n, provider, class_cache.as_ref(), block_metrics.as_ref()).await) }
         });
 
         // Have 10 fetches in parallel at once, using futures Buffered
@@ -47,6 +58,11 @@ pub async fn l2_fetch_task(
         }
     };
 
+    if next_block > last_block {
+        log::info!("🏁 The sync process fetched all the blocks up to #{last_block}.");
+        return Ok(());
+    }
+
     log::info!("🥳 The sync process caught up with the tip of the chain.");
 
     if let Some(sync_polling_interval) = sync_polling_interval {
@@ -54,11 +70,12 @@ pub async fn l2_fetch_task(
 
         let mut interval = tokio::time::interval(sync_polling_interval);
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-        loop {
+        while next_block <= last_block {
             interval.tick().await;
 
-            loop {
-                match fetch_block_and_updates(next_block, Arc::clone(&provider)).await {
+            while next_block <= last_block {
+                match timed_fetch(next_block, Arc::clone(&provider), class_cache.as_ref(), block_metrics.as_ref()).await
+                {
                     Err(L2SyncError::Provider(ProviderError::StarknetError(StarknetError::BlockNotFound))) => {
                         break;
                     }
@@ -68,6 +85,21 @@ pub async fn l2_fetch_task(
                 next_block += 1;
             }
         }
+        log::info!("🏁 The sync process fetched all the blocks up to #{last_block}.");
     }
     Ok(())
 }
+
+/// Fetch a block and its updates, recording the fetch latency of the blocks found
+async fn timed_fetch(
+    block_n: u64,
+    provider: Arc<SequencerGatewayProvider>,
+    class_cache: Option<&ClassCache>,
+    block_metrics: Option<&BlockMetrics>,
+) -> Result<L2BlockAndUpdates, L2SyncError> {
+    let sw = PerfStopwatch::new();
+    let block_and_updates = fetch_block_and_updates(block_n, provider, class_cache).await?;
+    stopwatch_end!(sw, "end fetch {}: {:?}", block_n);
+    observe_stage(block_metrics, SyncStage::Fetch, sw.elapsed());
+    Ok(block_and_updates)
+}
@@ -1,25 +1,35 @@
 //! Contains the necessaries to perform an L1 verification of the state
 
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
-use ethers::contract::{abigen, EthEvent};
-use ethers::providers::{Http, Middleware, Provider};
+use ethers::contract::{EthEvent, LogMeta};
+use ethers::providers::{
+    is_local_endpoint, Http, Middleware, Provider, ProviderError, RpcError, Ws, DEFAULT_LOCAL_POLL_INTERVAL,
+};
 use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::{Address, BlockNumber as EthBlockNumber, Filter, TransactionRequest, I256, U256, U64};
-use ethers::utils::hex::decode;
-use futures::stream::StreamExt;
+use ethers::types::{Address, BlockId, Bytes, Filter, Log, RawLog, TransactionRequest, I256, U256, U64};
+use futures::stream::{BoxStream, StreamExt};
 use lazy_static::lazy_static;
+use mc_db::{l2_message_hash, DbError, DeoxysBackend, L1StateUpdateRecord};
+use mp_digest_log::find_starknet_block;
 use mp_felt::Felt252Wrapper;
+use mp_types::block::DBlockT;
 use primitive_types::H256;
 use prometheus_endpoint::prometheus::core::Number;
 use reqwest::Url;
 use serde::Deserialize;
 use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Header as HeaderT;
 use starknet_api::hash::StarkHash;
 
+use crate::event_bus::{self, Reorg, SyncEvent};
 use crate::metrics::block_metrics::BlockMetrics;
-use crate::utility::{convert_log_state_update, l1_core_address};
+use crate::utility::{
+    convert_log_message_to_l2, convert_log_state_update, l1_core_address, l1_core_deployment_block, u256_to_starkfelt,
+};
 use crate::utils::constant::LOG_STATE_UPDTATE_TOPIC;
 
 lazy_static! {
@@ -47,19 +57,129 @@ pub struct LogStateUpdate {
     pub block_hash: U256,
 }
 
+/// Starknet core LogMessageToL2 event
+#[derive(Clone, Debug, EthEvent, Deserialize)]
+pub struct LogMessageToL2 {
+    #[ethevent(indexed)]
+    pub from_address: Address,
+    #[ethevent(indexed)]
+    pub to_address: U256,
+    #[ethevent(indexed)]
+    pub selector: U256,
+    pub payload: Vec<U256>,
+    pub nonce: U256,
+    pub fee: U256,
+}
+
+/// Starknet core ConsumedMessageToL1 event
+#[derive(Clone, Debug, EthEvent, Deserialize)]
+pub struct ConsumedMessageToL1 {
+    #[ethevent(indexed)]
+    pub from_address: U256,
+    #[ethevent(indexed)]
+    pub to_address: Address,
+    pub payload: Vec<U256>,
+}
+
+/// Errors raised by the L1 sync
+#[derive(thiserror::Error, Debug)]
+pub enum L1Error {
+    #[error("no L1 endpoint provided")]
+    NoEndpoint,
+    #[error("unsupported L1 endpoint scheme `{0}`, expected http(s) or ws(s)")]
+    UnsupportedScheme(String),
+    #[error("L1 provider error: {0}")]
+    Provider(#[from] ProviderError),
+    #[error("invalid L1 response for {0}: {1}")]
+    InvalidResponse(&'static str, String),
+    #[error("invalid {0} event: {1}")]
+    InvalidEvent(String, String),
+    #[error("no LogStateUpdate event found since L1 block {0}")]
+    NoEventFound(u64),
+    #[error("L1 event stream ended")]
+    StreamEnded,
+    #[error(
+        "the L1 endpoint does not serve the state at L1 block {0}, bisecting the L1 history needs an archive node"
+    )]
+    HistoricalStateUnavailable(u64),
+    #[error("db error: {0}")]
+    Db(#[from] DbError),
+    #[error("failed to handle L1 event: {0:#}")]
+    Handler(anyhow::Error),
+}
+
+impl L1Error {
+    /// Whether the error comes from the L1 endpoint, in which case reconnecting may solve it
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self,
+            Self::Provider(_)
+                | Self::InvalidResponse(..)
+                | Self::InvalidEvent(..)
+                | Self::NoEventFound(_)
+                | Self::StreamEnded
+                | Self::HistoricalStateUnavailable(_)
+        )
+    }
+}
+
+/// Selector of the `stateBlockNumber` function of the Starknet core contract
+const STATE_BLOCK_NUMBER_SELECTOR: [u8; 4] = [0x35, 0xbe, 0xfa, 0x5d];
+/// Selector of the `stateRoot` function of the Starknet core contract
+const STATE_ROOT_SELECTOR: [u8; 4] = [0x95, 0x88, 0xec, 0xa2];
+/// Selector of the `stateBlockHash` function of the Starknet core contract
+const STATE_BLOCK_HASH_SELECTOR: [u8; 4] = [0x38, 0x2d, 0x83, 0xe3];
+
+/// Number of L1 blocks queried at once with `eth_getLogs`, as providers cap the range of a query
+const GET_LOGS_RANGE: u64 = 10_000;
+
+/// Delay before switching to the next L1 endpoint after a connection error, doubled after each
+/// consecutive failure up to [`L1_MAX_RECONNECT_DELAY`]
+const L1_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const L1_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Connection to the L1 node. WebSocket endpoints get new events pushed instead of polling for them
+#[derive(Clone)]
+enum L1Provider {
+    Http(Arc<Provider<Http>>),
+    Ws(Arc<Provider<Ws>>),
+}
+
+/// Runs the same code against the underlying provider, whatever its transport
+macro_rules! with_provider {
+    ($provider:expr, $p:ident => $body:expr) => {
+        match $provider {
+            L1Provider::Http($p) => $body,
+            L1Provider::Ws($p) => $body,
+        }
+    };
+}
+
 /// Ethereum client to interact with L1
 #[derive(Clone)]
 pub struct EthereumClient {
-    provider: Arc<Provider<Http>>,
+    provider: L1Provider,
     url: Url,
 }
 
 /// Implementation of the Ethereum client to interact with L1
 impl EthereumClient {
-    /// Create a new EthereumClient instance with the given RPC URL
-    pub async fn new(url: Url) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(url.as_str())?;
-        Ok(Self { provider: Arc::new(provider), url })
+    /// Create a new EthereumClient instance with the given RPC URL, connecting over WebSocket for
+    /// `ws` and `wss` URLs
+    pub async fn new(url: Url) -> Result<Self, L1Error> {
+        let provider = match url.scheme() {
+            "http" | "https" => {
+                let mut provider = Provider::new(Http::new(url.clone()));
+                // new events are polled for, faster on a local node
+                if is_local_endpoint(url.as_str()) {
+                    provider.set_interval(DEFAULT_LOCAL_POLL_INTERVAL);
+                }
+                L1Provider::Http(Arc::new(provider))
+            }
+            "ws" | "wss" => L1Provider::Ws(Arc::new(Provider::<Ws>::connect(url.as_str()).await?)),
+            scheme => return Err(L1Error::UnsupportedScheme(scheme.to_string())),
+        };
+        Ok(Self { provider, url })
     }
 
     /// Get current RPC URL
@@ -68,76 +188,222 @@ impl EthereumClient {
     }
 
     /// Call the Ethereum RPC endpoint with the given JSON-RPC payload
-    pub async fn call_ethereum(&self, method: &str, params: Vec<Value>) -> Result<Value, Box<dyn std::error::Error>> {
-        let response: Value = self.provider.request(method, params).await?;
-        Ok(response)
+    pub async fn call_ethereum(&self, method: &str, params: Vec<Value>) -> Result<Value, L1Error> {
+        Ok(with_provider!(&self.provider, provider => provider.request(method, params).await)?)
+    }
+
+    /// Retrieves the logs matching the given filter
+    pub async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, L1Error> {
+        Ok(with_provider!(&self.provider, provider => provider.get_logs(filter).await)?)
     }
 
     /// Retrieves the latest Ethereum block number
-    pub async fn get_latest_block_number(&self) -> anyhow::Result<U64> {
-        let block_number = self.provider.get_block_number().await?;
-        Ok(block_number.as_u64().into())
+    pub async fn get_latest_block_number(&self) -> Result<U64, L1Error> {
+        Ok(with_provider!(&self.provider, provider => provider.get_block_number().await)?)
+    }
+
+    /// Get the block number of the last occurrence of a given event, searching the L1 history
+    /// backward from the latest block down to the deployment of the core contract
+    pub async fn get_last_event_block_number(&self) -> Result<u64, L1Error> {
+        let topic = H256::from_slice(&hex::decode(&LOG_STATE_UPDTATE_TOPIC[2..]).expect("valid topic constant"));
+        let filter = Filter::new().address(vec![l1_core_address()]).topic0(topic);
+
+        let latest_block = self.get_latest_block_number().await?.as_u64();
+        for (from_block, to_block) in backward_log_ranges(l1_core_deployment_block(), latest_block) {
+            let logs = self.get_logs(&filter.clone().from_block(from_block).to_block(to_block)).await?;
+
+            if let Some(last_log) = logs.last() {
+                return last_log
+                    .block_number
+                    .map(|block_number| block_number.as_u64())
+                    .ok_or(L1Error::InvalidResponse("LogStateUpdate event", "no block number in log".to_string()));
+            }
+        }
+
+        Err(L1Error::NoEventFound(l1_core_deployment_block()))
+    }
+
+    /// Find the LogStateUpdate event verifying the given Starknet block
+    ///
+    /// Only the L1 block at which the core contract first verified this block or a later one is
+    /// searched for the event: `None` means the block was never verified on L1 on its own.
+    pub async fn find_state_update(&self, block_number: u64) -> Result<Option<L1StateUpdateRecord>, L1Error> {
+        let Some(l1_block) = self.first_l1_block_verifying(block_number).await? else {
+            return Ok(None);
+        };
+        let state_updates = self.state_updates_between(l1_block, l1_block).await?;
+        Ok(state_updates.into_iter().find(|state_update| state_update.block_number == block_number))
+    }
+
+    /// Find the last LogStateUpdate event verifying a Starknet block before the given one, which
+    /// ends the batch of blocks preceding the one verified with the given block
+    ///
+    /// `None` means no block before the given one was verified on L1.
+    pub async fn find_previous_state_update(&self, block_number: u64) -> Result<Option<L1StateUpdateRecord>, L1Error> {
+        let to_block = match self.first_l1_block_verifying(block_number).await? {
+            Some(l1_block) => l1_block,
+            None => self.get_latest_block_number().await?.as_u64(),
+        };
+        for (from_block, to_block) in backward_log_ranges(l1_core_deployment_block(), to_block) {
+            let state_updates = self.state_updates_between(from_block, to_block).await?;
+            if let Some(state_update) =
+                state_updates.into_iter().rev().find(|state_update| state_update.block_number < block_number)
+            {
+                return Ok(Some(state_update));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the first L1 block at which the core contract verified the given Starknet block or a
+    /// later one
+    ///
+    /// The verified block only increases, so the L1 history is bisected on the state of the core
+    /// contract, which needs an archive node serving historical state. Other endpoints fall back to
+    /// scanning the LogStateUpdate events. `None` means the block has not been verified yet.
+    async fn first_l1_block_verifying(&self, block_number: u64) -> Result<Option<u64>, L1Error> {
+        match self.bisect_first_l1_block_verifying(block_number).await {
+            Err(e @ L1Error::HistoricalStateUnavailable(_)) => {
+                log::warn!("⚠️ {e}, scanning the LogStateUpdate events instead");
+                self.scan_first_l1_block_verifying(block_number).await
+            }
+            result => result,
+        }
     }
 
-    /// Get the block number of the last occurrence of a given event.
-    pub async fn get_last_event_block_number(&self) -> anyhow::Result<u64> {
-        let topic = H256::from_slice(&hex::decode(&LOG_STATE_UPDTATE_TOPIC[2..])?);
-        let address = l1_core_address();
-        let latest_block = self.get_latest_block_number().await.expect("Failed to retrieve latest block number");
+    async fn bisect_first_l1_block_verifying(&self, block_number: u64) -> Result<Option<u64>, L1Error> {
+        let verifies = |verified: Option<u64>| verified.is_some_and(|verified| verified >= block_number);
+
+        let (mut low, mut high) = (l1_core_deployment_block(), self.get_latest_block_number().await?.as_u64());
+        if !verifies(self.state_block_number_at(high).await?) {
+            return Ok(None);
+        }
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if verifies(self.state_block_number_at(middle).await?) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        Ok(Some(high))
+    }
+
+    /// Scans the LogStateUpdate events backward from the latest L1 block, down to the last one
+    /// verifying a block before the given one
+    async fn scan_first_l1_block_verifying(&self, block_number: u64) -> Result<Option<u64>, L1Error> {
+        let latest_block = self.get_latest_block_number().await?.as_u64();
+        let mut first_verifying = None;
+        for (from_block, to_block) in backward_log_ranges(l1_core_deployment_block(), latest_block) {
+            for state_update in self.state_updates_between(from_block, to_block).await?.into_iter().rev() {
+                if state_update.block_number < block_number {
+                    return Ok(first_verifying);
+                }
+                first_verifying = Some(state_update.l1_block_number);
+            }
+        }
+
+        Ok(first_verifying)
+    }
+
+    /// Get the last Starknet block number verified on L1 at the given L1 block, `None` if no block
+    /// was verified yet
+    async fn state_block_number_at(&self, l1_block: u64) -> Result<Option<u64>, L1Error> {
+        let result = match self.call_core_contract_at(STATE_BLOCK_NUMBER_SELECTOR, Some(l1_block)).await {
+            Err(L1Error::Provider(e)) if is_missing_historical_state(&e) => {
+                return Err(L1Error::HistoricalStateUnavailable(l1_block));
+            }
+            result => result?,
+        };
+        if result.len() != 32 {
+            return Err(L1Error::InvalidResponse(
+                "stateBlockNumber",
+                format!("expected 32 bytes, got {}", result.len()),
+            ));
+        }
+
+        let block_number = I256::from_raw(U256::from_big_endian(&result));
+        if block_number.is_negative() {
+            return Ok(None);
+        }
+        block_number
+            .into_raw()
+            .try_into()
+            .map(Some)
+            .map_err(|_| L1Error::InvalidResponse("stateBlockNumber", format!("{result} does not fit in a u64")))
+    }
 
-        // Assuming an avg Block time of 15sec we check for a LogStateUpdate occurence in the last ~24h
+    /// The LogStateUpdate events emitted between two L1 blocks, inclusive
+    async fn state_updates_between(&self, from_block: u64, to_block: u64) -> Result<Vec<L1StateUpdateRecord>, L1Error> {
         let filter = Filter::new()
-            .from_block(latest_block - 6000)
-            .to_block(EthBlockNumber::Latest)
-            .address(vec![address])
-            .topic0(topic);
-
-        let logs = self.provider.get_logs(&filter).await?;
-
-        if let Some(last_log) = logs.last() {
-            let last_block = last_log.block_number.context("no block number in log")?;
-            Ok(last_block.as_u64())
-        } else {
-            bail!("no event found")
+            .address(vec![l1_core_address()])
+            .topic0(LogStateUpdate::signature())
+            .from_block(from_block)
+            .to_block(to_block);
+
+        let mut state_updates = vec![];
+        for log in self.get_logs(&filter).await? {
+            let (event, meta) = decode_event::<LogStateUpdate>(log)?;
+            let state_update = convert_log_state_update(event)
+                .map_err(|e| L1Error::InvalidEvent(LogStateUpdate::name().into_owned(), e.to_string()))?;
+
+            state_updates.push(L1StateUpdateRecord {
+                l1_block_number: meta.block_number.as_u64(),
+                l1_transaction_hash: meta.transaction_hash,
+                block_number: state_update.block_number,
+                global_root: state_update.global_root,
+                block_hash: state_update.block_hash,
+            });
         }
+
+        Ok(state_updates)
     }
 
-    /// Get the last Starknet block number verified on L1
-    pub async fn get_last_block_number(&self) -> anyhow::Result<u64> {
-        let data = decode("35befa5d")?;
-        let to: Address = l1_core_address();
-        let tx_request = TransactionRequest::new().to(to).data(data);
+    /// Call a view function of the Starknet core contract
+    async fn call_core_contract(&self, selector: [u8; 4]) -> Result<Bytes, L1Error> {
+        self.call_core_contract_at(selector, None).await
+    }
+
+    /// Call a view function of the Starknet core contract at the given L1 block, the latest one by
+    /// default
+    async fn call_core_contract_at(&self, selector: [u8; 4], l1_block: Option<u64>) -> Result<Bytes, L1Error> {
+        let tx_request = TransactionRequest::new().to(l1_core_address()).data(selector.to_vec());
         let tx = TypedTransaction::Legacy(tx_request);
-        let result = self.provider.call(&tx, None).await.expect("Failed to get last block number");
-        let result_str = result.to_string();
-        let hex_str = result_str.trim_start_matches("Bytes(0x").trim_end_matches(')').trim_start_matches("0x");
+        let block = l1_block.map(|l1_block| BlockId::Number(l1_block.into()));
+        Ok(with_provider!(&self.provider, provider => provider.call(&tx, block).await)?)
+    }
+
+    /// Get the last Starknet block number verified on L1
+    pub async fn get_last_block_number(&self) -> Result<u64, L1Error> {
+        let result = self.call_core_contract(STATE_BLOCK_NUMBER_SELECTOR).await?;
+        if result.len() != 32 {
+            return Err(L1Error::InvalidResponse(
+                "stateBlockNumber",
+                format!("expected 32 bytes, got {}", result.len()),
+            ));
+        }
 
-        let block_number = u64::from_str_radix(hex_str, 16).expect("Failed to parse block number");
-        Ok(block_number)
+        U256::from_big_endian(&result)
+            .try_into()
+            .map_err(|_| L1Error::InvalidResponse("stateBlockNumber", format!("{result} does not fit in a u64")))
     }
 
     /// Get the last Starknet state root verified on L1
-    pub async fn get_last_state_root(&self) -> Result<StarkHash> {
-        let data = decode("9588eca2")?;
-        let to: Address = l1_core_address();
-        let tx_request = TransactionRequest::new().to(to).data(data);
-        let tx = TypedTransaction::Legacy(tx_request);
-        let result = self.provider.call(&tx, None).await.expect("Failed to get last state root");
-        Ok(StarkHash::from(Felt252Wrapper::from_hex_be(&result.to_string()).expect("Failed to parse state root")))
+    pub async fn get_last_state_root(&self) -> Result<StarkHash, L1Error> {
+        let result = self.call_core_contract(STATE_ROOT_SELECTOR).await?;
+        felt_from_response("stateRoot", &result)
     }
 
     /// Get the last Starknet block hash verified on L1
-    pub async fn get_last_block_hash(&self) -> Result<StarkHash> {
-        let data = decode("0x382d83e3")?;
-        let to: Address = l1_core_address();
-        let tx_request = TransactionRequest::new().to(to).data(data);
-        let tx = TypedTransaction::Legacy(tx_request);
-        let result = self.provider.call(&tx, None).await.expect("Failed to get last block hash");
-        Ok(StarkHash::from(Felt252Wrapper::from_hex_be(&result.to_string()).expect("Failed to parse block hash")))
+    pub async fn get_last_block_hash(&self) -> Result<StarkHash, L1Error> {
+        let result = self.call_core_contract(STATE_BLOCK_HASH_SELECTOR).await?;
+        felt_from_response("stateBlockHash", &result)
     }
 
     /// Get the last Starknet state update verified on the L1
-    pub async fn get_initial_state(client: &EthereumClient) -> anyhow::Result<L1StateUpdate> {
+    pub async fn get_initial_state(client: &EthereumClient) -> Result<L1StateUpdate, L1Error> {
         let block_number = client.get_last_block_number().await?;
         let block_hash = client.get_last_block_hash().await?;
         let global_root = client.get_last_state_root().await?;
@@ -151,29 +417,318 @@ impl EthereumClient {
         &self,
         start_block: u64,
         block_metrics: Option<BlockMetrics>,
-    ) -> anyhow::Result<()> {
-        let client = self.provider.clone();
-        let address: Address = l1_core_address();
-        abigen!(
-            StarknetCore,
-            "crates/client/sync/src/utils/abis/starknet_core.json",
-            event_derives(serde::Deserialize, serde::Serialize)
+    ) -> Result<(), L1Error> {
+        self.listen_to_event(
+            start_block,
+            |log: LogStateUpdate, meta| store_log_state_update(log, meta, block_metrics.clone()),
+            |log: LogStateUpdate, meta| remove_log_state_update(log, meta, block_metrics.clone()),
+        )
+        .await
+    }
+
+    /// Subscribes to the LogMessageToL2 event from the Starknet core contract and index the
+    /// messages sent to L2
+    pub async fn listen_and_index_messages(&self, start_block: u64) -> Result<(), L1Error> {
+        self.listen_to_event(start_block, store_log_message_to_l2, remove_log_message_to_l2).await
+    }
+
+    /// Subscribes to the ConsumedMessageToL1 event from the Starknet core contract and index the
+    /// messages consumed on L1
+    pub async fn listen_and_index_consumed_messages(&self, start_block: u64) -> Result<(), L1Error> {
+        self.listen_to_event(start_block, store_consumed_message_to_l1, remove_consumed_message_to_l1).await
+    }
+
+    /// Calls `on_event` for each event of type `E` emitted by the Starknet core contract since
+    /// `start_block`, then for each new one, and `on_removed` for each event dropped by an L1 reorg
+    ///
+    /// Only returns on error: an event stream ending is reported as [`L1Error::StreamEnded`].
+    async fn listen_to_event<E, F, R>(
+        &self,
+        start_block: u64,
+        mut on_event: F,
+        mut on_removed: R,
+    ) -> Result<(), L1Error>
+    where
+        E: EthEvent,
+        F: FnMut(E, LogMeta) -> Result<()>,
+        R: FnMut(E, LogMeta) -> Result<()>,
+    {
+        let filter = Filter::new().address(l1_core_address()).topic0(E::signature());
+
+        // Watch for new events before catching up on the ones emitted since `start_block`, so that the
+        // stored history has no gaps
+        let log_stream = match &self.provider {
+            L1Provider::Http(provider) => provider.watch(&filter).await?.boxed(),
+            L1Provider::Ws(provider) => provider.subscribe_logs(&filter).await?.boxed(),
+        };
+
+        let latest_block = self.get_latest_block_number().await?.as_u64();
+        for (from_block, to_block) in log_ranges(start_block, latest_block) {
+            for log in self.get_logs(&filter.clone().from_block(from_block).to_block(to_block)).await? {
+                let (event, meta) = decode_event(log)?;
+                on_event(event, meta).map_err(L1Error::Handler)?;
+            }
+        }
+
+        forward_events(log_stream, latest_block, &mut on_event, &mut on_removed).await
+    }
+}
+
+/// Splits the L1 blocks from `from_block` to `to_block` into ranges of at most [`GET_LOGS_RANGE`]
+/// blocks, in increasing order
+fn log_ranges(from_block: u64, to_block: u64) -> impl Iterator<Item = (u64, u64)> {
+    (from_block..=to_block)
+        .step_by(GET_LOGS_RANGE as usize)
+        .map(move |from_block| (from_block, to_block.min(from_block + GET_LOGS_RANGE - 1)))
+}
+
+/// Whether an `eth_call` at a past L1 block failed because the endpoint pruned that state, as
+/// full nodes only keep the state of the latest blocks
+fn is_missing_historical_state(error: &ProviderError) -> bool {
+    const PRUNED_STATE_MESSAGES: [&str; 4] =
+        ["missing trie node", "header not found", "historical state", "state is not available"];
+
+    error.as_error_response().is_some_and(|response| {
+        let message = response.message.to_lowercase();
+        PRUNED_STATE_MESSAGES.iter().any(|pruned_state| message.contains(pruned_state))
+    })
+}
+
+/// Splits the L1 blocks from `from_block` to `to_block` into ranges of at most [`GET_LOGS_RANGE`]
+/// blocks, latest first
+fn backward_log_ranges(from_block: u64, to_block: u64) -> impl Iterator<Item = (u64, u64)> {
+    let mut next_to_block = (from_block <= to_block).then_some(to_block);
+    std::iter::from_fn(move || {
+        let to_block = next_to_block?;
+        let range_start = to_block.saturating_sub(GET_LOGS_RANGE - 1).max(from_block);
+        next_to_block = (range_start > from_block).then(|| range_start - 1);
+        Some((range_start, to_block))
+    })
+}
+
+/// Calls `on_event` for each log of the stream emitted after `after_block`, and `on_removed` for
+/// each log removed by an L1 reorg, until the stream ends
+async fn forward_events<E, F, R>(
+    mut log_stream: BoxStream<'_, Log>,
+    after_block: u64,
+    on_event: &mut F,
+    on_removed: &mut R,
+) -> Result<(), L1Error>
+where
+    E: EthEvent,
+    F: FnMut(E, LogMeta) -> Result<()>,
+    R: FnMut(E, LogMeta) -> Result<()>,
+{
+    // logs up to `after_block` were already handled when catching up, unless they come from a reorg
+    let mut caught_up = false;
+
+    while let Some(log) = log_stream.next().await {
+        // On L1 reorgs, the logs of the dropped blocks are sent again flagged as removed, followed by
+        // the logs of the new blocks which overwrite what was stored from the dropped ones
+        if log.removed == Some(true) {
+            log::warn!("↩️ Removing {} event dropped by an L1 reorg", E::name());
+            event_bus::publish(SyncEvent::ReorgDetected(Reorg::L1 {
+                event: E::name().into_owned(),
+                l1_block_number: log.block_number.map(|block_number| block_number.as_u64()),
+            }));
+            caught_up = true;
+
+            let (event, meta) = decode_event(log)?;
+            on_removed(event, meta).map_err(L1Error::Handler)?;
+            continue;
+        }
+        if !caught_up && log.block_number.is_some_and(|block_number| block_number.as_u64() <= after_block) {
+            continue;
+        }
+        caught_up = true;
+
+        let (event, meta) = decode_event(log)?;
+        on_event(event, meta).map_err(L1Error::Handler)?;
+    }
+
+    Err(L1Error::StreamEnded)
+}
+
+/// Decode an event emitted by the Starknet core contract along with the position of its log
+pub(crate) fn decode_event<E: EthEvent>(log: Log) -> Result<(E, LogMeta), L1Error> {
+    if log.block_number.is_none()
+        || log.block_hash.is_none()
+        || log.transaction_hash.is_none()
+        || log.transaction_index.is_none()
+        || log.log_index.is_none()
+    {
+        return Err(L1Error::InvalidEvent(E::name().into_owned(), "log is missing its position".to_string()));
+    }
+
+    let meta = LogMeta::from(&log);
+    let event = <E as EthEvent>::decode_log(&RawLog::from(log))
+        .map_err(|e| L1Error::InvalidEvent(E::name().into_owned(), e.to_string()))?;
+    Ok((event, meta))
+}
+
+/// Parse a felt returned by a view function of the Starknet core contract
+fn felt_from_response(function: &'static str, result: &[u8]) -> Result<StarkHash, L1Error> {
+    Felt252Wrapper::try_from(result)
+        .map(StarkHash::from)
+        .map_err(|e| L1Error::InvalidResponse(function, format!("0x{}: {e}", hex::encode(result))))
+}
+
+/// Hash of the message consumed by a ConsumedMessageToL1 event
+fn consumed_message_hash(log: ConsumedMessageToL1) -> Result<H256> {
+    let from_address = u256_to_starkfelt(log.from_address).context("formatting ConsumedMessageToL1 event")?;
+    let payload = log.payload.into_iter().map(u256_to_starkfelt).collect::<Result<Vec<_>>>()?;
+    Ok(l2_message_hash(&from_address, &log.to_address, &payload))
+}
+
+/// Index a ConsumedMessageToL1 event in the messaging db
+fn store_consumed_message_to_l1(log: ConsumedMessageToL1, meta: LogMeta) -> Result<()> {
+    let message_hash = consumed_message_hash(log)?;
+
+    DeoxysBackend::messaging()
+        .write_l1_consumed_message(
+            message_hash,
+            meta.block_number.as_u64(),
+            meta.transaction_hash,
+            meta.log_index.as_u64(),
+        )
+        .context("storing consumed L2 message")?;
+    Ok(())
+}
+
+/// Remove a ConsumedMessageToL1 event dropped by an L1 reorg from the messaging db
+fn remove_consumed_message_to_l1(log: ConsumedMessageToL1, meta: LogMeta) -> Result<()> {
+    let message_hash = consumed_message_hash(log)?;
+
+    DeoxysBackend::messaging()
+        .remove_l1_consumed_message(
+            message_hash,
+            meta.block_number.as_u64(),
+            meta.transaction_hash,
+            meta.log_index.as_u64(),
+        )
+        .context("removing consumed L2 message")?;
+    Ok(())
+}
+
+/// Index a LogMessageToL2 event in the messaging db
+fn store_log_message_to_l2(log: LogMessageToL2, meta: LogMeta) -> Result<()> {
+    let message = convert_log_message_to_l2(log, meta).context("formatting LogMessageToL2 event")?;
+    log::debug!("📨 Indexed L1 message #{} sent to {}", message.nonce, message.to_address);
+
+    DeoxysBackend::messaging().write_l1_message(&message).context("storing L1 message")?;
+    Ok(())
+}
+
+/// Remove a LogMessageToL2 event dropped by an L1 reorg from the messaging db
+fn remove_log_message_to_l2(log: LogMessageToL2, meta: LogMeta) -> Result<()> {
+    let message = convert_log_message_to_l2(log, meta).context("formatting LogMessageToL2 event")?;
+    log::debug!("↩️ Removed L1 message #{} sent to {}", message.nonce, message.to_address);
+
+    DeoxysBackend::messaging()
+        .remove_l1_message(message.nonce, message.l1_transaction_hash)
+        .context("removing L1 message")?;
+    Ok(())
+}
+
+/// Persist a LogStateUpdate event in the l1 db and make it the latest L1 state
+fn store_log_state_update(log: LogStateUpdate, meta: LogMeta, block_metrics: Option<BlockMetrics>) -> Result<()> {
+    let state_update = convert_log_state_update(log).context("formatting event into an L1StateUpdate")?;
+
+    DeoxysBackend::l1()
+        .write_state_update(&L1StateUpdateRecord {
+            l1_block_number: meta.block_number.as_u64(),
+            l1_transaction_hash: meta.transaction_hash,
+            block_number: state_update.block_number,
+            global_root: state_update.global_root,
+            block_hash: state_update.block_hash,
+        })
+        .context("storing L1 state update")?;
+
+    update_l1(state_update, block_metrics);
+    Ok(())
+}
+
+/// Remove a LogStateUpdate event dropped by an L1 reorg from the l1 db, falling back to the
+/// previous stored state as the latest L1 state
+fn remove_log_state_update(log: LogStateUpdate, meta: LogMeta, block_metrics: Option<BlockMetrics>) -> Result<()> {
+    let state_update = convert_log_state_update(log).context("formatting event into an L1StateUpdate")?;
+
+    DeoxysBackend::l1()
+        .remove_state_update(state_update.block_number, meta.transaction_hash)
+        .context("removing L1 state update")?;
+
+    if let Some(last_state_update) = DeoxysBackend::l1().last_state_update().context("reading last L1 state update")? {
+        update_l1(
+            L1StateUpdate {
+                block_number: last_state_update.block_number,
+                global_root: last_state_update.global_root,
+                block_hash: last_state_update.block_hash,
+            },
+            block_metrics,
         );
-        let contract = StarknetCore::new(address, client);
+    }
+    Ok(())
+}
 
-        let event_filter = contract.event::<LogStateUpdate>().from_block(start_block).to_block(EthBlockNumber::Latest);
+/// Check the L1 state updates stored in db against the local chain
+///
+/// Every state update verifying a block which has already been synced must match the block hash
+/// and global state root stored for that block. A mismatch means the local db cannot be trusted
+/// and the node should not be started on top of it. The blocks of a state imported from a
+/// checkpoint, either the one given or the one already accepted, were not synced by this node so
+/// only their hash is checked.
+pub fn verify_l1_history<C>(client: &C, checkpoint_block: Option<u64>) -> Result<()>
+where
+    C: HeaderBackend<DBlockT>,
+{
+    let mapping = DeoxysBackend::mapping();
+    let checkpoint_block =
+        checkpoint_block.max(DeoxysBackend::meta().checkpoint_block().context("reading checkpoint block")?);
 
-        let mut event_stream = event_filter.stream().await.context("initiatializing event stream")?;
+    for state_update in DeoxysBackend::l1().state_updates().context("reading L1 state updates")? {
+        let block_number = state_update.block_number;
+        let Some(block_hash) =
+            mapping.starknet_block_hash_from_block_number(block_number).context("reading block hash")?
+        else {
+            // the block has not been synced yet
+            continue;
+        };
 
-        while let Some(event_result) = event_stream.next().await {
-            let log = event_result.context("listening for events")?;
-            let format_event =
-                convert_log_state_update(log.clone()).context("formatting event into an L1StateUpdate")?;
-            update_l1(format_event, block_metrics.clone());
+        if block_hash != state_update.block_hash {
+            bail!(
+                "L1 state update at block #{block_number} has hash {}, but the local block hash is {}",
+                state_update.block_hash,
+                block_hash
+            );
+        }
+        if checkpoint_block.is_some_and(|checkpoint_block| block_number <= checkpoint_block) {
+            continue;
         }
 
-        Ok(())
+        let substrate_block_hash = mapping
+            .substrate_block_hash(block_hash)
+            .context("reading substrate block hash")?
+            .and_then(|hashes| hashes.last().copied())
+            .with_context(|| format!("no substrate block found for block #{block_number}"))?;
+        let header = client
+            .header(substrate_block_hash)
+            .context("reading substrate header")?
+            .with_context(|| format!("no substrate header found for block #{block_number}"))?;
+        let global_root = find_starknet_block(header.digest())
+            .with_context(|| format!("reading starknet block #{block_number}"))?
+            .header()
+            .global_state_root;
+
+        if global_root != state_update.global_root {
+            bail!(
+                "L1 state update at block #{block_number} has state root {}, but the local state root is {}",
+                state_update.global_root,
+                global_root
+            );
+        }
     }
+
+    Ok(())
 }
 
 /// Update the L1 state with the latest data
@@ -194,6 +749,8 @@ pub fn update_l1(state_update: L1StateUpdate, block_metrics: Option<BlockMetrics
         let mut new_state_update = last_state_update.write().expect("poisoned lock");
         *new_state_update = state_update.clone();
     }
+
+    event_bus::publish(SyncEvent::L1StateVerified(state_update));
 }
 
 // /// Verify the L1 state with the latest data
@@ -231,107 +788,357 @@ pub fn update_l1(state_update: L1StateUpdate, block_metrics: Option<BlockMetrics
 // }
 
 /// Syncronize with the L1 latest state updates
-pub async fn sync(l1_url: Url, block_metrics: Option<BlockMetrics>) -> anyhow::Result<()> {
-    let client = EthereumClient::new(l1_url).await.context("creating ethereum client")?;
+///
+/// The endpoints are used in order: on a connection error, the sync switches to the next one and
+/// resumes from the last stored events. This only returns on errors which are not related to the
+/// L1 connection, such as db errors.
+pub async fn sync(l1_urls: Vec<Url>, block_metrics: Option<BlockMetrics>) -> anyhow::Result<()> {
+    if l1_urls.is_empty() {
+        return Err(L1Error::NoEndpoint.into());
+    }
+    // fail early rather than when falling back to a misconfigured endpoint
+    if let Some(l1_url) = l1_urls.iter().find(|l1_url| !matches!(l1_url.scheme(), "http" | "https" | "ws" | "wss")) {
+        return Err(L1Error::UnsupportedScheme(l1_url.scheme().to_string()).into());
+    }
+
+    // Serve the last stored verified state until L1 answers
+    if let Some(state_update) = DeoxysBackend::l1().last_state_update().context("reading last L1 state update")? {
+        update_l1(
+            L1StateUpdate {
+                block_number: state_update.block_number,
+                global_root: state_update.global_root,
+                block_hash: state_update.block_hash,
+            },
+            block_metrics.clone(),
+        );
+    }
+
+    let mut endpoint_index = 0;
+    let mut reconnect_delay = L1_RECONNECT_DELAY;
+    loop {
+        let l1_url = &l1_urls[endpoint_index];
+        // only log the host, as the url may contain an api key
+        let host = l1_url.host_str().unwrap_or_default().to_string();
+        let started_at = Instant::now();
+
+        let e = match sync_with_endpoint(l1_url.clone(), block_metrics.clone()).await {
+            Ok(()) => L1Error::StreamEnded,
+            Err(e) => e,
+        };
+        if !e.is_connection_error() {
+            return Err(e).context("syncing L1 state");
+        }
+
+        // the connection was healthy for a while, this is not a consecutive failure
+        if started_at.elapsed() > L1_MAX_RECONNECT_DELAY {
+            reconnect_delay = L1_RECONNECT_DELAY;
+        }
+        endpoint_index = (endpoint_index + 1) % l1_urls.len();
+        log::warn!(
+            "⚠️ Lost connection to L1 endpoint {host}: {e}, switching to endpoint #{endpoint_index} in {}s",
+            reconnect_delay.as_secs()
+        );
+        tokio::time::sleep(reconnect_delay).await;
+        reconnect_delay = (reconnect_delay * 2).min(L1_MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Follow the Starknet core contract events through a single L1 endpoint, until an error occurs
+async fn sync_with_endpoint(l1_url: Url, block_metrics: Option<BlockMetrics>) -> Result<(), L1Error> {
+    let client = EthereumClient::new(l1_url).await?;
 
     log::info!("🚀 Subscribed to L1 state verification");
 
     // Get and store the latest verified state
-    let initial_state = EthereumClient::get_initial_state(&client).await.context("getting initial ethereum state")?;
+    let initial_state = EthereumClient::get_initial_state(&client).await?;
     update_l1(initial_state, block_metrics.clone());
 
-    // Listen to LogStateUpdate (0x77552641) update and send changes continusly
-    let start_block =
-        EthereumClient::get_last_event_block_number(&client).await.context("retrieving the last event block number")?;
-    EthereumClient::listen_and_update_state(&client, start_block, block_metrics)
-        .await
-        .context("subscribing to the LogStateUpdate event")?;
+    // Listen to LogStateUpdate (0x77552641) update and send changes continusly, resuming from the last
+    // stored event if any
+    let start_block = match DeoxysBackend::l1().last_state_update()? {
+        Some(state_update) => state_update.l1_block_number + 1,
+        None => EthereumClient::get_last_event_block_number(&client).await?,
+    };
+
+    // Index messaging events, resuming from the last indexed L1 to L2 message if any, else from the
+    // deployment of the core contract so that the fee paid for every message is known
+    let messages_start_block = match DeoxysBackend::messaging().last_l1_message()? {
+        Some(message) => message.l1_block_number + 1,
+        None => l1_core_deployment_block(),
+    };
+    // Consumed messages have their own cursor, as they are emitted independently from the messages
+    // sent to L2. The last indexed block is indexed again, in case only part of its events were.
+    let consumed_messages_start_block =
+        DeoxysBackend::messaging().l1_consumed_messages_cursor()?.unwrap_or_else(l1_core_deployment_block);
+
+    tokio::try_join!(
+        EthereumClient::listen_and_update_state(&client, start_block, block_metrics),
+        EthereumClient::listen_and_index_messages(&client, messages_start_block),
+        EthereumClient::listen_and_index_consumed_messages(&client, consumed_messages_start_block),
+    )?;
 
     Ok(())
 }
 
 #[cfg(test)]
 mod l1_sync_tests {
-    use ethers::contract::EthEvent;
-    use ethers::core::types::*;
-    use ethers::prelude::*;
-    use ethers::providers::Provider;
-    use tokio;
-    use url::Url;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 
     use super::*;
-    use crate::l1::EthereumClient;
+    use crate::tests::mock_l1::{state_update_log, MockL1Node};
+    use crate::tests::{init_test_config, open_test_backend};
+
+    fn felt(value: u64) -> StarkHash {
+        StarkHash::from(value)
+    }
+
+    #[tokio::test]
+    async fn test_initial_state() {
+        init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.set_core_contract_state(42, felt(1), felt(2));
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+        let initial_state = EthereumClient::get_initial_state(&client).await.expect("Failed to get initial state");
+
+        assert_eq!(initial_state.block_number, 42);
+        assert_eq!(initial_state.global_root, felt(1));
+        assert_eq!(initial_state.block_hash, felt(2));
+    }
+
+    #[tokio::test]
+    async fn test_initial_state_not_a_felt() {
+        init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.set_raw_core_contract_state([0xff; 32], [0xff; 32]);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+        let error = client.get_last_state_root().await.unwrap_err();
+
+        assert!(matches!(error, L1Error::InvalidResponse("stateRoot", _)), "unexpected error: {error}");
+        assert!(error.is_connection_error());
+    }
+
+    #[tokio::test]
+    async fn test_failing_endpoint() {
+        init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.set_failing(true);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+        let error = EthereumClient::get_initial_state(&client).await.unwrap_err();
 
-    #[derive(Clone, Debug, EthEvent)]
-    pub struct Transfer {
-        #[ethevent(indexed)]
-        pub from: Address,
-        #[ethevent(indexed)]
-        pub to: Address,
-        pub tokens: U256,
+        assert!(matches!(error, L1Error::Provider(_)), "unexpected error: {error}");
+        assert!(error.is_connection_error());
     }
 
-    pub mod eth_rpc {
-        pub const MAINNET: &str = "<ENTER-YOUR-RPC-URL-HERE>";
+    #[tokio::test]
+    async fn test_unsupported_scheme() {
+        let error = EthereumClient::new("ftp://127.0.0.1".parse().unwrap()).await.err().unwrap();
+
+        assert!(matches!(error, L1Error::UnsupportedScheme(ref scheme) if scheme == "ftp"));
+        assert!(!error.is_connection_error());
     }
 
     #[tokio::test]
     async fn test_starting_block() {
-        let url = Url::parse(eth_rpc::MAINNET).expect("Failed to parse URL");
-        let client = EthereumClient::new(url).await.expect("Failed to create EthereumClient");
+        let core_address = init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.set_block_number(100);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+        assert!(matches!(client.get_last_event_block_number().await, Err(L1Error::NoEventFound(_))));
 
-        let start_block =
-            EthereumClient::get_last_event_block_number(&client).await.expect("Failed to get last event block number");
-        println!("The latest emission of the LogStateUpdate event was on block: {:?}", start_block);
+        mock.push_log(state_update_log(core_address, 90, 1, felt(1), felt(11)));
+        mock.push_log(state_update_log(core_address, 95, 2, felt(2), felt(12)));
+
+        let start_block = client.get_last_event_block_number().await.expect("Failed to get last event block number");
+        assert_eq!(start_block, 95);
     }
 
     #[tokio::test]
-    async fn test_initial_state() {
-        let url = Url::parse(eth_rpc::MAINNET).expect("Failed to parse URL");
+    async fn test_starting_block_paged() {
+        let core_address = init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.set_max_logs_range(GET_LOGS_RANGE);
+        mock.push_log(state_update_log(core_address, 5, 1, felt(1), felt(11)));
+        mock.set_block_number(30_000);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+
+        // found after searching back through several ranges, down to the deployment block
+        let start_block = client.get_last_event_block_number().await.expect("Failed to get last event block number");
+        assert_eq!(start_block, 5);
+    }
+
+    #[test]
+    fn test_log_ranges() {
+        assert_eq!(log_ranges(5, 4).count(), 0);
+        assert_eq!(log_ranges(5, 5).collect::<Vec<_>>(), vec![(5, 5)]);
+        assert_eq!(log_ranges(0, 25_000).collect::<Vec<_>>(), vec![(0, 9_999), (10_000, 19_999), (20_000, 25_000)]);
+
+        assert_eq!(backward_log_ranges(5, 4).count(), 0);
+        assert_eq!(backward_log_ranges(0, 0).collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(
+            backward_log_ranges(100, 25_000).collect::<Vec<_>>(),
+            vec![(15_001, 25_000), (5_001, 15_000), (100, 5_000)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_state_update() {
+        let core_address = init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.push_log(state_update_log(core_address, 5, 10, felt(1), felt(11)));
+        mock.push_log(state_update_log(core_address, 25_000, 20, felt(2), felt(12)));
+        mock.set_block_number(30_000);
+        // the event is looked up in the L1 block found by bisecting the state of the core contract,
+        // without scanning the L1 history
+        mock.set_max_logs_range(1);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+
+        let state_update = client.find_state_update(10).await.unwrap().expect("State update not found");
+        assert_eq!(state_update.l1_block_number, 5);
+        assert_eq!(state_update.block_hash, felt(11));
+        assert_eq!(state_update.global_root, felt(1));
+        let state_update = client.find_state_update(20).await.unwrap().expect("State update not found");
+        assert_eq!(state_update.l1_block_number, 25_000);
+
+        // not verified on its own, before the first state update, or not verified yet
+        assert!(client.find_state_update(15).await.unwrap().is_none());
+        assert!(client.find_state_update(5).await.unwrap().is_none());
+        assert!(client.find_state_update(25).await.unwrap().is_none());
+
+        // the state update preceding a block delimits the batch verified with it
+        mock.set_max_logs_range(GET_LOGS_RANGE);
+        let previous = client.find_previous_state_update(20).await.unwrap().expect("State update not found");
+        assert_eq!((previous.block_number, previous.l1_block_number), (10, 5));
+        let previous = client.find_previous_state_update(25).await.unwrap().expect("State update not found");
+        assert_eq!((previous.block_number, previous.l1_block_number), (20, 25_000));
+        assert!(client.find_previous_state_update(10).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_state_update_without_archive_node() {
+        let core_address = init_test_config();
+        let mock = MockL1Node::start().await;
+        mock.push_log(state_update_log(core_address, 5, 10, felt(1), felt(11)));
+        mock.push_log(state_update_log(core_address, 25_000, 20, felt(2), felt(12)));
+        mock.set_block_number(30_000);
+        // the state of the core contract at past blocks is pruned: the LogStateUpdate events are
+        // scanned instead
+        mock.set_pruned(true);
+
+        let client = EthereumClient::new(mock.http_url()).await.expect("Failed to create EthereumClient");
+
+        let state_update = client.find_state_update(10).await.unwrap().expect("State update not found");
+        assert_eq!(state_update.l1_block_number, 5);
+        let state_update = client.find_state_update(20).await.unwrap().expect("State update not found");
+        assert_eq!(state_update.l1_block_number, 25_000);
+        assert!(client.find_state_update(15).await.unwrap().is_none());
+        assert!(client.find_state_update(5).await.unwrap().is_none());
+        assert!(client.find_state_update(25).await.unwrap().is_none());
+    }
+
+    async fn next_event(receiver: &mut UnboundedReceiver<(u64, StarkHash)>) -> (u64, StarkHash) {
+        tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("Timed out waiting for an event")
+            .expect("Listener stopped")
+    }
+
+    /// Listen to LogStateUpdate events through `url`, as pushed to `mock`
+    async fn check_event_listener(mock: MockL1Node, url: Url) {
+        let core_address = init_test_config();
+        mock.push_log(state_update_log(core_address, 10, 1, felt(1), felt(11)));
+        mock.set_block_number(12);
+
         let client = EthereumClient::new(url).await.expect("Failed to create EthereumClient");
+        let (sender, mut receiver) = unbounded_channel();
+        let (removed_sender, mut removed_receiver) = unbounded_channel();
+        let listener = tokio::spawn(async move {
+            client
+                .listen_to_event(
+                    0,
+                    |log: LogStateUpdate, meta: LogMeta| {
+                        let state_update = convert_log_state_update(log)?;
+                        sender.send((meta.block_number.as_u64(), state_update.block_hash))?;
+                        Ok(())
+                    },
+                    |log: LogStateUpdate, meta: LogMeta| {
+                        let state_update = convert_log_state_update(log)?;
+                        removed_sender.send((meta.block_number.as_u64(), state_update.block_hash))?;
+                        Ok(())
+                    },
+                )
+                .await
+        });
 
-        let initial_state = EthereumClient::get_initial_state(&client).await.expect("Failed to get initial state");
-        assert!(!initial_state.global_root.0.is_empty(), "Global root should not be empty");
-        assert!(!initial_state.block_number > 0, "Block number should be greater than 0");
-        assert!(!initial_state.block_hash.0.is_empty(), "Block hash should not be empty");
+        // past event, the subscription or filter is live once it has been received
+        assert_eq!(next_event(&mut receiver).await, (10, felt(11)));
+
+        mock.push_log(state_update_log(core_address, 13, 2, felt(2), felt(12)));
+        assert_eq!(next_event(&mut receiver).await, (13, felt(12)));
+
+        // the event of the dropped block is removed rather than handled again, then its replacement
+        // is handled
+        mock.reorg(12, vec![state_update_log(core_address, 13, 2, felt(2), felt(22))]);
+        assert_eq!(next_event(&mut removed_receiver).await, (13, felt(12)));
+        assert_eq!(next_event(&mut receiver).await, (13, felt(22)));
+        assert!(receiver.try_recv().is_err());
+        assert!(removed_receiver.try_recv().is_err());
+
+        listener.abort();
     }
 
     #[tokio::test]
-    async fn test_event_subscription() -> Result<(), Box<dyn std::error::Error>> {
-        abigen!(
-            IERC20,
-            r#"[
-                function totalSupply() external view returns (uint256)
-                function balanceOf(address account) external view returns (uint256)
-                function transfer(address recipient, uint256 amount) external returns (bool)
-                function allowance(address owner, address spender) external view returns (uint256)
-                function approve(address spender, uint256 amount) external returns (bool)
-                function transferFrom( address sender, address recipient, uint256 amount) external returns (bool)
-                event Transfer(address indexed from, address indexed to, uint256 value)
-                event Approval(address indexed owner, address indexed spender, uint256 value)
-            ]"#,
-        );
+    async fn test_event_subscription() {
+        let mock = MockL1Node::start().await;
+        let url = mock.ws_url();
+        check_event_listener(mock, url).await;
+    }
 
-        const WETH_ADDRESS: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    #[tokio::test]
+    async fn test_event_watch() {
+        let mock = MockL1Node::start().await;
+        let url = mock.http_url();
+        check_event_listener(mock, url).await;
+    }
+
+    #[tokio::test]
+    async fn test_sync_falls_back_to_next_endpoint() {
+        let core_address = init_test_config();
+        open_test_backend();
 
-        let provider = Provider::<Http>::try_from(eth_rpc::MAINNET)?;
-        let client = Arc::new(provider);
-        let address: Address = WETH_ADDRESS.parse()?;
-        let contract = IERC20::new(address, client);
+        let failing = MockL1Node::start().await;
+        failing.set_failing(true);
 
-        let event = contract.event::<Transfer>().from_block(0).to_block(EthBlockNumber::Latest);
+        let healthy = MockL1Node::start().await;
+        healthy.set_core_contract_state(7, felt(70), felt(700));
+        healthy.push_log(state_update_log(core_address, 20, 7, felt(70), felt(700)));
+        healthy.set_block_number(25);
 
-        let mut event_stream = event.stream().await?;
+        let sync = tokio::spawn(sync(vec![failing.ws_url(), healthy.ws_url()], None));
 
-        while let Some(event_result) = event_stream.next().await {
-            match event_result {
-                Ok(log) => {
-                    println!("Transfer event: {:?}", log);
+        let state_update = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(state_update) = DeoxysBackend::l1().state_update(7).unwrap() {
+                    break state_update;
                 }
-                Err(e) => println!("Error while listening for events: {:?}", e),
+                tokio::time::sleep(Duration::from_millis(50)).await;
             }
-        }
+        })
+        .await
+        .expect("Timed out waiting for the state update to be stored");
+
+        assert_eq!(state_update.l1_block_number, 20);
+        assert_eq!(state_update.block_hash, felt(700));
+        assert_eq!(state_update.global_root, felt(70));
+        assert!(!sync.is_finished(), "L1 sync should keep running");
 
-        Ok(())
+        sync.abort();
     }
 }
@@ -1,42 +1,57 @@
 //! Contains the code required to sync data from the feeder efficiently.
-use std::pin::pin;
+use std::collections::HashMap;
+use std::future::Future;
+use std::num::NonZeroU64;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Instant;
 
 use anyhow::{bail, Context};
-use futures::{stream, StreamExt, TryStreamExt};
+use blockifier::state::cached_state::CommitmentStateDiff;
+use futures::{stream, FutureExt, StreamExt, TryStreamExt};
 use lazy_static::lazy_static;
 use mc_db::storage_handler::primitives::contract_class::{ClassUpdateWrapper, ContractClassData};
-use mc_db::storage_handler::DeoxysStorageError;
-use mc_db::storage_updates::{store_class_update, store_key_update, store_mapping, store_state_update};
+use mc_db::storage_handler::{self, DeoxysStorageError};
+use mc_db::storage_updates::{
+    store_class_update, store_key_update, store_l1_handler_transactions, store_l2_to_l1_messages, store_mapping,
+    store_state_update,
+};
 use mc_db::DeoxysBackend;
-use mp_block::DeoxysBlock;
+use mp_block::{DeoxysBlock, Header};
 use mp_felt::{trim_hash, Felt252Wrapper};
 use mp_types::block::{DBlockT, DHashT};
 use serde::Deserialize;
 use sp_blockchain::HeaderBackend;
 use sp_core::H256;
 use starknet_api::hash::{StarkFelt, StarkHash};
-use starknet_core::types::{PendingStateUpdate, StateUpdate};
+use starknet_api::transaction::Transaction;
+use starknet_core::types::{
+    BlockId as BlockIdCore, ContractClass as ContractClassCore, DeclaredClassItem, ExecutionResult, PendingStateUpdate,
+    StateUpdate,
+};
+use starknet_ff::FieldElement;
 use starknet_providers::sequencer::models::{BlockId, StateUpdateWithBlock};
-use starknet_providers::{ProviderError, SequencerGatewayProvider};
+use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 use tokio::sync::mpsc;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{Sender, WeakSender};
 use tokio::time::Duration;
 
-use crate::commitments::lib::{build_commitment_state_diff, update_state_root};
+use crate::commitments::lib::{
+    build_commitment_state_diff, commit_state_root, squash_state_diff, update_state_root, update_state_tries,
+};
 use crate::convert::{convert_block, ConvertedBlock};
+use crate::event_bus::{self, ImportedBlock, PendingBlock, Reorg, StateDiffSummary, SyncEvent};
+use crate::fetch::class_cache::{self, ClassCache};
 use crate::fetch::fetchers::L2BlockAndUpdates;
 use crate::fetch::l2_fetch_task;
 use crate::l1::ETHEREUM_STATE_UPDATE;
-use crate::metrics::block_metrics::{update_metrics, BlockMetrics};
+use crate::metrics::block_metrics::{observe_stage, update_metrics, BlockMetrics, SyncStage};
 use crate::utils::PerfStopwatch;
 use crate::{stopwatch_end, CommandSink};
 
 /// Prefer this compared to [`tokio::spawn_blocking`], as spawn_blocking creates new OS threads and
 /// we don't really need that
-async fn spawn_compute<F, R>(func: F) -> R
+pub(crate) async fn spawn_compute<F, R>(func: F) -> R
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
@@ -50,6 +65,9 @@ where
     rx.await.expect("tokio channel closed")
 }
 
+/// Delay between two samples of the number of blocks waiting between the sync tasks
+const QUEUE_DEPTH_SAMPLING_INTERVAL: Duration = Duration::from_secs(1);
+
 // TODO: add more error variants, which are more explicit
 #[derive(thiserror::Error, Debug)]
 pub enum L2SyncError {
@@ -57,8 +75,20 @@ pub enum L2SyncError {
     Provider(#[from] ProviderError),
     #[error("db error")]
     Db(#[from] DeoxysStorageError),
-    #[error("mismatched block hash for block {0}")]
-    MismatchedBlockHash(u64),
+    #[error("mismatched block hash for block {block_number}: expected {expected:#x}, computed {computed:#x}")]
+    MismatchedBlockHash { block_number: u64, expected: FieldElement, computed: FieldElement },
+    #[error("mismatched transaction hash for transaction {index} of block {block_number}: expected {expected:#x}")]
+    MismatchedTransactionHash { block_number: u64, index: usize, expected: FieldElement, computed: FieldElement },
+    #[error("mismatched class hash: expected {expected:#x}, computed {computed:#x}")]
+    MismatchedClassHash { expected: FieldElement, computed: FieldElement },
+    #[error("mismatched compiled class hash: expected {expected:#x}, computed {computed:#x}")]
+    MismatchedCompiledClassHash { expected: FieldElement, computed: FieldElement },
+    #[error("failed to compile class {0:#x} to CASM")]
+    CasmCompilation(FieldElement),
+    #[error("failed to compute the hash of class {0:#x}")]
+    ClassHashComputation(FieldElement),
+    #[error("failed to convert class {0:#x}")]
+    ClassConversion(FieldElement),
 }
 
 /// Contains the latest Starknet verified state on L2
@@ -69,6 +99,40 @@ pub struct L2StateUpdate {
     pub block_hash: StarkHash,
 }
 
+/// When the state root computed from the state tries is checked against the fetched blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootVerification {
+    /// The state tries are not updated and the state root is never checked
+    Disabled,
+    /// The state root is checked at every block
+    EveryBlock,
+    /// The state root is checked every n blocks
+    EveryNBlocks(NonZeroU64),
+    /// The state root is checked at the blocks verified by a stored L1 state update, and at every
+    /// block past the last one verified on L1
+    L1Verified,
+}
+
+impl RootVerification {
+    /// Whether the state root of a block should be checked. The state tries are updated at every
+    /// block whatever the cadence, unless the verification is disabled.
+    fn is_due(&self, block_number: u64) -> anyhow::Result<bool> {
+        Ok(match self {
+            Self::Disabled => false,
+            Self::EveryBlock => true,
+            Self::EveryNBlocks(n) => block_number % n.get() == 0,
+            Self::L1Verified => {
+                let last_l1_block = ETHEREUM_STATE_UPDATE
+                    .read()
+                    .expect("Failed to acquire read lock on ETHEREUM_STATE_UPDATE")
+                    .block_number;
+                block_number > last_l1_block
+                    || DeoxysBackend::l1().state_update(block_number).context("reading L1 state update")?.is_some()
+            }
+        })
+    }
+}
+
 /// The current syncing status:
 ///
 /// - SyncVerifiedState: the node is syncing AcceptedOnL1 blocks
@@ -106,6 +170,18 @@ lazy_static! {
     static ref STARKNET_PENDING_STATE_UPDATE: RwLock<Option<PendingStateUpdate>> = RwLock::new(None);
 }
 
+lazy_static! {
+    /// Shared definitions of the classes declared in the pending block, by class hash
+    static ref STARKNET_PENDING_CLASSES: RwLock<HashMap<FieldElement, Arc<ContractClassCore>>> =
+        RwLock::new(HashMap::new());
+}
+
+lazy_static! {
+    /// Shared execution results of the transactions of the pending block, by transaction hash
+    static ref STARKNET_PENDING_EXECUTION_RESULTS: RwLock<HashMap<StarkHash, ExecutionResult>> =
+        RwLock::new(HashMap::new());
+}
+
 pub fn get_pending_block() -> Option<DeoxysBlock> {
     STARKNET_PENDING_BLOCK.read().expect("Failed to acquire read lock on STARKNET_PENDING_BLOCK").clone()
 }
@@ -114,6 +190,24 @@ pub fn get_pending_state_update() -> Option<PendingStateUpdate> {
     STARKNET_PENDING_STATE_UPDATE.read().expect("Failed to acquire read lock on STARKNET_PENDING_BLOCK").clone()
 }
 
+/// Returns the definition of a class declared in the pending block
+pub fn get_pending_class(class_hash: &FieldElement) -> Option<ContractClassCore> {
+    STARKNET_PENDING_CLASSES
+        .read()
+        .expect("Failed to acquire read lock on STARKNET_PENDING_CLASSES")
+        .get(class_hash)
+        .map(|class| class.as_ref().clone())
+}
+
+/// Returns the execution result of a transaction of the pending block, as reported by its receipt
+pub fn get_pending_execution_result(transaction_hash: &StarkHash) -> Option<ExecutionResult> {
+    STARKNET_PENDING_EXECUTION_RESULTS
+        .read()
+        .expect("Failed to acquire read lock on STARKNET_PENDING_EXECUTION_RESULTS")
+        .get(transaction_hash)
+        .cloned()
+}
+
 /// The configuration of the senders responsible for sending blocks and state
 /// updates from the feeder.
 pub struct SenderConfig {
@@ -124,11 +218,17 @@ pub struct SenderConfig {
     pub command_sink: CommandSink,
 }
 
+/// Applies the converted blocks in order, until the conversion task stops or `shutdown` resolves.
+///
+/// A block is always applied entirely, and the state root of the blocks applied since the last
+/// verification is verified before returning, so that the next start resumes from the last
+/// applied block without replaying any.
 async fn l2_verify_and_apply_task(
     mut updates_receiver: mpsc::Receiver<L2ConvertedBlockAndUpdates>,
+    shutdown: impl Future<Output = ()>,
     block_sender: Sender<DeoxysBlock>,
     mut command_sink: CommandSink,
-    verify: bool,
+    root_verification: RootVerification,
     backup_every_n_blocks: Option<usize>,
     block_metrics: Option<BlockMetrics>,
     sync_timer: Arc<Mutex<Option<Instant>>>,
@@ -137,49 +237,89 @@ async fn l2_verify_and_apply_task(
 
     let mut last_block_hash = None;
 
-    while let Some(L2ConvertedBlockAndUpdates { block_n, converted_block, state_update, class_update }) =
-        pin!(updates_receiver.recv()).await
-    {
-        let ConvertedBlock { block, block_hash, txs_hashes } = converted_block;
+    // the trie updates are only committed when the state root is computed, the blocks applied since
+    // then have to be applied again after a restart
+    let (mut last_verified_block, mut unverified_state_diff) = match root_verification {
+        RootVerification::Disabled => (None, None),
+        _ => {
+            let (last_verified_block, unverified_state_diff) =
+                spawn_compute(replay_unverified_blocks).await.context("replaying unverified blocks")?;
+            (Some(last_verified_block), unverified_state_diff)
+        }
+    };
+    let mut last_unverified_block = None;
+
+    tokio::pin!(shutdown);
+    loop {
+        let update = tokio::select! {
+            biased;
+            _ = &mut shutdown => None,
+            update = updates_receiver.recv() => update,
+        };
+        let Some(L2ConvertedBlockAndUpdates { block_n, converted_block, state_update, class_update }) = update else {
+            break;
+        };
+        let ConvertedBlock { block, block_hash, txs_hashes, execution_results, l2_to_l1_messages } = converted_block;
         let block_header = block.header().clone();
         let global_state_root = block_header.global_state_root;
-        let state_update = if verify {
-            let state_update = Arc::new(state_update);
-            let state_update_1 = Arc::clone(&state_update);
-
-            let state_root = spawn_compute(move || {
+        detect_reorg(&block_header)?;
+        let verify_root = root_verification.is_due(block_n)?;
+        let state_update = if root_verification == RootVerification::Disabled {
+            state_update
+        } else {
+            let mut unverified = unverified_state_diff.take();
+            let block_metrics = block_metrics.clone();
+            let (state_update, unverified) = spawn_compute(move || {
                 let sw = PerfStopwatch::new();
-                let state_root = verify_l2(block_n, &state_update)?;
+                let csd = build_commitment_state_diff(&state_update.state_diff);
+                update_state_tries(&csd);
+                squash_into(&mut unverified, csd);
+                if verify_root {
+                    let csd = unverified.take().expect("the state diff of the block was just added");
+                    verify_state_root(block_n, global_state_root, &csd, last_verified_block)?;
+                }
                 stopwatch_end!(sw, "verify_l2: {:?}");
+                observe_stage(block_metrics.as_ref(), SyncStage::VerifyL2, sw.elapsed());
 
-                anyhow::Ok(state_root)
+                anyhow::Ok((state_update, unverified))
             })
             .await?;
+            unverified_state_diff = unverified;
+            last_unverified_block = unverified_state_diff.is_some().then_some((block_n, global_state_root));
+            state_update
+        };
 
-            if global_state_root != state_root {
-                // TODO(fault tolerance): we should have a single rocksdb transaction for the whole l2 update.
-                // let prev_block = block_n.checked_sub(1).expect("no block to revert to");
-
-                // storage_handler::contract_trie_mut().revert_to(prev_block);
-                // storage_handler::contract_storage_trie_mut().revert_to(prev_block);
-                // storage_handler::contract_class_trie_mut().revert_to(prev_block);
-                // TODO(charpa): make other stuff revertible, maybe history?
+        // L1 handler transactions are linked to the L1 message they consume through their nonce
+        let l1_handlers: Vec<_> = block
+            .transactions()
+            .iter()
+            .zip(&txs_hashes)
+            .filter_map(|(tx, tx_hash)| match tx {
+                Transaction::L1Handler(tx) => Some((tx.nonce.0, *tx_hash)),
+                _ => None,
+            })
+            .collect();
 
-                bail!("Verified state: {} doesn't match fetched state: {}", state_root, global_state_root);
-            }
+        let reverted_transactions: Vec<_> = txs_hashes
+            .iter()
+            .zip(execution_results)
+            .filter_map(|(tx_hash, execution_result)| match execution_result {
+                ExecutionResult::Reverted { reason } => Some((*tx_hash, reason)),
+                ExecutionResult::Succeeded => None,
+            })
+            .collect();
 
-            // UNWRAP: we need a 'static future as we are spawning tokio tasks further down the line
-            //         this is a hack to achieve that, we put the update in an arc and then unwrap it at the end
-            //         this will not panic as the Arc should not be aliased.
-            Arc::try_unwrap(state_update_1).unwrap()
-        } else {
-            state_update
+        let imported_block = ImportedBlock {
+            header: block_header.clone(),
+            block_hash,
+            tx_hashes: txs_hashes.clone(),
+            state_diff: StateDiffSummary::from(&state_update.state_diff),
         };
 
         let block_sender = Arc::clone(&block_sender);
         let storage_diffs = state_update.state_diff.storage_diffs.clone();
         let (block_hash_sender, block_hash_receiver) = tokio::sync::oneshot::channel();
-        tokio::join!(
+        let (_, _, _, _, created_block, ..) = tokio::join!(
             async move {
                 block_sender.send(block).await.expect("block reciever channel is closed");
             },
@@ -189,6 +329,7 @@ async fn l2_verify_and_apply_task(
                     log::error!("❗ Failed to store state update for block {block_n}");
                 };
                 stopwatch_end!(sw, "end store_state {}: {:?}", block_n);
+                observe_stage(block_metrics.as_ref(), SyncStage::StoreState, sw.elapsed());
             },
             async {
                 let sw = PerfStopwatch::new();
@@ -196,6 +337,7 @@ async fn l2_verify_and_apply_task(
                     log::error!("❗ Failed to store class update for block {block_n}");
                 };
                 stopwatch_end!(sw, "end store_class {}: {:?}", block_n);
+                observe_stage(block_metrics.as_ref(), SyncStage::StoreClass, sw.elapsed());
             },
             async {
                 let sw = PerfStopwatch::new();
@@ -203,6 +345,7 @@ async fn l2_verify_and_apply_task(
                     log::error!("❗ Failed to store key update for block {block_n}");
                 };
                 stopwatch_end!(sw, "end store_key {}: {:?}", block_n);
+                observe_stage(block_metrics.as_ref(), SyncStage::StoreKey, sw.elapsed());
             },
             async {
                 let sw = PerfStopwatch::new();
@@ -213,18 +356,35 @@ async fn l2_verify_and_apply_task(
                     block_metrics.as_ref(),
                     sync_timer.clone(),
                 )
-                .await
-                .expect("creating block");
+                .await?;
                 stopwatch_end!(sw, "end create_block {}: {:?}", block_n);
+                observe_stage(block_metrics.as_ref(), SyncStage::CreateBlock, sw.elapsed());
                 block_hash_sender.send(substrate_block_hash).expect("block hash receiver channel is closed");
+                Ok::<_, String>(())
             },
             async {
-                let substrate_block_hash = block_hash_receiver.await.expect("block hash receiver channel is closed");
+                // no block was created, the block is applied again on the next start
+                let Ok(substrate_block_hash) = block_hash_receiver.await else {
+                    return;
+                };
                 let sw = PerfStopwatch::new();
-                if store_mapping(block_n, block_hash, substrate_block_hash, txs_hashes).await.is_err() {
+                let stored =
+                    store_mapping(block_n, block_hash, substrate_block_hash, txs_hashes, reverted_transactions).await;
+                if stored.is_err() {
                     log::error!("❗ Failed to store mapping for block {block_n}");
                 };
                 stopwatch_end!(sw, "end store_mapping {}: {:?}", block_n);
+                observe_stage(block_metrics.as_ref(), SyncStage::StoreMapping, sw.elapsed());
+            },
+            async {
+                if store_l1_handler_transactions(l1_handlers).await.is_err() {
+                    log::error!("❗ Failed to store L1 handler transactions for block {block_n}");
+                };
+            },
+            async {
+                if store_l2_to_l1_messages(l2_to_l1_messages).await.is_err() {
+                    log::error!("❗ Failed to store L2 to L1 messages for block {block_n}");
+                };
             },
             async {
                 if let Some(block_metrics) = block_metrics.as_ref() {
@@ -234,8 +394,18 @@ async fn l2_verify_and_apply_task(
                 }
             },
         );
+        created_block.map_err(anyhow::Error::msg).with_context(|| format!("creating block #{block_n}"))?;
 
         DeoxysBackend::meta().set_current_sync_block(block_n).context("setting current sync block")?;
+        // the state root of a block whose verification is not due yet is checked with a later block,
+        // which fails the sync if it does not match
+        update_l2(L2StateUpdate { block_number: block_n, global_root: global_state_root, block_hash });
+        class_cache::release_classes(block_n);
+        if verify_root {
+            DeoxysBackend::meta().set_last_root_verified_block(block_n).context("setting last verified block")?;
+            last_verified_block = Some(block_n);
+        }
+        event_bus::publish(SyncEvent::BlockImported(Arc::new(imported_block)));
         log::info!(
             "✨ Imported #{} ({}) and updated state root ({})",
             block_n,
@@ -255,6 +425,109 @@ async fn l2_verify_and_apply_task(
         }
     }
 
+    // the state root of the last blocks is checked even if the sync stops before the next verification
+    if let (Some(csd), Some((block_n, global_state_root))) = (unverified_state_diff, last_unverified_block) {
+        spawn_compute(move || verify_state_root(block_n, global_state_root, &csd, last_verified_block)).await?;
+        DeoxysBackend::meta().set_last_root_verified_block(block_n).context("setting last verified block")?;
+    }
+    DeoxysBackend::flush().context("flushing db")?;
+
+    Ok(())
+}
+
+/// Publishes a reorg event when a fetched block is not a child of the block stored before it
+///
+/// Reorgs are not handled yet: the block is still verified and applied as usual.
+fn detect_reorg(header: &Header) -> anyhow::Result<()> {
+    let Some(parent_block_number) = header.block_number.checked_sub(1) else {
+        return Ok(());
+    };
+    let local_parent_block_hash = DeoxysBackend::mapping()
+        .starknet_block_hash_from_block_number(parent_block_number)
+        .context("reading parent block hash")?;
+
+    if let Some(local_parent_block_hash) = local_parent_block_hash
+        && local_parent_block_hash != header.parent_block_hash
+    {
+        log::warn!(
+            "⚠️ Reorg detected at block #{}: parent hash {} doesn't match the local block hash {}",
+            header.block_number,
+            header.parent_block_hash,
+            local_parent_block_hash
+        );
+        event_bus::publish(SyncEvent::ReorgDetected(Reorg::L2 {
+            block_number: header.block_number,
+            parent_block_hash: header.parent_block_hash,
+            local_parent_block_hash,
+        }));
+    }
+    Ok(())
+}
+
+/// Applies again to the state tries the blocks synced since the state root was last verified,
+/// returning the last verified block and the state diff of the blocks applied since then
+fn replay_unverified_blocks() -> anyhow::Result<(u64, Option<CommitmentStateDiff>)> {
+    let current_sync_block = DeoxysBackend::meta().current_sync_block()?;
+    let Some(last_verified_block) = DeoxysBackend::meta().last_root_verified_block()? else {
+        // the state root used to be verified at every block
+        DeoxysBackend::meta().set_last_root_verified_block(current_sync_block)?;
+        return Ok((current_sync_block, None));
+    };
+
+    let mut unverified_state_diff = None;
+    for block_number in last_verified_block + 1..=current_sync_block {
+        let state_diff = storage_handler::block_state_diff()
+            .get(block_number)?
+            .with_context(|| format!("no state diff stored for block #{block_number}"))?;
+        let csd = build_commitment_state_diff(&state_diff);
+        update_state_tries(&csd);
+        squash_into(&mut unverified_state_diff, csd);
+    }
+
+    Ok((last_verified_block, unverified_state_diff))
+}
+
+fn squash_into(squashed: &mut Option<CommitmentStateDiff>, csd: CommitmentStateDiff) {
+    match squashed {
+        Some(squashed) => squash_state_diff(squashed, csd),
+        None => *squashed = Some(csd),
+    }
+}
+
+/// Commits the state tries and checks their root against the fetched state root of a block
+///
+/// `csd` is the state diff of the blocks applied to the tries since the state root was last
+/// verified, at `last_verified_block`: a mismatch means one of these blocks is invalid.
+fn verify_state_root(
+    block_number: u64,
+    fetched_state_root: StarkFelt,
+    csd: &CommitmentStateDiff,
+    last_verified_block: Option<u64>,
+) -> anyhow::Result<()> {
+    let state_root: StarkFelt = commit_state_root(csd, block_number).into();
+
+    if state_root != fetched_state_root {
+        // TODO(fault tolerance): we should have a single rocksdb transaction for the whole l2 update.
+        // let prev_block = block_n.checked_sub(1).expect("no block to revert to");
+
+        // storage_handler::contract_trie_mut().revert_to(prev_block);
+        // storage_handler::contract_storage_trie_mut().revert_to(prev_block);
+        // storage_handler::contract_class_trie_mut().revert_to(prev_block);
+        // TODO(charpa): make other stuff revertible, maybe history?
+
+        let unverified_blocks = match last_verified_block {
+            Some(last_verified_block) if last_verified_block + 1 < block_number => {
+                format!("one of blocks #{} to #{block_number} is invalid", last_verified_block + 1)
+            }
+            _ => format!("block #{block_number} is invalid"),
+        };
+        bail!(
+            "Verified state: {} doesn't match fetched state: {}, {unverified_blocks}",
+            state_root,
+            fetched_state_root
+        );
+    }
+
     Ok(())
 }
 
@@ -268,16 +541,19 @@ pub struct L2ConvertedBlockAndUpdates {
 async fn l2_block_conversion_task(
     updates_receiver: mpsc::Receiver<L2BlockAndUpdates>,
     output: mpsc::Sender<L2ConvertedBlockAndUpdates>,
+    block_metrics: Option<BlockMetrics>,
 ) -> anyhow::Result<()> {
     // Items of this stream are futures that resolve to blocks, which becomes a regular stream of blocks
     // using futures buffered.
     let conversion_stream = stream::unfold(updates_receiver, |mut updates_recv| async {
         updates_recv.recv().await.map(|L2BlockAndUpdates { block_n, block, state_update, class_update }| {
+            let block_metrics = block_metrics.clone();
             (
                 spawn_compute(move || {
                     let sw = PerfStopwatch::new();
                     let converted_block = convert_block(block)?;
                     stopwatch_end!(sw, "convert_block: {:?}");
+                    observe_stage(block_metrics.as_ref(), SyncStage::Convert, sw.elapsed());
                     Ok(L2ConvertedBlockAndUpdates { block_n, converted_block, state_update, class_update })
                 }),
                 updates_recv,
@@ -294,16 +570,47 @@ async fn l2_block_conversion_task(
         .await
 }
 
+/// Samples the number of blocks waiting in the channels between the sync tasks
+///
+/// The channels are only weakly referenced, so that sampling does not keep the tasks alive.
+async fn sample_queue_depths(
+    fetch_queue: WeakSender<L2BlockAndUpdates>,
+    conversion_queue: WeakSender<L2ConvertedBlockAndUpdates>,
+    block_metrics: Option<BlockMetrics>,
+) {
+    let Some(block_metrics) = block_metrics else {
+        return std::future::pending().await;
+    };
+    fn depth<T>(queue: &WeakSender<T>) -> f64 {
+        queue.upgrade().map_or(0, |sender| sender.max_capacity() - sender.capacity()) as f64
+    }
+
+    let mut interval = tokio::time::interval(QUEUE_DEPTH_SAMPLING_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        let (fetch_queue_depth, conversion_queue_depth) = (depth(&fetch_queue), depth(&conversion_queue));
+        log::trace!("sync queues: {fetch_queue_depth} fetched blocks, {conversion_queue_depth} converted blocks");
+        block_metrics.l2_fetch_queue_depth.set(fetch_queue_depth);
+        block_metrics.l2_conversion_queue_depth.set(conversion_queue_depth);
+    }
+}
+
 pub struct L2SyncConfig {
     pub first_block: u64,
     pub n_blocks_to_sync: Option<u64>,
-    pub verify: bool,
+    pub sync_until: Option<u64>,
+    pub root_verification: RootVerification,
     pub sync_polling_interval: Option<Duration>,
     pub backup_every_n_blocks: Option<usize>,
+    pub class_cache: Option<ClassCache>,
 }
 
 /// Spawns workers to fetch blocks and state updates from the feeder.
 /// `n_blocks` is optionally the total number of blocks to sync, for debugging/benchmark purposes.
+///
+/// Returns once the blocks up to `sync_until` are applied, or once the block being applied is
+/// stored when `shutdown` resolves.
 pub async fn sync<C>(
     block_sender: Sender<DeoxysBlock>,
     command_sink: CommandSink,
@@ -311,6 +618,7 @@ pub async fn sync<C>(
     client: Arc<C>,
     config: L2SyncConfig,
     block_metrics: Option<BlockMetrics>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
 ) -> anyhow::Result<()>
 where
     C: HeaderBackend<DBlockT> + 'static,
@@ -319,6 +627,11 @@ where
     let (block_conv_sender, block_conv_receiver) = mpsc::channel(30);
     let provider = Arc::new(provider);
     let sync_timer = Arc::new(Mutex::new(None));
+    let shutdown = shutdown.shared();
+    // the classes claimed by the blocks fetched before a restart were never stored
+    class_cache::clear_claims();
+    let queue_depths =
+        sample_queue_depths(fetch_stream_sender.downgrade(), block_conv_sender.downgrade(), block_metrics.clone());
 
     // [Fetch task] ==new blocks and updates=> [Block conversion task] ======> [Verification and apply
     // task]
@@ -333,22 +646,35 @@ where
     let mut fetch_task = tokio::spawn(l2_fetch_task(
         config.first_block,
         config.n_blocks_to_sync,
+        config.sync_until,
         fetch_stream_sender,
         Arc::clone(&provider),
         config.sync_polling_interval,
+        config.class_cache,
+        block_metrics.clone(),
     ));
-    let mut block_conversion_task = tokio::spawn(l2_block_conversion_task(fetch_stream_receiver, block_conv_sender));
-    let mut verify_and_apply_task = tokio::spawn(l2_verify_and_apply_task(
+    let mut block_conversion_task =
+        tokio::spawn(l2_block_conversion_task(fetch_stream_receiver, block_conv_sender, block_metrics.clone()));
+    // the blocks are applied on a blocking thread, which the runtime waits for when the node stops:
+    // the task sees the shutdown on its own, and stores the block being applied even once the sync
+    // itself is dropped
+    let verify_and_apply = l2_verify_and_apply_task(
         block_conv_receiver,
+        shutdown.clone(),
         block_sender,
         command_sink,
-        config.verify,
+        config.root_verification,
         config.backup_every_n_blocks,
         block_metrics,
         Arc::clone(&sync_timer),
-    ));
+    );
+    let runtime = tokio::runtime::Handle::current();
+    let mut verify_and_apply_task = tokio::task::spawn_blocking(move || runtime.block_on(verify_and_apply));
 
-    tokio::select!(
+    let stopped = tokio::select!(
+        biased;
+        // the apply task stops on shutdown too, which must not be taken for the end of the sync
+        _ = shutdown => Ok(true),
         // update highest block hash and number, update pending block and state update
         // TODO: remove
         _ = async {
@@ -360,13 +686,26 @@ where
                     log::error!("{:#}", e);
                 }
             }
-        } => Ok(()),
-        res = &mut fetch_task => res.context("task was canceled")?,
-        res = &mut block_conversion_task => res.context("task was canceled")?,
-        res = &mut verify_and_apply_task => res.context("task was canceled")?,
-        _ = tokio::signal::ctrl_c() => Ok(()), // graceful shutdown
+        } => Ok(false),
+        _ = queue_depths => Ok(false),
+        res = &mut fetch_task => res.context("task was canceled")?.map(|()| false),
+        res = &mut block_conversion_task => res.context("task was canceled")?.map(|()| false),
+        res = &mut verify_and_apply_task => res.context("task was canceled")?.map(|()| false),
     )?;
 
+    if stopped {
+        // graceful shutdown: the fetched blocks are dropped, they are fetched again on the next start
+        log::info!("⏳ Stopping the sync once the block being applied is stored...");
+        fetch_task.abort();
+        block_conversion_task.abort();
+        verify_and_apply_task.await.context("task was canceled")??;
+        log::info!(
+            "✅ Sync stopped at block #{}",
+            DeoxysBackend::meta().current_sync_block().context("getting current sync block")?
+        );
+        return Ok(());
+    }
+
     // one of the task exited, which means it has dropped its channel and downstream tasks should be
     // able to detect that and gracefully finish their business this ensures no task outlive their
     // parent
@@ -391,7 +730,7 @@ async fn create_block(
         parent_hash: None,
         sender: Some(sender),
     })
-    .unwrap();
+    .map_err(|err| format!("failed to send the seal command: {err}"))?;
 
     let create_block_info = receiver
         .await
@@ -435,9 +774,34 @@ pub fn update_l2(state_update: L2StateUpdate) {
     }
 }
 
+/// Replace the pending block with a newer one, and publish it to the event bus
+pub fn update_pending(
+    block: DeoxysBlock,
+    txs_hashes: Vec<StarkHash>,
+    execution_results: Vec<ExecutionResult>,
+    state_update: PendingStateUpdate,
+    classes: HashMap<FieldElement, Arc<ContractClassCore>>,
+) {
+    let pending_block = PendingBlock { header: block.header().clone(), tx_hashes: txs_hashes.clone() };
+
+    *STARKNET_PENDING_BLOCK.write().expect("Failed to acquire write lock on STARKNET_PENDING_BLOCK") = Some(block);
+
+    *STARKNET_PENDING_STATE_UPDATE.write().expect("Failed to aquire write lock on STARKNET_PENDING_STATE_UPDATE") =
+        Some(state_update);
+
+    *STARKNET_PENDING_CLASSES.write().expect("Failed to acquire write lock on STARKNET_PENDING_CLASSES") = classes;
+
+    *STARKNET_PENDING_EXECUTION_RESULTS
+        .write()
+        .expect("Failed to acquire write lock on STARKNET_PENDING_EXECUTION_RESULTS") =
+        txs_hashes.into_iter().zip(execution_results).collect();
+
+    event_bus::publish(SyncEvent::PendingBlockUpdated(Arc::new(pending_block)));
+}
+
 /// Verify and update the L2 state according to the latest state update
 pub fn verify_l2(block_number: u64, state_update: &StateUpdate) -> anyhow::Result<StarkFelt> {
-    let csd = build_commitment_state_diff(state_update);
+    let csd = build_commitment_state_diff(&state_update.state_diff);
     let state_root = update_state_root(csd, block_number);
     let block_hash = state_update.block_hash;
 
@@ -464,11 +828,12 @@ where
 
     if hash_best == tmp {
         // TODO: remove unwrap on convert_block
-        *STARKNET_PENDING_BLOCK.write().expect("Failed to acquire write lock on STARKNET_PENDING_BLOCK") =
-            Some(spawn_compute(|| crate::convert::convert_block(block)).await.unwrap().block);
+        let ConvertedBlock { block, txs_hashes, execution_results, .. } =
+            spawn_compute(|| crate::convert::convert_block(block)).await.unwrap();
+        let state_update = crate::convert::state_update(state_update);
+        let pending_classes = fetch_pending_classes(provider, &state_update).await;
 
-        *STARKNET_PENDING_STATE_UPDATE.write().expect("Failed to aquire write lock on STARKNET_PENDING_STATE_UPDATE") =
-            Some(crate::convert::state_update(state_update));
+        update_pending(block, txs_hashes, execution_results, state_update, pending_classes);
     }
 
     DeoxysBackend::meta()
@@ -483,3 +848,315 @@ where
     );
     Ok(())
 }
+
+/// Fetches the definitions of the classes declared in the pending block, reusing the ones fetched
+/// for a previous version of that block.
+///
+/// Like the rest of the pending data, these definitions are not verified. Classes which fail to
+/// download are left out until the next pending update.
+async fn fetch_pending_classes(
+    provider: &SequencerGatewayProvider,
+    state_update: &PendingStateUpdate,
+) -> HashMap<FieldElement, Arc<ContractClassCore>> {
+    let previous_classes =
+        STARKNET_PENDING_CLASSES.read().expect("Failed to acquire read lock on STARKNET_PENDING_CLASSES").clone();
+
+    let class_hashes = std::iter::empty()
+        .chain(
+            state_update
+                .state_diff
+                .declared_classes
+                .iter()
+                .map(|DeclaredClassItem { class_hash, compiled_class_hash: _ }| class_hash),
+        )
+        .chain(state_update.state_diff.deprecated_declared_classes.iter());
+
+    let mut classes = HashMap::new();
+    for class_hash in class_hashes {
+        if let Some(class) = previous_classes.get(class_hash) {
+            classes.insert(*class_hash, Arc::clone(class));
+            continue;
+        }
+        match provider.get_class(BlockIdCore::Pending, *class_hash).await {
+            Ok(class) => {
+                classes.insert(*class_hash, Arc::new(class));
+            }
+            Err(e) => log::warn!("⚠️ Failed to fetch pending class {class_hash:#x}: {e}"),
+        }
+    }
+    classes
+}
+
+#[cfg(test)]
+pub(crate) mod l2_sync_tests {
+    use std::time::Duration;
+
+    use ethers::types::Address;
+    use futures::channel::mpsc as futures_mpsc;
+    use mc_db::storage_handler;
+    use sc_client_api::in_mem::Blockchain;
+    use sc_consensus_manual_seal::rpc::{CreatedBlock, EngineCommand};
+    use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+    use starknet_api::state::StorageKey;
+    use starknet_core::types::{BlockId as BlockIdCore, ContractClass, StarknetError};
+    use starknet_providers::Provider;
+
+    use super::*;
+    use crate::tests::mock_gateway::{Fault, MockGateway};
+    use crate::tests::{init_test_config, open_test_backend};
+
+    const SIERRA_CLASS_HASH: &str = "0x4a7c0de";
+
+    fn stark_felt(felt: FieldElement) -> StarkFelt {
+        Felt252Wrapper::from(felt).into()
+    }
+
+    fn contract_address(address: u64) -> ContractAddress {
+        ContractAddress(PatriciaKey::try_from(StarkFelt::from(address)).unwrap())
+    }
+
+    fn storage_at(address: u64, key: u64, block_number: u64) -> StarkFelt {
+        let key = StorageKey(PatriciaKey::try_from(StarkFelt::from(key)).unwrap());
+        storage_handler::contract_storage()
+            .get_at(&(contract_address(address), key), block_number)
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    fn nonce_at(address: u64, block_number: u64) -> Nonce {
+        storage_handler::contract_nonces().get_at(&contract_address(address), block_number).unwrap().unwrap_or_default()
+    }
+
+    fn sync_config(first_block: u64) -> L2SyncConfig {
+        L2SyncConfig {
+            first_block,
+            n_blocks_to_sync: None,
+            sync_until: None,
+            root_verification: RootVerification::EveryBlock,
+            sync_polling_interval: None,
+            backup_every_n_blocks: None,
+            class_cache: None,
+        }
+    }
+
+    /// Stand-in for the manual seal engine, sealing every requested block
+    fn mock_block_authoring() -> CommandSink {
+        let (command_sink, mut commands) = futures_mpsc::channel(64);
+        tokio::spawn(async move {
+            let mut sealed = 0;
+            while let Some(command) = commands.next().await {
+                if let EngineCommand::SealNewBlock { sender: Some(sender), .. } = command {
+                    sealed += 1;
+                    let _ = sender.send(Ok(created_block(H256::from_low_u64_be(sealed))));
+                }
+            }
+        });
+        command_sink
+    }
+
+    fn created_block(hash: H256) -> CreatedBlock<H256> {
+        // built from its serialized form, as its fields vary between Substrate versions
+        serde_json::from_value(serde_json::json!({
+            "hash": hash,
+            "aux": sc_consensus::ImportedAux::default(),
+            "proof_size": 0,
+        }))
+        .expect("valid created block")
+    }
+
+    async fn run_sync(gateway: &MockGateway, config: L2SyncConfig) -> (anyhow::Result<()>, Vec<DeoxysBlock>) {
+        run_sync_until_shutdown(gateway, config, std::future::pending()).await
+    }
+
+    async fn run_sync_until_shutdown(
+        gateway: &MockGateway,
+        config: L2SyncConfig,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> (anyhow::Result<()>, Vec<DeoxysBlock>) {
+        let (block_sender, mut block_receiver) = mpsc::channel(64);
+        let client = Arc::new(Blockchain::<DBlockT>::new());
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(60),
+            sync(block_sender, mock_block_authoring(), gateway.provider(), client, config, None, shutdown),
+        )
+        .await
+        .expect("Timed out syncing the mock chain");
+
+        let mut blocks = vec![];
+        while let Ok(block) = block_receiver.try_recv() {
+            blocks.push(block);
+        }
+        (result, blocks)
+    }
+
+    #[tokio::test]
+    async fn test_mock_gateway() {
+        init_test_config();
+        let gateway = MockGateway::start("chain.json").await;
+        let provider = gateway.provider();
+
+        gateway.inject_faults(0, Fault::RateLimited, 1);
+        let error = provider.get_block(BlockId::Number(0)).await.unwrap_err();
+        assert!(matches!(error, ProviderError::RateLimited), "unexpected error: {error}");
+
+        let block = provider.get_block(BlockId::Number(0)).await.expect("Failed to get block");
+        assert_eq!(block.block_hash, Some(gateway.block_hash(0)));
+        assert_eq!(gateway.pending_faults(), 0);
+
+        let StateUpdateWithBlock { state_update, block } =
+            provider.get_state_update_with_block(BlockId::Number(1)).await.expect("Failed to get state update");
+        assert_eq!(block.parent_block_hash, gateway.block_hash(0));
+        assert_eq!(state_update.old_root, gateway.state_root(0));
+        assert_eq!(state_update.new_root, Some(gateway.state_root(1)));
+
+        let error = provider.get_state_update_with_block(BlockId::Number(gateway.chain_len())).await.unwrap_err();
+        assert!(
+            matches!(error, ProviderError::StarknetError(StarknetError::BlockNotFound)),
+            "unexpected error: {error}"
+        );
+
+        let class_hash = FieldElement::from_hex_be(SIERRA_CLASS_HASH).unwrap();
+        let class = provider.get_class(BlockIdCore::Number(0), class_hash).await.expect("Failed to get class");
+        assert!(matches!(class, ContractClass::Sierra(_)));
+    }
+
+    lazy_static! {
+        /// Whether the mock chain is synced into the backend shared by the test binary, held by the
+        /// tests reading the synced chain until they are done
+        static ref MOCK_CHAIN_SYNCED: tokio::sync::Mutex<bool> = tokio::sync::Mutex::new(false);
+    }
+
+    /// The mock chain synced into the test backend
+    pub(crate) struct SyncedMockChain {
+        pub gateway: MockGateway,
+        pub core_address: Address,
+        pub tip: u64,
+        _synced: tokio::sync::MutexGuard<'static, bool>,
+    }
+
+    /// Syncs the mock chain into the test backend on the first call, the scenarios reading the
+    /// synced chain then run in turn as the backend is shared by the whole test binary
+    pub(crate) async fn synced_mock_chain() -> SyncedMockChain {
+        let core_address = init_test_config();
+        open_test_backend();
+
+        let mut synced = MOCK_CHAIN_SYNCED.lock().await;
+        let gateway = MockGateway::start("chain.json").await;
+        if !*synced {
+            sync_mock_chain(&gateway).await;
+            *synced = true;
+        }
+        let tip = gateway.chain_len() - 1;
+        SyncedMockChain { gateway, core_address, tip, _synced: synced }
+    }
+
+    /// Syncs the mock chain through injected faults and a stop of the node
+    async fn sync_mock_chain(gateway: &MockGateway) {
+        gateway.inject_faults(1, Fault::RateLimited, 2);
+        gateway.inject_faults(2, Fault::ServerError, 1);
+
+        // the tries are updated at every block, the state root is verified at blocks 0 and 2, and at the
+        // last block applied by each run
+        let mut events = event_bus::subscribe();
+        let every_2_blocks = RootVerification::EveryNBlocks(NonZeroU64::new(2).unwrap());
+        let config = |first_block| L2SyncConfig { root_verification: every_2_blocks, ..sync_config(first_block) };
+
+        let (result, mut blocks) = run_sync(gateway, L2SyncConfig { sync_until: Some(0), ..config(0) }).await;
+        result.expect("Failed to sync the mock chain up to block #0");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(DeoxysBackend::meta().current_sync_block().unwrap(), 0);
+        assert_eq!(DeoxysBackend::meta().last_root_verified_block().unwrap(), Some(0));
+
+        // the node is stopped once block #1 is imported: the blocks already fetched are dropped, and
+        // the state root of block #1 is verified before the sync returns
+        let mut stop_events = event_bus::subscribe();
+        let shutdown = async move {
+            while let Ok(event) = stop_events.recv().await {
+                if let SyncEvent::BlockImported(block) = event
+                    && block.header.block_number == 1
+                {
+                    break;
+                }
+            }
+        };
+        let (result, stopped_blocks) = run_sync_until_shutdown(gateway, config(1), shutdown).await;
+        result.expect("Failed to stop the sync");
+        blocks.extend(stopped_blocks);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(DeoxysBackend::meta().current_sync_block().unwrap(), 1);
+        assert_eq!(DeoxysBackend::meta().last_root_verified_block().unwrap(), Some(1));
+
+        // the sync resumes after the last stored block
+        let (result, resumed_blocks) = run_sync(gateway, config(2)).await;
+        result.expect("Failed to sync the mock chain");
+        blocks.extend(resumed_blocks);
+        assert_eq!(gateway.pending_faults(), 0);
+
+        let tip = gateway.chain_len() - 1;
+        assert_eq!(DeoxysBackend::meta().current_sync_block().unwrap(), tip);
+        assert_eq!(DeoxysBackend::meta().last_root_verified_block().unwrap(), Some(tip));
+        assert_eq!(blocks.len() as u64, gateway.chain_len());
+        for (block_number, block) in (0..).zip(&blocks) {
+            assert_eq!(block.header().block_number, block_number);
+            assert_eq!(block.header().global_state_root, stark_felt(gateway.state_root(block_number)));
+            assert_eq!(
+                DeoxysBackend::mapping().starknet_block_hash_from_block_number(block_number).unwrap(),
+                Some(stark_felt(gateway.block_hash(block_number)))
+            );
+        }
+        assert_eq!(STARKNET_STATE_UPDATE.read().unwrap().global_root, stark_felt(gateway.state_root(tip)));
+
+        let imported_blocks: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                SyncEvent::BlockImported(block) => Some(block),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(imported_blocks.len() as u64, gateway.chain_len());
+        for (block_number, imported) in (0..).zip(&imported_blocks) {
+            assert_eq!(imported.header.block_number, block_number);
+            assert_eq!(imported.block_hash, stark_felt(gateway.block_hash(block_number)));
+            assert_eq!(imported.tx_hashes.len() as u128, imported.header.transaction_count);
+        }
+        assert_eq!(imported_blocks[3].state_diff.storage_updates, 2);
+    }
+
+    /// Checks the state of the synced mock chain, then that a fork below the synced tip is caught
+    /// by the state root verification
+    #[tokio::test]
+    async fn test_sync_mock_chain() {
+        let SyncedMockChain { gateway, tip, .. } = synced_mock_chain().await;
+        let mut events = event_bus::subscribe();
+
+        assert_eq!(storage_at(0x1000, 0x1, 0), StarkFelt::from(0x2a_u64));
+        assert_eq!(storage_at(0x1000, 0x1, 3), StarkFelt::ZERO);
+        assert_eq!(storage_at(0x1000, 0x2, 3), StarkFelt::from(0x1_u64));
+        assert_eq!(storage_at(0x2000, 0x1, 2), StarkFelt::from(0x64_u64));
+        assert_eq!(storage_at(0x2000, 0x1, 3), StarkFelt::from(0x65_u64));
+        assert_eq!(nonce_at(0x2000, 2), Nonce(StarkFelt::from(1_u64)));
+        assert_eq!(nonce_at(0x2000, 3), Nonce(StarkFelt::from(2_u64)));
+
+        // the node has no reorg support yet: blocks built on top of a fork must not be applied
+        let gateway_tip_hash = gateway.block_hash(tip);
+        gateway.fork_at(2, "fork.json");
+        let (result, _) = run_sync(&gateway, sync_config(tip + 1)).await;
+        let error = result.expect_err("Synced a block from a fork");
+        assert!(format!("{error:#}").contains("doesn't match"), "unexpected error: {error:#}");
+        assert!(
+            format!("{error:#}").contains(&format!("block #{} is invalid", tip + 1)),
+            "unexpected error: {error:#}"
+        );
+        assert_eq!(DeoxysBackend::meta().current_sync_block().unwrap(), tip);
+        assert_eq!(STARKNET_STATE_UPDATE.read().unwrap().block_number, tip);
+        assert_eq!(STARKNET_STATE_UPDATE.read().unwrap().global_root, stark_felt(gateway.state_root(tip)));
+
+        let reorg = std::iter::from_fn(|| events.try_recv().ok()).find_map(|event| match event {
+            SyncEvent::ReorgDetected(Reorg::L2 { block_number, local_parent_block_hash, .. }) => {
+                Some((block_number, local_parent_block_hash))
+            }
+            _ => None,
+        });
+        assert_eq!(reorg, Some((tip + 1, stark_felt(gateway_tip_hash))));
+    }
+}
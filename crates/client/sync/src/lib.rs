@@ -5,12 +5,17 @@
 // use sp_runtime::traits::Block as BlockT;
 // use reqwest::Url;
 
+pub mod checkpoint;
 pub mod commitments;
+pub mod da;
+pub mod event_bus;
 pub mod fetch;
 pub mod l1;
 pub mod l2;
 pub mod metrics;
 pub mod reorgs;
+#[cfg(test)]
+mod tests;
 pub mod utils;
 
 pub use l2::SenderConfig;
@@ -24,9 +29,11 @@ use crate::l2::L2SyncConfig;
 type CommandSink = futures::channel::mpsc::Sender<sc_consensus_manual_seal::rpc::EngineCommand<sp_core::H256>>;
 
 pub mod starknet_sync_worker {
+    use std::future::Future;
     use std::sync::Arc;
 
     use anyhow::Context;
+    use mc_db::DeoxysBackend;
     use mp_block::DeoxysBlock;
     use mp_convert::state_update::ToStateUpdateCore;
     use reqwest::Url;
@@ -35,6 +42,9 @@ pub mod starknet_sync_worker {
     use starknet_providers::SequencerGatewayProvider;
     use tokio::sync::mpsc::Sender;
 
+    use self::checkpoint::{verify_checkpoint, Checkpoint};
+    use self::da::DaConfig;
+    use self::fetch::class_cache::ClassCache;
     use self::fetch::fetchers::FetchConfig;
     use super::*;
     use crate::l2::verify_l2;
@@ -45,11 +55,14 @@ pub mod starknet_sync_worker {
         fetch_config: FetchConfig,
         block_sender: Sender<DeoxysBlock>,
         command_sink: CommandSink,
-        l1_url: Url,
+        l1_urls: Vec<Url>,
+        da_config: Option<DaConfig>,
+        checkpoint: Option<Checkpoint>,
         client: Arc<C>,
         starting_block: u32,
         backup_every_n_blocks: Option<usize>,
         block_metrics: Option<BlockMetrics>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
     ) -> anyhow::Result<()>
     where
         C: HeaderBackend<DBlockT> + 'static,
@@ -66,6 +79,15 @@ pub mod starknet_sync_worker {
             None => provider,
         };
 
+        let class_cache =
+            fetch_config.class_cache_dir.as_ref().map(ClassCache::open).transpose().context("opening class cache")?;
+
+        // The imported state must match the checkpoint, and the checkpoint the L1 history, before
+        // syncing on top of it
+        if let Some(checkpoint) = &checkpoint {
+            verify_checkpoint(checkpoint, &l1_urls).await.context("verifying checkpoint")?;
+        }
+
         if starting_block == 1 {
             let state_update = provider
                 .get_state_update(BlockId::Number(0))
@@ -75,9 +97,17 @@ pub mod starknet_sync_worker {
             verify_l2(0, &state_update)?;
         }
 
-        tokio::select!(
-            res = l1::sync(l1_url.clone(), block_metrics.clone()) => res.context("syncing L1 state")?,
-            res = l2::sync(
+        // The L1 sync and the data availability verification reconnect on their own on L1 connection
+        // errors, they only return on unrecoverable errors
+        let da_sync = async {
+            match da_config {
+                Some(da_config) => da::sync(l1_urls.clone(), da_config).await,
+                None => std::future::pending().await,
+            }
+        };
+        // Once the target block is stored, the node keeps running, and following L1, without syncing
+        let l2_sync = async {
+            l2::sync(
                 block_sender,
                 command_sink,
                 provider,
@@ -85,12 +115,29 @@ pub mod starknet_sync_worker {
                 L2SyncConfig {
                     first_block: starting_block.into(),
                     n_blocks_to_sync: fetch_config.n_blocks_to_sync,
-                    verify: fetch_config.verify,
+                    sync_until: fetch_config.sync_until,
+                    root_verification: fetch_config.root_verification,
                     sync_polling_interval: fetch_config.sync_polling_interval,
                     backup_every_n_blocks,
+                    class_cache,
                 },
-                block_metrics,
-            ) => res.context("syncing L2 state")?
+                block_metrics.clone(),
+                shutdown,
+            )
+            .await?;
+
+            if let Some(sync_until) = fetch_config.sync_until
+                && DeoxysBackend::meta().current_sync_block()? >= sync_until
+            {
+                log::info!("🏁 Synced up to block #{sync_until}, the sync is stopped");
+                std::future::pending().await
+            }
+            anyhow::Ok(())
+        };
+        tokio::select!(
+            res = l1::sync(l1_urls.clone(), block_metrics.clone()) => res.context("syncing L1 state")?,
+            res = da_sync => res.context("verifying L1 data availability")?,
+            res = l2_sync => res.context("syncing L2 state")?
         );
 
         Ok(())
@@ -1,7 +1,39 @@
+use std::time::Duration;
+
 use num_traits::FromPrimitive;
-use prometheus_endpoint::prometheus::Gauge;
+use prometheus_endpoint::prometheus::{exponential_buckets, Gauge, HistogramOpts, HistogramVec};
 use prometheus_endpoint::{register, PrometheusError, Registry};
 
+/// A stage of the L2 sync pipeline, timed for each block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStage {
+    /// Fetching a block, its state update and its classes from the feeder gateway
+    Fetch,
+    Convert,
+    /// Updating the state tries and verifying the state root when it is due
+    VerifyL2,
+    StoreState,
+    StoreClass,
+    StoreKey,
+    StoreMapping,
+    CreateBlock,
+}
+
+impl SyncStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fetch => "fetch",
+            Self::Convert => "convert",
+            Self::VerifyL2 => "verify_l2",
+            Self::StoreState => "store_state",
+            Self::StoreClass => "store_class",
+            Self::StoreKey => "store_key",
+            Self::StoreMapping => "store_mapping",
+            Self::CreateBlock => "create_block",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockMetrics {
     // L2 network metrics
@@ -11,6 +43,10 @@ pub struct BlockMetrics {
     pub l2_latest_sync_time: Gauge,
     pub transaction_count: Gauge,
     pub event_count: Gauge,
+    // L2 sync pipeline metrics
+    pub l2_stage_duration: HistogramVec,
+    pub l2_fetch_queue_depth: Gauge,
+    pub l2_conversion_queue_depth: Gauge,
     // L1 network metrics
     pub l1_block_number: Gauge,
     pub l1_gas_price_wei: Gauge,
@@ -42,6 +78,32 @@ impl BlockMetrics {
                 registry,
             )?,
             event_count: register(Gauge::new("deoxys_event_count", "Gauge for deoxys event count")?, registry)?,
+            l2_stage_duration: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "deoxys_l2_stage_duration_seconds",
+                        "Histogram of the time spent by deoxys on each stage of the L2 sync, per block",
+                    )
+                    // 1ms to ~65s
+                    .buckets(exponential_buckets(0.001, 2.0, 17)?),
+                    &["stage"],
+                )?,
+                registry,
+            )?,
+            l2_fetch_queue_depth: register(
+                Gauge::new(
+                    "deoxys_l2_fetch_queue_depth",
+                    "Gauge for the number of fetched blocks waiting to be converted by deoxys",
+                )?,
+                registry,
+            )?,
+            l2_conversion_queue_depth: register(
+                Gauge::new(
+                    "deoxys_l2_conversion_queue_depth",
+                    "Gauge for the number of converted blocks waiting to be verified and stored by deoxys",
+                )?,
+                registry,
+            )?,
             l1_gas_price_wei: register(Gauge::new("deoxys_l1_gas_price", "Gauge for deoxys L1 gas price")?, registry)?,
             l1_gas_price_strk: register(
                 Gauge::new("deoxys_l1_gas_price_strk", "Gauge for deoxys L1 gas price in strk")?,
@@ -51,6 +113,19 @@ impl BlockMetrics {
     }
 }
 
+impl BlockMetrics {
+    pub fn observe_stage(&self, stage: SyncStage, elapsed: Duration) {
+        self.l2_stage_duration.with_label_values(&[stage.as_str()]).observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Record the time spent by a block on a stage of the sync, when metrics are enabled
+pub fn observe_stage(block_metrics: Option<&BlockMetrics>, stage: SyncStage, elapsed: Duration) {
+    if let Some(block_metrics) = block_metrics {
+        block_metrics.observe_stage(stage, elapsed);
+    }
+}
+
 pub async fn update_metrics(block_metrics: &BlockMetrics, block_header: mp_block::Header) {
     block_metrics.l2_block_number.set(block_header.block_number as f64);
     block_metrics.transaction_count.set(f64::from_u128(block_header.transaction_count).unwrap_or(f64::MIN));
@@ -0,0 +1,432 @@
+//! In-process feeder gateway used to test the L2 sync without a live sequencer
+//!
+//! The gateway serves `get_state_update`, `get_block`, `get_class_by_hash` and
+//! `get_compiled_class_by_class_hash` from the fixture files in `resources/mock_gateway`. Fixtures
+//! are written in the feeder gateway format, but their block hashes, parent block hashes and state
+//! roots are placeholders: they are recomputed when the chain is loaded, so fixtures can be edited
+//! by hand. State roots are recomputed from scratch by a reference implementation of the Starknet
+//! tries, independent of the bonsai tries used by the node.
+//!
+//! Faults can be injected on the requests for a given block, and the chain can be forked at any
+//! height.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use mp_hashers::pedersen::PedersenHasher;
+use mp_hashers::HasherT;
+use serde_json::{json, Value};
+use starknet_ff::FieldElement;
+use starknet_providers::sequencer::models as p;
+use starknet_providers::SequencerGatewayProvider;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use super::utils::read_resource::read_resource_file;
+use crate::convert::convert_block;
+use crate::l2::L2SyncError;
+
+/// A fault answered instead of the requested data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// `429 Too Many Requests`
+    RateLimited,
+    /// `503 Service Unavailable`
+    ServerError,
+}
+
+/// A fixture block, sealed with its recomputed hash and state root
+struct SealedBlock {
+    block: Value,
+    state_update: Value,
+    block_hash: FieldElement,
+    state_root: FieldElement,
+}
+
+#[derive(Default)]
+struct MockState {
+    chain: Vec<SealedBlock>,
+    /// Class definitions and compiled classes by class hash
+    classes: HashMap<FieldElement, Value>,
+    compiled_classes: HashMap<FieldElement, Value>,
+    /// Number of `get_class_by_hash` requests answered for each class hash
+    class_requests: HashMap<FieldElement, usize>,
+    /// Faults left to answer to the requests for a block
+    faults: HashMap<u64, Vec<Fault>>,
+}
+
+/// A mock feeder gateway serving sealed fixtures
+pub struct MockGateway {
+    state: Arc<Mutex<MockState>>,
+    address: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl MockGateway {
+    /// Start a mock gateway on a random local port, serving the chain of the `chain` fixture and
+    /// the classes of the `classes.json` fixture
+    pub async fn start(chain: &str) -> Self {
+        let classes: BTreeMap<FieldElement, ClassFixture> = fixture("classes.json");
+        let state = MockState {
+            chain: seal(fixture(chain)),
+            compiled_classes: classes
+                .iter()
+                .filter_map(|(class_hash, class)| Some((*class_hash, class.compiled_class.clone()?)))
+                .collect(),
+            classes: classes.into_iter().map(|(class_hash, class)| (class_hash, class.class)).collect(),
+            class_requests: HashMap::new(),
+            faults: HashMap::new(),
+        };
+        let state = Arc::new(Mutex::new(state));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("starting mock gateway");
+        let address = listener.local_addr().expect("mock gateway address");
+        let server = tokio::spawn(serve(listener, Arc::clone(&state)));
+
+        Self { state, address, server }
+    }
+
+    /// A provider pointing to this gateway
+    pub fn provider(&self) -> SequencerGatewayProvider {
+        SequencerGatewayProvider::new(
+            format!("http://{}/gateway", self.address).parse().unwrap(),
+            format!("http://{}/feeder_gateway", self.address).parse().unwrap(),
+            FieldElement::from_byte_slice_be(b"SN_TEST").unwrap(),
+        )
+    }
+
+    /// Number of blocks of the served chain
+    pub fn chain_len(&self) -> u64 {
+        self.state.lock().unwrap().chain.len() as u64
+    }
+
+    /// Recomputed hash of a block of the served chain
+    pub fn block_hash(&self, block_number: u64) -> FieldElement {
+        self.state.lock().unwrap().chain[block_number as usize].block_hash
+    }
+
+    /// Recomputed state root of a block of the served chain
+    pub fn state_root(&self, block_number: u64) -> FieldElement {
+        self.state.lock().unwrap().chain[block_number as usize].state_root
+    }
+
+    /// Answer the next `count` requests for `block_number` with `fault`
+    pub fn inject_faults(&self, block_number: u64, fault: Fault, count: usize) {
+        self.state.lock().unwrap().faults.entry(block_number).or_default().extend(std::iter::repeat(fault).take(count));
+    }
+
+    /// Number of injected faults not answered yet
+    pub fn pending_faults(&self) -> usize {
+        self.state.lock().unwrap().faults.values().map(Vec::len).sum()
+    }
+
+    /// Number of times the definition of a class was requested
+    pub fn class_requests(&self, class_hash: FieldElement) -> usize {
+        self.state.lock().unwrap().class_requests.get(&class_hash).copied().unwrap_or_default()
+    }
+
+    /// Replace the blocks from `block_number` with the blocks of the `fork` fixture, which must
+    /// start at that height
+    pub fn fork_at(&self, block_number: u64, fork: &str) {
+        let fork: Vec<FixtureBlock> = fixture(fork);
+        assert_eq!(
+            fork.first().map(FixtureBlock::block_number),
+            Some(block_number),
+            "fork must start at the fork height"
+        );
+
+        let mut state = self.state.lock().unwrap();
+        let chain: Vec<FixtureBlock> = std::mem::take(&mut state.chain)
+            .into_iter()
+            .take(block_number as usize)
+            .map(|SealedBlock { block, state_update, .. }| FixtureBlock { block, state_update })
+            .chain(fork)
+            .collect();
+        state.chain = seal(chain);
+    }
+}
+
+impl Drop for MockGateway {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// A block and its state update, in the `get_state_update?includeBlock=true` format
+#[derive(serde::Deserialize)]
+struct FixtureBlock {
+    block: Value,
+    state_update: Value,
+}
+
+impl FixtureBlock {
+    fn block_number(&self) -> u64 {
+        self.block["block_number"].as_u64().expect("fixture blocks are numbered")
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ClassFixture {
+    class: Value,
+    #[serde(default)]
+    compiled_class: Option<Value>,
+}
+
+fn fixture<T: serde::de::DeserializeOwned>(name: &str) -> T {
+    let path = format!("mock_gateway/{name}");
+    serde_json::from_str(&read_resource_file(&path)).unwrap_or_else(|err| panic!("invalid fixture {path}: {err}"))
+}
+
+/// Link the blocks of a chain and fill in their hashes and state roots
+fn seal(chain: Vec<FixtureBlock>) -> Vec<SealedBlock> {
+    let mut state = ReferenceState::default();
+    let mut parent_hash = FieldElement::ZERO;
+    let mut old_root = FieldElement::ZERO;
+
+    chain
+        .into_iter()
+        .enumerate()
+        .map(|(index, FixtureBlock { mut block, mut state_update })| {
+            let block_number = index as u64;
+            assert_eq!(
+                block["block_number"].as_u64(),
+                Some(block_number),
+                "fixture chains are contiguous from genesis"
+            );
+
+            let diff: p::StateUpdate =
+                serde_json::from_value(state_update.clone()).expect("invalid fixture state update");
+            state.apply(&diff.state_diff);
+            let state_root = state.root();
+
+            block["parent_block_hash"] = felt_json(parent_hash);
+            block["state_root"] = felt_json(state_root);
+            let block_hash = block_hash(&block);
+            block["block_hash"] = felt_json(block_hash);
+
+            state_update["block_hash"] = felt_json(block_hash);
+            state_update["new_root"] = felt_json(state_root);
+            state_update["old_root"] = felt_json(old_root);
+
+            parent_hash = block_hash;
+            old_root = state_root;
+            SealedBlock { block, state_update, block_hash, state_root }
+        })
+        .collect()
+}
+
+/// The hash the node computes for a block, whatever hash the block claims
+fn block_hash(block: &Value) -> FieldElement {
+    let block: p::Block = serde_json::from_value(block.clone()).expect("invalid fixture block");
+    match convert_block(block) {
+        Ok(converted) => FieldElement::from_bytes_be(&converted.block_hash.0).unwrap(),
+        Err(L2SyncError::MismatchedBlockHash { computed, .. }) => computed,
+        Err(err) => panic!("invalid fixture block: {err:#}"),
+    }
+}
+
+fn felt_json(felt: FieldElement) -> Value {
+    json!(format!("{felt:#x}"))
+}
+
+/// The state built by the fixture state diffs, from which state roots are recomputed
+///
+/// Only storage and nonce updates are supported: deployed and declared classes would have to be
+/// served with definitions matching their hashes.
+#[derive(Default)]
+struct ReferenceState {
+    storage: BTreeMap<FieldElement, BTreeMap<FieldElement, FieldElement>>,
+    nonces: BTreeMap<FieldElement, FieldElement>,
+}
+
+impl ReferenceState {
+    fn apply(&mut self, diff: &p::state_update::StateDiff) {
+        assert!(
+            diff.deployed_contracts.is_empty()
+                && diff.declared_classes.is_empty()
+                && diff.old_declared_contracts.is_empty()
+                && diff.replaced_classes.is_empty(),
+            "fixture state diffs only support storage and nonce updates"
+        );
+
+        for (address, entries) in &diff.storage_diffs {
+            let storage = self.storage.entry(*address).or_default();
+            for entry in entries {
+                storage.insert(entry.key, entry.value);
+            }
+        }
+        for (address, nonce) in &diff.nonces {
+            self.storage.entry(*address).or_default();
+            self.nonces.insert(*address, *nonce);
+        }
+    }
+
+    /// The contract trie root, which is the state root as long as no class is declared
+    fn root(&self) -> FieldElement {
+        let leaves = self
+            .storage
+            .iter()
+            .map(|(address, storage)| {
+                let storage_root = trie_root(storage.iter().map(|(key, value)| (*key, *value)));
+                let nonce = self.nonces.get(address).copied().unwrap_or_default();
+                // contract state hash of an undeployed contract, whose class hash is zero
+                let hash = PedersenHasher::hash_elements(FieldElement::ZERO, storage_root);
+                let hash = PedersenHasher::hash_elements(hash, nonce);
+                (*address, PedersenHasher::hash_elements(hash, FieldElement::ZERO))
+            })
+            .collect::<Vec<_>>();
+        trie_root(leaves)
+    }
+}
+
+/// Root of a binary Merkle-Patricia trie of height 251 hashed with Pedersen
+///
+/// Binary nodes hash to `H(left, right)` and edges to `H(child, path) + length`. Zero values are
+/// not stored in the trie, and an empty trie has a zero root.
+fn trie_root(leaves: impl IntoIterator<Item = (FieldElement, FieldElement)>) -> FieldElement {
+    let mut leaves: Vec<([u8; 32], FieldElement)> = leaves
+        .into_iter()
+        .filter(|(_, value)| *value != FieldElement::ZERO)
+        .map(|(key, value)| (key.to_bytes_be(), value))
+        .collect();
+    leaves.sort_by_key(|(key, _)| *key);
+
+    if leaves.is_empty() { FieldElement::ZERO } else { node_hash(subtree(&leaves, 0)) }
+}
+
+/// Bit `depth` of a 251 bits key, from the root
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    let index = depth + 5;
+    (key[index / 8] >> (7 - index % 8)) & 1 == 1
+}
+
+/// The node at `depth` above sorted, non empty `leaves`, as the hash of the node below its edge,
+/// the edge path and the edge length
+fn subtree(leaves: &[([u8; 32], FieldElement)], depth: usize) -> (FieldElement, FieldElement, u64) {
+    if depth == 251 {
+        return (leaves[0].1, FieldElement::ZERO, 0);
+    }
+
+    let split = leaves.partition_point(|(key, _)| !bit(key, depth));
+    if split == 0 || split == leaves.len() {
+        let (hash, path, length) = subtree(leaves, depth + 1);
+        let path = if bit(&leaves[0].0, depth) {
+            path + (0..length).fold(FieldElement::ONE, |power, _| power + power)
+        } else {
+            path
+        };
+        (hash, path, length + 1)
+    } else {
+        let left = node_hash(subtree(&leaves[..split], depth + 1));
+        let right = node_hash(subtree(&leaves[split..], depth + 1));
+        (PedersenHasher::hash_elements(left, right), FieldElement::ZERO, 0)
+    }
+}
+
+fn node_hash((hash, path, length): (FieldElement, FieldElement, u64)) -> FieldElement {
+    if length == 0 { hash } else { PedersenHasher::hash_elements(hash, path) + FieldElement::from(length) }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else { continue };
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            // the client may hang up at any time
+            let _ = handle_connection(stream, &state).await;
+        });
+    }
+}
+
+/// Answer a single request, closing the connection afterwards
+async fn handle_connection(mut stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    {
+        let mut reader = BufReader::new(&mut stream);
+        reader.read_line(&mut request_line).await?;
+        // requests to the feeder gateway have no body, skip the headers
+        let mut header = String::new();
+        while reader.read_line(&mut header).await? > 2 {
+            header.clear();
+        }
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = respond(state, target);
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn respond(state: &Mutex<MockState>, target: &str) -> (&'static str, String) {
+    let Ok(url) = url::Url::parse(&format!("http://mock{target}")) else {
+        return ("400 Bad Request", String::new());
+    };
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let endpoint = url.path_segments().and_then(|mut segments| segments.next_back()).unwrap_or_default();
+    let mut state = state.lock().unwrap();
+
+    let block_number = params.get("blockNumber").and_then(|block_number| block_number.parse::<u64>().ok());
+    if let Some(fault) = block_number.and_then(|block_number| next_fault(&mut state, block_number)) {
+        return match fault {
+            Fault::RateLimited => ("429 Too Many Requests", "Too Many Requests".to_string()),
+            Fault::ServerError => ("503 Service Unavailable", "Service Unavailable".to_string()),
+        };
+    }
+    let block = block_number.and_then(|block_number| state.chain.get(block_number as usize));
+    let class_hash = params.get("classHash").and_then(|class_hash| FieldElement::from_hex_be(class_hash).ok());
+
+    let data = match endpoint {
+        "get_state_update" => block.map(|block| {
+            if params.get("includeBlock").is_some_and(|include| include == "true") {
+                json!({ "block": block.block, "state_update": block.state_update })
+            } else {
+                block.state_update.clone()
+            }
+        }),
+        "get_block" => block.map(|block| block.block.clone()),
+        "get_block_id_by_hash" => {
+            let block_hash = params.get("blockHash").and_then(|block_hash| FieldElement::from_hex_be(block_hash).ok());
+            state
+                .chain
+                .iter()
+                .position(|block| Some(block.block_hash) == block_hash)
+                .map(|block_number| json!(block_number))
+        }
+        "get_class_by_hash" => {
+            if let Some(class_hash) = class_hash {
+                *state.class_requests.entry(class_hash).or_default() += 1;
+            }
+            return match class_hash.and_then(|class_hash| state.classes.get(&class_hash)) {
+                Some(class) => ("200 OK", class.to_string()),
+                None => sequencer_error("UNDECLARED_CLASS", "Class is not declared."),
+            };
+        }
+        "get_compiled_class_by_class_hash" => {
+            return match class_hash.and_then(|class_hash| state.compiled_classes.get(&class_hash)) {
+                Some(class) => ("200 OK", class.to_string()),
+                None => sequencer_error("UNDECLARED_CLASS", "Class is not declared."),
+            };
+        }
+        _ => return ("404 Not Found", String::new()),
+    };
+
+    match data {
+        Some(data) => ("200 OK", data.to_string()),
+        None => sequencer_error("BLOCK_NOT_FOUND", "Block was not found."),
+    }
+}
+
+fn next_fault(state: &mut MockState, block_number: u64) -> Option<Fault> {
+    let faults = state.faults.get_mut(&block_number)?;
+    if faults.is_empty() { None } else { Some(faults.remove(0)) }
+}
+
+fn sequencer_error(code: &str, message: &str) -> (&'static str, String) {
+    ("400 Bad Request", json!({ "code": format!("StarknetErrorCode.{code}"), "message": message }).to_string())
+}
@@ -0,0 +1,404 @@
+//! In-process Ethereum JSON-RPC server used to test the L1 sync without a live endpoint
+//!
+//! The server answers over both HTTP and WebSocket from a scripted state: the chain head, the
+//! state exposed by the Starknet core contract view functions, the transactions and the logs
+//! emitted so far. Logs pushed while a client is subscribed are forwarded to it, or returned by the
+//! next `eth_getFilterChanges` poll of the filters it installed, and reorgs notify the dropped logs
+//! flagged as removed, as a real node would.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ethers::abi::{encode, Token};
+use ethers::contract::EthEvent;
+use ethers::types::{Address, Bytes, Log, H256, I256, U256, U64};
+use jsonrpsee::core::traits::IdProvider;
+use jsonrpsee::core::Error;
+use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::types::error::{CallError, ErrorObject};
+use jsonrpsee::types::SubscriptionId;
+use jsonrpsee::RpcModule;
+use serde_json::Value;
+use starknet_api::hash::StarkHash;
+use tokio::sync::broadcast;
+
+use crate::l1::LogStateUpdate;
+
+/// Selectors of the Starknet core contract view functions served by `eth_call`
+const STATE_BLOCK_NUMBER_SELECTOR: &str = "35befa5d";
+const STATE_ROOT_SELECTOR: &str = "9588eca2";
+const STATE_BLOCK_HASH_SELECTOR: &str = "382d83e3";
+
+/// Scripted state of the mock node
+#[derive(Default)]
+struct MockState {
+    block_number: u64,
+    /// Words returned by the core contract view functions
+    state_block_number: [u8; 32],
+    state_root: [u8; 32],
+    state_block_hash: [u8; 32],
+    logs: Vec<Log>,
+    /// Transactions served by `eth_getTransactionByHash`, as JSON
+    transactions: HashMap<H256, Value>,
+    /// Whether every request should fail
+    failing: bool,
+    /// Whether the state at past blocks is pruned, as on a full node
+    pruned: bool,
+    /// Maximum number of blocks an `eth_getLogs` query may span, as enforced by most providers
+    max_logs_range: Option<u64>,
+    /// Filters installed with `eth_newFilter`, along with the logs emitted since they were last
+    /// polled
+    filters: HashMap<U256, (Value, Vec<Log>)>,
+    last_filter_id: u64,
+}
+
+impl MockState {
+    /// The block number verified by the last `LogStateUpdate` event emitted at or before
+    /// `l1_block`, -1 before the first one
+    fn state_block_number_at(&self, l1_block: u64) -> [u8; 32] {
+        let block_number = self
+            .logs
+            .iter()
+            .rev()
+            .find(|log| {
+                log.topics.first() == Some(&LogStateUpdate::signature())
+                    && log.block_number.unwrap().as_u64() <= l1_block
+            })
+            .map(|log| I256::from_raw(U256::from_big_endian(&log.data[32..64])))
+            .unwrap_or(I256::minus_one());
+        block_number.into_raw().into()
+    }
+
+    /// Queue a new log for the next poll of the matching filters
+    fn notify_filters(&mut self, log: &Log) {
+        for (filter, changes) in self.filters.values_mut() {
+            if log_matches(filter, log) {
+                changes.push(log.clone());
+            }
+        }
+    }
+}
+
+struct MockContext {
+    state: Mutex<MockState>,
+    /// New logs, forwarded to every `logs` subscription
+    logs_sender: broadcast::Sender<Log>,
+}
+
+/// A mock Ethereum node serving scripted fixtures
+pub struct MockL1Node {
+    context: Arc<MockContext>,
+    address: SocketAddr,
+    handle: ServerHandle,
+}
+
+impl MockL1Node {
+    /// Start a mock node on a random local port
+    pub async fn start() -> Self {
+        let (logs_sender, _) = broadcast::channel(64);
+        let context = Arc::new(MockContext { state: Mutex::new(MockState::default()), logs_sender });
+
+        let server = ServerBuilder::default()
+            .set_id_provider(HexIdProvider::default())
+            .build("127.0.0.1:0")
+            .await
+            .expect("starting mock L1 node");
+        let address = server.local_addr().expect("mock L1 node address");
+        let handle = server.start(rpc_module(Arc::clone(&context))).expect("starting mock L1 node");
+
+        Self { context, address, handle }
+    }
+
+    pub fn http_url(&self) -> url::Url {
+        format!("http://{}", self.address).parse().unwrap()
+    }
+
+    pub fn ws_url(&self) -> url::Url {
+        format!("ws://{}", self.address).parse().unwrap()
+    }
+
+    /// Set the number of the latest L1 block
+    pub fn set_block_number(&self, block_number: u64) {
+        self.context.state.lock().unwrap().block_number = block_number;
+    }
+
+    /// Set the Starknet state returned by the core contract view functions
+    pub fn set_core_contract_state(&self, block_number: u64, global_root: StarkHash, block_hash: StarkHash) {
+        let mut state = self.context.state.lock().unwrap();
+        state.state_block_number = U256::from(block_number).into();
+        state.state_root = global_root.0;
+        state.state_block_hash = block_hash.0;
+    }
+
+    /// Set the raw words returned by the `stateRoot` and `stateBlockHash` view functions, which
+    /// may not be valid felts
+    pub fn set_raw_core_contract_state(&self, state_root: [u8; 32], state_block_hash: [u8; 32]) {
+        let mut state = self.context.state.lock().unwrap();
+        state.state_root = state_root;
+        state.state_block_hash = state_block_hash;
+    }
+
+    /// Make every request fail, or succeed again
+    pub fn set_failing(&self, failing: bool) {
+        self.context.state.lock().unwrap().failing = failing;
+    }
+
+    /// Fail the `eth_call` requests at past blocks, as a full node which pruned their state
+    pub fn set_pruned(&self, pruned: bool) {
+        self.context.state.lock().unwrap().pruned = pruned;
+    }
+
+    /// Reject the `eth_getLogs` queries spanning more than `max_logs_range` blocks
+    pub fn set_max_logs_range(&self, max_logs_range: u64) {
+        self.context.state.lock().unwrap().max_logs_range = Some(max_logs_range);
+    }
+
+    /// Serve a transaction, given as JSON with at least its `hash`
+    pub fn push_transaction(&self, tx: Value) {
+        let hash = serde_json::from_value(tx["hash"].clone()).expect("fixture transactions have a hash");
+        self.context.state.lock().unwrap().transactions.insert(hash, tx);
+    }
+
+    /// Emit a log, moving the chain head to its block if needed
+    pub fn push_log(&self, log: Log) {
+        {
+            let mut state = self.context.state.lock().unwrap();
+            let block_number = log.block_number.expect("fixture logs are mined").as_u64();
+            state.block_number = state.block_number.max(block_number);
+            state.logs.push(log.clone());
+            state.notify_filters(&log);
+        }
+        // there may be no subscriber
+        let _ = self.context.logs_sender.send(log);
+    }
+
+    /// Drop the blocks above `common_ancestor` and emit `new_logs` instead
+    ///
+    /// Subscribers are notified of the dropped logs flagged as removed, then of the new logs.
+    pub fn reorg(&self, common_ancestor: u64, new_logs: Vec<Log>) {
+        let removed: Vec<_> = {
+            let mut state = self.context.state.lock().unwrap();
+            state.block_number = common_ancestor;
+            let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut state.logs)
+                .into_iter()
+                .partition(|log| log.block_number.unwrap().as_u64() <= common_ancestor);
+            state.logs = kept;
+
+            let removed = removed.into_iter().map(|log| Log { removed: Some(true), ..log }).collect::<Vec<_>>();
+            for log in &removed {
+                state.notify_filters(log);
+            }
+            removed
+        };
+
+        for log in removed {
+            let _ = self.context.logs_sender.send(log);
+        }
+        for log in new_logs {
+            self.push_log(log);
+        }
+    }
+}
+
+impl Drop for MockL1Node {
+    fn drop(&mut self) {
+        let _ = self.handle.clone().stop();
+    }
+}
+
+/// Build the log of a `LogStateUpdate` event emitted by `core_address` in L1 block `l1_block`
+pub fn state_update_log(
+    core_address: Address,
+    l1_block: u64,
+    block_number: u64,
+    global_root: StarkHash,
+    block_hash: StarkHash,
+) -> Log {
+    let data = encode(&[
+        Token::Uint(U256::from_big_endian(&global_root.0)),
+        Token::Int(I256::from(block_number).into_raw()),
+        Token::Uint(U256::from_big_endian(&block_hash.0)),
+    ]);
+
+    Log {
+        address: core_address,
+        topics: vec![LogStateUpdate::signature()],
+        data: Bytes::from(data),
+        block_hash: Some(H256::from_low_u64_be(l1_block)),
+        block_number: Some(U64::from(l1_block)),
+        transaction_hash: Some(H256::from_low_u64_be((l1_block << 32) | block_number)),
+        transaction_index: Some(U64::zero()),
+        log_index: Some(U256::zero()),
+        removed: Some(false),
+        ..Default::default()
+    }
+}
+
+fn rpc_module(context: Arc<MockContext>) -> RpcModule<Arc<MockContext>> {
+    let mut module = RpcModule::new(context);
+
+    module
+        .register_method("eth_blockNumber", |_, context| {
+            let state = lock_state(context)?;
+            Ok(U64::from(state.block_number))
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_call", |params, context| {
+            let state = lock_state(context)?;
+            let (tx, block): (Value, Value) = params.parse()?;
+            let data = tx.get("input").or_else(|| tx.get("data")).and_then(Value::as_str).unwrap_or_default();
+            let past_block = block.as_str().filter(|block| block.starts_with("0x")).is_some();
+            if past_block && state.pruned {
+                return Err(failure("missing trie node 0x1f0ad1 (path ) state is not available".to_string()));
+            }
+
+            let word = match data.trim_start_matches("0x") {
+                STATE_BLOCK_NUMBER_SELECTOR if past_block => {
+                    state.state_block_number_at(block_number_param(&block, state.block_number))
+                }
+                STATE_BLOCK_NUMBER_SELECTOR => state.state_block_number,
+                STATE_ROOT_SELECTOR => state.state_root,
+                STATE_BLOCK_HASH_SELECTOR => state.state_block_hash,
+                selector => return Err(failure(format!("unknown selector {selector}"))),
+            };
+            Ok(Bytes::from(word.to_vec()))
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_getLogs", |params, context| {
+            let state = lock_state(context)?;
+            let [filter]: [Value; 1] = params.parse()?;
+            let from_block = block_number_param(&filter["fromBlock"], 0);
+            let to_block = block_number_param(&filter["toBlock"], state.block_number);
+            if let Some(max_logs_range) = state.max_logs_range
+                && to_block.saturating_sub(from_block) >= max_logs_range
+            {
+                return Err(failure(format!("query exceeds the max range of {max_logs_range} blocks")));
+            }
+
+            Ok(state
+                .logs
+                .iter()
+                .filter(|log| (from_block..=to_block).contains(&log.block_number.unwrap().as_u64()))
+                .filter(|log| log_matches(&filter, log))
+                .cloned()
+                .collect::<Vec<_>>())
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_newFilter", |params, context| {
+            let mut state = lock_state(context)?;
+            let [filter]: [Value; 1] = params.parse()?;
+
+            state.last_filter_id += 1;
+            let id = U256::from(state.last_filter_id);
+            state.filters.insert(id, (filter, Vec::new()));
+            Ok(id)
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_getFilterChanges", |params, context| {
+            let mut state = lock_state(context)?;
+            let [id]: [U256; 1] = params.parse()?;
+
+            match state.filters.get_mut(&id) {
+                Some((_, changes)) => Ok(std::mem::take(changes)),
+                None => Err(failure(format!("filter {id:#x} not found"))),
+            }
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_uninstallFilter", |params, context| {
+            let mut state = lock_state(context)?;
+            let [id]: [U256; 1] = params.parse()?;
+            Ok(state.filters.remove(&id).is_some())
+        })
+        .unwrap();
+
+    module
+        .register_method("eth_getTransactionByHash", |params, context| {
+            let state = lock_state(context)?;
+            let [hash]: [H256; 1] = params.parse()?;
+            Ok(state.transactions.get(&hash).cloned().unwrap_or(Value::Null))
+        })
+        .unwrap();
+
+    module
+        .register_subscription("eth_subscribe", "eth_subscription", "eth_unsubscribe", |params, mut sink, context| {
+            let filter = match params.parse::<(String, Value)>() {
+                Ok((kind, filter)) if kind == "logs" => filter,
+                _ => {
+                    sink.reject(ErrorObject::owned(-32602, "only logs subscriptions are supported", None::<()>))?;
+                    return Ok(());
+                }
+            };
+            sink.accept()?;
+
+            let mut logs_receiver = context.logs_sender.subscribe();
+            tokio::spawn(async move {
+                while let Ok(log) = logs_receiver.recv().await {
+                    if log_matches(&filter, &log) && !matches!(sink.send(&log), Ok(true)) {
+                        break;
+                    }
+                }
+            });
+            Ok(())
+        })
+        .unwrap();
+
+    module
+}
+
+fn lock_state(context: &Arc<MockContext>) -> Result<std::sync::MutexGuard<'_, MockState>, Error> {
+    let state = context.state.lock().unwrap();
+    if state.failing {
+        return Err(failure("scripted failure".to_string()));
+    }
+    Ok(state)
+}
+
+fn failure(message: String) -> Error {
+    Error::Call(CallError::Failed(anyhow::anyhow!(message)))
+}
+
+/// Parse a block number filter parameter, tags resolving to `default`
+fn block_number_param(param: &Value, default: u64) -> u64 {
+    param
+        .as_str()
+        .and_then(|block| block.strip_prefix("0x"))
+        .and_then(|block| u64::from_str_radix(block, 16).ok())
+        .unwrap_or(default)
+}
+
+/// Whether a log matches the address and first topic of a `logs` filter
+fn log_matches(filter: &Value, log: &Log) -> bool {
+    fn contains<T: PartialEq + std::str::FromStr>(param: &Value, value: &T) -> bool {
+        let parse = |param: &Value| param.as_str().and_then(|param| param.parse::<T>().ok());
+        match param {
+            Value::Null => true,
+            Value::Array(params) => params.iter().filter_map(parse).any(|param| &param == value),
+            param => parse(param).as_ref() == Some(value),
+        }
+    }
+
+    let topic0 = log.topics.first().copied().unwrap_or_default();
+    contains(&filter["address"], &log.address) && contains(&filter["topics"][0], &topic0)
+}
+
+/// Subscription ids are hex numbers on Ethereum nodes
+#[derive(Debug, Default)]
+struct HexIdProvider(AtomicU64);
+
+impl IdProvider for HexIdProvider {
+    fn next_id(&self) -> SubscriptionId<'static> {
+        SubscriptionId::Str(format!("{:#x}", self.0.fetch_add(1, Ordering::Relaxed) + 1).into())
+    }
+}
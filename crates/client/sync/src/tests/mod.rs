@@ -0,0 +1,49 @@
+//! Helpers shared by the sync tests
+
+use std::sync::Once;
+
+use ethers::types::Address;
+use mc_db::DeoxysBackend;
+use starknet_ff::FieldElement;
+
+use crate::fetch::fetchers::FetchConfig;
+use crate::l2::RootVerification;
+use crate::utility::{l1_core_address, set_config};
+
+pub mod mock_gateway;
+pub mod mock_l1;
+pub mod utils;
+
+/// Register the global sync config used by the tests, returning the L1 core contract address
+pub fn init_test_config() -> Address {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_config(&FetchConfig {
+            gateway: "http://127.0.0.1/gateway".parse().unwrap(),
+            feeder_gateway: "http://127.0.0.1/feeder_gateway".parse().unwrap(),
+            chain_id: FieldElement::from_byte_slice_be(b"SN_TEST").unwrap(),
+            workers: 1,
+            sound: false,
+            l1_core_address: Address::from_low_u64_be(0xc0de),
+            l1_core_deployment_block: 0,
+            root_verification: RootVerification::EveryBlock,
+            api_key: None,
+            sync_polling_interval: None,
+            n_blocks_to_sync: None,
+            sync_until: None,
+            class_cache_dir: None,
+        })
+    });
+    l1_core_address()
+}
+
+/// Open the global backend in a temporary directory, once per test binary
+pub fn open_test_backend() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let path = std::env::temp_dir().join(format!("deoxys-sync-tests-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(path.join("starknet")).expect("creating test db directory");
+        DeoxysBackend::open(&path, None, false).expect("opening test db");
+    });
+}
@@ -1,4 +1,2 @@
 #[cfg(test)]
 pub mod read_resource;
-#[cfg(test)]
-pub mod retry;
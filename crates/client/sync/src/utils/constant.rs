@@ -8,4 +8,20 @@ pub mod starknet_core_address {
     pub const SEPOLIA_INTEGRATION: &str = "0x4737c0c1B4D5b1A687B42610DdabEE781152359c";
 }
 
+/// L1 blocks from which the events of the Starknet core contracts are searched, at or before the
+/// deployment of the contracts
+pub mod starknet_core_deployment_block {
+    pub const MAINNET: u64 = 13_000_000;
+    pub const SEPOLIA_TESTNET: u64 = 4_000_000;
+    pub const SEPOLIA_INTEGRATION: u64 = 4_000_000;
+}
+
+/// First Starknet blocks produced with Starknet v0.11, which changed the encoding of the state
+/// diffs published on L1. The Sepolia networks started after it.
+pub mod starknet_v0_11_first_block {
+    pub const MAINNET: u64 = 28_613;
+    pub const SEPOLIA_TESTNET: u64 = 0;
+    pub const SEPOLIA_INTEGRATION: u64 = 0;
+}
+
 pub const LOG_STATE_UPDTATE_TOPIC: &str = "0xd342ddf7a308dec111745b00315c14b7efb2bdae570a6856e088ed0c65a3576c";
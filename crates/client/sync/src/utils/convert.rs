@@ -5,6 +5,7 @@ use std::num::NonZeroU128;
 use std::sync::Arc;
 
 use blockifier::block::GasPrices;
+use mc_db::L2MessageRecord;
 use mp_block::DeoxysBlock;
 use mp_felt::Felt252Wrapper;
 use starknet_api::hash::StarkFelt;
@@ -13,7 +14,7 @@ use starknet_api::transaction::{
     InvokeTransaction, L1HandlerTransaction, Transaction,
 };
 use starknet_core::types::{
-    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, PendingStateUpdate,
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, ExecutionResult, NonceUpdate, PendingStateUpdate,
     ReplacedClassItem, StateDiff as StateDiffCore, StorageEntry,
 };
 use starknet_ff::FieldElement;
@@ -30,10 +31,15 @@ pub struct ConvertedBlock {
     pub block: DeoxysBlock,
     pub block_hash: StarkFelt,
     pub txs_hashes: Vec<StarkFelt>,
+    /// Execution results of the transactions, in the order of their hashes
+    pub execution_results: Vec<ExecutionResult>,
+    pub l2_to_l1_messages: Vec<L2MessageRecord>,
 }
 
 /// Compute heavy, this should only be called in a rayon ctx
 pub fn convert_block(block: p::Block) -> Result<ConvertedBlock, L2SyncError> {
+    // the hashes sent by the feeder gateway are kept to be checked against the recomputed ones
+    let expected_txs_hashes: Vec<FieldElement> = block.transactions.iter().map(transaction_hash).collect();
     // converts starknet_provider transactions and events to mp_transactions and starknet_api events
     let transactions = transactions(block.transactions);
     let events = events(&block.transaction_receipts);
@@ -47,6 +53,7 @@ pub fn convert_block(block: p::Block) -> Result<ConvertedBlock, L2SyncError> {
     let event_count = events.len() as u128;
 
     let ((transaction_commitment, txs_hashes), event_commitment) = commitments(&transactions, &events, block_number);
+    verify_transaction_hashes(block_number, &expected_txs_hashes, &txs_hashes)?;
 
     let protocol_version = starknet_version(&block.starknet_version);
     let l1_gas_price = resource_price(block.l1_gas_price, block.l1_data_gas_price);
@@ -72,7 +79,11 @@ pub fn convert_block(block: p::Block) -> Result<ConvertedBlock, L2SyncError> {
     let computed_block_hash: FieldElement = header.hash::<mp_hashers::pedersen::PedersenHasher>().into();
     // mismatched block hash is allowed for blocks 1466..=2242
     if computed_block_hash != block_hash && !(1466..=2242).contains(&block_number) {
-        return Err(L2SyncError::MismatchedBlockHash(block_number));
+        return Err(L2SyncError::MismatchedBlockHash {
+            block_number,
+            expected: block_hash,
+            computed: computed_block_hash,
+        });
     }
     let ordered_events: Vec<mp_block::OrderedEvents> = block
         .transaction_receipts
@@ -82,13 +93,44 @@ pub fn convert_block(block: p::Block) -> Result<ConvertedBlock, L2SyncError> {
         .map(|(i, r)| mp_block::OrderedEvents::new(i as u128, r.events.iter().map(event).collect()))
         .collect();
 
+    let txs_hashes: Vec<StarkFelt> = txs_hashes.into_iter().map(felt).collect();
+    let l2_to_l1_messages = l2_to_l1_messages(block_number, &block.transaction_receipts, &txs_hashes);
+    let execution_results = execution_results(&block.transaction_receipts);
+
     Ok(ConvertedBlock {
         block: DeoxysBlock::new(header, transactions, ordered_events),
         block_hash: felt(block_hash),
-        txs_hashes: txs_hashes.into_iter().map(felt).collect(),
+        txs_hashes,
+        execution_results,
+        l2_to_l1_messages,
     })
 }
 
+/// Checks that the transaction hashes recomputed with the configured chain id match the ones
+/// provided by the feeder gateway.
+fn verify_transaction_hashes(
+    block_number: u64,
+    expected_txs_hashes: &[FieldElement],
+    txs_hashes: &[FieldElement],
+) -> Result<(), L2SyncError> {
+    for (index, (&expected, &computed)) in expected_txs_hashes.iter().zip(txs_hashes).enumerate() {
+        if expected != computed {
+            return Err(L2SyncError::MismatchedTransactionHash { block_number, index, expected, computed });
+        }
+    }
+    Ok(())
+}
+
+fn transaction_hash(transaction: &p::TransactionType) -> FieldElement {
+    match transaction {
+        p::TransactionType::Declare(tx) => tx.transaction_hash,
+        p::TransactionType::Deploy(tx) => tx.transaction_hash,
+        p::TransactionType::DeployAccount(tx) => tx.transaction_hash,
+        p::TransactionType::InvokeFunction(tx) => tx.transaction_hash,
+        p::TransactionType::L1Handler(tx) => tx.transaction_hash,
+    }
+}
+
 fn transactions(txs: Vec<p::TransactionType>) -> Vec<Transaction> {
     txs.into_iter().map(transaction).collect()
 }
@@ -399,6 +441,42 @@ fn events(receipts: &[p::ConfirmedTransactionReceipt]) -> Vec<starknet_api::tran
     receipts.iter().flat_map(|r| &r.events).map(event).collect()
 }
 
+fn l2_to_l1_messages(
+    block_number: u64,
+    receipts: &[p::ConfirmedTransactionReceipt],
+    txs_hashes: &[StarkFelt],
+) -> Vec<L2MessageRecord> {
+    receipts
+        .iter()
+        .zip(txs_hashes)
+        .enumerate()
+        .flat_map(|(transaction_index, (receipt, transaction_hash))| {
+            receipt.l2_to_l1_messages.iter().enumerate().map(move |(message_index, message)| L2MessageRecord {
+                block_number,
+                transaction_index: transaction_index as u32,
+                message_index: message_index as u32,
+                transaction_hash: *transaction_hash,
+                from_address: felt(message.from_address),
+                to_address: sp_core::H160(message.to_address.0),
+                payload: message.payload.iter().copied().map(felt).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Receipts from before Starknet v0.12.1 have no execution status, as transactions could not revert
+fn execution_results(receipts: &[p::ConfirmedTransactionReceipt]) -> Vec<ExecutionResult> {
+    receipts
+        .iter()
+        .map(|receipt| match receipt.execution_status {
+            Some(p::TransactionExecutionStatus::Reverted) => {
+                ExecutionResult::Reverted { reason: receipt.revert_error.clone().unwrap_or_default() }
+            }
+            _ => ExecutionResult::Succeeded,
+        })
+        .collect()
+}
+
 fn event(event: &p::Event) -> starknet_api::transaction::Event {
     use starknet_api::transaction::{EventContent, EventData, EventKey};
 
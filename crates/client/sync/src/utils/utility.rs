@@ -4,7 +4,9 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use anyhow::{bail, Context};
+use ethers::contract::LogMeta;
 use ethers::types::{I256, U256};
+use mc_db::L1MessageRecord;
 use once_cell::sync::OnceCell;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
@@ -16,7 +18,7 @@ use starknet_ff::FieldElement;
 use url::Url;
 
 use crate::fetch::fetchers::FetchConfig;
-use crate::l1::{L1StateUpdate, LogStateUpdate};
+use crate::l1::{L1StateUpdate, LogMessageToL2, LogStateUpdate};
 use crate::l2::L2StateUpdate;
 
 static CONFIG: OnceCell<FetchConfig> = OnceCell::new();
@@ -34,6 +36,10 @@ pub fn l1_core_address() -> H160 {
     CONFIG.get().expect("CONFIG not initialized").l1_core_address
 }
 
+pub fn l1_core_deployment_block() -> u64 {
+    CONFIG.get().expect("CONFIG not initialized").l1_core_deployment_block
+}
+
 pub fn gateway() -> Url {
     CONFIG.get().expect("CONFIG not initialized").gateway.clone()
 }
@@ -149,3 +155,16 @@ pub fn convert_log_state_update(log_state_update: LogStateUpdate) -> anyhow::Res
 
     Ok(L1StateUpdate { block_number, global_root, block_hash })
 }
+
+pub fn convert_log_message_to_l2(log_message: LogMessageToL2, meta: LogMeta) -> anyhow::Result<L1MessageRecord> {
+    Ok(L1MessageRecord {
+        l1_block_number: meta.block_number.as_u64(),
+        l1_transaction_hash: meta.transaction_hash,
+        nonce: u256_to_starkfelt(log_message.nonce)?,
+        from_address: log_message.from_address,
+        to_address: u256_to_starkfelt(log_message.to_address)?,
+        selector: u256_to_starkfelt(log_message.selector)?,
+        payload: log_message.payload.into_iter().map(u256_to_starkfelt).collect::<anyhow::Result<_>>()?,
+        fee: u128::try_from(log_message.fee).map_err(|_| anyhow::anyhow!("message fee does not fit in a u128"))?,
+    })
+}
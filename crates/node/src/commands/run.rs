@@ -1,17 +1,23 @@
-use std::path::PathBuf;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
 use std::result::Result as StdResult;
 use std::time::Duration;
 
 use deoxys_runtime::SealingMode;
+use mc_db::DeoxysBackend;
+use mc_sync::checkpoint::Checkpoint;
+use mc_sync::da::DaConfig;
 use mc_sync::fetch::fetchers::{fetch_apply_genesis_block, FetchConfig};
+use mc_sync::l2::RootVerification;
 use mc_sync::utility::set_config;
-use mc_sync::utils::constant::starknet_core_address;
+use mc_sync::utils::constant::{starknet_core_address, starknet_core_deployment_block, starknet_v0_11_first_block};
 use reqwest::Url;
 use sc_cli::{Result, RpcMethods, RunCmd, SubstrateCli};
 use serde::{Deserialize, Serialize};
 use sp_core::H160;
 
 use crate::cli::Cli;
+use crate::configs::db_config_dir;
 use crate::service;
 
 /// Available Sealing methods.
@@ -74,6 +80,22 @@ impl NetworkType {
         }
     }
 
+    pub fn l1_core_deployment_block(&self) -> u64 {
+        match self {
+            NetworkType::Main => starknet_core_deployment_block::MAINNET,
+            NetworkType::Test => starknet_core_deployment_block::SEPOLIA_TESTNET,
+            NetworkType::Integration => starknet_core_deployment_block::SEPOLIA_INTEGRATION,
+        }
+    }
+
+    pub fn v0_11_first_block(&self) -> u64 {
+        match self {
+            NetworkType::Main => starknet_v0_11_first_block::MAINNET,
+            NetworkType::Test => starknet_v0_11_first_block::SEPOLIA_TESTNET,
+            NetworkType::Integration => starknet_v0_11_first_block::SEPOLIA_INTEGRATION,
+        }
+    }
+
     pub fn block_fetch_config(&self) -> FetchConfig {
         let uri = self.uri();
         let chain_id = self.chain_id();
@@ -81,6 +103,7 @@ impl NetworkType {
         let gateway = format!("{uri}/gateway").parse().unwrap();
         let feeder_gateway = format!("{uri}/feeder_gateway").parse().unwrap();
         let l1_core_address = self.l1_core_address();
+        let l1_core_deployment_block = self.l1_core_deployment_block();
 
         FetchConfig {
             gateway,
@@ -89,10 +112,13 @@ impl NetworkType {
             workers: 5,
             sound: false,
             l1_core_address,
-            verify: true,
+            l1_core_deployment_block,
+            root_verification: RootVerification::EveryBlock,
             api_key: None,
             sync_polling_interval: Some(Duration::from_secs(2)),
             n_blocks_to_sync: None,
+            sync_until: None,
+            class_cache_dir: None,
         }
     }
 }
@@ -110,14 +136,33 @@ pub struct ExtendedRunCmd {
     #[clap(long, value_enum, ignore_case = true)]
     pub sealing: Option<Sealing>,
 
-    /// The L1 rpc endpoint url for state verification
+    /// The L1 rpc endpoint url for state verification, `ws://` and `wss://` urls subscribe to new
+    /// L1 events instead of polling for them
     #[clap(long, value_parser = parse_url)]
     pub l1_endpoint: Option<Url>,
 
+    /// Comma separated L1 rpc endpoint urls to switch to when the current one fails
+    #[clap(long, value_parser = parse_url, value_delimiter = ',')]
+    pub l1_fallback_endpoints: Vec<Url>,
+
+    /// Check the imported state diffs against the data availability published on L1
+    #[clap(long)]
+    pub l1_da_verification: bool,
+
+    /// The consensus layer api url serving the blobs published on L1 since Starknet v0.13.1,
+    /// needed by the data availability verification
+    #[clap(long, value_parser = parse_url)]
+    pub l1_beacon_endpoint: Option<Url>,
+
     /// The block you want to start syncing from.
     #[clap(long)]
     pub starting_block: Option<u32>,
 
+    /// Start syncing from a trusted checkpoint file. Its snapshot, if any, is restored when there
+    /// is no database yet.
+    #[clap(long)]
+    pub checkpoint: Option<PathBuf>,
+
     /// The network type to connect to.
     #[clap(long, short, default_value = "integration")]
     pub network: NetworkType,
@@ -131,9 +176,19 @@ pub struct ExtendedRunCmd {
     pub deoxys: bool,
 
     /// Disable root verification
-    #[clap(long)]
+    #[clap(long, conflicts_with_all = ["verify_root_every", "verify_root_on_l1"])]
     pub disable_root: bool,
 
+    /// Only verify the state root every n blocks, the state tries are still updated at every block.
+    /// A mismatch is reported for all the blocks since the last verification.
+    #[clap(long, conflicts_with = "verify_root_on_l1")]
+    pub verify_root_every: Option<NonZeroU64>,
+
+    /// Only verify the state root at the blocks verified on L1, and at every block once past the
+    /// last one verified on L1
+    #[clap(long)]
+    pub verify_root_on_l1: bool,
+
     /// Gateway api key to avoid rate limiting (optional)
     #[clap(long)]
     pub gateway_key: Option<String>,
@@ -150,6 +205,15 @@ pub struct ExtendedRunCmd {
     #[clap(long)]
     pub n_blocks_to_sync: Option<u64>,
 
+    /// Stop syncing once this block is stored, the node keeps serving rpc requests
+    #[clap(long)]
+    pub sync_until: Option<u64>,
+
+    /// Directory where the fetched class definitions are kept, to avoid fetching them again on
+    /// resyncs. It can be shared between nodes, as the classes read from it are verified.
+    #[clap(long)]
+    pub class_cache: Option<PathBuf>,
+
     /// A flag to run the TUI dashboard
     #[cfg(feature = "tui")]
     #[clap(long)]
@@ -163,6 +227,22 @@ pub struct ExtendedRunCmd {
 
     #[clap(long, default_value = "false")]
     pub restore_from_latest_backup: bool,
+
+    /// Serve each version of the Starknet rpc at its own path on this port: `/rpc/v0_6`,
+    /// `/rpc/v0_7` and `/rpc/v0_8`. The server shares the interface, methods and CORS settings
+    /// of the rpc server, and is not started if no port is given.
+    #[clap(long)]
+    pub starknet_rpc_port: Option<u16>,
+
+    /// Memory budget of the cache of compiled classes shared by the execution rpc methods (call,
+    /// fee estimation, simulation and trace), in MiB
+    #[clap(long, default_value = "256")]
+    pub execution_class_cache_size: usize,
+
+    /// Delay after which a transaction submitted through the node is reported as rejected if no
+    /// block contains it, in seconds
+    #[clap(long, default_value = "300")]
+    pub submitted_transactions_timeout: u64,
 }
 
 pub fn run_node(mut cli: Cli) -> Result<()> {
@@ -206,44 +286,87 @@ pub fn run_node(mut cli: Cli) -> Result<()> {
     let runner = cli.create_runner(&cli.run.base)?;
 
     // TODO: verify that the l1_endpoint is valid
-    let l1_endpoint = if let Some(url) = cli.run.l1_endpoint {
-        url
+    let l1_endpoints: Vec<Url> = if let Some(url) = cli.run.l1_endpoint {
+        std::iter::once(url).chain(cli.run.l1_fallback_endpoints).collect()
     } else {
         return Err(sc_cli::Error::Input(
             "Missing required --l1-endpoint argument please reffer to https://deoxys-docs.kasar.io".to_string(),
         ));
     };
 
+    let checkpoint = cli.run.checkpoint.as_deref().map(load_checkpoint).transpose()?;
+    let starting_block = match (cli.run.starting_block, &checkpoint) {
+        (Some(starting_block), _) => Some(starting_block),
+        (None, Some(checkpoint)) => Some(u32::try_from(checkpoint.block_number).map_err(|_| {
+            sc_cli::Error::Input(format!("Checkpoint block #{} is out of range", checkpoint.block_number))
+        })?),
+        (None, None) => None,
+    };
+
     runner.run_node_until_exit(|config| async move {
+        if let Some(snapshot) = checkpoint.as_ref().and_then(|checkpoint| checkpoint.snapshot.as_ref()) {
+            let substrate_db_path = config
+                .database
+                .path()
+                .ok_or_else(|| sc_cli::Error::Input("Cannot restore a snapshot into a custom database".to_string()))?;
+            DeoxysBackend::restore_snapshot(&db_config_dir(&config), substrate_db_path, snapshot)
+                .map_err(|e| sc_cli::Error::Input(format!("Failed to restore snapshot: {e:#}")))?;
+        }
+
         let sealing = cli.run.sealing.map(Into::into).unwrap_or_default();
-        let starting_block = cli.run.starting_block;
         let mut fetch_block_config = cli.run.network.block_fetch_config();
         fetch_block_config.sound = cli.run.sound;
-        fetch_block_config.verify = !cli.run.disable_root;
+        fetch_block_config.root_verification = if cli.run.disable_root {
+            RootVerification::Disabled
+        } else if cli.run.verify_root_on_l1 {
+            RootVerification::L1Verified
+        } else if let Some(n) = cli.run.verify_root_every {
+            RootVerification::EveryNBlocks(n)
+        } else {
+            RootVerification::EveryBlock
+        };
         fetch_block_config.api_key = cli.run.gateway_key.clone();
         fetch_block_config.sync_polling_interval =
             if cli.run.no_sync_polling { None } else { Some(Duration::from_secs(cli.run.sync_polling_interval)) };
         fetch_block_config.n_blocks_to_sync = cli.run.n_blocks_to_sync;
+        fetch_block_config.sync_until = cli.run.sync_until;
+        fetch_block_config.class_cache_dir = cli.run.class_cache.clone();
         // unique set of static OnceCell configuration
         set_config(&fetch_block_config);
 
         let genesis_block = fetch_apply_genesis_block(fetch_block_config.clone()).await.unwrap();
 
+        let da_config = cli.run.l1_da_verification.then(|| DaConfig {
+            beacon_url: cli.run.l1_beacon_endpoint,
+            v0_11_first_block: cli.run.network.v0_11_first_block(),
+        });
+
         service::new_full(
             config,
             sealing,
-            l1_endpoint,
+            l1_endpoints,
+            da_config,
+            checkpoint,
             fetch_block_config,
             genesis_block,
             starting_block,
             cli.run.backup_every_n_blocks,
             cli.run.backup_dir,
             cli.run.restore_from_latest_backup,
+            cli.run.starknet_rpc_port,
+            cli.run.execution_class_cache_size,
+            cli.run.submitted_transactions_timeout,
         )
         .map_err(sc_cli::Error::Service)
     })
 }
 
+/// Load a checkpoint file
+fn load_checkpoint(checkpoint: &Path) -> Result<Checkpoint> {
+    Checkpoint::from_file(checkpoint)
+        .map_err(|e| sc_cli::Error::Input(format!("Failed to load checkpoint {}: {e}", checkpoint.display())))
+}
+
 fn override_dev_environment(cmd: &mut ExtendedRunCmd) {
     // create a reproducible dev environment
     // by disabling the default substrate `dev` behaviour
@@ -6,6 +6,7 @@
 #![warn(missing_docs)]
 
 mod starknet;
+mod versions;
 use std::sync::Arc;
 
 use futures::channel::mpsc;
@@ -23,6 +24,7 @@ use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 pub use starknet::StarknetDeps;
+pub use versions::{start_versioned_server, VersionedServerConfig};
 
 /// Full client dependencies.
 pub struct FullDeps<A: ChainApi, C, G: GenesisProvider, P> {
@@ -59,7 +61,6 @@ where
     P: TransactionPool<Block = DBlockT> + 'static,
     BE: Backend<DBlockT> + 'static,
 {
-    use mc_rpc::{Starknet, StarknetReadRpcApiServer, StarknetTraceRpcApiServer, StarknetWriteRpcApiServer};
     use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApiServer};
     use substrate_frame_rpc_system::{System, SystemApiServer};
 
@@ -67,21 +68,7 @@ where
     let FullDeps { client, pool, deny_unsafe, starknet: starknet_params, command_sink, .. } = deps;
 
     module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
-    module.merge(StarknetReadRpcApiServer::into_rpc(Starknet::<_, _, DHasherT>::new(
-        client.clone(),
-        starknet_params.sync_service.clone(),
-        starknet_params.starting_block,
-    )))?;
-    module.merge(StarknetWriteRpcApiServer::into_rpc(Starknet::<_, _, DHasherT>::new(
-        client.clone(),
-        starknet_params.sync_service.clone(),
-        starknet_params.starting_block,
-    )))?;
-    module.merge(StarknetTraceRpcApiServer::into_rpc(Starknet::<_, _, DHasherT>::new(
-        client,
-        starknet_params.sync_service,
-        starknet_params.starting_block,
-    )))?;
+    module.merge(create_starknet(starknet_params)?)?;
 
     if let Some(command_sink) = command_sink {
         module.merge(
@@ -93,3 +80,56 @@ where
 
     Ok(module)
 }
+
+/// Instantiate the Starknet RPC extensions.
+pub fn create_starknet<C, G, BE>(
+    starknet_params: StarknetDeps<C, G, DBlockT>,
+) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
+where
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    C: Send + Sync + 'static,
+    G: GenesisProvider + Send + Sync + 'static,
+    BE: Backend<DBlockT> + 'static,
+{
+    use mc_rpc::{
+        DeoxysRpcApiServer, Starknet, StarknetReadRpcApiServer, StarknetTraceRpcApiServer, StarknetWriteRpcApiServer,
+        StarknetWsRpcApiServer,
+    };
+
+    let mut module = RpcModule::new(());
+    let StarknetDeps { client, sync_service, starting_block, .. } = starknet_params;
+    let starknet = Starknet::<BE, _, DHasherT>::new(client, sync_service, starting_block);
+
+    module.merge(StarknetReadRpcApiServer::into_rpc(starknet.clone()))?;
+    module.merge(StarknetWriteRpcApiServer::into_rpc(starknet.clone()))?;
+    module.merge(StarknetTraceRpcApiServer::into_rpc(starknet.clone()))?;
+    module.merge(StarknetWsRpcApiServer::into_rpc(starknet.clone()))?;
+    module.merge(DeoxysRpcApiServer::into_rpc(starknet))?;
+
+    Ok(module)
+}
+
+/// Instantiate the methods of the versions of the Starknet API, only served by the server of the
+/// versions, see [`start_versioned_server`].
+pub fn create_starknet_versions<C, G, BE>(
+    starknet_params: StarknetDeps<C, G, DBlockT>,
+) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
+where
+    C: HeaderBackend<DBlockT> + BlockBackend<DBlockT> + StorageProvider<DBlockT, BE> + 'static,
+    C: Send + Sync + 'static,
+    G: GenesisProvider + Send + Sync + 'static,
+    BE: Backend<DBlockT> + 'static,
+{
+    use mc_rpc::versions::v0_6::StarknetV06RpcApiServer;
+    use mc_rpc::versions::v0_8::StarknetV08RpcApiServer;
+    use mc_rpc::Starknet;
+
+    let mut module = RpcModule::new(());
+    let StarknetDeps { client, sync_service, starting_block, .. } = starknet_params;
+    let starknet = Starknet::<BE, _, DHasherT>::new(client, sync_service, starting_block);
+
+    module.merge(StarknetV06RpcApiServer::into_rpc(starknet.clone()))?;
+    module.merge(StarknetV08RpcApiServer::into_rpc(starknet))?;
+
+    Ok(module)
+}
@@ -0,0 +1,183 @@
+//! Serving of the versions of the Starknet rpc specification at their own path
+//!
+//! The Substrate rpc server does not route the requests by path, the versions are served by a
+//! dedicated server which redirects the requests sent to the path of a version to its methods, see
+//! [`RpcVersion::rewrite_request`]. Websocket connections are not redirected, they are served the
+//! current version whatever their path.
+
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_LENGTH};
+use hyper::{Body, Request, Response, StatusCode, Uri};
+use jsonrpsee::server::{AllowHosts, RandomStringIdProvider, ServerBuilder, ServerHandle};
+use jsonrpsee::RpcModule;
+use mc_rpc::versions::RpcVersion;
+use tower::{Layer, Service};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+const MEGABYTE: u32 = 1024 * 1024;
+
+/// Settings of the server of the Starknet rpc versions, taken from the ones of the Substrate rpc
+/// server
+#[derive(Debug, Clone)]
+pub struct VersionedServerConfig {
+    /// Address the server listens on
+    pub addr: SocketAddr,
+    /// Origins allowed to send requests, any origin if `None`
+    pub cors: Option<Vec<String>>,
+    /// Maximum number of connections
+    pub max_connections: u32,
+    /// Maximum number of subscriptions per connection
+    pub max_subs_per_conn: u32,
+    /// Maximum size of the requests, in MiB
+    pub max_payload_in_mb: u32,
+    /// Maximum size of the responses, in MiB
+    pub max_payload_out_mb: u32,
+}
+
+/// Start the server of the Starknet rpc versions, serving `module` at the path of each version
+///
+/// The server is set up as the Substrate rpc server is, see `sc_rpc_server::start_server`.
+pub async fn start_versioned_server(
+    config: VersionedServerConfig,
+    module: RpcModule<()>,
+) -> Result<ServerHandle, Box<dyn StdError + Send + Sync>> {
+    let VersionedServerConfig { addr, cors, max_connections, max_subs_per_conn, max_payload_in_mb, max_payload_out_mb } =
+        config;
+
+    let methods: HashSet<String> = module.method_names().map(str::to_string).collect();
+    let middleware = tower::ServiceBuilder::new()
+        .layer(RpcVersionLayer {
+            methods: Arc::new(methods),
+            max_request_body_size: max_payload_in_mb.saturating_mul(MEGABYTE) as usize,
+        })
+        .layer(cors_layer(cors.as_ref())?);
+
+    let server = ServerBuilder::default()
+        .max_request_body_size(max_payload_in_mb.saturating_mul(MEGABYTE))
+        .max_response_body_size(max_payload_out_mb.saturating_mul(MEGABYTE))
+        .max_connections(max_connections)
+        .max_subscriptions_per_connection(max_subs_per_conn)
+        .ping_interval(Duration::from_secs(30))
+        .set_host_filtering(host_filtering(cors.is_some(), addr))
+        .set_id_provider(RandomStringIdProvider::new(16))
+        .set_middleware(middleware)
+        .build(addr)
+        .await?;
+    log::info!(
+        "🌐 Starknet rpc versions served at {}",
+        RpcVersion::ALL.map(|version| format!("http://{addr}{}", version.path())).join(", ")
+    );
+    Ok(server.start(module)?)
+}
+
+/// Only allows the origins of `cors`, all of them if it is `None`
+fn cors_layer(cors: Option<&Vec<String>>) -> Result<CorsLayer, Box<dyn StdError + Send + Sync>> {
+    match cors {
+        Some(cors) => {
+            let origins = cors.iter().map(|origin| HeaderValue::from_str(origin)).collect::<Result<Vec<_>, _>>()?;
+            Ok(CorsLayer::new().allow_origin(AllowOrigin::list(origins)))
+        }
+        None => Ok(CorsLayer::permissive()),
+    }
+}
+
+/// When the origins are restricted, only the requests sent to the listening address are allowed
+fn host_filtering(enabled: bool, addr: SocketAddr) -> AllowHosts {
+    if enabled {
+        let port = addr.port();
+        AllowHosts::Only(vec![format!("localhost:{port}").into(), format!("127.0.0.1:{port}").into()])
+    } else {
+        AllowHosts::Any
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RpcVersionLayer {
+    methods: Arc<HashSet<String>>,
+    max_request_body_size: usize,
+}
+
+impl<S> Layer<S> for RpcVersionLayer {
+    type Service = RpcVersionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcVersionService { inner, methods: self.methods.clone(), max_request_body_size: self.max_request_body_size }
+    }
+}
+
+/// Redirects the requests sent to the path of a version to the methods of this version
+#[derive(Debug, Clone)]
+struct RpcVersionService<S> {
+    inner: S,
+    /// Methods registered on the server
+    methods: Arc<HashSet<String>>,
+    /// Maximum size of the requests read to be redirected
+    max_request_body_size: usize,
+}
+
+impl<S> Service<Request<Body>> for RpcVersionService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<Box<dyn StdError + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn StdError + Send + Sync + 'static>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let Some(version) = RpcVersion::from_path(request.uri().path()) else {
+            let response = self.inner.call(request);
+            return Box::pin(async move { response.await.map_err(Into::into) });
+        };
+
+        // the service polled ready has to handle the request, a clone takes its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let methods = self.methods.clone();
+        let max_request_body_size = self.max_request_body_size;
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+            parts.uri = Uri::from_static("/");
+
+            let Some(body) = read_body(body, max_request_body_size).await? else {
+                return Ok(Response::builder().status(StatusCode::PAYLOAD_TOO_LARGE).body(Body::empty())?);
+            };
+            let body = match version.rewrite_request(&body, |method| methods.contains(method)) {
+                Some(body) => {
+                    parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    Body::from(body)
+                }
+                None => Body::from(body),
+            };
+
+            inner.call(Request::from_parts(parts, body)).await.map_err(Into::into)
+        })
+    }
+}
+
+/// Reads a request body, returns `None` if it exceeds `max_size`
+async fn read_body(mut body: Body, max_size: usize) -> Result<Option<Vec<u8>>, hyper::Error> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk?);
+        if bytes.len() > max_size {
+            return Ok(None);
+        }
+    }
+    Ok(Some(bytes))
+}
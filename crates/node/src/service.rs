@@ -1,17 +1,24 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
 use std::cell::RefCell;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use deoxys_runtime::{self, RuntimeApi, SealingMode};
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
 use futures::future::BoxFuture;
+use futures::{Future, FutureExt};
 use mc_db::DeoxysBackend;
 use mc_genesis_data_provider::OnDiskGenesisConfig;
+use mc_rpc::contract_class_cache::{self, ContractClassCacheMetrics};
+use mc_rpc::submitted_transactions;
+use mc_sync::checkpoint::Checkpoint;
+use mc_sync::da::DaConfig;
 use mc_sync::fetch::fetchers::FetchConfig;
 use mc_sync::metrics::block_metrics::BlockMetrics;
-use mc_sync::starknet_sync_worker;
+use mc_sync::{event_bus, starknet_sync_worker};
 use mp_block::DeoxysBlock;
 use mp_types::block::{DBlockT, DHashT};
 use parity_scale_codec::Encode;
@@ -21,7 +28,7 @@ use sc_consensus::{BasicQueue, BlockImportParams};
 use sc_consensus_manual_seal::{ConsensusDataProvider, Error};
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_service::error::Error as ServiceError;
-use sc_service::{new_db_backend, Configuration, TaskManager};
+use sc_service::{new_db_backend, Configuration, RpcMethods, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool::FullPool;
 use sp_api::{ConstructRuntimeApi, ProvideRuntimeApi};
@@ -32,7 +39,7 @@ use sp_runtime::DigestItem;
 
 use crate::configs::db_config_dir;
 use crate::genesis_block::DeoxysGenesisBlockBuilder;
-use crate::rpc::StarknetDeps;
+use crate::rpc::{start_versioned_server, DenyUnsafe, StarknetDeps, VersionedServerConfig};
 // Our native executor instance.
 pub struct ExecutorDispatch;
 
@@ -195,13 +202,18 @@ where
 pub fn new_full(
     config: Configuration,
     sealing: SealingMode,
-    l1_url: Url,
+    l1_urls: Vec<Url>,
+    da_config: Option<DaConfig>,
+    checkpoint: Option<Checkpoint>,
     fetch_config: FetchConfig,
     genesis_block: DeoxysBlock,
     starting_block: Option<u32>,
     backup_every_n_blocks: Option<usize>,
     backup_dir: Option<PathBuf>,
     restore_from_latest_backup: bool,
+    starknet_rpc_port: Option<u16>,
+    execution_class_cache_size: usize,
+    submitted_transactions_timeout: u64,
 ) -> Result<TaskManager, ServiceError> {
     let build_import_queue = build_manual_seal_import_queue;
 
@@ -232,7 +244,23 @@ pub fn new_full(
         })?;
 
     let prometheus_registry = config.prometheus_registry().cloned();
-    let block_metrics = prometheus_registry.and_then(|registry| BlockMetrics::register(&registry).ok());
+    let block_metrics = prometheus_registry.as_ref().and_then(|registry| BlockMetrics::register(registry).ok());
+    let contract_class_cache_metrics =
+        prometheus_registry.as_ref().and_then(|registry| ContractClassCacheMetrics::register(registry).ok());
+    contract_class_cache::configure(
+        execution_class_cache_size.saturating_mul(1024 * 1024),
+        contract_class_cache_metrics,
+    );
+    submitted_transactions::configure(Duration::from_secs(submitted_transactions_timeout));
+    task_manager.spawn_handle().spawn(
+        "starknet-submitted-transactions",
+        Some(DEOXYS_TASK_GROUP),
+        submitted_transactions::forget_included_transactions(event_bus::subscribe()),
+    );
+
+    // Refuse to start on top of a db which disagrees with the state verified on L1
+    mc_sync::l1::verify_l1_history(client.as_ref(), checkpoint.as_ref().map(|checkpoint| checkpoint.block_number))
+        .map_err(|e| ServiceError::Other(format!("L1 state update history mismatch: {e:#}")))?;
 
     let best_block = DeoxysBackend::meta().current_sync_block().expect("getting current sync block") as _;
     let on_block =
@@ -257,13 +285,14 @@ pub fn new_full(
         genesis_provider: genesis_data.into(),
     };
 
-    let rpc_extensions_builder = {
+    let starknet_versions_params = starknet_rpc_params.clone();
+    let rpc_builder = {
         let client = client.clone();
         let pool = transaction_pool.clone();
         let graph = transaction_pool.pool().clone();
         let command_sink = command_sink.clone();
 
-        Box::new(move |deny_unsafe, _| {
+        Arc::new(move |deny_unsafe: DenyUnsafe| {
             let deps = crate::rpc::FullDeps {
                 client: client.clone(),
                 pool: pool.clone(),
@@ -272,10 +301,42 @@ pub fn new_full(
                 starknet: starknet_rpc_params.clone(),
                 command_sink: command_sink.clone(),
             };
-            crate::rpc::create_full(deps).map_err(Into::into)
+            crate::rpc::create_full(deps)
         })
     };
 
+    // the versions of the Starknet rpc are served with the methods and settings of the Substrate rpc,
+    // along with the methods of each version
+    if let Some(starknet_rpc_port) = starknet_rpc_port {
+        let addr =
+            SocketAddr::new(config.rpc_addr.map_or(Ipv4Addr::LOCALHOST.into(), |addr| addr.ip()), starknet_rpc_port);
+        let server_config = VersionedServerConfig {
+            addr,
+            cors: config.rpc_cors.clone(),
+            max_connections: config.rpc_max_connections,
+            max_subs_per_conn: config.rpc_max_subs_per_conn,
+            max_payload_in_mb: config.rpc_max_request_size,
+            max_payload_out_mb: config.rpc_max_response_size,
+        };
+        let module = rpc_builder(deny_unsafe(addr, &config.rpc_methods))
+            .and_then(|mut module| {
+                module.merge(crate::rpc::create_starknet_versions(starknet_versions_params)?)?;
+                Ok(module)
+            })
+            .map_err(|e| ServiceError::Other(format!("Failed to create the Starknet rpc module: {e}")))?;
+        task_manager.spawn_handle().spawn("starknet-rpc-versions", Some(DEOXYS_TASK_GROUP), async move {
+            match start_versioned_server(server_config, module).await {
+                Ok(server) => server.stopped().await,
+                Err(e) => log::error!("Failed to start the Starknet rpc versions server: {e}"),
+            }
+        });
+    }
+
+    let rpc_extensions_builder = {
+        let rpc_builder = Arc::clone(&rpc_builder);
+        Box::new(move |deny_unsafe, _| rpc_builder(deny_unsafe).map_err(Into::into))
+    };
+
     let _rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
         network: network.clone(),
         client: client.clone(),
@@ -298,11 +359,14 @@ pub fn new_full(
             fetch_config,
             block_sender,
             command_sink.unwrap().clone(),
-            l1_url,
+            l1_urls,
+            da_config,
+            checkpoint,
             Arc::clone(&client),
             on_block.unwrap(),
             backup_every_n_blocks,
             block_metrics,
+            shutdown_signal(&task_manager),
         );
         async { fut.await.unwrap() }
     });
@@ -331,6 +395,27 @@ pub fn new_full(
     Ok(task_manager)
 }
 
+/// Whether the unsafe rpc methods are denied on a server listening on `addr`, decided as the
+/// Substrate rpc server does
+fn deny_unsafe(addr: SocketAddr, methods: &RpcMethods) -> DenyUnsafe {
+    match (addr.ip().is_loopback(), methods) {
+        (_, RpcMethods::Unsafe) | (true, RpcMethods::Auto) => DenyUnsafe::No,
+        _ => DenyUnsafe::Yes,
+    }
+}
+
+/// Resolves when the task manager shuts down, once the node is asked to stop
+///
+/// The task manager drops its tasks on shutdown: the signal is held by a task which never ends.
+fn shutdown_signal(task_manager: &TaskManager) -> impl Future<Output = ()> + Send + 'static {
+    let (guard, shutdown) = oneshot::channel::<()>();
+    task_manager.spawn_handle().spawn("deoxys-shutdown-signal", Some(DEOXYS_TASK_GROUP), async move {
+        let _guard = guard;
+        std::future::pending::<()>().await
+    });
+    shutdown.map(|_| ())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn run_manual_seal_authorship(
     block_receiver: tokio::sync::mpsc::Receiver<DeoxysBlock>,
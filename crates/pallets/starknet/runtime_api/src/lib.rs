@@ -13,5 +13,6 @@ pub enum StarknetTransactionExecutionError {
     ClassAlreadyDeclared,
     ClassHashNotFound,
     InvalidContractClass,
-    ContractError,
+    /// The execution of the contract failed, with the error or revert reason of the blockifier
+    ContractError(alloc::string::String),
 }
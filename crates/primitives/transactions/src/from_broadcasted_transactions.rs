@@ -59,22 +59,30 @@ pub enum BroadcastedTransactionConversionError {
 }
 
 pub trait ToAccountTransaction {
-    fn to_account_transaction(&self) -> Result<AccountTransaction, BroadcastedTransactionConversionError>;
+    /// Converts the transaction, its hash being computed for the given chain id
+    fn to_account_transaction(
+        &self,
+        chain_id: Felt252Wrapper,
+    ) -> Result<AccountTransaction, BroadcastedTransactionConversionError>;
 }
 
 // TODO: remove clone() and change method with &self
 impl ToAccountTransaction for BroadcastedTransaction {
-    fn to_account_transaction(&self) -> Result<AccountTransaction, BroadcastedTransactionConversionError> {
+    fn to_account_transaction(
+        &self,
+        chain_id: Felt252Wrapper,
+    ) -> Result<AccountTransaction, BroadcastedTransactionConversionError> {
         match self {
-            BroadcastedTransaction::Invoke(tx) => invoke_to_account_transaction(tx.clone()),
-            BroadcastedTransaction::Declare(tx) => declare_to_account_transaction(tx.clone()),
-            BroadcastedTransaction::DeployAccount(tx) => deploy_account_to_account_transaction(tx.clone()),
+            BroadcastedTransaction::Invoke(tx) => invoke_to_account_transaction(tx.clone(), chain_id),
+            BroadcastedTransaction::Declare(tx) => declare_to_account_transaction(tx.clone(), chain_id),
+            BroadcastedTransaction::DeployAccount(tx) => deploy_account_to_account_transaction(tx.clone(), chain_id),
         }
     }
 }
 
 fn declare_to_account_transaction(
     value: BroadcastedDeclareTransaction,
+    chain_id: Felt252Wrapper,
 ) -> Result<AccountTransaction, BroadcastedTransactionConversionError> {
     let user_tx = match value {
         BroadcastedDeclareTransaction::V1(BroadcastedDeclareTransactionV1 {
@@ -85,29 +93,8 @@ fn declare_to_account_transaction(
             contract_class,
             is_query: _,
         }) => {
-            // Create a GzipDecoder to decompress the bytes
-            let mut gz = GzDecoder::new(&contract_class.program[..]);
-
-            // Read the decompressed bytes into a Vec<u8>
-            let mut decompressed_bytes = Vec::new();
-            std::io::Read::read_to_end(&mut gz, &mut decompressed_bytes)
-                .map_err(|_| BroadcastedTransactionConversionError::ProgramDecompressionFailed)?;
-
-            let class_hash = {
-                let legacy_contract_class = LegacyContractClass {
-                    program: serde_json::from_slice(decompressed_bytes.as_slice())
-                        .map_err(|_| BroadcastedTransactionConversionError::ProgramDeserializationFailed)?,
-                    abi: match contract_class.abi.as_ref() {
-                        Some(abi) => Some(abi.iter().cloned().map(|entry| entry.into()).collect::<Vec<_>>()),
-                        None => vec![].into(),
-                    },
-                    entry_points_by_type: to_raw_legacy_entry_points(contract_class.entry_points_by_type.clone()),
-                };
-
-                legacy_contract_class
-                    .class_hash()
-                    .map_err(|_| BroadcastedTransactionConversionError::ClassHashComputationFailed)?
-            };
+            let decompressed_bytes = decompress_program(&contract_class.program)?;
+            let class_hash = legacy_class_hash(&contract_class, &decompressed_bytes)?;
 
             let blockifier_contract_class = instantiate_blockifier_contract_class(&contract_class, decompressed_bytes)?;
 
@@ -125,8 +112,7 @@ fn declare_to_account_transaction(
                 sender_address: Felt252Wrapper::from(sender_address).into(),
             });
 
-            // TODO: defaulted chain id
-            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
             let class_info = ClassInfo::new(
                 &blockifier_contract_class,
                 contract_class.clone().program.len(),
@@ -151,7 +137,7 @@ fn declare_to_account_transaction(
                 .map_err(|_| BroadcastedTransactionConversionError::SierraCompilationFailed)?;
 
             // ensure that the user has sign the correct class hash
-            if get_casm_contract_class_hash(&casm_contract_class) != compiled_class_hash {
+            if get_casm_contract_class_hash(&casm_contract_class)? != compiled_class_hash {
                 return Err(BroadcastedTransactionConversionError::InvalidCompiledClassHash);
             }
 
@@ -175,8 +161,7 @@ fn declare_to_account_transaction(
                 compiled_class_hash: Felt252Wrapper::from(compiled_class_hash).into(),
             });
 
-            // TODO: use real chain id
-            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
             let class_info = ClassInfo::new(
                 &blockifier_contract_class,
                 contract_class.sierra_program.len(),
@@ -206,7 +191,7 @@ fn declare_to_account_transaction(
                 .map_err(|_| BroadcastedTransactionConversionError::SierraCompilationFailed)?;
 
             // ensure that the user has sign the correct class hash
-            if get_casm_contract_class_hash(&casm_contract_class) != compiled_class_hash {
+            if get_casm_contract_class_hash(&casm_contract_class)? != compiled_class_hash {
                 return Err(BroadcastedTransactionConversionError::InvalidCompiledClassHash);
             }
 
@@ -238,8 +223,7 @@ fn declare_to_account_transaction(
                 fee_data_availability_mode: core_da_to_api_da(fee_data_availability_mode),
             });
 
-            // TODO: use real chain id
-            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = declare_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
             let class_info = ClassInfo::new(
                 &blockifier_contract_class,
                 contract_class.sierra_program.len(),
@@ -258,6 +242,7 @@ fn declare_to_account_transaction(
 
 fn invoke_to_account_transaction(
     value: BroadcastedInvokeTransaction,
+    chain_id: Felt252Wrapper,
 ) -> Result<AccountTransaction, BroadcastedTransactionConversionError> {
     let user_tx = match value {
         BroadcastedInvokeTransaction::V1(BroadcastedInvokeTransactionV1 {
@@ -285,7 +270,7 @@ fn invoke_to_account_transaction(
                 ),
             });
 
-            let tx_hash = invoke_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = invoke_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
 
             let tx = btx::InvokeTransaction::new(invoke_tx, tx_hash);
 
@@ -325,7 +310,7 @@ fn invoke_to_account_transaction(
                 fee_data_availability_mode: core_da_to_api_da(fee_data_availability_mode),
             });
 
-            let tx_hash = invoke_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = invoke_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
 
             let tx = btx::InvokeTransaction::new(invoke_tx, tx_hash);
 
@@ -337,6 +322,7 @@ fn invoke_to_account_transaction(
 
 fn deploy_account_to_account_transaction(
     tx: BroadcastedDeployAccountTransaction,
+    chain_id: Felt252Wrapper,
 ) -> Result<AccountTransaction, BroadcastedTransactionConversionError> {
     let user_tx = match tx {
         BroadcastedDeployAccountTransaction::V1(BroadcastedDeployAccountTransactionV1 {
@@ -369,7 +355,7 @@ fn deploy_account_to_account_transaction(
                 class_hash: Felt252Wrapper::from(class_hash).into(),
             });
 
-            let tx_hash = deploy_account_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = deploy_account_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
 
             let contract_address = calculate_contract_address(
                 Felt252Wrapper::from(contract_address_salt).into(),
@@ -419,7 +405,7 @@ fn deploy_account_to_account_transaction(
                 fee_data_availability_mode: core_da_to_api_da(fee_data_availability_mode),
             });
 
-            let tx_hash = deploy_account_tx.compute_hash::<PedersenHasher>(Felt252Wrapper::ZERO, false, None);
+            let tx_hash = deploy_account_tx.compute_hash::<PedersenHasher>(chain_id, false, None);
 
             let contract_address = calculate_contract_address(
                 Felt252Wrapper::from(contract_address_salt).into(),
@@ -495,6 +481,45 @@ fn instantiate_blockifier_contract_class(
     Ok(contract_class)
 }
 
+/// Decompresses the gzipped program of a [CompressedLegacyContractClass]
+fn decompress_program(program: &[u8]) -> Result<Vec<u8>, BroadcastedTransactionConversionError> {
+    // Create a GzipDecoder to decompress the bytes
+    let mut gz = GzDecoder::new(program);
+
+    // Read the decompressed bytes into a Vec<u8>
+    let mut decompressed_bytes = Vec::new();
+    std::io::Read::read_to_end(&mut gz, &mut decompressed_bytes)
+        .map_err(|_| BroadcastedTransactionConversionError::ProgramDecompressionFailed)?;
+
+    Ok(decompressed_bytes)
+}
+
+/// Computes the class hash of a legacy contract class given its decompressed program
+fn legacy_class_hash(
+    contract_class: &CompressedLegacyContractClass,
+    program_decompressed_bytes: &[u8],
+) -> Result<FieldElement, BroadcastedTransactionConversionError> {
+    let legacy_contract_class = LegacyContractClass {
+        program: serde_json::from_slice(program_decompressed_bytes)
+            .map_err(|_| BroadcastedTransactionConversionError::ProgramDeserializationFailed)?,
+        abi: match contract_class.abi.as_ref() {
+            Some(abi) => Some(abi.iter().cloned().map(|entry| entry.into()).collect::<Vec<_>>()),
+            None => vec![].into(),
+        },
+        entry_points_by_type: to_raw_legacy_entry_points(contract_class.entry_points_by_type.clone()),
+    };
+
+    legacy_contract_class.class_hash().map_err(|_| BroadcastedTransactionConversionError::ClassHashComputationFailed)
+}
+
+/// Computes the class hash of a [CompressedLegacyContractClass]
+pub fn get_legacy_contract_class_hash(
+    contract_class: &CompressedLegacyContractClass,
+) -> Result<FieldElement, BroadcastedTransactionConversionError> {
+    let decompressed_bytes = decompress_program(&contract_class.program)?;
+    legacy_class_hash(contract_class, &decompressed_bytes)
+}
+
 fn to_raw_legacy_entry_point(entry_point: LegacyContractEntryPoint) -> RawLegacyEntryPoint {
     RawLegacyEntryPoint { offset: LegacyEntrypointOffset::U64AsInt(entry_point.offset), selector: entry_point.selector }
 }
@@ -551,22 +576,35 @@ fn entry_points_by_type_to_contract_entry_points(value: EntryPointsByType) -> Co
 }
 
 // Utils to convert Casm contract class to Compiled class
-pub fn get_casm_contract_class_hash(casm_contract_class: &CasmContractClass) -> FieldElement {
-    let compiled_class = casm_contract_class_to_compiled_class(casm_contract_class);
-    compiled_class.class_hash().unwrap()
+pub fn get_casm_contract_class_hash(
+    casm_contract_class: &CasmContractClass,
+) -> Result<FieldElement, BroadcastedTransactionConversionError> {
+    let compiled_class = casm_contract_class_to_compiled_class(casm_contract_class)?;
+    compiled_class.class_hash().map_err(|_| BroadcastedTransactionConversionError::ClassHashComputationFailed)
 }
 
 /// Converts a [CasmContractClass] to a [CompiledClass]
-pub fn casm_contract_class_to_compiled_class(casm_contract_class: &CasmContractClass) -> CompiledClass {
-    CompiledClass {
+pub fn casm_contract_class_to_compiled_class(
+    casm_contract_class: &CasmContractClass,
+) -> Result<CompiledClass, BroadcastedTransactionConversionError> {
+    // `NestedIntList` is not exported by cairo-lang, so the segment lengths go through their
+    // common json representation. A single segment is equivalent to no segmentation.
+    let bytecode_segment_lengths = match &casm_contract_class.bytecode_segment_lengths {
+        Some(lengths) => serde_json::to_value(lengths)
+            .and_then(serde_json::from_value)
+            .map_err(|_| BroadcastedTransactionConversionError::CasmContractClassConversionFailed)?,
+        None => Default::default(),
+    };
+
+    Ok(CompiledClass {
         prime: casm_contract_class.prime.to_string(),
         compiler_version: casm_contract_class.compiler_version.clone(),
         bytecode: casm_contract_class.bytecode.iter().map(|x| biguint_to_field_element(&x.value)).collect(),
         entry_points_by_type: casm_entry_points_to_compiled_entry_points(&casm_contract_class.entry_points_by_type),
-        hints: vec![],                    // not needed to get class hash so ignoring this
-        pythonic_hints: None,             // not needed to get class hash so ignoring this
-        bytecode_segment_lengths: vec![], // TODO: implement this
-    }
+        hints: vec![],        // not needed to get class hash so ignoring this
+        pythonic_hints: None, // not needed to get class hash so ignoring this
+        bytecode_segment_lengths,
+    })
 }
 
 /// Converts a [BigUint] to a [FieldElement]
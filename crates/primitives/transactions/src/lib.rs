@@ -306,3 +306,6 @@ impl From<&AccountTransaction> for TxType {
 // pub fn tx_into_user_or_l1_vec(transactions: Vec<Transaction>) -> Vec<UserOrL1HandlerTransaction>
 // {     transactions.into_iter().map(|tx| tx.into()).collect()
 // }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,433 @@
+use std::sync::Arc;
+
+use mp_chain_id::{SN_MAIN_CHAIN_ID, SN_SEPOLIA_CHAIN_ID};
+use mp_hashers::pedersen::PedersenHasher;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector, Nonce, PatriciaKey};
+use starknet_api::data_availability::DataAvailabilityMode;
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{
+    AccountDeploymentData, Calldata, ContractAddressSalt, DeclareTransaction, DeclareTransactionV0V1,
+    DeclareTransactionV2, DeclareTransactionV3, DeployAccountTransaction, DeployAccountTransactionV1,
+    DeployAccountTransactionV3, DeployTransaction, Fee, InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1,
+    InvokeTransactionV3, L1HandlerTransaction, PaymasterData, Resource, ResourceBounds, ResourceBoundsMapping, Tip,
+    Transaction, TransactionHash, TransactionSignature, TransactionVersion,
+};
+
+use crate::compute_hash::ComputeTransactionHash;
+
+// All the test vectors below are real Starknet mainnet and Sepolia transactions. They can be
+// fetched by hash at https://alpha-mainnet.starknet.io/feeder_gateway/get_transaction?transactionHash=<hash>
+// and https://alpha-sepolia.starknet.io/feeder_gateway/get_transaction?transactionHash=<hash>
+
+fn felt(hex: &str) -> StarkFelt {
+    StarkFelt::try_from(hex).unwrap()
+}
+
+fn contract_address(hex: &str) -> ContractAddress {
+    ContractAddress(PatriciaKey::try_from(felt(hex)).unwrap())
+}
+
+fn resource_bounds(l1_gas: (u64, u128), l2_gas: (u64, u128)) -> ResourceBoundsMapping {
+    ResourceBoundsMapping::try_from(vec![
+        (Resource::L1Gas, ResourceBounds { max_amount: l1_gas.0, max_price_per_unit: l1_gas.1 }),
+        (Resource::L2Gas, ResourceBounds { max_amount: l2_gas.0, max_price_per_unit: l2_gas.1 }),
+    ])
+    .unwrap()
+}
+
+fn assert_mainnet_hash(transaction: Transaction, block_number: u64, expected_hash: &str) {
+    let hash = transaction.compute_hash::<PedersenHasher>(SN_MAIN_CHAIN_ID, false, Some(block_number));
+    assert_eq!(hash, TransactionHash(felt(expected_hash)), "transaction hash mismatch at block {block_number}");
+}
+
+fn assert_sepolia_hash(transaction: Transaction, expected_hash: &str) {
+    // The hash of transactions from version 1 onwards does not depend on the block number.
+    let hash = transaction.compute_hash::<PedersenHasher>(SN_SEPOLIA_CHAIN_ID, false, None);
+    assert_eq!(hash, TransactionHash(felt(expected_hash)), "transaction hash mismatch");
+}
+
+#[test]
+fn test_mainnet_invoke_v0_hash() {
+    let transaction = Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0 {
+        max_fee: Fee(0x892a33adefe4),
+        signature: TransactionSignature(vec![
+            felt("0x4d9f6a54e158cf0cfb086e8cdb30c0dd1e29809e504130ae5065b3c56f4d1d4"),
+            felt("0x49e753a978b34ade8b32c319e2f225121b5bb1f12c040046c85549ef7ab1344"),
+        ]),
+        contract_address: contract_address("0x16ba3e98d91e73aa2037bf80fa55f82be38211530632dc7e9ac47223688e34e"),
+        entry_point_selector: EntryPointSelector(felt(
+            "0x15d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad",
+        )),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x1"),
+            felt("0x16ba3e98d91e73aa2037bf80fa55f82be38211530632dc7e9ac47223688e34e"),
+            felt("0xf2f7c15cbe06c8d94597cd91fd7f3369eae842359235712def5584f8d270cd"),
+            felt("0x0"),
+            felt("0x1"),
+            felt("0x1"),
+            felt("0x33434ad846cdd5f23eb73ff09fe6fddd568284a0fb7d1be20ee482f044dabe2"),
+            felt("0x0"),
+        ])),
+    }));
+    assert_mainnet_hash(transaction, 206864, "0x7b199bef92f4d2dc7713c53927060b3388ccd6873dc084ee7c12cdac4209d3b");
+}
+
+#[test]
+fn test_mainnet_invoke_v1_hash() {
+    let transaction = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+        max_fee: Fee(0x7f49b0d6d7c),
+        signature: TransactionSignature(vec![
+            felt("0x1"),
+            felt("0xd8744b5d5c0da02d6562bd48d4271e95ac7753aae044c8e736862875ce2092"),
+            felt("0x4f24eb09e7e8c2105e204dfeaa737948cf9c5c4fb2749074c73fdbb2400a68f"),
+        ]),
+        nonce: Nonce(felt("0x62")),
+        sender_address: contract_address("0x6f7afd58d20aedbdb694ff539d3280ae497c1a510caddcc6a06c97eebd001dc"),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x2"),
+            felt("0x68f5c6a61780768455de69077e07e89787839bf8166decfbf92b645209c0fb8"),
+            felt("0x219209e083275171774dab1df80982e9df2096516f06319c5c6d71ae0a8480c"),
+            felt("0x3"),
+            felt("0x1114c7103e12c2b2ecbd3a2472ba9c48ddcbf702b1c242dd570057e26212111"),
+            felt("0xb67495"),
+            felt("0x0"),
+            felt("0x1114c7103e12c2b2ecbd3a2472ba9c48ddcbf702b1c242dd570057e26212111"),
+            felt("0x15543c3708653cda9d418b4ccd3be11368e40636c10c44b18cfe756b6d88b29"),
+            felt("0x7"),
+            felt("0x30baaaf1b243f6e74c656f98dcb24b98687dcbe783d25f35854148c4c602d41"),
+            felt("0x0"),
+            felt("0xb67495"),
+            felt("0x0"),
+            felt("0x1"),
+            felt("0x3a1045717884ca9abbc2e"),
+            felt("0x0"),
+        ])),
+    }));
+    assert_mainnet_hash(transaction, 636864, "0x215b2e7efdedc5d9c056dd6a691b8117f292997d2cc8c15a9cebfa90620e35");
+}
+
+#[test]
+fn test_mainnet_invoke_v3_hash() {
+    let transaction = Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+        resource_bounds: resource_bounds((0xa9e, 0x7f2a1ad4f2f1), (0x0, 0x0)),
+        tip: Tip(0),
+        signature: TransactionSignature(vec![
+            felt("0x1"),
+            felt("0x1c3a868705399362140bf5ae33f955f1c946a8f836d4c052f2aff397bcfe80a"),
+            felt("0x78163ce5979e2bc8a944ba353a63c194ca4f63d393bbaaa95857daa9223e93c"),
+        ]),
+        nonce: Nonce(felt("0x9d")),
+        sender_address: contract_address("0x69c0f9bcd79697bdceaf7748e3ff8f34aa39e4063ce44896af664c0c96f6c10"),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x1"),
+            felt("0x4c0a5193d58f74fbace4b74dcf65481e734ed1714121bdc571da345540efa05"),
+            felt("0x3943907ef0ef6f9d2e2408b05e520a66daaf74293dbf665e5a20b117676170e"),
+            felt("0x2"),
+            felt("0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"),
+            felt("0x16345785d8a0000"),
+        ])),
+        nonce_data_availability_mode: DataAvailabilityMode::L1,
+        fee_data_availability_mode: DataAvailabilityMode::L1,
+        paymaster_data: PaymasterData(vec![]),
+        account_deployment_data: AccountDeploymentData(vec![]),
+    }));
+    assert_mainnet_hash(transaction, 636864, "0x1d4735f4ba73a67be2f648d9b21cab3783383b8c229566b46b027c46012219");
+}
+
+#[test]
+fn test_mainnet_declare_v0_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V0(DeclareTransactionV0V1 {
+        max_fee: Fee(0x0),
+        signature: TransactionSignature(vec![]),
+        nonce: Nonce(felt("0x0")),
+        class_hash: ClassHash(felt("0x7319e2f01b0947afd86c0bb0e95029551b32f6dc192c47b2e8b08415eebbc25")),
+        sender_address: contract_address("0x1"),
+    }));
+    assert_mainnet_hash(transaction, 2700, "0x2f2ef64daffdc72bf33b34ad024891691b8eb1d0ab70cc7f8fb71f6fd5e1f22");
+}
+
+#[test]
+fn test_mainnet_declare_v1_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V1(DeclareTransactionV0V1 {
+        max_fee: Fee(0x1f04d0693837),
+        signature: TransactionSignature(vec![
+            felt("0x72d657c8bed37c254615966b94178bbaeb87edbe9ebd176657b80d538c8d7c5"),
+            felt("0x2fd512374a9eb0277338bf95fbf29315d449a35120a0880dfeff5fd39e7fe80"),
+        ]),
+        nonce: Nonce(felt("0x15")),
+        class_hash: ClassHash(felt("0x4dae654c7b6707667a178729b512d61494fe590ab4accc46923d6409b97e617")),
+        sender_address: contract_address("0x3e2a6434cebce4475cdf8843859f2137259918171eba8d462e3d34c5c4fd110"),
+    }));
+    assert_mainnet_hash(transaction, 346864, "0x70fb3ea4b786b752817a32d4835eaba19da495392df90f7f827cd9b60b2c925");
+}
+
+#[test]
+fn test_mainnet_declare_v2_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+        max_fee: Fee(0xb48df232e93750),
+        signature: TransactionSignature(vec![
+            felt("0x10fe9fe438cd3c5bd2c4aa94ea8723d1d0a7a2da63ea42a328bf4f8eb3812d7"),
+            felt("0x40c359c942fd1ce5f8fc3effe53c8943273df95418ecadef1cd71c1c667d016"),
+        ]),
+        nonce: Nonce(felt("0x1f9")),
+        class_hash: ClassHash(felt("0x4d90a3b52871831b34bc936d9aee304b7205202e649dceef5ee4392659ab33")),
+        compiled_class_hash: CompiledClassHash(felt(
+            "0x3c1296b5f7e6a30bc0167bf30e0700eebb2e9a06228e24cc3ad386502125bcf",
+        )),
+        sender_address: contract_address("0x75341b8090a4257f22dafffe3a4cb882006bd26302720d6a80a1fde154a3430"),
+    }));
+    assert_mainnet_hash(transaction, 446864, "0x7debe525c66a929048236c8f6da5903e4f141e5cb5e6cb23e9af33ecaabe062");
+}
+
+#[test]
+fn test_mainnet_declare_v3_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V3(DeclareTransactionV3 {
+        resource_bounds: resource_bounds((0xe38c2, 0x24e2649cc098), (0x0, 0x0)),
+        tip: Tip(0),
+        signature: TransactionSignature(vec![
+            felt("0x14bdaa66720b2f7cb5d55041de921f38bca2b8133e903f446a43e78943a1d05"),
+            felt("0x6ed9788306ec1f51157fdeeacb1094b2c1e001bcc7c4eaf31d2b66f02cdc5b0"),
+        ]),
+        nonce: Nonce(felt("0x3")),
+        class_hash: ClassHash(felt("0x7a9d1cd5dcf3d47b10e638eb1330d101f7d471f08eff9664b42dfa90f6973bf")),
+        compiled_class_hash: CompiledClassHash(felt(
+            "0x4fc6444f59a2fb0b2c67e1e22caba0edc3b48727e8a9cc19e59e5c3e5dc8270",
+        )),
+        sender_address: contract_address("0x46d3a562c606077c14c3479946e4145b7a372538875eb4e635e758fcd1d2c80"),
+        nonce_data_availability_mode: DataAvailabilityMode::L1,
+        fee_data_availability_mode: DataAvailabilityMode::L1,
+        paymaster_data: PaymasterData(vec![]),
+        account_deployment_data: AccountDeploymentData(vec![]),
+    }));
+    assert_mainnet_hash(transaction, 630721, "0x50ca961fd1b5f7f1ea1e8620ac980cc3c3fb4e434f0661c3a2d50893082b9fb");
+}
+
+#[test]
+fn test_mainnet_deploy_account_v1_hash() {
+    let transaction = Transaction::DeployAccount(DeployAccountTransaction::V1(DeployAccountTransactionV1 {
+        max_fee: Fee(0x3a23c71d8b9),
+        signature: TransactionSignature(vec![
+            felt("0x19bba00834026bc1a1045a71bfa909e6c6d41bc872b5bfa1364f1229144ac4a"),
+            felt("0x48f9fd27ec3a8cb2420a930c72cb81e47c69790519cbc963c320ed2c9b36456"),
+            felt("0x816dd0297efc55dc1e7559020a3a825e81ef734b558f03c83325d4da7e6253"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x534e5f4d41494e"),
+            felt("0x1d474323258a89e5b5de7ef0bfc162f85357c8d5140388fcf33c0f93993f7cf"),
+            felt("0x7f86bb20596368f23ca9a720e54dc05bf487f1c45b66da0d94ffab186ee1ac6"),
+        ]),
+        nonce: Nonce(felt("0x0")),
+        class_hash: ClassHash(felt("0x13bfe114fb1cf405bfc3a7f8dbe2d91db146c17521d40dcf57e16d6b59fa8e6")),
+        contract_address_salt: ContractAddressSalt(felt(
+            "0x54c617a2e91df5344958e0eb2c30c58a1134b3f8e59e88deba60a24f95c0a2c",
+        )),
+        constructor_calldata: Calldata(Arc::new(vec![felt(
+            "0x54c617a2e91df5344958e0eb2c30c58a1134b3f8e59e88deba60a24f95c0a2c",
+        )])),
+    }));
+    assert_mainnet_hash(transaction, 636864, "0x40e7ac7efc374f3d1241c6f991de2ea534d84e8be307420658353527226c5e4");
+}
+
+#[test]
+fn test_mainnet_deploy_account_v3_hash() {
+    let transaction = Transaction::DeployAccount(DeployAccountTransaction::V3(DeployAccountTransactionV3 {
+        resource_bounds: resource_bounds((0x4c, 0x8ab967606cb9), (0x0, 0x0)),
+        tip: Tip(0),
+        signature: TransactionSignature(vec![
+            felt("0x516550aaa957b0f931b1f9347304bb557e29036b5056c0b97b047778c1c8fec"),
+            felt("0x782d87c2df96241acb2d88260e6ae2f18ad36fd68bd1036cfb2166a30bb9e14"),
+            felt("0x816dd0297efc55dc1e7559020a3a825e81ef734b558f03c83325d4da7e6253"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x0"),
+            felt("0x534e5f4d41494e"),
+            felt("0x3d4d482132b50ef44c8308b7102ca9a21dcaec5b24a8a00b363f6b4824d35f9"),
+            felt("0x18099527f0007975481b3e34ea897c07d3f137117d382e49859436f8eb25140"),
+        ]),
+        nonce: Nonce(felt("0x0")),
+        class_hash: ClassHash(felt("0x13bfe114fb1cf405bfc3a7f8dbe2d91db146c17521d40dcf57e16d6b59fa8e6")),
+        contract_address_salt: ContractAddressSalt(felt(
+            "0x1f218cfa725d1679ff028c53d094a765dfd1de632787cab37ffe10f3915dcc7",
+        )),
+        constructor_calldata: Calldata(Arc::new(vec![felt(
+            "0x1f218cfa725d1679ff028c53d094a765dfd1de632787cab37ffe10f3915dcc7",
+        )])),
+        nonce_data_availability_mode: DataAvailabilityMode::L1,
+        fee_data_availability_mode: DataAvailabilityMode::L1,
+        paymaster_data: PaymasterData(vec![]),
+    }));
+    assert_mainnet_hash(transaction, 636864, "0x25cbbc1e197e1e6dd8886b4b359c2dbe26245a282547624d7e54e7c3e2cf15");
+}
+
+#[test]
+fn test_mainnet_deploy_hash() {
+    let transaction = Transaction::Deploy(DeployTransaction {
+        version: TransactionVersion(felt("0x0")),
+        class_hash: ClassHash(felt("0x25ec026985a3bf9d0cc1fe17326b245dfdc3ff89b8fde106542a3ea56c5a918")),
+        contract_address_salt: ContractAddressSalt(felt(
+            "0x77d0eb177b763933debc8596399528e720951662ad078752302f9c447fa59ef",
+        )),
+        constructor_calldata: Calldata(Arc::new(vec![
+            felt("0x3e327de1c40540b98d05cbcb13552008e36f0ec8d61d46956d2f9752c294328"),
+            felt("0x79dc0da7c54b95f10aa182ad0a46400db63156920adb65eca2654c0945a463"),
+            felt("0x2"),
+            felt("0x77d0eb177b763933debc8596399528e720951662ad078752302f9c447fa59ef"),
+            felt("0x0"),
+        ])),
+    });
+    assert_mainnet_hash(transaction, 6864, "0x44ce170a27953fec1809b3d2a03a44ad6b475355748a5f1c01db972f20b295e");
+}
+
+#[test]
+fn test_mainnet_l1_handler_hash() {
+    let transaction = Transaction::L1Handler(L1HandlerTransaction {
+        version: TransactionVersion(felt("0x0")),
+        nonce: Nonce(felt("0x18e94d")),
+        contract_address: contract_address("0x73314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82"),
+        entry_point_selector: EntryPointSelector(felt(
+            "0x1b64b1b3b690b43b9b514fb81377518f4039cd3e4f4914d8a6bdf01d679fb19",
+        )),
+        calldata: Calldata(Arc::new(vec![
+            felt("0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419"),
+            felt("0x455448"),
+            felt("0xc27947400e26e534e677afc2e9b2ec1bab14fc89"),
+            felt("0x4af4754baf89f1b8b449215a8ea7ce558824a33a5393eaa3829658549f2bfa2"),
+            felt("0x9184e72a000"),
+            felt("0x0"),
+        ])),
+    });
+    assert_mainnet_hash(transaction, 546864, "0x439e12f67962c353182d72b4af12c3f11eaba4b36e552aebcdcd6db66971bdb");
+}
+
+#[test]
+fn test_mainnet_legacy_deploy_hash() {
+    let transaction = Transaction::Deploy(DeployTransaction {
+        version: TransactionVersion(felt("0x0")),
+        class_hash: ClassHash(felt("0x71c3c99f5cf76fc19945d4b8b7d34c7c5528f22730d56192b50c6bbfd338a64")),
+        contract_address_salt: ContractAddressSalt(felt(
+            "0x1cb8f5514ceac8a6abdf232cd24ea9eacfb310eaba427432784b80b5f95bc8d",
+        )),
+        constructor_calldata: Calldata(Arc::new(vec![felt(
+            "0x5f28c66afd8a6799ddbe1933bce2c144625031aafa881fa38fa830790eff204",
+        )])),
+    });
+    assert_mainnet_hash(transaction, 1470, "0x2b30ab254aaac75326e5d6a1ed6f040ddd61902155381a754a849e9aa616153");
+}
+
+#[test]
+fn test_mainnet_legacy_invoke_v0_hash() {
+    let transaction = Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0 {
+        max_fee: Fee(0x0),
+        signature: TransactionSignature(vec![]),
+        contract_address: contract_address("0x67b664b0ea8df2687352cbd636cd0782bf8f563a0072b233853e3c5a70b5897"),
+        entry_point_selector: EntryPointSelector(felt(
+            "0x79dc0da7c54b95f10aa182ad0a46400db63156920adb65eca2654c0945a463",
+        )),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x1cb8f5514ceac8a6abdf232cd24ea9eacfb310eaba427432784b80b5f95bc8d"),
+            felt("0x0"),
+        ])),
+    }));
+    assert_mainnet_hash(transaction, 1470, "0x1d7b514ed5f8d37c36bbb70db9df33994e0e77bb8d6f62790894f0b56462e62");
+}
+
+#[test]
+fn test_mainnet_legacy_l1_handler_hash() {
+    let transaction = Transaction::L1Handler(L1HandlerTransaction {
+        version: TransactionVersion(felt("0x0")),
+        nonce: Nonce(felt("0x6")),
+        contract_address: contract_address("0xda8054260ec00606197a4103eb2ef08d6c8af0b6a808b610152d1ce498f8c3"),
+        entry_point_selector: EntryPointSelector(felt(
+            "0xe3f5e9e1456ffa52a3fbc7e8c296631d4cc2120c0be1e2829301c0d8fa026b",
+        )),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x142273bcbfca76512b2a05aed21f134c4495208"),
+            felt("0xd9aa5ef3a8b31910ad5f26fcd782ce18bcfcfc45"),
+            felt("0x2"),
+            felt("0x453b0310bcdfa50d3c2e7f757e284ac6cd4171933a4e67d1bdcfdbc7f3cbc93"),
+        ])),
+    });
+    assert_mainnet_hash(transaction, 1367, "0x76e77f01890b45e0b0e90e50e5e30cf6e401e7110ffb67ca9a55716d9bc1c0e");
+}
+
+#[test]
+fn test_sepolia_declare_v1_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V1(DeclareTransactionV0V1 {
+        max_fee: Fee(0x625e5879c08f4),
+        signature: TransactionSignature(vec![
+            felt("0x3609667964a8ed946bc507721ec35a851d97a097d159ef0ec2af8fab490223f"),
+            felt("0x68846bad9f0f010fac4eeaf39f9dd609b28765fd2336b70ce026e33e2421c15"),
+        ]),
+        nonce: Nonce(felt("0x7")),
+        class_hash: ClassHash(felt("0x3131fa018d520a037686ce3efddeab8f28895662f019ca3ca18a626650f7d1e")),
+        sender_address: contract_address("0x68922eb87daed71fc3099031e178b6534fc39a570022342e8c166024da893f5"),
+    }));
+    assert_sepolia_hash(transaction, "0xb2d88f64d9655a7d47a5519d66b969168d02d0d33f6476f0d2539c51686329");
+}
+
+#[test]
+fn test_sepolia_declare_v2_hash() {
+    let transaction = Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+        max_fee: Fee(0x92fa1ac712614),
+        signature: TransactionSignature(vec![
+            felt("0x4ab3e77908396c66b39326f52334b447fe878d1d899a287c9e3cf7bd09839ea"),
+            felt("0x79a56f9e61eb834f1ac524eb35da33cccf92ff3b01a7a8eaf68cbb64bebdba9"),
+        ]),
+        nonce: Nonce(felt("0x6")),
+        class_hash: ClassHash(felt("0x1a736d6ed154502257f02b1ccdf4d9d1089f80811cd6acad48e6b6a9d1f2003")),
+        compiled_class_hash: CompiledClassHash(felt(
+            "0x29787a427a423ffc5986d43e630077a176e4391fcef3ebf36014b154069ae4",
+        )),
+        sender_address: contract_address("0x68922eb87daed71fc3099031e178b6534fc39a570022342e8c166024da893f5"),
+    }));
+    assert_sepolia_hash(transaction, "0x4cacc2bbdd5ec77b20e908f311ab27d6495b69761e929bb24ba02632716944");
+}
+
+#[test]
+fn test_sepolia_invoke_v3_hash() {
+    let transaction = Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+        resource_bounds: resource_bounds((0x61a80, 0x5af3107a4000), (0x0, 0x0)),
+        tip: Tip(0),
+        signature: TransactionSignature(vec![
+            felt("0x389bca189562763f6a73da4aaab30d87d8bbc243571f4a353c48493a43a0634"),
+            felt("0x62d30041a0b1199b3ad93515066d5c7791211fa32f585956fafe630082270e9"),
+        ]),
+        nonce: Nonce(felt("0x1084b")),
+        sender_address: contract_address("0x35acd6dd6c5045d18ca6d0192af46b335a5402c02d41f46e4e77ea2c951d9a3"),
+        calldata: Calldata(Arc::new(vec![
+            felt("0x1"),
+            felt("0x47ad6a25df680763e5663bd0eba3d2bfd18b24b1e8f6bd36b71c37433c63ed0"),
+            felt("0x19a35a6e95cb7a3318dbb244f20975a1cd8587cc6b5259f15f61d7beb7ee43b"),
+            felt("0x2"),
+            felt("0x4d0b88ace5705bb7825f91ee95557d906600b7e7762f5615e6a4f407185a43a"),
+            felt("0x630ac7edd6c7c097e4f9774fe5855bed3a2b8886286c61f1f7afd601e124d60"),
+        ])),
+        nonce_data_availability_mode: DataAvailabilityMode::L1,
+        fee_data_availability_mode: DataAvailabilityMode::L1,
+        paymaster_data: PaymasterData(vec![]),
+        account_deployment_data: AccountDeploymentData(vec![]),
+    }));
+    assert_sepolia_hash(transaction, "0x22772429229cbca26cb062f6f6a0991a4e84d0f11f3b1bda1913613a5e609e0");
+}
+
+// Sepolia has no invoke v0 nor deploy transaction. The vectors still missing for the other kinds
+// are the following Sepolia transactions, their bodies are to be fetched from the feeder gateway
+// before being added here:
+// - invoke v1: 0x3f786ecc4955a2602c91a291328518ef866cb7f3d50e4b16fd42282952623aa
+// - declare v3: 0x54270d103c875a613e013d1fd555edcff2085feca9d7b4532243a8257fd5cf3
+// - deploy_account v1: 0x24ed6b82e2f6d3a811ec180a25c1ccd0bdc7bdba8ebd709de2ed697a1e82193
+// - deploy_account v3: 0x11c67fb3a9a623b3190c9ac41ebf7f5dd421f2583344c498a30a7280c660f01
+// - l1_handler: 0x785c2ada3f53fbc66078d47715c27718f92e6e48b96372b36e5197de69b82b5